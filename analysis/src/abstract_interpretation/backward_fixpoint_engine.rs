@@ -0,0 +1,166 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    abstract_interpretation::{AbstractState, AnalysisResult},
+    PointwiseState,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::mir;
+use rustc_span::def_id::DefId;
+use std::{collections::BTreeSet, iter::FromIterator};
+
+/// Trait to be used to define a backward abstract-interpretation-based static analysis of a MIR
+/// body, such as a liveness analysis.
+///
+/// The resulting `PointwiseState` contains, before each statement and terminator, the state
+/// *before* that statement or terminator, i.e. the state that is computed by applying its effect
+/// to the state after it. The state on the CFG edge from a block to one of its successors is the
+/// state before the first statement of the successor.
+pub trait BackwardFixpointEngine<'mir, 'tcx: 'mir> {
+    type State: AbstractState;
+
+    /// Return the DefId of the MIR body to be analyzed.
+    fn def_id(&self) -> DefId;
+
+    /// Return the MIR body to be analyzed.
+    fn body(&self) -> &'mir mir::Body<'tcx>;
+
+    /// Creates a new abstract state which corresponds to the bottom element in the lattice
+    fn new_bottom(&self) -> Self::State;
+
+    /// Creates the abstract state after the terminators that have no successors, e.g. after a
+    /// `return`.
+    fn new_final(&self) -> Self::State;
+
+    /// Determines if the number of times a block was traversed by the analyzer given in `counter`
+    /// is large enough to widen the state
+    fn need_to_widen(counter: u32) -> bool;
+
+    /// Modify the state after the statement at `location` to the state before it.
+    ///
+    /// The statement can be extracted using
+    /// `self.mir[location.block].statements[location.statement_index]`.
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::State,
+        location: mir::Location,
+    ) -> AnalysisResult<()>;
+
+    /// Modify the state after the terminator at `location` to the state before it. The state
+    /// after the terminator is the join of the states of all its successors.
+    ///
+    /// The terminator can be extracted using `self.mir[location.block].terminator()`.
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::State,
+        location: mir::Location,
+    ) -> AnalysisResult<()>;
+
+    /// Produces an abstract state for every program point in `mir` by iterating over all statements
+    /// in reverse program order until a fixed point is reached (i.e. by abstract interpretation).
+    fn run_bwd_analysis(&self) -> AnalysisResult<PointwiseState<'mir, 'tcx, Self::State>> {
+        let mir = self.body();
+        let mut p_state = PointwiseState::new(mir);
+        let mut work_set: BTreeSet<mir::BasicBlock> =
+            BTreeSet::from_iter(mir.basic_blocks().indices());
+
+        let mut counters: FxHashMap<mir::BasicBlock, u32> =
+            FxHashMap::with_capacity_and_hasher(mir.basic_blocks().len(), Default::default());
+        // The state after each block, before the effect of its terminator, used for widening.
+        let mut states_after_block: FxHashMap<mir::BasicBlock, Self::State> = FxHashMap::default();
+
+        // Every cycle of the CFG contains an edge that goes back in reverse postorder. In a
+        // backward analysis the information flows against the edges, so it is enough to widen at
+        // the sources of these edges.
+        let mut rpo_indices: FxHashMap<mir::BasicBlock, usize> = FxHashMap::default();
+        for (index, (bb, _)) in mir::traversal::reverse_postorder(mir).enumerate() {
+            rpo_indices.insert(bb, index);
+        }
+        let widening_points: FxHashSet<mir::BasicBlock> = mir
+            .basic_blocks()
+            .indices()
+            .filter(|bb| {
+                mir[*bb].terminator().successors().any(|succ_bb| {
+                    match (rpo_indices.get(bb), rpo_indices.get(succ_bb)) {
+                        (Some(index), Some(succ_index)) => index >= succ_index,
+                        // unreachable blocks
+                        _ => true,
+                    }
+                })
+            })
+            .collect();
+
+        // extract the bb with the maximal index -> blocks are usually visited after their
+        // successors
+        while let Some(&bb) = work_set.iter().next_back() {
+            work_set.remove(&bb);
+
+            let terminator = mir[bb].terminator();
+            let mut state_after_block = if terminator.successors().next().is_none() {
+                self.new_final()
+            } else {
+                self.new_bottom()
+            };
+            let mut edge_states = Vec::new();
+            for &succ_bb in terminator.successors() {
+                let succ_location = mir::Location {
+                    block: succ_bb,
+                    statement_index: 0,
+                };
+                // if no state is present: assume bottom => no effect on join
+                let succ_state = p_state
+                    .lookup_before(succ_location)
+                    .cloned()
+                    .unwrap_or_else(|| self.new_bottom());
+                state_after_block.join(&succ_state);
+                edge_states.push((succ_bb, succ_state));
+            }
+            p_state.lookup_mut_after_block(bb).extend(edge_states);
+
+            // widen if needed
+            let counter = counters.entry(bb).or_insert(0);
+            *counter += 1;
+
+            if widening_points.contains(&bb) && Self::need_to_widen(*counter) {
+                if let Some(previous) = states_after_block.get(&bb) {
+                    state_after_block.widen(previous);
+                }
+            }
+            states_after_block.insert(bb, state_after_block.clone());
+
+            let first_location = mir::Location {
+                block: bb,
+                statement_index: 0,
+            };
+            let previous_state_before_block = p_state.lookup_before(first_location).cloned();
+
+            // terminator effect
+            let mut current_state = state_after_block;
+            let location = mir.terminator_loc(bb);
+            self.apply_terminator_effect(&mut current_state, location)?;
+            p_state.set_before(location, current_state.clone());
+
+            // statement effects, in reverse order
+            for statement_index in (0..mir[bb].statements.len()).rev() {
+                let location = mir::Location {
+                    block: bb,
+                    statement_index,
+                };
+                self.apply_statement_effect(&mut current_state, location)?;
+                p_state.set_before(location, current_state.clone());
+            }
+
+            // the state before the block is the input of the predecessors
+            if previous_state_before_block.as_ref() != Some(&current_state) {
+                for &pred_bb in &mir.predecessors()[bb] {
+                    work_set.insert(pred_bb);
+                }
+            }
+        }
+        AnalysisResult::Ok(p_state)
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     PointwiseState,
 };
 pub use crate::{domains::*, AnalysisError};
-use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_middle::mir;
 use rustc_span::def_id::DefId;
 use std::{collections::BTreeSet, iter::FromIterator};
@@ -71,6 +71,27 @@ pub trait FixpointEngine<'mir, 'tcx: 'mir> {
         let mut counters: FxHashMap<mir::BasicBlock, u32> =
             FxHashMap::with_capacity_and_hasher(mir.basic_blocks().len(), Default::default());
 
+        // Every cycle of the CFG contains an edge that goes back in reverse postorder, so it is
+        // enough to widen at the targets of these edges. Widening at other blocks would lose the
+        // information gained on the edges that leave a loop head, e.g. from its condition.
+        let mut rpo_indices: FxHashMap<mir::BasicBlock, usize> = FxHashMap::default();
+        for (index, (bb, _)) in mir::traversal::reverse_postorder(mir).enumerate() {
+            rpo_indices.insert(bb, index);
+        }
+        let widening_points: FxHashSet<mir::BasicBlock> = mir
+            .basic_blocks()
+            .indices()
+            .filter(|bb| {
+                mir.predecessors()[*bb].iter().any(|pred_bb| {
+                    match (rpo_indices.get(pred_bb), rpo_indices.get(bb)) {
+                        (Some(pred_index), Some(index)) => pred_index >= index,
+                        // unreachable blocks
+                        _ => true,
+                    }
+                })
+            })
+            .collect();
+
         //'block_loop:
         // extract the bb with the minimal index -> hopefully better performance
         // use pop_first when it becomes stable?
@@ -97,7 +118,7 @@ pub trait FixpointEngine<'mir, 'tcx: 'mir> {
             let counter = counters.entry(bb).or_insert(0);
             *counter += 1;
 
-            if Self::need_to_widen(*counter) {
+            if widening_points.contains(&bb) && Self::need_to_widen(*counter) {
                 let location = mir::Location {
                     block: bb,
                     statement_index: 0,
@@ -5,7 +5,11 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 mod abstract_state;
+mod backward_fixpoint_engine;
 mod fixpoint_engine;
+mod summaries;
 
 pub use abstract_state::*;
+pub use backward_fixpoint_engine::*;
 pub use fixpoint_engine::*;
+pub use summaries::*;
@@ -0,0 +1,118 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::{
+    mir,
+    ty::{self, TyCtxt},
+};
+use rustc_span::def_id::DefId;
+use std::{cell::RefCell, rc::Rc};
+
+/// The effect of a function on the state of its callers, used by an analysis to handle the calls
+/// to that function.
+pub trait Summary: Sized {
+    /// Computes the summary of the function `def_id` from its MIR body. The summaries of the
+    /// functions that it calls can be looked up in `summaries`.
+    fn from_body<'tcx>(
+        summaries: &Summaries<'_, 'tcx, Self>,
+        def_id: DefId,
+        body: &mir::Body<'tcx>,
+    ) -> Self;
+
+    /// Computes a conservative summary of the function `def_id` from its signature only.
+    fn from_signature<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Self;
+}
+
+/// A cache of the summaries of the functions, keyed by `DefId`.
+///
+/// The summary of a function is computed from its MIR body if that is available. Otherwise, the
+/// summary is derived from the contract of the function, if a `contract_summaries` callback was
+/// given, or from its signature.
+pub struct Summaries<'a, 'tcx: 'a, S: Summary> {
+    tcx: TyCtxt<'tcx>,
+    /// The value is `None` while the summary of the function is being computed.
+    cache: RefCell<FxHashMap<DefId, Option<Rc<S>>>>,
+    contract_summaries: Option<Box<dyn Fn(DefId) -> Option<S> + 'a>>,
+}
+
+impl<'a, 'tcx: 'a, S: Summary> Summaries<'a, 'tcx, S> {
+    pub fn new(tcx: TyCtxt<'tcx>) -> Self {
+        Summaries {
+            tcx,
+            cache: RefCell::new(FxHashMap::default()),
+            contract_summaries: None,
+        }
+    }
+
+    /// Like `new`, but `contract_summaries` is used to get the summary of the functions whose
+    /// body is not available, e.g. trait methods or foreign functions.
+    pub fn with_contract_summaries(
+        tcx: TyCtxt<'tcx>,
+        contract_summaries: impl Fn(DefId) -> Option<S> + 'a,
+    ) -> Self {
+        Summaries {
+            tcx,
+            cache: RefCell::new(FxHashMap::default()),
+            contract_summaries: Some(Box::new(contract_summaries)),
+        }
+    }
+
+    pub fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    /// Returns the summary of the function called by the operand `func` of a `Call` terminator
+    /// in the body of `caller`, or `None` if the called function is not statically known, e.g.
+    /// because `func` is a function pointer.
+    pub fn get_call_summary(&self, caller: DefId, func: &mir::Operand<'tcx>) -> Option<Rc<S>> {
+        let (def_id, substs) = func.const_fn_def()?;
+        let param_env = self.tcx.param_env(caller);
+        // The MIR body of the caller might contain region variables
+        let substs = self.tcx.erase_regions(substs);
+        match ty::Instance::resolve(self.tcx, param_env, def_id, substs) {
+            Ok(Some(ty::Instance {
+                def: ty::InstanceDef::Item(def),
+                ..
+            })) => Some(self.get_summary(def.did)),
+            // The implementation that will be called is not known, so the body of `def_id` (e.g.
+            // the default implementation of a trait method) cannot be used.
+            _ => Some(Rc::new(self.summary_without_body(def_id))),
+        }
+    }
+
+    /// Returns the summary of the function `def_id`.
+    ///
+    /// The summaries of recursive functions are not computed with a fixed point: a recursive
+    /// call is handled with the summary derived from the signature of the callee.
+    pub fn get_summary(&self, def_id: DefId) -> Rc<S> {
+        if let Some(cached) = self.cache.borrow().get(&def_id) {
+            return match cached {
+                Some(summary) => summary.clone(),
+                None => Rc::new(S::from_signature(self.tcx, def_id)),
+            };
+        }
+        let summary = if self.tcx.is_mir_available(def_id) {
+            self.cache.borrow_mut().insert(def_id, None);
+            let body = self.tcx.optimized_mir(def_id);
+            S::from_body(self, def_id, body)
+        } else {
+            self.summary_without_body(def_id)
+        };
+        let summary = Rc::new(summary);
+        self.cache
+            .borrow_mut()
+            .insert(def_id, Some(summary.clone()));
+        summary
+    }
+
+    fn summary_without_body(&self, def_id: DefId) -> S {
+        self.contract_summaries
+            .as_ref()
+            .and_then(|contract_summaries| contract_summaries(def_id))
+            .unwrap_or_else(|| S::from_signature(self.tcx, def_id))
+    }
+}
@@ -13,11 +13,13 @@ extern crate rustc_middle;
 extern crate rustc_session;
 
 use analysis::{
-    abstract_interpretation::FixpointEngine,
+    abstract_interpretation::{AnalysisResult, BackwardFixpointEngine, FixpointEngine, Summaries},
     domains::{
-        DefinitelyAccessibleAnalysis, DefinitelyInitializedAnalysis, FramingAnalysis,
-        MaybeBorrowedAnalysis, ReachingDefsAnalysis,
+        BorrowSummary, DefinitelyAccessibleAnalysis, DefinitelyInitializedAnalysis,
+        FramingAnalysis, IntervalAnalysis, LiveVariablesAnalysis, MaybeBorrowedAnalysis,
+        MutationSummary, ReachingDefsAnalysis,
     },
+    PointwiseState,
 };
 use polonius_engine::{Algorithm, Output};
 use rustc_ast::ast;
@@ -26,16 +28,80 @@ use rustc_driver::Compilation;
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_interface::{interface, Config, Queries};
 use rustc_middle::{
-    ty,
+    mir, ty,
     ty::query::{query_values::mir_borrowck, ExternProviders, Providers},
 };
 use rustc_session::{Attribute, Session};
+use serde::Serialize;
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use vir::common::graphviz::ToGraphviz;
 
 struct OurCompilerCalls {
     args: Vec<String>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    /// The state of each function as pretty JSON, preceded by the name of the function.
+    Text,
+    /// A single JSON object that maps the name of each function to its state.
+    Json,
+    /// A Graphviz graph of the CFG of each function, annotated with the states.
+    Dot,
+}
+
+impl OutputFormat {
+    fn parse(format: &str) -> Self {
+        match format {
+            "text" => OutputFormat::Text,
+            "json" => OutputFormat::Json,
+            "dot" => OutputFormat::Dot,
+            _ => panic!("Unknown output format: {}", format),
+        }
+    }
+}
+
+struct ResultPrinter {
+    format: OutputFormat,
+    /// The results collected for the `Json` format, printed at the end of the analysis.
+    json_results: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ResultPrinter {
+    fn print<S: Serialize>(
+        &mut self,
+        function_name: String,
+        result: AnalysisResult<PointwiseState<S>>,
+        body: &mir::Body,
+    ) {
+        let state = match result {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("{}", e.to_pretty_str(body));
+                return;
+            }
+        };
+        match self.format {
+            OutputFormat::Text => {
+                println!("{}", serde_json::to_string_pretty(&state).unwrap());
+            }
+            OutputFormat::Json => {
+                self.json_results
+                    .insert(function_name, serde_json::to_value(&state).unwrap());
+            }
+            OutputFormat::Dot => {
+                state.to_graphviz(&mut std::io::stdout()).unwrap();
+            }
+        }
+    }
+
+    fn finish(self) {
+        if self.format == OutputFormat::Json {
+            println!("{}", serde_json::Value::Object(self.json_results));
+        }
+    }
+}
+
 fn get_attribute<'tcx>(
     tcx: ty::TyCtxt<'tcx>,
     def_id: DefId,
@@ -145,16 +211,30 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
         let abstract_domain: &str = self
             .args
             .iter()
-            .filter(|a| a.starts_with("--analysis"))
+            .filter(|a| a.starts_with("--analysis="))
             .flat_map(|a| a.rsplit('='))
             .next()
             .expect("Please add --analysis=<DOMAIN>");
+        let format = self
+            .args
+            .iter()
+            .filter(|a| a.starts_with("--format="))
+            .flat_map(|a| a.rsplit('='))
+            .next()
+            .map(OutputFormat::parse)
+            .unwrap_or(OutputFormat::Text);
+        let mut output = ResultPrinter {
+            format,
+            json_results: serde_json::Map::new(),
+        };
 
-        println!(
-            "Analyzing file {} using {}...",
-            compiler.input().source_name().prefer_local(),
-            abstract_domain
-        );
+        if format == OutputFormat::Text {
+            println!(
+                "Analyzing file {} using {}...",
+                compiler.input().source_name().prefer_local(),
+                abstract_domain
+            );
+        }
 
         queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
             // collect all functions with attribute #[analyzer::run]
@@ -171,11 +251,20 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
                     .span
             });
 
+            // The summaries of the functions without a body can be given with
+            // #[analyzer::pure], meaning that the function does not modify its arguments.
+            let summaries = Summaries::with_contract_summaries(tcx, |def_id| {
+                get_attribute(tcx, def_id, "analyzer", "pure").map(|_| {
+                    let arg_count = tcx.fn_sig(def_id).inputs().skip_binder().len();
+                    MutationSummary::new(vec![false; arg_count])
+                })
+            });
+
             for &local_def_id in local_def_ids {
-                println!(
-                    "Result for function {}():",
-                    tcx.item_name(local_def_id.to_def_id())
-                );
+                let function_name = tcx.item_name(local_def_id.to_def_id()).to_string();
+                if format == OutputFormat::Text {
+                    println!("Result for function {}():", function_name);
+                }
 
                 // SAFETY: This is safe because we are feeding in the same `tcx`
                 // that was used to store the data.
@@ -191,25 +280,20 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
 
                 match abstract_domain {
                     "ReachingDefsAnalysis" => {
-                        let result = ReachingDefsAnalysis::new(tcx, local_def_id.to_def_id(), body)
-                            .run_fwd_analysis();
-                        match result {
-                            Ok(state) => {
-                                println!("{}", serde_json::to_string_pretty(&state).unwrap())
-                            }
-                            Err(e) => eprintln!("{}", e.to_pretty_str(body)),
-                        }
+                        let result = ReachingDefsAnalysis::new_with_summaries(
+                            tcx,
+                            local_def_id.to_def_id(),
+                            body,
+                            &summaries,
+                        )
+                        .run_fwd_analysis();
+                        output.print(function_name, result, body);
                     }
                     "DefinitelyInitializedAnalysis" => {
                         let result =
                             DefinitelyInitializedAnalysis::new(tcx, local_def_id.to_def_id(), body)
                                 .run_fwd_analysis();
-                        match result {
-                            Ok(state) => {
-                                println!("{}", serde_json::to_string_pretty(&state).unwrap())
-                            }
-                            Err(e) => eprintln!("{}", e.to_pretty_str(body)),
-                        }
+                        output.print(function_name, result, body);
                     }
                     "RelaxedDefinitelyInitializedAnalysis" => {
                         let result = DefinitelyInitializedAnalysis::new_relaxed(
@@ -218,21 +302,26 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
                             body,
                         )
                         .run_fwd_analysis();
-                        match result {
-                            Ok(state) => {
-                                println!("{}", serde_json::to_string_pretty(&state).unwrap())
-                            }
-                            Err(e) => eprintln!("{}", e.to_pretty_str(body)),
-                        }
+                        output.print(function_name, result, body);
+                    }
+                    "IntervalAnalysis" => {
+                        let result = IntervalAnalysis::new(tcx, local_def_id.to_def_id(), body)
+                            .run_fwd_analysis();
+                        output.print(function_name, result, body);
+                    }
+                    "LiveVariablesAnalysis" => {
+                        let result = LiveVariablesAnalysis::new(local_def_id.to_def_id(), body)
+                            .run_bwd_analysis();
+                        output.print(function_name, result, body);
                     }
                     "MaybeBorrowedAnalysis" => {
-                        let analyzer = MaybeBorrowedAnalysis::new(tcx, &body_with_facts);
-                        match analyzer.run_analysis() {
-                            Ok(state) => {
-                                println!("{}", serde_json::to_string_pretty(&state).unwrap())
-                            }
-                            Err(e) => eprintln!("{}", e.to_pretty_str(body)),
-                        }
+                        let borrow_summaries = Summaries::<BorrowSummary>::new(tcx);
+                        let analyzer = MaybeBorrowedAnalysis::new_with_summaries(
+                            tcx,
+                            &body_with_facts,
+                            &borrow_summaries,
+                        );
+                        output.print(function_name, analyzer.run_analysis(), body);
                     }
                     "DefinitelyAccessibleAnalysis" => {
                         let analyzer = DefinitelyAccessibleAnalysis::new(
@@ -240,27 +329,18 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
                             local_def_id.to_def_id(),
                             &body_with_facts,
                         );
-                        match analyzer.run_analysis() {
-                            Ok(state) => {
-                                println!("{}", serde_json::to_string_pretty(&state).unwrap());
-                            }
-                            Err(e) => eprintln!("{}", e.to_pretty_str(body)),
-                        }
+                        output.print(function_name, analyzer.run_analysis(), body);
                     }
                     "FramingAnalysis" => {
                         let analyzer =
                             FramingAnalysis::new(tcx, local_def_id.to_def_id(), &body_with_facts);
-                        match analyzer.run_analysis() {
-                            Ok(state) => {
-                                println!("{}", serde_json::to_string_pretty(&state).unwrap());
-                            }
-                            Err(e) => eprintln!("{}", e.to_pretty_str(body)),
-                        }
+                        output.print(function_name, analyzer.run_analysis(), body);
                     }
                     _ => panic!("Unknown domain argument: {}", abstract_domain),
                 }
             }
         });
+        output.finish();
 
         Compilation::Stop
     }
@@ -271,13 +351,16 @@ impl rustc_driver::Callbacks for OurCompilerCalls {
 /// Give arguments to the analyzer by prefixing them with '--analysis'
 /// A abstract domain has to be provided by using '--analysis=' (without spaces), e.g.:
 /// --analysis=ReachingDefsState or --analysis=DefinitelyInitializedAnalysis
+///
+/// The output format can be selected with '--format=text', '--format=json' or '--format=dot'.
+/// The default is 'text'.
 fn main() {
     env_logger::init();
     rustc_driver::init_rustc_env_logger();
     let mut compiler_args = Vec::new();
     let mut callback_args = Vec::new();
     for arg in std::env::args() {
-        if arg.starts_with("--analysis") {
+        if arg.starts_with("--analysis") || arg.starts_with("--format=") {
             callback_args.push(arg);
         } else {
             compiler_args.push(arg);
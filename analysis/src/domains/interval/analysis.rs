@@ -0,0 +1,106 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    abstract_interpretation::{AnalysisResult, FixpointEngine},
+    domains::IntervalState,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::{mir, ty::TyCtxt};
+use rustc_span::def_id::DefId;
+use std::rc::Rc;
+
+/// Computes the range of the values of the integer locals, e.g. to prove the
+/// absence of arithmetic overflows.
+pub struct IntervalAnalysis<'mir, 'tcx: 'mir> {
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    mir: &'mir mir::Body<'tcx>,
+    /// The locals whose address is taken, which the analysis does not track.
+    untracked: Rc<FxHashSet<mir::Local>>,
+}
+
+impl<'mir, 'tcx: 'mir> IntervalAnalysis<'mir, 'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>, def_id: DefId, mir: &'mir mir::Body<'tcx>) -> Self {
+        let mut untracked = FxHashSet::default();
+        for bb_data in mir.basic_blocks() {
+            for stmt in &bb_data.statements {
+                if let mir::StatementKind::Assign(box (_, rvalue)) = &stmt.kind {
+                    match rvalue {
+                        mir::Rvalue::Ref(_, _, place) | mir::Rvalue::AddressOf(_, place) => {
+                            untracked.insert(place.local);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        IntervalAnalysis {
+            tcx,
+            def_id,
+            mir,
+            untracked: Rc::new(untracked),
+        }
+    }
+}
+
+impl<'mir, 'tcx: 'mir> FixpointEngine<'mir, 'tcx> for IntervalAnalysis<'mir, 'tcx> {
+    type State = IntervalState<'mir, 'tcx>;
+
+    fn def_id(&self) -> DefId {
+        self.def_id
+    }
+
+    fn body(&self) -> &'mir mir::Body<'tcx> {
+        self.mir
+    }
+
+    /// The bottom element of the lattice is the state of unreachable program
+    /// points.
+    fn new_bottom(&self) -> Self::State {
+        IntervalState {
+            reachable: false,
+            intervals: FxHashMap::default(),
+            checked_results: FxHashMap::default(),
+            comparisons: FxHashMap::default(),
+            copies: FxHashMap::default(),
+            untracked: self.untracked.clone(),
+            mir: self.mir,
+            tcx: self.tcx,
+            param_env: self.tcx.param_env(self.def_id),
+        }
+    }
+
+    /// The arguments can have any value of their type.
+    fn new_initial(&self) -> Self::State {
+        IntervalState {
+            reachable: true,
+            ..self.new_bottom()
+        }
+    }
+
+    fn need_to_widen(counter: u32) -> bool {
+        // Give loops a couple of iterations to stabilize before losing
+        // precision.
+        counter > 3
+    }
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::State,
+        location: mir::Location,
+    ) -> AnalysisResult<()> {
+        state.apply_statement_effect(location)
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        state: &Self::State,
+        location: mir::Location,
+    ) -> AnalysisResult<Vec<(mir::BasicBlock, Self::State)>> {
+        state.apply_terminator_effect(location)
+    }
+}
@@ -0,0 +1,854 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{abstract_interpretation::AbstractState, AnalysisError};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::{
+    mir,
+    ty::{self, Ty, TyCtxt},
+};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::{collections::BTreeMap, convert::TryFrom, fmt, rc::Rc};
+
+/// A range of integers. A `None` bound means that the range is unbounded on
+/// that side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub lower: Option<i128>,
+    pub upper: Option<i128>,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.lower {
+            Some(lower) => write!(f, "[{}, ", lower)?,
+            None => write!(f, "[-inf, ")?,
+        }
+        match self.upper {
+            Some(upper) => write!(f, "{}]", upper),
+            None => write!(f, "+inf]"),
+        }
+    }
+}
+
+/// Is the lower bound `a` strictly smaller than the lower bound `b`?
+fn lower_lt(a: Option<i128>, b: Option<i128>) -> bool {
+    match (a, b) {
+        (None, Some(_)) => true,
+        (Some(a), Some(b)) => a < b,
+        _ => false,
+    }
+}
+
+/// Is the upper bound `a` strictly greater than the upper bound `b`?
+fn upper_gt(a: Option<i128>, b: Option<i128>) -> bool {
+    match (a, b) {
+        (None, Some(_)) => true,
+        (Some(a), Some(b)) => a > b,
+        _ => false,
+    }
+}
+
+/// Combine two bounds, giving up on the bound if either is missing or if the
+/// result does not fit in an `i128`.
+fn combine(a: Option<i128>, b: Option<i128>, op: fn(i128, i128) -> Option<i128>) -> Option<i128> {
+    a.zip(b).and_then(|(a, b)| op(a, b))
+}
+
+impl Interval {
+    pub fn new(lower: Option<i128>, upper: Option<i128>) -> Self {
+        Interval { lower, upper }
+    }
+
+    pub fn top() -> Self {
+        Interval::new(None, None)
+    }
+
+    pub fn singleton(value: i128) -> Self {
+        Interval::new(Some(value), Some(value))
+    }
+
+    /// The range of the values of an integer type, or `None` for other types.
+    pub fn of_type<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Self> {
+        let pointer_bits = tcx.data_layout.pointer_size.bits();
+        match ty.kind() {
+            ty::TyKind::Uint(uint_ty) => {
+                let bits = uint_ty.bit_width().unwrap_or(pointer_bits);
+                // `u128::MAX` does not fit in an `i128`.
+                let upper = if bits == 128 {
+                    None
+                } else {
+                    Some((1i128 << bits) - 1)
+                };
+                Some(Interval::new(Some(0), upper))
+            }
+            ty::TyKind::Int(int_ty) => {
+                let bits = int_ty.bit_width().unwrap_or(pointer_bits);
+                if bits == 128 {
+                    Some(Interval::new(Some(i128::MIN), Some(i128::MAX)))
+                } else {
+                    let bound = 1i128 << (bits - 1);
+                    Some(Interval::new(Some(-bound), Some(bound - 1)))
+                }
+            }
+            ty::TyKind::Bool => Some(Interval::new(Some(0), Some(1))),
+            ty::TyKind::Char => Some(Interval::new(Some(0), Some(char::MAX as i128))),
+            _ => None,
+        }
+    }
+
+    /// Does this interval contain all the values of `other`?
+    pub fn contains(&self, other: &Interval) -> bool {
+        !lower_lt(other.lower, self.lower) && !upper_gt(other.upper, self.upper)
+    }
+
+    /// The smallest interval that contains both intervals.
+    pub fn hull(&self, other: &Interval) -> Self {
+        Interval::new(
+            if lower_lt(other.lower, self.lower) {
+                other.lower
+            } else {
+                self.lower
+            },
+            if upper_gt(other.upper, self.upper) {
+                other.upper
+            } else {
+                self.upper
+            },
+        )
+    }
+
+    /// The intersection of both intervals, or `None` if it is empty.
+    pub fn intersection(&self, other: &Interval) -> Option<Self> {
+        let lower = if lower_lt(self.lower, other.lower) {
+            other.lower
+        } else {
+            self.lower
+        };
+        let upper = if upper_gt(self.upper, other.upper) {
+            other.upper
+        } else {
+            self.upper
+        };
+        match (lower, upper) {
+            (Some(lower), Some(upper)) if lower > upper => None,
+            _ => Some(Interval::new(lower, upper)),
+        }
+    }
+
+    /// Drop the bounds that grew since `previous`.
+    pub fn widen(&self, previous: &Interval) -> Self {
+        Interval::new(
+            if lower_lt(self.lower, previous.lower) {
+                None
+            } else {
+                self.lower
+            },
+            if upper_gt(self.upper, previous.upper) {
+                None
+            } else {
+                self.upper
+            },
+        )
+    }
+
+    fn is_non_negative(&self) -> bool {
+        matches!(self.lower, Some(lower) if lower >= 0)
+    }
+
+    fn is_positive(&self) -> bool {
+        matches!(self.lower, Some(lower) if lower > 0)
+    }
+
+    pub fn add(&self, other: &Interval) -> Self {
+        Interval::new(
+            combine(self.lower, other.lower, i128::checked_add),
+            combine(self.upper, other.upper, i128::checked_add),
+        )
+    }
+
+    pub fn sub(&self, other: &Interval) -> Self {
+        Interval::new(
+            combine(self.lower, other.upper, i128::checked_sub),
+            combine(self.upper, other.lower, i128::checked_sub),
+        )
+    }
+
+    pub fn mul(&self, other: &Interval) -> Self {
+        if let (Some(a), Some(b), Some(c), Some(d)) =
+            (self.lower, self.upper, other.lower, other.upper)
+        {
+            let corners = [
+                a.checked_mul(c),
+                a.checked_mul(d),
+                b.checked_mul(c),
+                b.checked_mul(d),
+            ];
+            if corners.iter().all(Option::is_some) {
+                let corners = corners.iter().map(|corner| corner.unwrap());
+                return Interval::new(corners.clone().min(), corners.max());
+            }
+        }
+        if self.is_non_negative() && other.is_non_negative() {
+            Interval::new(
+                combine(self.lower, other.lower, i128::checked_mul),
+                combine(self.upper, other.upper, i128::checked_mul),
+            )
+        } else {
+            Interval::top()
+        }
+    }
+
+    /// Division rounding towards zero, as in Rust.
+    pub fn div(&self, other: &Interval) -> Self {
+        if !other.is_positive() {
+            return Interval::top();
+        }
+        let smallest_divisor = other.lower.unwrap();
+        // With a positive divisor, the quotient grows with the dividend, and
+        // gets closer to zero as the divisor grows.
+        let lower = self.lower.map(|lower| {
+            if lower >= 0 {
+                other.upper.map_or(0, |divisor| lower / divisor)
+            } else {
+                lower / smallest_divisor
+            }
+        });
+        let upper = self.upper.map(|upper| {
+            if upper >= 0 {
+                upper / smallest_divisor
+            } else {
+                other.upper.map_or(0, |divisor| upper / divisor)
+            }
+        });
+        Interval::new(lower, upper)
+    }
+
+    pub fn rem(&self, other: &Interval) -> Self {
+        if !self.is_non_negative() || !other.is_positive() {
+            return Interval::top();
+        }
+        let largest_remainder = other.upper.map(|divisor| divisor - 1);
+        let upper = if upper_gt(self.upper, largest_remainder) {
+            largest_remainder
+        } else {
+            self.upper
+        };
+        Interval::new(Some(0), upper)
+    }
+
+    pub fn bit_and(&self, other: &Interval) -> Self {
+        if !self.is_non_negative() || !other.is_non_negative() {
+            return Interval::top();
+        }
+        let upper = if upper_gt(self.upper, other.upper) {
+            other.upper
+        } else {
+            self.upper
+        };
+        Interval::new(Some(0), upper)
+    }
+}
+
+/// An operand of a comparison.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Term {
+    Local(mir::Local),
+    Constant(i128),
+}
+
+/// A comparison whose result was assigned to a boolean local.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Comparison {
+    pub op: mir::BinOp,
+    pub left: Term,
+    pub right: Term,
+}
+
+impl Comparison {
+    fn mentions(&self, local: mir::Local) -> bool {
+        self.left == Term::Local(local) || self.right == Term::Local(local)
+    }
+
+    fn negate(&self) -> Self {
+        let op = match self.op {
+            mir::BinOp::Lt => mir::BinOp::Ge,
+            mir::BinOp::Le => mir::BinOp::Gt,
+            mir::BinOp::Gt => mir::BinOp::Le,
+            mir::BinOp::Ge => mir::BinOp::Lt,
+            mir::BinOp::Eq => mir::BinOp::Ne,
+            mir::BinOp::Ne => mir::BinOp::Eq,
+            op => unreachable!("{:?} is not a comparison", op),
+        };
+        Comparison { op, ..*self }
+    }
+}
+
+/// The range of the values of each integer local.
+///
+/// Locals without an interval can have any value of their type. Locals whose
+/// address is taken are never tracked, because they can be modified through
+/// a reference.
+#[derive(Clone)]
+pub struct IntervalState<'mir, 'tcx: 'mir> {
+    /// False for the bottom element, i.e. when the program point cannot be
+    /// reached.
+    pub(super) reachable: bool,
+    pub(super) intervals: FxHashMap<mir::Local, Interval>,
+    /// The range of the value of the result of a checked operation, stored in
+    /// the first field of the tuple assigned to the local, assuming that the
+    /// operation does not overflow.
+    pub(super) checked_results: FxHashMap<mir::Local, Interval>,
+    /// The comparison that was assigned to a boolean local, if its operands
+    /// did not change since.
+    pub(super) comparisons: FxHashMap<mir::Local, Comparison>,
+    /// The locals that were assigned a copy of another local, if neither
+    /// changed since. MIR compares copies of the locals of the program.
+    pub(super) copies: FxHashMap<mir::Local, mir::Local>,
+    pub(super) untracked: Rc<FxHashSet<mir::Local>>,
+    pub(super) mir: &'mir mir::Body<'tcx>, // just for context
+    pub(super) tcx: TyCtxt<'tcx>,
+    pub(super) param_env: ty::ParamEnv<'tcx>,
+}
+
+impl<'mir, 'tcx: 'mir> fmt::Debug for IntervalState<'mir, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // ignore mir
+        f.debug_struct("IntervalState")
+            .field("reachable", &self.reachable)
+            .field("intervals", &self.intervals)
+            .field("checked_results", &self.checked_results)
+            .field("comparisons", &self.comparisons)
+            .field("copies", &self.copies)
+            .finish()
+    }
+}
+
+impl<'mir, 'tcx: 'mir> PartialEq for IntervalState<'mir, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        // Ignore the context fields.
+        self.reachable == other.reachable
+            && self.intervals == other.intervals
+            && self.checked_results == other.checked_results
+            && self.comparisons == other.comparisons
+            && self.copies == other.copies
+    }
+}
+impl<'mir, 'tcx: 'mir> Eq for IntervalState<'mir, 'tcx> {}
+
+impl<'mir, 'tcx: 'mir> Serialize for IntervalState<'mir, 'tcx> {
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        if !self.reachable {
+            return serializer.serialize_str("unreachable");
+        }
+        let ordered_intervals: BTreeMap<_, _> = self.intervals.iter().collect();
+        let ordered_results: BTreeMap<_, _> = self.checked_results.iter().collect();
+        let mut map =
+            serializer.serialize_map(Some(ordered_intervals.len() + ordered_results.len()))?;
+        for (local, interval) in ordered_intervals {
+            map.serialize_entry(&format!("{:?}", local), &interval.to_string())?;
+        }
+        for (local, interval) in ordered_results {
+            map.serialize_entry(&format!("{:?}.0", local), &interval.to_string())?;
+        }
+        map.end()
+    }
+}
+
+impl<'mir, 'tcx: 'mir> IntervalState<'mir, 'tcx> {
+    /// The range of the values of `local` at this program point, or `None` if
+    /// it does not have an integer type.
+    pub fn local_interval(&self, local: mir::Local) -> Option<Interval> {
+        let type_interval = Interval::of_type(self.tcx, self.mir.local_decls[local].ty)?;
+        Some(
+            self.intervals
+                .get(&local)
+                .and_then(|interval| interval.intersection(&type_interval))
+                .unwrap_or(type_interval),
+        )
+    }
+
+    /// Checks if the arithmetic operation `op` on the two operands cannot
+    /// overflow at this program point.
+    pub fn cannot_overflow(
+        &self,
+        op: mir::BinOp,
+        left: &mir::Operand<'tcx>,
+        right: &mir::Operand<'tcx>,
+    ) -> bool {
+        if !self.reachable {
+            return true;
+        }
+        let ty = left.ty(self.mir, self.tcx);
+        let type_interval = if let Some(interval) = Interval::of_type(self.tcx, ty) {
+            interval
+        } else {
+            return false;
+        };
+        let left = self.operand_interval(left);
+        let right = self.operand_interval(right);
+        match op {
+            mir::BinOp::Shl | mir::BinOp::Shr => {
+                // Shifting overflows if the shift amount is at least the
+                // number of bits.
+                let bits = self
+                    .tcx
+                    .layout_of(self.param_env.and(ty))
+                    .map_or(0, |layout| layout.size.bits() as i128);
+                Interval::new(Some(0), Some(bits - 1)).contains(&right)
+            }
+            _ => match arithmetic(op, &left, &right) {
+                Some(result) => type_interval.contains(&result),
+                None => false,
+            },
+        }
+    }
+
+    fn operand_interval(&self, operand: &mir::Operand<'tcx>) -> Interval {
+        let ty = operand.ty(self.mir, self.tcx);
+        let type_interval = Interval::of_type(self.tcx, ty).unwrap_or_else(Interval::top);
+        // Widening drops bounds, which the type bounds again.
+        let interval = match operand {
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => {
+                if let Some(local) = place.as_local() {
+                    self.intervals.get(&local).copied().unwrap_or(type_interval)
+                } else if let [mir::ProjectionElem::Field(field, _)] = place.projection[..] {
+                    match self.checked_results.get(&place.local) {
+                        Some(interval) if field.index() == 0 => *interval,
+                        _ => type_interval,
+                    }
+                } else {
+                    type_interval
+                }
+            }
+            mir::Operand::Constant(constant) => constant
+                .literal
+                .try_eval_bits(self.tcx, self.param_env, ty)
+                .and_then(|bits| self.bits_to_value(bits, ty))
+                .map(Interval::singleton)
+                .unwrap_or(type_interval),
+        };
+        interval
+            .intersection(&type_interval)
+            .unwrap_or(type_interval)
+    }
+
+    /// Interpret the bits of a constant of an integer type.
+    fn bits_to_value(&self, bits: u128, ty: Ty<'tcx>) -> Option<i128> {
+        if ty.is_signed() {
+            let size = self.tcx.layout_of(self.param_env.and(ty)).ok()?.size;
+            Some(size.sign_extend(bits) as i128)
+        } else {
+            i128::try_from(bits).ok()
+        }
+    }
+
+    fn operand_term(&self, operand: &mir::Operand<'tcx>) -> Option<Term> {
+        match operand {
+            mir::Operand::Copy(place) | mir::Operand::Move(place) => {
+                let local = place.as_local()?;
+                // Comparing a copy restricts the original.
+                let local = self.copies.get(&local).copied().unwrap_or(local);
+                if self.untracked.contains(&local) {
+                    None
+                } else {
+                    Some(Term::Local(local))
+                }
+            }
+            mir::Operand::Constant(_) => {
+                let interval = self.operand_interval(operand);
+                match (interval.lower, interval.upper) {
+                    (Some(lower), Some(upper)) if lower == upper => Some(Term::Constant(lower)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn term_interval(&self, term: Term) -> Interval {
+        match term {
+            Term::Local(local) => self.local_interval(local).unwrap_or_else(Interval::top),
+            Term::Constant(value) => Interval::singleton(value),
+        }
+    }
+
+    /// Forget everything that is known about the value of `local`.
+    fn forget(&mut self, local: mir::Local) {
+        self.intervals.remove(&local);
+        self.checked_results.remove(&local);
+        self.comparisons
+            .retain(|&bool_local, comparison| bool_local != local && !comparison.mentions(local));
+        self.copies
+            .retain(|&copy, original| copy != local && *original != local);
+    }
+
+    /// Restrict the range of `term` to `interval`.
+    fn restrict(&mut self, term: Term, interval: Interval) {
+        if matches!(term, Term::Local(local) if self.untracked.contains(&local)) {
+            return;
+        }
+        match self.term_interval(term).intersection(&interval) {
+            Some(restricted) => {
+                if let Term::Local(local) = term {
+                    self.intervals.insert(local, restricted);
+                }
+            }
+            None => self.reachable = false,
+        }
+    }
+
+    /// Restrict the state to the executions in which the comparison assigned
+    /// to `local` evaluates to `value`.
+    fn assume(&mut self, local: mir::Local, value: bool) {
+        let comparison = match self.comparisons.get(&local) {
+            Some(comparison) if value => *comparison,
+            Some(comparison) => comparison.negate(),
+            None => return,
+        };
+        let left = self.term_interval(comparison.left);
+        let right = self.term_interval(comparison.right);
+        let decrement = |bound: Option<i128>| bound.and_then(|bound| bound.checked_sub(1));
+        let increment = |bound: Option<i128>| bound.and_then(|bound| bound.checked_add(1));
+        match comparison.op {
+            mir::BinOp::Lt => {
+                self.restrict(comparison.left, Interval::new(None, decrement(right.upper)));
+                self.restrict(comparison.right, Interval::new(increment(left.lower), None));
+            }
+            mir::BinOp::Le => {
+                self.restrict(comparison.left, Interval::new(None, right.upper));
+                self.restrict(comparison.right, Interval::new(left.lower, None));
+            }
+            mir::BinOp::Gt => {
+                self.restrict(comparison.left, Interval::new(increment(right.lower), None));
+                self.restrict(comparison.right, Interval::new(None, decrement(left.upper)));
+            }
+            mir::BinOp::Ge => {
+                self.restrict(comparison.left, Interval::new(right.lower, None));
+                self.restrict(comparison.right, Interval::new(None, left.upper));
+            }
+            mir::BinOp::Eq => {
+                self.restrict(comparison.left, right);
+                self.restrict(comparison.right, left);
+            }
+            mir::BinOp::Ne => {
+                // Only excluding a bound of an interval makes a difference.
+                for (term, other) in [(comparison.left, right), (comparison.right, left)] {
+                    if let (Some(lower), Some(upper)) = (other.lower, other.upper) {
+                        if lower == upper {
+                            let interval = self.term_interval(term);
+                            if interval.lower == Some(lower) {
+                                self.restrict(term, Interval::new(increment(Some(lower)), None));
+                            } else if interval.upper == Some(lower) {
+                                self.restrict(term, Interval::new(None, decrement(Some(lower))));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn rvalue_interval(&self, rvalue: &mir::Rvalue<'tcx>, ty: Ty<'tcx>) -> Option<Interval> {
+        let type_interval = Interval::of_type(self.tcx, ty)?;
+        let interval = match rvalue {
+            mir::Rvalue::Use(operand) => self.operand_interval(operand),
+            mir::Rvalue::BinaryOp(op, box (left, right)) => {
+                let left = self.operand_interval(left);
+                let right = self.operand_interval(right);
+                match arithmetic(*op, &left, &right) {
+                    // Without overflow checks the operation wraps around.
+                    Some(result) if type_interval.contains(&result) => result,
+                    _ => type_interval,
+                }
+            }
+            mir::Rvalue::Cast(mir::CastKind::Misc, operand, _) => {
+                let interval = self.operand_interval(operand);
+                if type_interval.contains(&interval) {
+                    interval
+                } else {
+                    type_interval
+                }
+            }
+            _ => type_interval,
+        };
+        interval.intersection(&type_interval)
+    }
+
+    pub(super) fn apply_statement_effect(
+        &mut self,
+        location: mir::Location,
+    ) -> Result<(), AnalysisError> {
+        if !self.reachable {
+            return Ok(());
+        }
+        let stmt = &self.mir[location.block].statements[location.statement_index];
+        match &stmt.kind {
+            mir::StatementKind::Assign(box (target, rvalue)) => self.assign(target, rvalue),
+            mir::StatementKind::StorageLive(local) | mir::StatementKind::StorageDead(local) => {
+                self.forget(*local)
+            }
+            mir::StatementKind::SetDiscriminant { box place, .. } => self.forget(place.local),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn assign(&mut self, target: &mir::Place<'tcx>, rvalue: &mir::Rvalue<'tcx>) {
+        let local = target.local;
+        if target.as_local().is_none() || self.untracked.contains(&local) {
+            self.forget(local);
+            return;
+        }
+        // The right-hand side is evaluated before forgetting the old value,
+        // because it might use it.
+        let ty = self.mir.local_decls[local].ty;
+        let interval = self.rvalue_interval(rvalue, ty);
+        let mut checked_result = None;
+        let mut comparison = None;
+        let mut original = None;
+        match rvalue {
+            mir::Rvalue::Use(mir::Operand::Copy(place) | mir::Operand::Move(place)) => {
+                original = place
+                    .as_local()
+                    .filter(|&local| !self.untracked.contains(&local));
+            }
+            mir::Rvalue::CheckedBinaryOp(op, box (left, right)) => {
+                let operand_type = left.ty(self.mir, self.tcx);
+                if let Some(type_interval) = Interval::of_type(self.tcx, operand_type) {
+                    checked_result = arithmetic(
+                        *op,
+                        &self.operand_interval(left),
+                        &self.operand_interval(right),
+                    )
+                    .and_then(|result| result.intersection(&type_interval));
+                }
+            }
+            mir::Rvalue::BinaryOp(op, box (left, right))
+                if matches!(
+                    op,
+                    mir::BinOp::Lt
+                        | mir::BinOp::Le
+                        | mir::BinOp::Gt
+                        | mir::BinOp::Ge
+                        | mir::BinOp::Eq
+                        | mir::BinOp::Ne
+                ) && Interval::of_type(self.tcx, left.ty(self.mir, self.tcx)).is_some() =>
+            {
+                if let (Some(left), Some(right)) =
+                    (self.operand_term(left), self.operand_term(right))
+                {
+                    comparison = Some(Comparison {
+                        op: *op,
+                        left,
+                        right,
+                    });
+                }
+            }
+            _ => {}
+        }
+        self.forget(local);
+        if let Some(interval) = interval {
+            self.intervals.insert(local, interval);
+        }
+        if let Some(result) = checked_result {
+            self.checked_results.insert(local, result);
+        }
+        if let Some(comparison) = comparison {
+            // `_1 = Lt(_1, _2)` says nothing about the new value of `_1`.
+            if !comparison.mentions(local) {
+                self.comparisons.insert(local, comparison);
+            }
+        }
+        if let Some(original) = original {
+            if original != local {
+                let original = self.copies.get(&original).copied().unwrap_or(original);
+                self.copies.insert(local, original);
+            }
+        }
+    }
+
+    pub(super) fn apply_terminator_effect(
+        &self,
+        location: mir::Location,
+    ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
+        let terminator = self.mir[location.block].terminator();
+        if !self.reachable {
+            return Ok(terminator
+                .successors()
+                .map(|&bb| (bb, self.clone()))
+                .collect());
+        }
+        let mut res_vec = Vec::new();
+        match &terminator.kind {
+            mir::TerminatorKind::SwitchInt {
+                discr,
+                switch_ty,
+                targets,
+            } => {
+                let discr_local = discr.place().and_then(|place| place.as_local());
+                for (value, bb) in targets.iter() {
+                    let mut state = self.clone();
+                    if let Some(local) = discr_local {
+                        if switch_ty.is_bool() {
+                            state.assume(local, value != 0);
+                        } else if let Some(value) = self.bits_to_value(value, *switch_ty) {
+                            state.restrict(Term::Local(local), Interval::singleton(value));
+                        }
+                    }
+                    res_vec.push((bb, state));
+                }
+                let mut otherwise_state = self.clone();
+                if let Some(local) = discr_local {
+                    if switch_ty.is_bool() && targets.iter().all(|(value, _)| value == 0) {
+                        otherwise_state.assume(local, true);
+                    }
+                }
+                res_vec.push((targets.otherwise(), otherwise_state));
+            }
+            mir::TerminatorKind::Assert {
+                cond,
+                expected,
+                target,
+                cleanup,
+                ..
+            } => {
+                let mut target_state = self.clone();
+                if let Some(local) = cond.place().and_then(|place| place.as_local()) {
+                    target_state.assume(local, *expected);
+                }
+                res_vec.push((*target, target_state));
+                if let Some(bb) = cleanup {
+                    res_vec.push((*bb, self.clone()));
+                }
+            }
+            mir::TerminatorKind::Call {
+                destination,
+                cleanup,
+                ..
+            } => {
+                if let Some((place, bb)) = destination {
+                    let mut dest_state = self.clone();
+                    dest_state.forget(place.local);
+                    res_vec.push((*bb, dest_state));
+                }
+                if let Some(bb) = cleanup {
+                    let mut cleanup_state = self.clone();
+                    if let Some((place, _)) = destination {
+                        cleanup_state.forget(place.local);
+                    }
+                    res_vec.push((*bb, cleanup_state));
+                }
+            }
+            mir::TerminatorKind::DropAndReplace { place, .. }
+            | mir::TerminatorKind::Yield {
+                resume_arg: place, ..
+            } => {
+                let mut state = self.clone();
+                state.forget(place.local);
+                for &bb in terminator.successors() {
+                    res_vec.push((bb, state.clone()));
+                }
+            }
+            mir::TerminatorKind::InlineAsm { .. } => {
+                return Err(AnalysisError::UnsupportedStatement(location));
+            }
+            _ => {
+                for &bb in terminator.successors() {
+                    // no assignment -> no change of state
+                    res_vec.push((bb, self.clone()));
+                }
+            }
+        }
+
+        Ok(res_vec)
+    }
+}
+
+/// The range of the exact result of an arithmetic operation, or `None` if the
+/// operation is not supported.
+fn arithmetic(op: mir::BinOp, left: &Interval, right: &Interval) -> Option<Interval> {
+    Some(match op {
+        mir::BinOp::Add => left.add(right),
+        mir::BinOp::Sub => left.sub(right),
+        mir::BinOp::Mul => left.mul(right),
+        mir::BinOp::Div => left.div(right),
+        mir::BinOp::Rem => left.rem(right),
+        mir::BinOp::BitAnd => left.bit_and(right),
+        _ => return None,
+    })
+}
+
+impl<'mir, 'tcx: 'mir> AbstractState for IntervalState<'mir, 'tcx> {
+    fn is_bottom(&self) -> bool {
+        !self.reachable
+    }
+
+    fn join(&mut self, other: &Self) {
+        if !other.reachable {
+            return;
+        }
+        if !self.reachable {
+            self.reachable = true;
+            self.intervals = other.intervals.clone();
+            self.checked_results = other.checked_results.clone();
+            self.comparisons = other.comparisons.clone();
+            self.copies = other.copies.clone();
+            return;
+        }
+        // A local without interval can have any value of its type.
+        self.intervals
+            .retain(|local, interval| match other.intervals.get(local) {
+                Some(other_interval) => {
+                    *interval = interval.hull(other_interval);
+                    true
+                }
+                None => false,
+            });
+        self.checked_results
+            .retain(|local, interval| match other.checked_results.get(local) {
+                Some(other_interval) => {
+                    *interval = interval.hull(other_interval);
+                    true
+                }
+                None => false,
+            });
+        self.comparisons
+            .retain(|local, comparison| other.comparisons.get(local) == Some(comparison));
+        self.copies
+            .retain(|copy, original| other.copies.get(copy) == Some(original));
+    }
+
+    fn widen(&mut self, previous: &Self) {
+        if !previous.reachable {
+            return;
+        }
+        // Locals that had no interval keep none, so that intervals can only
+        // grow a finite number of times. Widened bounds fall back to the
+        // bounds of the type.
+        let (tcx, mir) = (self.tcx, self.mir);
+        self.intervals
+            .retain(|&local, interval| match previous.intervals.get(&local) {
+                Some(previous_interval) => {
+                    let widened = interval.widen(previous_interval);
+                    *interval = Interval::of_type(tcx, mir.local_decls[local].ty)
+                        .and_then(|type_interval| widened.intersection(&type_interval))
+                        .unwrap_or(widened);
+                    true
+                }
+                None => false,
+            });
+        self.checked_results.retain(
+            |local, interval| match previous.checked_results.get(local) {
+                Some(previous_interval) => {
+                    *interval = interval.widen(previous_interval);
+                    true
+                }
+                None => false,
+            },
+        );
+    }
+}
@@ -0,0 +1,72 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{
+    abstract_interpretation::{AnalysisResult, BackwardFixpointEngine},
+    domains::LiveVariablesState,
+};
+use rustc_data_structures::fx::FxHashSet;
+use rustc_middle::mir;
+use rustc_span::def_id::DefId;
+
+/// Computes the locals whose value might be read later, by a backward analysis.
+pub struct LiveVariablesAnalysis<'mir, 'tcx: 'mir> {
+    def_id: DefId,
+    mir: &'mir mir::Body<'tcx>,
+}
+
+impl<'mir, 'tcx: 'mir> LiveVariablesAnalysis<'mir, 'tcx> {
+    pub fn new(def_id: DefId, mir: &'mir mir::Body<'tcx>) -> Self {
+        LiveVariablesAnalysis { def_id, mir }
+    }
+}
+
+impl<'mir, 'tcx: 'mir> BackwardFixpointEngine<'mir, 'tcx> for LiveVariablesAnalysis<'mir, 'tcx> {
+    type State = LiveVariablesState<'mir, 'tcx>;
+
+    fn def_id(&self) -> DefId {
+        self.def_id
+    }
+
+    fn body(&self) -> &'mir mir::Body<'tcx> {
+        self.mir
+    }
+
+    /// The bottom element of the lattice contains no live locals.
+    fn new_bottom(&self) -> Self::State {
+        LiveVariablesState {
+            live: FxHashSet::default(),
+            mir: self.mir,
+        }
+    }
+
+    /// No local is read after the function returns. The `return` terminator reads the return
+    /// place itself.
+    fn new_final(&self) -> Self::State {
+        self.new_bottom()
+    }
+
+    fn need_to_widen(_counter: u32) -> bool {
+        // the set of locals is finite => no lattice of infinite height
+        false
+    }
+
+    fn apply_statement_effect(
+        &self,
+        state: &mut Self::State,
+        location: mir::Location,
+    ) -> AnalysisResult<()> {
+        state.apply_statement_effect(location)
+    }
+
+    fn apply_terminator_effect(
+        &self,
+        state: &mut Self::State,
+        location: mir::Location,
+    ) -> AnalysisResult<()> {
+        state.apply_terminator_effect(location)
+    }
+}
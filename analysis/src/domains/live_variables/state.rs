@@ -0,0 +1,169 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{abstract_interpretation::AbstractState, AnalysisError};
+use rustc_data_structures::fx::FxHashSet;
+use rustc_middle::mir::{
+    self,
+    visit::{MutatingUseContext, NonUseContext, PlaceContext, Visitor},
+};
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+use std::{collections::BTreeSet, fmt};
+
+/// The set of locals whose current value might be read later.
+#[derive(Clone)]
+pub struct LiveVariablesState<'mir, 'tcx: 'mir> {
+    pub(super) live: FxHashSet<mir::Local>,
+    pub(super) mir: &'mir mir::Body<'tcx>, // just for context
+}
+
+impl<'mir, 'tcx: 'mir> fmt::Debug for LiveVariablesState<'mir, 'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // ignore mir
+        f.debug_struct("LiveVariablesState")
+            .field("live", &self.live)
+            .finish()
+    }
+}
+
+impl<'mir, 'tcx: 'mir> PartialEq for LiveVariablesState<'mir, 'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        // Ignore the `mir` field.
+        self.live == other.live
+    }
+}
+impl<'mir, 'tcx: 'mir> Eq for LiveVariablesState<'mir, 'tcx> {}
+
+impl<'mir, 'tcx: 'mir> Serialize for LiveVariablesState<'mir, 'tcx> {
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.live.len()))?;
+        let ordered_live: BTreeSet<_> = self.live.iter().collect();
+        for local in ordered_live {
+            seq.serialize_element(&format!("{:?}", local))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'mir, 'tcx: 'mir> LiveVariablesState<'mir, 'tcx> {
+    pub fn get_live_locals(&self) -> &FxHashSet<mir::Local> {
+        &self.live
+    }
+
+    pub fn is_live(&self, local: mir::Local) -> bool {
+        self.live.contains(&local)
+    }
+
+    /// Compute the live locals before a statement from those after it.
+    pub(super) fn apply_statement_effect(
+        &mut self,
+        location: mir::Location,
+    ) -> Result<(), AnalysisError> {
+        let stmt = &self.mir[location.block].statements[location.statement_index];
+        let mut def_use = DefUse::default();
+        def_use.visit_statement(stmt, location);
+        self.apply(def_use);
+        Ok(())
+    }
+
+    /// Compute the live locals before a terminator from those after it, i.e. the union of the
+    /// live locals of all its successors.
+    pub(super) fn apply_terminator_effect(
+        &mut self,
+        location: mir::Location,
+    ) -> Result<(), AnalysisError> {
+        let terminator = self.mir[location.block].terminator();
+        let mut def_use = DefUse::default();
+        def_use.visit_terminator(terminator, location);
+        if let mir::TerminatorKind::Call {
+            destination: Some((place, _)),
+            cleanup: Some(_),
+            ..
+        } = terminator.kind
+        {
+            // The destination is not assigned when unwinding, so its previous value might
+            // still be read in the cleanup block.
+            def_use.defs.remove(&place.local);
+        }
+        self.apply(def_use);
+        Ok(())
+    }
+
+    fn apply(&mut self, def_use: DefUse) {
+        for local in def_use.defs {
+            self.live.remove(&local);
+        }
+        self.live.extend(def_use.uses);
+    }
+}
+
+/// The locals that a statement or terminator overwrites and reads.
+#[derive(Default)]
+struct DefUse {
+    defs: FxHashSet<mir::Local>,
+    uses: FxHashSet<mir::Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for DefUse {
+    fn visit_local(&mut self, local: &mir::Local, context: PlaceContext, location: mir::Location) {
+        // Only reached for locals that are not part of a place, e.g. the return place of a
+        // `return` terminator.
+        self.visit_place(&mir::Place::from(*local), context, location);
+    }
+
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        _location: mir::Location,
+    ) {
+        for elem in place.projection {
+            if let mir::ProjectionElem::Index(local) = elem {
+                self.uses.insert(local);
+            }
+        }
+        if place.is_indirect() {
+            // The reference or pointer is read, whatever the access.
+            self.uses.insert(place.local);
+            return;
+        }
+        match context {
+            PlaceContext::NonUse(NonUseContext::StorageLive | NonUseContext::StorageDead) => {
+                self.defs.insert(place.local);
+            }
+            PlaceContext::NonUse(_) => {}
+            PlaceContext::MutatingUse(
+                MutatingUseContext::Store
+                | MutatingUseContext::AsmOutput
+                | MutatingUseContext::Call
+                | MutatingUseContext::Yield,
+            ) => {
+                // Assigning to a field keeps the value of the other fields.
+                if place.projection.is_empty() {
+                    self.defs.insert(place.local);
+                }
+            }
+            PlaceContext::MutatingUse(_) | PlaceContext::NonMutatingUse(_) => {
+                self.uses.insert(place.local);
+            }
+        }
+    }
+}
+
+impl<'mir, 'tcx: 'mir> AbstractState for LiveVariablesState<'mir, 'tcx> {
+    fn is_bottom(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    fn join(&mut self, other: &Self) {
+        self.live.extend(other.live.iter().copied());
+    }
+
+    fn widen(&mut self, _previous: &Self) {
+        // the set of locals is finite => widening should not be needed
+        unimplemented!()
+    }
+}
@@ -4,26 +4,82 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use super::summary::propagate_references;
 use crate::{
-    abstract_interpretation::AnalysisResult, domains::MaybeBorrowedState,
-    mir_utils::get_blocked_place, AnalysisError, PointwiseState,
+    abstract_interpretation::{AnalysisResult, BackwardFixpointEngine, Summaries},
+    domains::{BorrowSummary, LiveVariablesAnalysis, MaybeBorrowedState},
+    mir_utils::get_blocked_place,
+    AnalysisError, PointwiseState,
 };
 use log::{error, trace};
-use rustc_borrowck::{consumers::RichLocation, BodyWithBorrowckFacts};
-use rustc_data_structures::fx::FxHashMap;
+use polonius_engine::FactTypes;
+use rustc_borrowck::{
+    consumers::{RichLocation, RustcFacts},
+    BodyWithBorrowckFacts,
+};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_middle::{mir, ty::TyCtxt};
 
+type Loan = <RustcFacts as FactTypes>::Loan;
+
 pub struct MaybeBorrowedAnalysis<'mir, 'tcx: 'mir> {
     tcx: TyCtxt<'tcx>,
     body_with_facts: &'mir BodyWithBorrowckFacts<'tcx>,
+    summaries: Option<&'mir Summaries<'mir, 'tcx, BorrowSummary>>,
 }
 
 impl<'mir, 'tcx: 'mir> MaybeBorrowedAnalysis<'mir, 'tcx> {
+    /// The resulting analysis considers a place to be borrowed as long as the borrow checker
+    /// considers the loan to be live. The lifetimes in the signatures of the called functions
+    /// might keep a loan live after the last use of the references derived from it.
     pub fn new(tcx: TyCtxt<'tcx>, body_with_facts: &'mir BodyWithBorrowckFacts<'tcx>) -> Self {
         MaybeBorrowedAnalysis {
             tcx,
             body_with_facts,
+            summaries: None,
+        }
+    }
+
+    /// Like `new`, but a live loan is ignored when none of the locals that might contain a
+    /// reference derived from it is live. Which arguments of a call might be borrowed by its
+    /// result is looked up in `summaries`.
+    pub fn new_with_summaries(
+        tcx: TyCtxt<'tcx>,
+        body_with_facts: &'mir BodyWithBorrowckFacts<'tcx>,
+        summaries: &'mir Summaries<'mir, 'tcx, BorrowSummary>,
+    ) -> Self {
+        MaybeBorrowedAnalysis {
+            tcx,
+            body_with_facts,
+            summaries: Some(summaries),
+        }
+    }
+
+    /// Computes the locals that might contain a reference derived from each loan, or `None` if
+    /// this cannot be tracked.
+    fn loan_carriers(
+        &self,
+        summaries: &Summaries<'mir, 'tcx, BorrowSummary>,
+        loan_issued_at_location: &FxHashMap<Loan, mir::Location>,
+    ) -> Option<FxHashMap<Loan, Vec<mir::Local>>> {
+        let body = &self.body_with_facts.body;
+        let mut origins: FxHashMap<mir::Local, FxHashSet<Loan>> = FxHashMap::default();
+        for (&loan, loan_location) in loan_issued_at_location.iter() {
+            let loan_stmt = &body[loan_location.block].statements[loan_location.statement_index];
+            if let mir::StatementKind::Assign(box (lhs, _)) = &loan_stmt.kind {
+                origins.entry(lhs.local).or_default().insert(loan);
+            }
+        }
+        if !propagate_references(summaries, body.source.def_id(), body, &mut origins) {
+            return None;
         }
+        let mut carriers: FxHashMap<Loan, Vec<mir::Local>> = FxHashMap::default();
+        for (local, loans) in origins {
+            for loan in loans {
+                carriers.entry(loan).or_default().push(local);
+            }
+        }
+        Some(carriers)
     }
 
     pub fn run_analysis(
@@ -45,6 +101,15 @@ impl<'mir, 'tcx: 'mir> MaybeBorrowedAnalysis<'mir, 'tcx> {
                 (loan, location)
             })
             .collect();
+        let loan_carriers = self
+            .summaries
+            .and_then(|summaries| self.loan_carriers(summaries, &loan_issued_at_location));
+        let live_variables = match loan_carriers {
+            Some(_) => {
+                Some(LiveVariablesAnalysis::new(body.source.def_id(), body).run_bwd_analysis()?)
+            }
+            None => None,
+        };
         let mut analysis_state: PointwiseState<MaybeBorrowedState> = PointwiseState::default(body);
 
         trace!("There are {} loan_live_at output facts", loan_live_at.len());
@@ -54,6 +119,18 @@ impl<'mir, 'tcx: 'mir> MaybeBorrowedAnalysis<'mir, 'tcx> {
                 trace!("  Location {:?}:", rich_location);
                 let state = analysis_state.lookup_mut_before(location).unwrap();
                 for loan in loans {
+                    if let (Some(loan_carriers), Some(live_variables)) =
+                        (&loan_carriers, &live_variables)
+                    {
+                        let live_locals = live_variables.lookup_before(location).unwrap();
+                        let is_used_later = loan_carriers.get(loan).map_or(false, |carriers| {
+                            carriers.iter().any(|&local| live_locals.is_live(local))
+                        });
+                        if !is_used_later {
+                            trace!("    Loan {:?}: no reference derived from it is live", loan);
+                            continue;
+                        }
+                    }
                     let loan_location = loan_issued_at_location[loan];
                     let loan_stmt =
                         &body[loan_location.block].statements[loan_location.statement_index];
@@ -6,6 +6,8 @@
 
 mod analysis;
 mod state;
+mod summary;
 
 pub use self::analysis::*;
 pub use state::*;
+pub use summary::*;
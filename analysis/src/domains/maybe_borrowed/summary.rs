@@ -0,0 +1,321 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::abstract_interpretation::{Summaries, Summary};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::{
+    mir,
+    mir::visit::{PlaceContext, Visitor},
+    ty::{self, subst::GenericArgKind, Ty, TyCtxt},
+};
+use rustc_span::def_id::DefId;
+use std::hash::Hash;
+
+/// The arguments of a function from which the references in its result might be derived.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BorrowSummary {
+    /// `borrowed_args[i]` is true if the result of the function might contain a reference that
+    /// was derived from its `i`-th argument. Missing entries are considered to be true.
+    borrowed_args: Vec<bool>,
+}
+
+impl BorrowSummary {
+    pub fn new(borrowed_args: Vec<bool>) -> Self {
+        BorrowSummary { borrowed_args }
+    }
+
+    /// Returns true if the result of the function might contain a reference derived from its
+    /// argument with index `arg_index`.
+    pub fn may_return_borrow_of(&self, arg_index: usize) -> bool {
+        self.borrowed_args.get(arg_index).copied().unwrap_or(true)
+    }
+}
+
+impl Summary for BorrowSummary {
+    fn from_body<'tcx>(
+        summaries: &Summaries<'_, 'tcx, Self>,
+        def_id: DefId,
+        body: &mir::Body<'tcx>,
+    ) -> Self {
+        let mut origins: FxHashMap<mir::Local, FxHashSet<usize>> = FxHashMap::default();
+        for (arg_index, local) in body.args_iter().enumerate() {
+            if may_contain_references(body.local_decls[local].ty) {
+                origins.entry(local).or_default().insert(arg_index);
+            }
+        }
+        if origins.is_empty() {
+            return BorrowSummary {
+                borrowed_args: vec![false; body.arg_count],
+            };
+        }
+        if !propagate_references(summaries, def_id, body, &mut origins) {
+            return BorrowSummary {
+                borrowed_args: vec![true; body.arg_count],
+            };
+        }
+        let result_origins = origins.remove(&mir::RETURN_PLACE).unwrap_or_default();
+        BorrowSummary {
+            borrowed_args: (0..body.arg_count)
+                .map(|arg_index| result_origins.contains(&arg_index))
+                .collect(),
+        }
+    }
+
+    fn from_signature<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Self {
+        if tcx.is_closure(def_id) {
+            // The signature of a closure is stored in its type, not in `fn_sig`.
+            return BorrowSummary {
+                borrowed_args: vec![],
+            };
+        }
+        // The late-bound regions are kept, to compare the regions of the inputs and the output.
+        let sig = tcx.fn_sig(def_id).skip_binder();
+        let output = sig.output();
+        // Outlives bounds might relate regions that do not appear together.
+        let has_outlives_bounds = tcx
+            .predicates_of(def_id)
+            .instantiate_identity(tcx)
+            .predicates
+            .iter()
+            .any(|predicate| {
+                matches!(
+                    predicate.kind().skip_binder(),
+                    ty::PredicateKind::RegionOutlives(_) | ty::PredicateKind::TypeOutlives(_)
+                )
+            });
+        let related_regions = related_regions(sig.inputs_and_output.iter());
+        let output_regions = regions(output);
+        BorrowSummary {
+            borrowed_args: sig
+                .inputs()
+                .iter()
+                .map(|&input| {
+                    if !may_contain_references(output) || !may_contain_references(input) {
+                        false
+                    } else if has_outlives_bounds
+                        || has_opaque_types(output)
+                        || has_opaque_types(input)
+                    {
+                        true
+                    } else {
+                        regions(input).iter().any(|region| {
+                            related_regions
+                                .iter()
+                                .find(|group| group.contains(region))
+                                .map_or(output_regions.contains(region), |group| {
+                                    !group.is_disjoint(&output_regions)
+                                })
+                        })
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Propagates `origins`, which maps locals to the origins of the references that they might
+/// contain, through the assignments and the calls of `body`, until a fixed point is reached. The
+/// propagation is flow-insensitive. The results of the calls are handled with the summaries of
+/// the callees.
+///
+/// Returns false if the references cannot be tracked, e.g. because of inline assembly.
+pub(super) fn propagate_references<'tcx, K: Copy + Eq + Hash>(
+    summaries: &Summaries<'_, 'tcx, BorrowSummary>,
+    def_id: DefId,
+    body: &mir::Body<'tcx>,
+    origins: &mut FxHashMap<mir::Local, FxHashSet<K>>,
+) -> bool {
+    // The flows between locals, as (sources, target) pairs.
+    let mut flows: Vec<(Vec<mir::Local>, mir::Local)> = vec![];
+    for (block, block_data) in body.basic_blocks().iter_enumerated() {
+        for (statement_index, statement) in block_data.statements.iter().enumerate() {
+            if let mir::StatementKind::Assign(box (target, rvalue)) = &statement.kind {
+                let mut used_locals = UsedLocals::default();
+                let location = mir::Location {
+                    block,
+                    statement_index,
+                };
+                used_locals.visit_rvalue(rvalue, location);
+                if let mir::Rvalue::Ref(_, _, place) | mir::Rvalue::AddressOf(_, place) = rvalue {
+                    // The references stored through the new reference, e.g. by assigning to its
+                    // target, end up in the borrowed place.
+                    flows.push((vec![target.local], place.local));
+                }
+                flows.push((used_locals.0, target.local));
+            }
+        }
+        match &block_data.terminator().kind {
+            mir::TerminatorKind::Call {
+                func,
+                args,
+                destination,
+                ..
+            } => {
+                let callee_summary = summaries.get_call_summary(def_id, func);
+                let arg_locals: Vec<_> = args
+                    .iter()
+                    .map(|arg| arg.place().map(|place| place.local))
+                    .collect();
+                if let Some((target, _)) = destination {
+                    let sources = arg_locals
+                        .iter()
+                        .enumerate()
+                        .filter(|(arg_index, _)| {
+                            callee_summary
+                                .as_ref()
+                                .map_or(true, |summary| summary.may_return_borrow_of(*arg_index))
+                        })
+                        .filter_map(|(_, local)| *local)
+                        .collect();
+                    flows.push((sources, target.local));
+                }
+                // The callee might store the references of an argument in the memory reachable
+                // from another one.
+                for (arg, local) in args.iter().zip(&arg_locals) {
+                    if let Some(local) = local {
+                        if may_store_references(arg.ty(body, summaries.tcx())) {
+                            flows.push((arg_locals.iter().flatten().copied().collect(), *local));
+                        }
+                    }
+                }
+            }
+            mir::TerminatorKind::InlineAsm { .. } => return false,
+            _ => {}
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (sources, target) in &flows {
+            let new_origins: Vec<K> = sources
+                .iter()
+                .filter_map(|source| origins.get(source))
+                .flatten()
+                .copied()
+                .collect();
+            let target_origins = origins.entry(*target).or_default();
+            for origin in new_origins {
+                changed |= target_origins.insert(origin);
+            }
+        }
+    }
+    true
+}
+
+/// The regions that appear in `ty`, except `'static`.
+fn regions(ty: Ty<'_>) -> FxHashSet<ty::Region<'_>> {
+    ty.walk()
+        .filter_map(|arg| match arg.unpack() {
+            GenericArgKind::Lifetime(region) if !region.is_static() => Some(region),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Groups the regions of `types` that might outlive each other, because the types imply an
+/// outlives relation between them. For example, `&'a &'b u32` implies that `'b: 'a`.
+fn related_regions<'tcx>(
+    types: impl Iterator<Item = Ty<'tcx>>,
+) -> Vec<FxHashSet<ty::Region<'tcx>>> {
+    let mut groups: Vec<FxHashSet<ty::Region<'tcx>>> = vec![];
+    for ty in types {
+        for arg in ty.walk() {
+            let mut group = match arg.unpack() {
+                GenericArgKind::Type(ty) => match ty.kind() {
+                    ty::Ref(region, inner_ty, _) => {
+                        let mut group = regions(*inner_ty);
+                        if !region.is_static() {
+                            group.insert(*region);
+                        }
+                        group
+                    }
+                    // The where clauses of an ADT might relate its regions.
+                    ty::Adt(..) => regions(ty),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            // Merge the groups that share a region with the new one.
+            groups.retain(|other| {
+                if other.is_disjoint(&group) {
+                    true
+                } else {
+                    group.extend(other.iter().copied());
+                    false
+                }
+            });
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Returns true if `ty` contains types whose references are not visible in the type itself.
+fn has_opaque_types(ty: Ty<'_>) -> bool {
+    ty.walk().any(|arg| match arg.unpack() {
+        GenericArgKind::Type(ty) => matches!(
+            ty.kind(),
+            ty::RawPtr(_)
+                | ty::Param(_)
+                | ty::Projection(_)
+                | ty::Opaque(..)
+                | ty::Dynamic(..)
+                | ty::Foreign(_)
+                | ty::FnPtr(_)
+                | ty::Closure(..)
+                | ty::Generator(..)
+        ),
+        _ => false,
+    })
+}
+
+/// Returns true if a value of type `ty` might contain a reference to memory that it does not own.
+fn may_contain_references(ty: Ty<'_>) -> bool {
+    !regions(ty).is_empty() || has_opaque_types(ty)
+}
+
+/// Returns true if references could be stored in the memory reachable from a value of type
+/// `ty`, but not owned by it.
+fn may_store_references(ty: Ty<'_>) -> bool {
+    ty.walk().any(|arg| match arg.unpack() {
+        GenericArgKind::Type(ty) => match ty.kind() {
+            ty::Ref(_, inner_ty, mir::Mutability::Mut)
+            | ty::RawPtr(ty::TypeAndMut {
+                ty: inner_ty,
+                mutbl: mir::Mutability::Mut,
+            }) => may_contain_references(*inner_ty),
+            // The target of a shared reference can only be modified through interior
+            // mutability, which needs an ADT.
+            ty::Ref(_, inner_ty, mir::Mutability::Not) => {
+                may_contain_references(*inner_ty)
+                    && inner_ty
+                        .walk()
+                        .any(|arg| matches!(arg.unpack(), GenericArgKind::Type(ty) if ty.is_adt()))
+            }
+            // The fields of an ADT might contain mutable references with the lifetimes of its
+            // parameters.
+            ty::Adt(_, substs) => substs.regions().next().is_some(),
+            _ => has_opaque_types(ty),
+        },
+        _ => false,
+    })
+}
+
+/// Collects the locals used by the visited MIR.
+#[derive(Default)]
+struct UsedLocals(Vec<mir::Local>);
+
+impl<'tcx> Visitor<'tcx> for UsedLocals {
+    fn visit_local(
+        &mut self,
+        local: &mir::Local,
+        _context: PlaceContext,
+        _location: mir::Location,
+    ) {
+        self.0.push(*local);
+    }
+}
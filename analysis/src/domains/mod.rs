@@ -7,11 +7,15 @@
 mod definitely_accessible;
 mod definitely_initialized;
 mod framing;
+mod interval;
+mod live_variables;
 mod maybe_borrowed;
 mod reaching_definitions;
 
 pub use definitely_accessible::*;
 pub use definitely_initialized::*;
 pub use framing::*;
+pub use interval::*;
+pub use live_variables::*;
 pub use maybe_borrowed::*;
 pub use reaching_definitions::*;
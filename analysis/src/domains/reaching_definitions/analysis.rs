@@ -5,8 +5,8 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::{
-    abstract_interpretation::{AnalysisResult, FixpointEngine},
-    domains::{DefLocation, ReachingDefsState},
+    abstract_interpretation::{AnalysisResult, FixpointEngine, Summaries},
+    domains::{DefLocation, MutationSummary, ReachingDefsState},
 };
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_middle::{mir, ty::TyCtxt};
@@ -16,11 +16,35 @@ pub struct ReachingDefsAnalysis<'mir, 'tcx: 'mir> {
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
     mir: &'mir mir::Body<'tcx>,
+    summaries: Option<&'mir Summaries<'mir, 'tcx, MutationSummary>>,
 }
 
 impl<'mir, 'tcx: 'mir> ReachingDefsAnalysis<'mir, 'tcx> {
+    /// The resulting analysis ignores the modifications done by the calls through references.
     pub fn new(tcx: TyCtxt<'tcx>, def_id: DefId, mir: &'mir mir::Body<'tcx>) -> Self {
-        ReachingDefsAnalysis { tcx, def_id, mir }
+        ReachingDefsAnalysis {
+            tcx,
+            def_id,
+            mir,
+            summaries: None,
+        }
+    }
+
+    /// Like `new`, but the calls that might modify a local through a reference passed as
+    /// argument are considered as (non-killing) definitions of the local. Which arguments are
+    /// modified by a call is looked up in `summaries`.
+    pub fn new_with_summaries(
+        tcx: TyCtxt<'tcx>,
+        def_id: DefId,
+        mir: &'mir mir::Body<'tcx>,
+        summaries: &'mir Summaries<'mir, 'tcx, MutationSummary>,
+    ) -> Self {
+        ReachingDefsAnalysis {
+            tcx,
+            def_id,
+            mir,
+            summaries: Some(summaries),
+        }
     }
 }
 
@@ -79,6 +103,6 @@ impl<'mir, 'tcx: 'mir> FixpointEngine<'mir, 'tcx> for ReachingDefsAnalysis<'mir,
         state: &Self::State,
         location: mir::Location,
     ) -> AnalysisResult<Vec<(mir::BasicBlock, Self::State)>> {
-        state.apply_terminator_effect(location)
+        state.apply_terminator_effect(location, self.summaries)
     }
 }
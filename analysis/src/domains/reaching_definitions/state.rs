@@ -5,7 +5,10 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::{
-    abstract_interpretation::AbstractState, mir_utils::location_to_stmt_str, AnalysisError,
+    abstract_interpretation::{AbstractState, Summaries},
+    domains::MutationSummary,
+    mir_utils::location_to_stmt_str,
+    AnalysisError,
 };
 use rustc_data_structures::{
     fingerprint::Fingerprint,
@@ -118,20 +121,101 @@ impl<'mir, 'tcx: 'mir> ReachingDefsState<'mir, 'tcx> {
         Ok(())
     }
 
+    /// Returns the locals that might be modified by a call through a reference passed as argument.
+    /// Only the references that are created in the body, possibly through reborrows or moves,
+    /// can be tracked.
+    fn get_locals_mutated_by_call(
+        &self,
+        func: &mir::Operand<'tcx>,
+        args: &[mir::Operand<'tcx>],
+        summaries: &Summaries<'_, 'tcx, MutationSummary>,
+    ) -> FxHashSet<mir::Local> {
+        let caller = self.mir.source.def_id();
+        let callee_summary = summaries.get_call_summary(caller, func);
+        // The locals that contain the references passed to the call.
+        let mut reference_locals: Vec<mir::Local> = args
+            .iter()
+            .enumerate()
+            .filter(|(arg_index, _)| {
+                callee_summary
+                    .as_ref()
+                    .map_or(true, |summary| summary.may_mutate_arg(*arg_index))
+            })
+            .filter_map(|(_, arg)| arg.place())
+            .map(|place| place.local)
+            .collect();
+        let mut visited_locals = FxHashSet::default();
+        let mut mutated_locals = FxHashSet::default();
+        while let Some(local) = reference_locals.pop() {
+            if !visited_locals.insert(local) {
+                continue;
+            }
+            for def_location in self.reaching_defs.get(&local).into_iter().flatten() {
+                let def_location = match def_location {
+                    DefLocation::Assignment(def_location) => def_location,
+                    DefLocation::Parameter(_) => continue,
+                };
+                let def_block = &self.mir[def_location.block];
+                if def_location.statement_index == def_block.statements.len() {
+                    // The reference is the result of a call
+                    continue;
+                }
+                let def_stmt = &def_block.statements[def_location.statement_index];
+                if let mir::StatementKind::Assign(box (_, ref rvalue)) = def_stmt.kind {
+                    match rvalue {
+                        mir::Rvalue::Ref(_, _, borrowed_place)
+                        | mir::Rvalue::AddressOf(_, borrowed_place) => {
+                            if borrowed_place.is_indirect() {
+                                // A reborrow
+                                reference_locals.push(borrowed_place.local);
+                            } else {
+                                mutated_locals.insert(borrowed_place.local);
+                            }
+                        }
+                        mir::Rvalue::Use(operand) => {
+                            if let Some(place) = operand.place() {
+                                reference_locals.push(place.local);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        mutated_locals
+    }
+
     pub(super) fn apply_terminator_effect(
         &self,
         location: mir::Location,
+        summaries: Option<&Summaries<'_, 'tcx, MutationSummary>>,
     ) -> Result<Vec<(mir::BasicBlock, Self)>, AnalysisError> {
         let mut res_vec = Vec::new();
         let terminator = self.mir[location.block].terminator();
         match terminator.kind {
             mir::TerminatorKind::Call {
+                ref func,
+                ref args,
                 ref destination,
                 cleanup,
                 ..
             } => {
+                // The locals that the call might modify, but not necessarily: their previous
+                // definitions are kept.
+                let mutated_locals = summaries
+                    .map(|summaries| self.get_locals_mutated_by_call(func, args, summaries))
+                    .unwrap_or_default();
+                let mut call_state = self.clone();
+                for local in mutated_locals {
+                    call_state
+                        .reaching_defs
+                        .entry(local)
+                        .or_insert_with(FxHashSet::default)
+                        .insert(DefLocation::Assignment(location));
+                }
+
                 if let Some((place, bb)) = destination {
-                    let mut dest_state = self.clone();
+                    let mut dest_state = call_state.clone();
                     if let Some(local) = place.as_local() {
                         let location_set = dest_state
                             .reaching_defs
@@ -144,7 +228,7 @@ impl<'mir, 'tcx: 'mir> ReachingDefsState<'mir, 'tcx> {
                 }
 
                 if let Some(bb) = cleanup {
-                    let mut cleanup_state = self.clone();
+                    let mut cleanup_state = call_state;
                     // error state -> be conservative & add destination as possible reaching def
                     // while keeping all others
                     if let Some((place, _)) = destination {
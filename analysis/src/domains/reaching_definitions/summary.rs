@@ -0,0 +1,231 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::abstract_interpretation::{Summaries, Summary};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::{
+    mir,
+    mir::visit::{MutatingUseContext, PlaceContext, Visitor},
+    ty::{self, subst::GenericArgKind, Ty, TyCtxt},
+};
+use rustc_span::{def_id::DefId, DUMMY_SP};
+
+/// The arguments of a function through which it might modify the memory of its caller.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutationSummary {
+    /// `mutated_args[i]` is true if the function might modify the memory reachable from its
+    /// `i`-th argument. Missing entries are considered to be true.
+    mutated_args: Vec<bool>,
+}
+
+impl MutationSummary {
+    pub fn new(mutated_args: Vec<bool>) -> Self {
+        MutationSummary { mutated_args }
+    }
+
+    /// Returns true if the function might modify the memory reachable from its argument with
+    /// index `arg_index`.
+    pub fn may_mutate_arg(&self, arg_index: usize) -> bool {
+        self.mutated_args.get(arg_index).copied().unwrap_or(true)
+    }
+}
+
+impl Summary for MutationSummary {
+    fn from_body<'tcx>(
+        summaries: &Summaries<'_, 'tcx, Self>,
+        def_id: DefId,
+        body: &mir::Body<'tcx>,
+    ) -> Self {
+        let tcx = summaries.tcx();
+        let param_env = tcx.param_env(def_id);
+
+        // The arguments to whose memory the value of each local might point.
+        let mut aliases: FxHashMap<mir::Local, FxHashSet<usize>> = FxHashMap::default();
+        for (arg_index, local) in body.args_iter().enumerate() {
+            if may_mutate_through(tcx, param_env, body.local_decls[local].ty) {
+                aliases.entry(local).or_default().insert(arg_index);
+            }
+        }
+        let mut mutated_args = vec![false; body.arg_count];
+        if aliases.is_empty() {
+            return MutationSummary { mutated_args };
+        }
+
+        // Propagate the aliases through the assignments and the results of the calls, until a
+        // fixed point is reached. The propagation is flow-insensitive.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (block, block_data) in body.basic_blocks().iter_enumerated() {
+                for (statement_index, statement) in block_data.statements.iter().enumerate() {
+                    if let mir::StatementKind::Assign(box (target, rvalue)) = &statement.kind {
+                        let mut used_locals = UsedLocals::default();
+                        let location = mir::Location {
+                            block,
+                            statement_index,
+                        };
+                        used_locals.visit_rvalue(rvalue, location);
+                        changed |= propagate_aliases(&mut aliases, &used_locals.0, target.local);
+                    }
+                }
+                if let mir::TerminatorKind::Call {
+                    args,
+                    destination: Some((target, _)),
+                    ..
+                } = &block_data.terminator().kind
+                {
+                    let used_locals: Vec<_> = args
+                        .iter()
+                        .filter_map(|arg| arg.place())
+                        .map(|place| place.local)
+                        .collect();
+                    changed |= propagate_aliases(&mut aliases, &used_locals, target.local);
+                }
+            }
+        }
+
+        let mut mark_mutated = |local: mir::Local| {
+            for &arg_index in aliases.get(&local).into_iter().flatten() {
+                mutated_args[arg_index] = true;
+            }
+        };
+        let mut mutations = IndirectMutations::default();
+        mutations.visit_body(body);
+        for local in mutations.0 {
+            mark_mutated(local);
+        }
+        for block_data in body.basic_blocks() {
+            match &block_data.terminator().kind {
+                mir::TerminatorKind::Call { func, args, .. } => {
+                    let callee_summary = summaries.get_call_summary(def_id, func);
+                    for (arg_index, arg) in args.iter().enumerate() {
+                        let may_mutate = callee_summary
+                            .as_ref()
+                            .map_or(true, |summary| summary.may_mutate_arg(arg_index));
+                        if let Some(place) = arg.place().filter(|_| may_mutate) {
+                            mark_mutated(place.local);
+                        }
+                    }
+                }
+                mir::TerminatorKind::InlineAsm { .. } => {
+                    return MutationSummary {
+                        mutated_args: vec![true; body.arg_count],
+                    };
+                }
+                _ => {}
+            }
+        }
+        MutationSummary { mutated_args }
+    }
+
+    fn from_signature<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Self {
+        if tcx.is_closure(def_id) {
+            // The signature of a closure is stored in its type, not in `fn_sig`.
+            return MutationSummary {
+                mutated_args: vec![],
+            };
+        }
+        let param_env = tcx.param_env(def_id);
+        let sig = tcx.erase_late_bound_regions(tcx.fn_sig(def_id));
+        MutationSummary {
+            mutated_args: sig
+                .inputs()
+                .iter()
+                .map(|&ty| may_mutate_through(tcx, param_env, ty))
+                .collect(),
+        }
+    }
+}
+
+/// Returns true if a value of type `ty` might give access to memory that is not owned by the
+/// value, and that can be modified.
+fn may_mutate_through<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    ty: Ty<'tcx>,
+) -> bool {
+    ty.walk().any(|arg| match arg.unpack() {
+        GenericArgKind::Type(ty) => match ty.kind() {
+            ty::Ref(_, _, mir::Mutability::Mut) | ty::RawPtr(_) => true,
+            ty::Ref(_, inner_ty, mir::Mutability::Not) => !tcx
+                .erase_regions(*inner_ty)
+                .is_freeze(tcx.at(DUMMY_SP), param_env),
+            // The fields of an ADT might contain references with the lifetimes of its parameters.
+            ty::Adt(_, substs) => substs.regions().next().is_some(),
+            ty::Param(_)
+            | ty::Projection(_)
+            | ty::Opaque(..)
+            | ty::Dynamic(..)
+            | ty::Foreign(_)
+            | ty::FnPtr(_)
+            | ty::Closure(..)
+            | ty::Generator(..) => true,
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+/// Adds the aliases of `sources` to the aliases of `target`. Returns true if they changed.
+fn propagate_aliases(
+    aliases: &mut FxHashMap<mir::Local, FxHashSet<usize>>,
+    sources: &[mir::Local],
+    target: mir::Local,
+) -> bool {
+    let mut new_aliases: FxHashSet<usize> = sources
+        .iter()
+        .filter_map(|source| aliases.get(source))
+        .flatten()
+        .copied()
+        .collect();
+    let target_aliases = aliases.entry(target).or_default();
+    new_aliases.retain(|arg_index| !target_aliases.contains(arg_index));
+    let changed = !new_aliases.is_empty();
+    target_aliases.extend(new_aliases);
+    changed
+}
+
+/// Collects the locals used by the visited MIR.
+#[derive(Default)]
+struct UsedLocals(Vec<mir::Local>);
+
+impl<'tcx> Visitor<'tcx> for UsedLocals {
+    fn visit_local(
+        &mut self,
+        local: &mir::Local,
+        _context: PlaceContext,
+        _location: mir::Location,
+    ) {
+        self.0.push(*local);
+    }
+}
+
+/// Collects the locals through which the visited MIR modifies memory, e.g. `_1` in
+/// `(*_1).0 = const 0`. Borrows are not modifications, because they are handled as aliases.
+#[derive(Default)]
+struct IndirectMutations(FxHashSet<mir::Local>);
+
+impl<'tcx> Visitor<'tcx> for IndirectMutations {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext,
+        location: mir::Location,
+    ) {
+        if let PlaceContext::MutatingUse(mutating_use) = context {
+            let is_modification = !matches!(
+                mutating_use,
+                MutatingUseContext::Borrow
+                    | MutatingUseContext::AddressOf
+                    | MutatingUseContext::Retag
+            );
+            if is_modification && place.is_indirect() {
+                self.0.insert(place.local);
+            }
+        }
+        self.super_place(place, context, location);
+    }
+}
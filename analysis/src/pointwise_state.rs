@@ -8,6 +8,7 @@ use rustc_data_structures::fx::FxHashMap;
 use rustc_middle::mir;
 use serde::{ser::SerializeMap, Serialize, Serializer};
 use std::{collections::BTreeMap, fmt};
+use vir::common::graphviz::{escape_html, Graph, ToGraphviz};
 
 /// Records the state of the analysis at every program point and CFG edge of `mir`.
 pub struct PointwiseState<'mir, 'tcx: 'mir, S: Serialize> {
@@ -79,6 +80,65 @@ impl<'mir, 'tcx: 'mir, S: Serialize> Serialize for PointwiseState<'mir, 'tcx, S>
     }
 }
 
+impl<'mir, 'tcx: 'mir, S: Serialize> ToGraphviz for PointwiseState<'mir, 'tcx, S> {
+    /// Render the CFG of the MIR body, annotating each statement and terminator with the state
+    /// before and after it. The states are serialized as JSON.
+    fn to_graph(&self) -> Graph {
+        let state_to_text = |state: Option<&S>| match state {
+            Some(state) => escape_html(serde_json::to_string(state).unwrap()),
+            None => "n/a".to_string(),
+        };
+        let mut graph =
+            Graph::with_columns(&["location", "state before", "statement", "state after"]);
+        for (bb, bb_data) in self.mir.basic_blocks().iter_enumerated() {
+            let mut node_builder = graph.create_node(format!("{:?}", bb));
+            for (statement_index, statement) in bb_data.statements.iter().enumerate() {
+                let location = mir::Location {
+                    block: bb,
+                    statement_index,
+                };
+                let mut row_builder = node_builder.create_row();
+                row_builder.set("location", escape_html(format!("{:?}", location)));
+                row_builder.set("state before", state_to_text(self.lookup_before(location)));
+                row_builder.set("statement", escape_html(format!("{:?}", statement)));
+                row_builder.set("state after", state_to_text(self.lookup_after(location)));
+                row_builder.build();
+            }
+            let terminator = bb_data.terminator();
+            let location = self.mir.terminator_loc(bb);
+            let mut row_builder = node_builder.create_row();
+            row_builder.set("location", escape_html(format!("{:?}", location)));
+            row_builder.set("state before", state_to_text(self.lookup_before(location)));
+            row_builder.set("statement", escape_html(format!("{:?}", terminator.kind)));
+            row_builder.set("state after", "".to_string());
+            row_builder.build();
+            let states_after_block: BTreeMap<_, _> =
+                self.lookup_after_block(bb).into_iter().flatten().collect();
+            for (succ_bb, state) in states_after_block {
+                node_builder.add_row_single(format!(
+                    "state on edge to {:?}: {}",
+                    succ_bb,
+                    state_to_text(Some(state))
+                ));
+            }
+            node_builder.build();
+
+            let unwind_bb = terminator.unwind().copied().flatten();
+            for &succ_bb in terminator.successors() {
+                if Some(succ_bb) == unwind_bb {
+                    graph.add_unwind_edge(format!("{:?}", bb), format!("{:?}", succ_bb));
+                } else {
+                    graph.add_regular_edge(format!("{:?}", bb), format!("{:?}", succ_bb));
+                }
+            }
+            if terminator.successors().next().is_none() {
+                graph.add_exit_edge(format!("{:?}", bb), format!("{:?}", terminator.kind));
+            }
+        }
+        graph
+    }
+}
+
 impl<'mir, 'tcx: 'mir, S: Serialize> PointwiseState<'mir, 'tcx, S> {
     pub fn new(mir: &'mir mir::Body<'tcx>) -> Self {
         Self {
@@ -51,6 +51,16 @@ fn test_runner(_tests: &[&()]) {
         "tests/test_cases/relaxed_definitely_initialized",
         vec!["--analysis=RelaxedDefinitelyInitializedAnalysis".into()],
     );
+    run_tests(
+        "ui",
+        "tests/test_cases/interval",
+        vec!["--analysis=IntervalAnalysis".into()],
+    );
+    run_tests(
+        "ui",
+        "tests/test_cases/live_variables",
+        vec!["--analysis=LiveVariablesAnalysis".into()],
+    );
     run_tests(
         "ui",
         "tests/test_cases/maybe_borrowed",
@@ -66,4 +76,12 @@ fn test_runner(_tests: &[&()]) {
         "tests/test_cases/framing",
         vec!["--analysis=FramingAnalysis".into()],
     );
+    run_tests(
+        "ui",
+        "tests/test_cases/graphviz",
+        vec![
+            "--analysis=LiveVariablesAnalysis".into(),
+            "--format=dot".into(),
+        ],
+    );
 }
@@ -0,0 +1,12 @@
+fn main() {
+    let _ = max(1, 2);
+}
+
+#[analyzer::run]
+fn max(a: u32, b: u32) -> u32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
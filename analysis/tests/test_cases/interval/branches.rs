@@ -0,0 +1,16 @@
+fn main() {
+    println!("{}", clamp(-7));
+}
+
+#[analyzer::run]
+fn clamp(x: i32) -> i32 {
+    let res;
+    if x > 10 {
+        res = 10;
+    } else if x < -10 {
+        res = -10;
+    } else {
+        res = x;
+    }
+    res * 2
+}
@@ -0,0 +1,16 @@
+fn main() {
+    println!("{}", sum_up_to(10));
+}
+
+#[analyzer::run]
+fn sum_up_to(n: u32) -> u32 {
+    let mut i = 0;
+    let mut count = 0u8;
+    while i < n {
+        i += 1;
+        if count < 100 {
+            count += 1;
+        }
+    }
+    i
+}
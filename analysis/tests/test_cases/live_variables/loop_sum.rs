@@ -0,0 +1,15 @@
+fn main() {
+    println!("{}", sum(10));
+}
+
+#[analyzer::run]
+fn sum(n: u32) -> u32 {
+    let mut res = 0;
+    let mut i = 0;
+    let unused = n;
+    while i < n {
+        i += 1;
+        res += i;
+    }
+    res
+}
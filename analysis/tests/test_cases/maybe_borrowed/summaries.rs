@@ -0,0 +1,19 @@
+#[analyzer::run]
+fn first<'a>(x: &'a mut u32, _y: &'a mut u32) -> &'a mut u32 {
+    x
+}
+
+#[analyzer::run]
+fn borrow_through_call() {
+    let mut a = 1;
+    let mut b = 2;
+    let r = first(&mut a, &mut b);
+    // `b` is no longer borrowed, even if the signature of `first` keeps the loan live.
+    *r = 3;
+    noop();
+}
+
+#[analyzer::run]
+fn noop() {}
+
+fn main() {}
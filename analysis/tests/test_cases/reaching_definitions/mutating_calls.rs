@@ -0,0 +1,49 @@
+fn main() {
+    calls();
+    trait_calls(Counter1 { value: 0 });
+}
+
+fn read(x: &mut u32) -> u32 {
+    *x
+}
+
+fn write(x: &mut u32) {
+    *x = 1;
+}
+
+fn forward(x: &mut u32) {
+    write(x);
+}
+
+trait Counter {
+    #[analyzer::pure]
+    fn get(&mut self) -> u32;
+    fn incr(&mut self);
+}
+
+struct Counter1 {
+    value: u32,
+}
+
+impl Counter for Counter1 {
+    fn get(&mut self) -> u32 {
+        self.value
+    }
+    fn incr(&mut self) {
+        self.value += 1;
+    }
+}
+
+#[analyzer::run]
+fn calls() {
+    let mut a = 0;
+    read(&mut a);
+    write(&mut a);
+    forward(&mut a);
+}
+
+#[analyzer::run]
+fn trait_calls<T: Counter>(mut c: T) {
+    c.get();
+    c.incr();
+}
@@ -69,6 +69,8 @@ lazy_static! {
         settings.set_default("viper_backend", "Silicon").unwrap();
         settings.set_default("check_foldunfold_state", false).unwrap();
         settings.set_default("check_overflows", true).unwrap();
+        settings.set_default("interval_analysis", false).unwrap();
+        settings.set_default("loop_framing_invariants", false).unwrap();
         settings.set_default("check_panics", true).unwrap();
         settings.set_default("encode_unsigned_num_constraint", false).unwrap();
         settings.set_default("encode_bitvectors", false).unwrap();
@@ -83,20 +85,31 @@ lazy_static! {
         settings.set_default("dump_reborrowing_dag_in_debug_info", false).unwrap();
         settings.set_default("dump_borrowck_info", false).unwrap();
         settings.set_default("dump_viper_program", false).unwrap();
+        settings.set_default::<Option<String>>("emit_viper", None).unwrap();
+        settings.set_default::<Option<String>>("export_bundle", None).unwrap();
         settings.set_default("foldunfold_state_filter", "").unwrap();
         settings.set_default("contracts_lib", "").unwrap();
         settings.set_default::<Vec<String>>("extra_jvm_args", vec![]).unwrap();
         settings.set_default::<Vec<String>>("extra_verifier_args", vec![]).unwrap();
         settings.set_default("quiet", false).unwrap();
         settings.set_default("assert_timeout", 10_000).unwrap();
+        settings.set_default::<Option<i64>>("method_timeout", None).unwrap();
         settings.set_default("use_more_complete_exhale", true).unwrap();
         settings.set_default("skip_unsupported_features", false).unwrap();
         settings.set_default("allow_unreachable_unsupported_code", false).unwrap();
+        settings.set_default("report_support_status", false).unwrap();
+        settings.set_default("error_on_partially_supported", false).unwrap();
+        settings.set_default("support_status_only", false).unwrap();
+        settings.set_default::<Option<String>>("support_status_report_dir", None).unwrap();
         settings.set_default("no_verify", false).unwrap();
         settings.set_default("no_verify_deps", false).unwrap();
+        settings.set_default::<Option<String>>("verification_summary_dir", None).unwrap();
+        settings.set_default::<Option<String>>("contract_report_path", None).unwrap();
         settings.set_default("full_compilation", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
         settings.set_default("optimizations","all").unwrap();
+        settings.set_default::<Option<String>>("optimization_passes", None).unwrap();
+        settings.set_default("validate_optimizations", false).unwrap();
         settings.set_default("intern_names", true).unwrap();
         settings.set_default("enable_purification_optimization", false).unwrap();
         settings.set_default("enable_manual_axiomatization", false).unwrap();
@@ -107,10 +120,12 @@ lazy_static! {
         settings.set_default("print_desugared_specs", false).unwrap();
         settings.set_default("print_typeckd_specs", false).unwrap();
         settings.set_default("print_collected_verification_items", false).unwrap();
+        settings.set_default("print_permission_inference", false).unwrap();
         settings.set_default("hide_uuids", false).unwrap();
         settings.set_default("counterexample", false).unwrap();
         settings.set_default("print_hash", false).unwrap();
         settings.set_default("enable_cache", true).unwrap();
+        settings.set_default("split_programs_by_method", false).unwrap();
 
         // Flags for debugging Prusti that can change verification results.
         settings.set_default("disable_name_mangling", false).unwrap();
@@ -123,7 +138,11 @@ lazy_static! {
         let mut allowed_keys = get_keys(&settings);
         allowed_keys.insert("server_max_stored_verifiers".to_string());
         allowed_keys.insert("server_max_concurrency".to_string());
+        allowed_keys.insert("server_workers".to_string());
+        allowed_keys.insert("server_worker_memory_limit".to_string());
         allowed_keys.insert("server_address".to_string());
+        allowed_keys.insert("server_session".to_string());
+        allowed_keys.insert("server_priority".to_string());
         allowed_keys.insert("config".to_string());
         allowed_keys.insert("log".to_string());
         allowed_keys.insert("log_style".to_string());
@@ -274,6 +293,18 @@ pub fn dump_viper_program() -> bool {
     read_setting("dump_viper_program")
 }
 
+/// In which folder should we write the Viper programs as text, without
+/// going through the JVM?
+pub fn emit_viper() -> Option<PathBuf> {
+    read_setting::<Option<String>>("emit_viper").map(PathBuf::from)
+}
+
+/// To which file should we write a bundle of the verification requests, to
+/// replay them with `prusti-server --replay`?
+pub fn export_bundle() -> Option<PathBuf> {
+    read_setting::<Option<String>>("export_bundle").map(PathBuf::from)
+}
+
 /// The Viper backend that should be used for the verification
 pub fn foldunfold_state_filter() -> String {
     read_setting("foldunfold_state_filter")
@@ -294,6 +325,18 @@ pub fn check_overflows() -> bool {
     read_setting("check_overflows")
 }
 
+/// Skip the overflow checks of the arithmetic operations that an interval
+/// analysis proves to be safe.
+pub fn interval_analysis() -> bool {
+    read_setting("interval_analysis")
+}
+
+/// Add the permissions and values of the places that a loop does not modify
+/// to its invariant.
+pub fn loop_framing_invariants() -> bool {
+    read_setting("loop_framing_invariants")
+}
+
 /// Encode (and check) that unsigned integers are non-negative.
 pub fn encode_unsigned_num_constraint() -> bool {
     read_setting("encode_unsigned_num_constraint")
@@ -329,6 +372,21 @@ pub fn assert_timeout() -> u64 {
     read_setting("assert_timeout")
 }
 
+/// The wall-clock budget (in seconds) that Silicon gets for verifying a single
+/// method.
+///
+/// Methods that exceed it are reported with a "verification timed out" error
+/// while the remaining methods are still verified.
+///
+/// The budget is passed to Silicon as `--timeout`, which bounds the whole run
+/// on a Viper program. It is a per-method budget only because every procedure
+/// is encoded into a separate program.
+pub fn method_timeout() -> Option<u64> {
+    read_setting::<Option<i64>>("method_timeout").map(|value| {
+        value.try_into().expect("method_timeout must be a valid u64")
+    })
+}
+
 /// Use the Silicon configuration option `--enableMoreCompleteExhale`.
 pub fn use_more_complete_exhale() -> bool {
     read_setting("use_more_complete_exhale")
@@ -339,6 +397,12 @@ pub fn print_collected_verification_items() -> bool {
     read_setting("print_collected_verification_items")
 }
 
+/// Should Prusti print the permissions that the fold-unfold inference of the
+/// core proof encoding computed at each MIR location.
+pub fn print_permission_inference() -> bool {
+    read_setting("print_permission_inference")
+}
+
 /// Should Prusti print the AST with desugared specifications.
 pub fn print_desugared_specs() -> bool {
     read_setting("print_desugared_specs")
@@ -369,6 +433,12 @@ pub fn enable_cache() -> bool {
     read_setting("enable_cache")
 }
 
+/// Should Prusti verify every method in a separate program that contains only
+/// the definitions the method depends on.
+pub fn split_programs_by_method() -> bool {
+    read_setting("split_programs_by_method")
+}
+
 /**
 The maximum amount of instantiated viper verifiers the server will keep around for reuse.
 If not set, this defaults to `SERVER_MAX_CONCURRENT_VERIFICATION_OPERATIONS`.
@@ -388,6 +458,21 @@ pub fn server_max_concurrency() -> Option<usize> {
     read_optional_setting("server_max_concurrency")
 }
 
+/// The number of worker processes in which a standalone server verifies
+/// requests. Each worker has its own JVM, so that a crash only affects the
+/// request being verified.
+///
+/// If not set or set to 0, the server verifies in its own process.
+pub fn server_workers() -> Option<usize> {
+    read_optional_setting("server_workers")
+}
+
+/// The maximum resident memory (in MiB) of a worker process of the server.
+/// A worker that exceeds it is restarted.
+pub fn server_worker_memory_limit() -> Option<u64> {
+    read_optional_setting("server_worker_memory_limit")
+}
+
 /// When set, Prusti will connect to this server and use it for its verification backend (i.e. the things using the JVM/Viper).
 /// Set to "MOCK" to run the server off-thread, effectively mocking connecting to a server without having to start it up separately.
 /// e.g. "127.0.0.1:2468"
@@ -395,6 +480,21 @@ pub fn server_address() -> Option<String> {
     read_optional_setting("server_address")
 }
 
+/// The session that Prusti reports in its requests to the server. A request
+/// supersedes the unanswered requests of the same session for the same
+/// method, which the server cancels.
+pub fn server_session() -> Option<String> {
+    read_optional_setting("server_session")
+}
+
+/// The priority of the requests that Prusti sends to the server. Queued
+/// requests with a higher priority are verified first.
+///
+/// If not set, this defaults to 0.
+pub fn server_priority() -> i32 {
+    read_optional_setting("server_priority").unwrap_or(0)
+}
+
 /// If true, communication with the server will be encoded as json and not the default of bincode.
 pub fn json_communication() -> bool {
     read_setting("json_communication")
@@ -459,6 +559,26 @@ pub fn optimizations() -> Optimizations {
     opt
 }
 
+/// The optimization passes to run on the Viper programs, in order, as a
+/// comma-separated list of pass names. If not set, the passes enabled by
+/// `optimizations`, `encode_bitvectors` and `enable_purification_optimization`
+/// run in their default order.
+pub fn optimization_passes() -> Option<Vec<String>> {
+    read_setting::<Option<String>>("optimization_passes").map(|passes| {
+        passes
+            .split(',')
+            .map(|pass| pass.trim().to_string())
+            .filter(|pass| !pass.is_empty())
+            .collect()
+    })
+}
+
+/// When enabled, each optimization pass is checked not to break structural
+/// invariants of the methods, and the first offending pass is reported.
+pub fn validate_optimizations() -> bool {
+    read_setting("validate_optimizations")
+}
+
 /// Enable purification optimization for impure functions.
 pub fn enable_purification_optimization() -> bool {
     read_setting("enable_purification_optimization")
@@ -512,6 +632,29 @@ pub fn allow_unreachable_unsupported_code() -> bool {
     read_setting("allow_unreachable_unsupported_code")
 }
 
+/// Report, before the encoding, which functions use unsupported or partially
+/// supported features.
+pub fn report_support_status() -> bool {
+    read_setting("report_support_status")
+}
+
+/// Report partially supported features as errors instead of warnings.
+pub fn error_on_partially_supported() -> bool {
+    read_setting("error_on_partially_supported")
+}
+
+/// Only check the support status of the functions, without encoding or
+/// verifying them.
+pub fn support_status_only() -> bool {
+    read_setting("support_status_only")
+}
+
+/// Directory in which to write a JSON report of the support status of each
+/// function of the crate.
+pub fn support_status_report_dir() -> Option<PathBuf> {
+    read_setting::<Option<String>>("support_status_report_dir").map(PathBuf::from)
+}
+
 /// Skip the verification
 pub fn no_verify() -> bool {
     read_setting("no_verify")
@@ -522,6 +665,18 @@ pub fn no_verify_deps() -> bool {
     read_setting("no_verify_deps")
 }
 
+/// The directory in which Prusti writes, per crate and kind of crate, how many
+/// items verified, failed, use unsupported features or are trusted, as JSON.
+pub fn verification_summary_dir() -> Option<PathBuf> {
+    read_setting::<Option<String>>("verification_summary_dir").map(PathBuf::from)
+}
+
+/// The file to which Prusti writes the contracts of the functions of the
+/// crate, as JSON.
+pub fn contract_report_path() -> Option<PathBuf> {
+    read_setting::<Option<String>>("contract_report_path").map(PathBuf::from)
+}
+
 /// Continue the compilation and generate the binary after Prusti terminates
 pub fn full_compilation() -> bool {
     read_setting("full_compilation")
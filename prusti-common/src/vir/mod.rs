@@ -5,7 +5,9 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 pub use self::low_to_viper::{ToViperDecl, ToViper};
+pub use self::position_ids::PositionIdRenumbering;
 pub use self::to_graphviz::ToGraphViz;
+pub use self::to_viper_text::{ToViperText, ViperTextWriter};
 pub use vir::legacy::*;
 pub use vir::polymorphic as polymorphic_vir;
 pub use vir::high as vir_high;
@@ -14,6 +16,9 @@ pub mod fixes;
 pub mod optimizations;
 mod to_viper;
 mod low_to_viper;
+mod to_viper_text;
 mod to_graphviz;
 pub mod program;
+mod slicing;
+mod position_ids;
 pub mod macros;
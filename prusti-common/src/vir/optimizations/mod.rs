@@ -12,14 +12,19 @@ use crate::{
         polymorphic_vir::{CfgMethod, Program},
         ToGraphViz,
     },
+    Stopwatch,
 };
 
 pub mod bitvectors;
 pub mod folding;
 pub mod functions;
 pub mod methods;
+mod passes;
 pub mod predicates;
 pub mod purification;
+mod validation;
+
+pub use self::passes::{register_pass, OptimizationPass, PassRegistry};
 
 fn log_method(
     source_file_name: &str,
@@ -56,78 +61,43 @@ fn log_methods(
     }
 }
 
+/// Run the passes selected by `PassRegistry::pipeline` on `p`.
 pub fn optimize_program(p: Program, source_file_name: &str) -> Program {
     let mut program = p;
-    let optimizations = config::optimizations();
-    debug!("Enabled optimisations: {:?}", optimizations);
-
-    if config::encode_bitvectors() {
-        log_methods(
-            source_file_name,
-            &program.methods,
-            "encode_bitvectors",
-            false,
-        );
-        if bitvectors::uses_bit_operations(&program) {
-            bitvectors::replace_all_ints(&mut program);
-        }
-        log_methods(
-            source_file_name,
-            &program.methods,
-            "encode_bitvectors",
-            true,
-        );
+    let pipeline = PassRegistry::new().pipeline();
+    debug!(
+        "Optimization passes: {:?}",
+        pipeline.iter().map(|pass| pass.name()).collect::<Vec<_>>()
+    );
+    for pass in pipeline {
+        run_pass(pass.as_ref(), &mut program, source_file_name);
     }
+    program
+}
 
-    // can't borrow self because we need to move fields
-    if optimizations.inline_constant_functions {
-        log_methods(
-            source_file_name,
-            &program.methods,
-            "inline_constant_functions",
-            false,
-        );
-        let (new_methods, new_functions) =
-            functions::inline_constant_functions(program.methods, program.functions);
-        program.methods = new_methods;
-        program.functions = new_functions;
-        log_methods(
-            source_file_name,
-            &program.methods,
-            "inline_constant_functions",
-            true,
+fn run_pass(pass: &dyn OptimizationPass, program: &mut Program, source_file_name: &str) {
+    if !pass.precondition(program) {
+        debug!(
+            "Skipping optimization pass {} on {}: its precondition does not hold",
+            pass.name(),
+            program.name
         );
+        return;
     }
-    if optimizations.optimize_folding {
-        log_methods(source_file_name, &program.methods, "folding", false);
-        program.methods = program
-            .methods
-            .into_iter()
-            .map(folding::FoldingOptimizer::optimize)
-            .collect();
-        program.functions = program
-            .functions
-            .into_iter()
-            .map(folding::FoldingOptimizer::optimize)
-            .collect();
-        log_methods(source_file_name, &program.methods, "folding", true);
+    log_methods(source_file_name, &program.methods, pass.name(), false);
+    let snapshot = if config::validate_optimizations() {
+        Some(validation::ProgramSnapshot::new(program))
+    } else {
+        None
+    };
+    let stopwatch = Stopwatch::start_debug(
+        "prusti-viper",
+        format!("optimization pass {} on {}", pass.name(), program.name),
+    );
+    pass.run(program);
+    stopwatch.finish();
+    log_methods(source_file_name, &program.methods, pass.name(), true);
+    if let Some(snapshot) = snapshot {
+        snapshot.validate(program, pass.name());
     }
-    program.methods = program
-        .methods
-        .into_iter()
-        .map(|method| methods::optimize_method_encoding(method, source_file_name, &optimizations))
-        .collect();
-    if optimizations.delete_unused_predicates {
-        program.viper_predicates = predicates::delete_unused_predicates(
-            &program.methods,
-            &program.functions,
-            program.viper_predicates,
-        );
-    }
-
-    if config::enable_purification_optimization() {
-        program.methods = purification::purify_methods(program.methods, &program.viper_predicates);
-    }
-
-    program
 }
@@ -0,0 +1,226 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The passes run by `optimize_program` and the registry that decides which
+//! passes run and in which order.
+
+use super::{bitvectors, folding, functions, methods, predicates, purification};
+use crate::{
+    config::{self, Optimizations},
+    vir::polymorphic_vir::{CfgMethod, Program},
+};
+use std::{
+    mem,
+    sync::{Arc, RwLock},
+};
+
+/// A transformation of a whole VIR program.
+pub trait OptimizationPass: Send + Sync {
+    /// The name of the pass, used to select it with the `optimization_passes`
+    /// configuration flag and in the names of the debug dumps.
+    fn name(&self) -> &str;
+
+    /// Whether the pass runs when the `optimization_passes` configuration
+    /// flag is not set.
+    fn is_enabled_by_default(&self) -> bool;
+
+    /// Whether the pass can be applied to `program`. The pass is skipped
+    /// otherwise.
+    fn precondition(&self, _program: &Program) -> bool {
+        true
+    }
+
+    fn run(&self, program: &mut Program);
+}
+
+lazy_static! {
+    static ref REGISTERED_PASSES: RwLock<Vec<Arc<dyn OptimizationPass>>> = RwLock::new(vec![]);
+}
+
+/// Make `pass` available to `optimize_program`. Unless the
+/// `optimization_passes` configuration flag says otherwise, the registered
+/// passes that are enabled by default run after the built-in passes, in
+/// the order in which they were registered.
+pub fn register_pass(pass: Arc<dyn OptimizationPass>) {
+    REGISTERED_PASSES.write().unwrap().push(pass);
+}
+
+/// The passes that are available to `optimize_program`, in their default
+/// order.
+pub struct PassRegistry {
+    passes: Vec<Arc<dyn OptimizationPass>>,
+}
+
+impl PassRegistry {
+    /// The built-in passes followed by the passes added with `register_pass`.
+    pub fn new() -> Self {
+        let mut passes = builtin_passes();
+        passes.extend(REGISTERED_PASSES.read().unwrap().iter().cloned());
+        PassRegistry { passes }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn OptimizationPass>> {
+        self.passes.iter().find(|pass| pass.name() == name)
+    }
+
+    /// The passes to run, in order. If the `optimization_passes`
+    /// configuration flag is set, these are exactly the passes that it lists.
+    /// Otherwise, these are the passes that are enabled by default.
+    pub fn pipeline(&self) -> Vec<Arc<dyn OptimizationPass>> {
+        match config::optimization_passes() {
+            Some(names) => names
+                .iter()
+                .filter_map(|name| {
+                    let pass = self.get(name);
+                    if pass.is_none() {
+                        warn!("Ignoring unknown optimization pass '{}'", name);
+                    }
+                    pass.cloned()
+                })
+                .collect(),
+            None => self
+                .passes
+                .iter()
+                .filter(|pass| pass.is_enabled_by_default())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl Default for PassRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pass that transforms the whole program.
+struct ProgramPass {
+    name: &'static str,
+    is_enabled_by_default: fn() -> bool,
+    precondition: fn(&Program) -> bool,
+    run: fn(&mut Program),
+}
+
+impl OptimizationPass for ProgramPass {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn is_enabled_by_default(&self) -> bool {
+        (self.is_enabled_by_default)()
+    }
+
+    fn precondition(&self, program: &Program) -> bool {
+        (self.precondition)(program)
+    }
+
+    fn run(&self, program: &mut Program) {
+        (self.run)(program)
+    }
+}
+
+/// A pass that transforms each method of the program separately.
+struct MethodPass {
+    name: &'static str,
+    is_enabled_by_default: fn(&Optimizations) -> bool,
+    optimization: fn(CfgMethod) -> CfgMethod,
+}
+
+impl OptimizationPass for MethodPass {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn is_enabled_by_default(&self) -> bool {
+        (self.is_enabled_by_default)(&config::optimizations())
+    }
+
+    fn run(&self, program: &mut Program) {
+        program.methods = mem::take(&mut program.methods)
+            .into_iter()
+            .map(self.optimization)
+            .collect();
+    }
+}
+
+fn builtin_passes() -> Vec<Arc<dyn OptimizationPass>> {
+    macro_rules! method_pass {
+        ($optimization: ident) => {
+            Arc::new(MethodPass {
+                name: stringify!($optimization),
+                is_enabled_by_default: |optimizations| optimizations.$optimization,
+                optimization: methods::$optimization,
+            })
+        };
+    }
+    vec![
+        Arc::new(ProgramPass {
+            name: "encode_bitvectors",
+            is_enabled_by_default: config::encode_bitvectors,
+            precondition: bitvectors::uses_bit_operations,
+            run: bitvectors::replace_all_ints,
+        }),
+        Arc::new(ProgramPass {
+            name: "inline_constant_functions",
+            is_enabled_by_default: || config::optimizations().inline_constant_functions,
+            precondition: |_| true,
+            run: |program| {
+                let (new_methods, new_functions) = functions::inline_constant_functions(
+                    mem::take(&mut program.methods),
+                    mem::take(&mut program.functions),
+                );
+                program.methods = new_methods;
+                program.functions = new_functions;
+            },
+        }),
+        Arc::new(ProgramPass {
+            name: "optimize_folding",
+            is_enabled_by_default: || config::optimizations().optimize_folding,
+            precondition: |_| true,
+            run: |program| {
+                program.methods = mem::take(&mut program.methods)
+                    .into_iter()
+                    .map(folding::FoldingOptimizer::optimize)
+                    .collect();
+                program.functions = mem::take(&mut program.functions)
+                    .into_iter()
+                    .map(folding::FoldingOptimizer::optimize)
+                    .collect();
+            },
+        }),
+        method_pass!(purify_vars),
+        method_pass!(fix_unfoldings),
+        method_pass!(fix_quantifiers),
+        method_pass!(remove_empty_if),
+        method_pass!(remove_unused_vars),
+        method_pass!(remove_trivial_assertions),
+        method_pass!(clean_cfg),
+        Arc::new(ProgramPass {
+            name: "delete_unused_predicates",
+            is_enabled_by_default: || config::optimizations().delete_unused_predicates,
+            precondition: |_| true,
+            run: |program| {
+                program.viper_predicates = predicates::delete_unused_predicates(
+                    &program.methods,
+                    &program.functions,
+                    mem::take(&mut program.viper_predicates),
+                );
+            },
+        }),
+        Arc::new(ProgramPass {
+            name: "purify_methods",
+            is_enabled_by_default: config::enable_purification_optimization,
+            precondition: |_| true,
+            run: |program| {
+                program.methods = purification::purify_methods(
+                    mem::take(&mut program.methods),
+                    &program.viper_predicates,
+                );
+            },
+        }),
+    ]
+}
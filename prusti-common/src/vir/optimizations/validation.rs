@@ -0,0 +1,538 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Translation validation of the optimization passes: checks that a pass did
+//! not break structural invariants of the methods that it transformed.
+//!
+//! The checked invariants are:
+//!
+//! 1. every variable that is used by a method is declared by it;
+//! 2. every fold, unfold and unfolding has a single place of reference type as
+//!    argument, and refers to a predicate that was not deleted;
+//! 3. no assertion or exhale of a reachable block is dropped, unless it was
+//!    redundant: `true`, or only permissions to variables that were removed.
+//!
+//! Only violations introduced by a pass are reported, so that the bugs of the
+//! encoding are not blamed on the optimizations.
+
+use crate::vir::polymorphic_vir::{ast, cfg, CfgMethod, Program};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+/// The number of unchanged lines printed around the changed lines of a diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// The methods and predicates of a program before running a pass on it.
+pub struct ProgramSnapshot {
+    methods: Vec<CfgMethod>,
+    predicates: HashSet<ast::Type>,
+    violations: HashMap<String, HashSet<String>>,
+}
+
+impl ProgramSnapshot {
+    pub fn new(program: &Program) -> Self {
+        let predicates = declared_predicates(program);
+        let violations = program
+            .methods
+            .iter()
+            .map(|method| {
+                let violations = check_method(method, &predicates, &predicates)
+                    .into_iter()
+                    .collect();
+                (method.name(), violations)
+            })
+            .collect();
+        ProgramSnapshot {
+            methods: program.methods.clone(),
+            predicates,
+            violations,
+        }
+    }
+
+    /// Checks the invariants on the methods of `program`, which is the result
+    /// of running the pass `pass_name` on the snapshotted program. Panics with
+    /// a description of the first violation introduced by the pass and a diff
+    /// of the offending method.
+    pub fn validate(&self, program: &Program, pass_name: &str) {
+        let predicates_after = declared_predicates(program);
+        for method_after in &program.methods {
+            let method_name = method_after.name();
+            let method_before = self
+                .methods
+                .iter()
+                .find(|method| method.name() == method_name);
+            let mut violations = check_method(method_after, &self.predicates, &predicates_after);
+            if let Some(method_before) = method_before {
+                violations.extend(check_dropped_assertions(method_before, method_after));
+            }
+            let old_violations = self.violations.get(&method_name);
+            let new_violation = violations.into_iter().find(|violation| {
+                old_violations.map_or(true, |old_violations| !old_violations.contains(violation))
+            });
+            if let Some(violation) = new_violation {
+                let before = method_before
+                    .map(|method| method.to_string())
+                    .unwrap_or_default();
+                panic!(
+                    "Optimization pass {} broke method {}: {}\n{}",
+                    pass_name,
+                    method_name,
+                    violation,
+                    diff(&before, &method_after.to_string())
+                );
+            }
+        }
+    }
+}
+
+fn declared_predicates(program: &Program) -> HashSet<ast::Type> {
+    program
+        .viper_predicates
+        .iter()
+        .map(|predicate| predicate.get_type().clone())
+        .collect()
+}
+
+/// Checks the invariants 1 and 2 on `method`. A predicate counts as deleted
+/// if it is in `predicates_before` but not in `predicates_after`.
+fn check_method(
+    method: &CfgMethod,
+    predicates_before: &HashSet<ast::Type>,
+    predicates_after: &HashSet<ast::Type>,
+) -> Vec<String> {
+    let mut checker = InvariantChecker {
+        declared_vars: method
+            .get_all_vars()
+            .into_iter()
+            .map(|var| var.name)
+            .collect(),
+        bound_vars: Vec::new(),
+        predicates_before,
+        predicates_after,
+        violations: Vec::new(),
+    };
+    method.walk_statements(|stmt| {
+        ast::StmtWalker::walk(&mut checker, stmt);
+    });
+    method.walk_successors(|successor| {
+        if let cfg::Successor::GotoSwitch(conditional_targets, _) = successor {
+            for (guard, _) in conditional_targets {
+                ast::ExprWalker::walk(&mut checker, guard);
+            }
+        }
+    });
+    checker.violations
+}
+
+struct InvariantChecker<'a> {
+    declared_vars: HashSet<String>,
+    /// The variables of the enclosing quantifiers, let expressions and
+    /// magic wand packages.
+    bound_vars: Vec<String>,
+    predicates_before: &'a HashSet<ast::Type>,
+    predicates_after: &'a HashSet<ast::Type>,
+    violations: Vec<String>,
+}
+
+impl<'a> InvariantChecker<'a> {
+    fn check_var(&mut self, var: &ast::LocalVar) {
+        if !self.declared_vars.contains(&var.name) && !self.bound_vars.contains(&var.name) {
+            self.violations
+                .push(format!("variable {} is used but not declared", var.name));
+        }
+    }
+
+    fn check_predicate_use(&mut self, kind: &str, predicate: &ast::Type, arguments: &[ast::Expr]) {
+        let is_well_formed = match arguments {
+            [argument] => argument.is_place() && argument.get_type().is_typed_ref_or_type_var(),
+            _ => false,
+        };
+        if !is_well_formed {
+            self.violations.push(format!(
+                "{} of {} has malformed arguments ({})",
+                kind,
+                predicate.name(),
+                arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if self.predicates_before.contains(predicate) && !self.predicates_after.contains(predicate)
+        {
+            self.violations.push(format!(
+                "{} of {} refers to a deleted predicate",
+                kind,
+                predicate.name()
+            ));
+        }
+    }
+
+    fn walk_with_bound_vars(&mut self, vars: &[ast::LocalVar], walk: impl FnOnce(&mut Self)) {
+        let len = self.bound_vars.len();
+        self.bound_vars
+            .extend(vars.iter().map(|var| var.name.clone()));
+        walk(self);
+        self.bound_vars.truncate(len);
+    }
+}
+
+impl<'a> ast::ExprWalker for InvariantChecker<'a> {
+    fn walk_local(&mut self, ast::Local { variable, .. }: &ast::Local) {
+        self.check_var(variable);
+    }
+
+    fn walk_unfolding(
+        &mut self,
+        ast::Unfolding {
+            predicate,
+            arguments,
+            base,
+            ..
+        }: &ast::Unfolding,
+    ) {
+        self.check_predicate_use("unfolding", predicate, arguments);
+        for argument in arguments {
+            ast::ExprWalker::walk(self, argument);
+        }
+        ast::ExprWalker::walk(self, base);
+    }
+
+    fn walk_forall(
+        &mut self,
+        ast::ForAll {
+            variables,
+            triggers,
+            body,
+            ..
+        }: &ast::ForAll,
+    ) {
+        self.walk_with_bound_vars(variables, |this| {
+            for trigger in triggers {
+                for expr in trigger.elements() {
+                    ast::ExprWalker::walk(this, expr);
+                }
+            }
+            ast::ExprWalker::walk(this, body);
+        });
+    }
+
+    fn walk_exists(
+        &mut self,
+        ast::Exists {
+            variables,
+            triggers,
+            body,
+            ..
+        }: &ast::Exists,
+    ) {
+        self.walk_with_bound_vars(variables, |this| {
+            for trigger in triggers {
+                for expr in trigger.elements() {
+                    ast::ExprWalker::walk(this, expr);
+                }
+            }
+            ast::ExprWalker::walk(this, body);
+        });
+    }
+
+    fn walk_let_expr(
+        &mut self,
+        ast::LetExpr {
+            variable,
+            def,
+            body,
+            ..
+        }: &ast::LetExpr,
+    ) {
+        ast::ExprWalker::walk(self, def);
+        self.walk_with_bound_vars(std::slice::from_ref(variable), |this| {
+            ast::ExprWalker::walk(this, body);
+        });
+    }
+
+    // The formal arguments of functions are not uses of variables.
+    fn walk_func_app(&mut self, ast::FuncApp { arguments, .. }: &ast::FuncApp) {
+        for argument in arguments {
+            ast::ExprWalker::walk(self, argument);
+        }
+    }
+
+    fn walk_domain_func_app(&mut self, ast::DomainFuncApp { arguments, .. }: &ast::DomainFuncApp) {
+        for argument in arguments {
+            ast::ExprWalker::walk(self, argument);
+        }
+    }
+}
+
+impl<'a> ast::StmtWalker for InvariantChecker<'a> {
+    fn walk_expr(&mut self, expr: &ast::Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+
+    fn walk_local_var(&mut self, local_var: &ast::LocalVar) {
+        self.check_var(local_var);
+    }
+
+    fn walk_fold(
+        &mut self,
+        ast::Fold {
+            predicate,
+            arguments,
+            ..
+        }: &ast::Fold,
+    ) {
+        self.check_predicate_use("fold", predicate, arguments);
+        for argument in arguments {
+            self.walk_expr(argument);
+        }
+    }
+
+    fn walk_unfold(
+        &mut self,
+        ast::Unfold {
+            predicate,
+            arguments,
+            ..
+        }: &ast::Unfold,
+    ) {
+        self.check_predicate_use("unfold", predicate, arguments);
+        for argument in arguments {
+            self.walk_expr(argument);
+        }
+    }
+
+    fn walk_package_magic_wand(
+        &mut self,
+        ast::PackageMagicWand {
+            magic_wand,
+            package_stmts,
+            variables,
+            ..
+        }: &ast::PackageMagicWand,
+    ) {
+        self.walk_with_bound_vars(variables, |this| {
+            this.walk_expr(magic_wand);
+            for stmt in package_stmts {
+                ast::StmtWalker::walk(this, stmt);
+            }
+        });
+    }
+}
+
+/// Checks the invariant 3. Assertions are matched by their position, which the
+/// passes preserve when they rewrite an assertion.
+fn check_dropped_assertions(before: &CfgMethod, after: &CfgMethod) -> Vec<String> {
+    let declared_vars_after: HashSet<_> = after
+        .get_all_vars()
+        .into_iter()
+        .map(|var| var.name)
+        .collect();
+    let mut required: HashMap<ast::Position, Vec<&ast::Expr>> = HashMap::new();
+    for stmt in reachable_stmts(before) {
+        collect_assertions(stmt, &mut |expr, position| {
+            if !is_redundant(expr, &declared_vars_after) {
+                required.entry(position).or_default().push(expr);
+            }
+        });
+    }
+    let mut remaining: HashMap<ast::Position, usize> = HashMap::new();
+    after.walk_statements(|stmt| {
+        collect_assertions(stmt, &mut |_, position| {
+            *remaining.entry(position).or_default() += 1;
+        });
+    });
+    let mut violations: Vec<_> = required
+        .into_iter()
+        .filter(|(position, exprs)| remaining.get(position).copied().unwrap_or(0) < exprs.len())
+        .map(|(position, exprs)| {
+            format!(
+                "assertion {} at {:?} was dropped although it is not redundant",
+                exprs[0], position
+            )
+        })
+        .collect();
+    violations.sort();
+    violations
+}
+
+/// The statements of the blocks that are reachable from the start block.
+fn reachable_stmts(method: &CfgMethod) -> Vec<&ast::Stmt> {
+    let mut stmts = Vec::new();
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![0];
+    while let Some(index) = to_visit.pop() {
+        if index >= method.basic_blocks.len() || !visited.insert(index) {
+            continue;
+        }
+        let block = &method.basic_blocks[index];
+        stmts.extend(&block.stmts);
+        to_visit.extend(
+            block
+                .successor
+                .get_following()
+                .iter()
+                .map(|target| target.index()),
+        );
+    }
+    stmts
+}
+
+/// Calls `callback` on the expression and position of every assertion and
+/// exhale in `stmt`, including the nested ones.
+fn collect_assertions<'a>(
+    stmt: &'a ast::Stmt,
+    callback: &mut impl FnMut(&'a ast::Expr, ast::Position),
+) {
+    match stmt {
+        ast::Stmt::Assert(ast::Assert { expr, position })
+        | ast::Stmt::Exhale(ast::Exhale { expr, position }) => callback(expr, *position),
+        ast::Stmt::If(ast::If {
+            then_stmts,
+            else_stmts,
+            ..
+        }) => {
+            for stmt in then_stmts.iter().chain(else_stmts) {
+                collect_assertions(stmt, callback);
+            }
+        }
+        ast::Stmt::PackageMagicWand(ast::PackageMagicWand { package_stmts, .. }) => {
+            for stmt in package_stmts {
+                collect_assertions(stmt, callback);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether dropping an assertion of `expr` cannot make a method verify: `expr`
+/// is `true`, or only requires permissions to variables that were removed,
+/// which is what `remove_unused_vars` and `remove_trivial_assertions` do.
+fn is_redundant(expr: &ast::Expr, declared_vars: &HashSet<String>) -> bool {
+    match expr {
+        ast::Expr::Const(ast::ConstExpr {
+            value: ast::Const::Bool(true),
+            ..
+        }) => true,
+        ast::Expr::BinOp(ast::BinOp {
+            op_kind: ast::BinaryOpKind::And,
+            left,
+            right,
+            ..
+        }) => is_redundant(left, declared_vars) && is_redundant(right, declared_vars),
+        ast::Expr::PredicateAccessPredicate(ast::PredicateAccessPredicate { argument, .. }) => {
+            argument.is_place() && !declared_vars.contains(&argument.get_base().name)
+        }
+        ast::Expr::FieldAccessPredicate(ast::FieldAccessPredicate { base, .. }) => {
+            base.is_place() && !declared_vars.contains(&base.get_base().name)
+        }
+        _ => false,
+    }
+}
+
+/// A line-based diff of `before` and `after` that shows the changed lines,
+/// prefixed with `-` or `+`, and a few unchanged lines around them.
+fn diff(before: &str, after: &str) -> String {
+    let before: Vec<_> = before.lines().collect();
+    let after: Vec<_> = after.lines().collect();
+    let prefix_len = before
+        .iter()
+        .zip(&after)
+        .take_while(|(line_before, line_after)| line_before == line_after)
+        .count();
+    let suffix_len = before[prefix_len..]
+        .iter()
+        .rev()
+        .zip(after[prefix_len..].iter().rev())
+        .take_while(|(line_before, line_after)| line_before == line_after)
+        .count();
+    let context_start = prefix_len.saturating_sub(DIFF_CONTEXT_LINES);
+    let mut result = String::new();
+    for line in &before[context_start..prefix_len] {
+        writeln!(result, "  {}", line).unwrap();
+    }
+    for line in &before[prefix_len..before.len() - suffix_len] {
+        writeln!(result, "- {}", line).unwrap();
+    }
+    for line in &after[prefix_len..after.len() - suffix_len] {
+        writeln!(result, "+ {}", line).unwrap();
+    }
+    let context_end = (after.len() - suffix_len + DIFF_CONTEXT_LINES).min(after.len());
+    for line in &after[after.len() - suffix_len..context_end] {
+        writeln!(result, "  {}", line).unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProgramSnapshot;
+    use crate::vir::polymorphic_vir::{
+        ast::*,
+        cfg::{CfgMethod, Successor},
+        Program,
+    };
+
+    fn method(vars: &[&str], stmts: Vec<Stmt>) -> CfgMethod {
+        let vars = vars
+            .iter()
+            .map(|name| LocalVar::new(*name, Type::Int))
+            .collect();
+        let mut method = CfgMethod::new("m".to_string(), 0, vec![], vars, vec![]);
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, Successor::Return);
+        method
+    }
+
+    fn program(method: CfgMethod) -> Program {
+        Program {
+            name: "program".to_string(),
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![],
+            viper_predicates: vec![],
+        }
+    }
+
+    fn assert_positive(name: &str) -> Stmt {
+        Stmt::Assert(Assert {
+            expr: Expr::gt_cmp(Expr::local(LocalVar::new(name, Type::Int)), 0.into()),
+            position: Position::new(1, 1, 5),
+        })
+    }
+
+    #[test]
+    #[should_panic(expected = "Optimization pass drop broke method m: assertion")]
+    fn rejects_a_dropped_assertion() {
+        let snapshot = ProgramSnapshot::new(&program(method(&["x"], vec![assert_positive("x")])));
+        snapshot.validate(&program(method(&["x"], vec![])), "drop");
+    }
+
+    #[test]
+    #[should_panic(expected = "variable y is used but not declared")]
+    fn rejects_an_undeclared_variable() {
+        let snapshot = ProgramSnapshot::new(&program(method(&["x"], vec![assert_positive("x")])));
+        snapshot.validate(
+            &program(method(&["x"], vec![assert_positive("y")])),
+            "rename",
+        );
+    }
+
+    #[test]
+    fn accepts_a_dropped_trivial_assertion() {
+        let assert_true = Stmt::Assert(Assert {
+            expr: true.into(),
+            position: Position::new(1, 1, 5),
+        });
+        let snapshot = ProgramSnapshot::new(&program(method(&["x"], vec![assert_true])));
+        snapshot.validate(&program(method(&["x"], vec![])), "drop");
+    }
+}
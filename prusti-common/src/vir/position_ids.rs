@@ -0,0 +1,218 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Collects the position ids of a program.
+//!
+//! The `Hash` implementation of `Position` ignores the position, but the
+//! verifier reports errors by position id. The verification cache therefore
+//! has to include the ids in its key, otherwise a cached result could point to
+//! the positions of an older encoding of the same method. The encoder numbers
+//! positions across the whole crate, so the ids are renumbered per program
+//! before they become part of the key.
+
+use crate::vir::{
+    ast::*,
+    cfg::{CfgMethod, Successor},
+    Program,
+};
+use std::collections::HashMap;
+
+/// Returns the non-default position ids of `program` in a deterministic order.
+fn position_ids(program: &Program) -> Vec<u64> {
+    let mut collector = PositionCollector::default();
+    for method in &program.methods {
+        collector.collect_method(method);
+    }
+    for function in &program.functions {
+        for expr in function
+            .pres
+            .iter()
+            .chain(&function.posts)
+            .chain(&function.body)
+        {
+            ExprWalker::walk(&mut collector, expr);
+        }
+    }
+    for predicate in &program.viper_predicates {
+        if let Some(body) = predicate.body() {
+            ExprWalker::walk(&mut collector, &body);
+        }
+    }
+    for domain in &program.domains {
+        for axiom in &domain.axioms {
+            ExprWalker::walk(&mut collector, &axiom.expr);
+        }
+    }
+    collector.ids
+}
+
+/// Maps the position ids of a program to local ids `1..=n` in the order of
+/// their first occurrence, and back. The local ids do not depend on how many
+/// positions the encoder created for other programs.
+pub struct PositionIdRenumbering {
+    local_ids: HashMap<u64, u64>,
+    global_ids: Vec<u64>,
+    /// The local ids of all positions, in the order of `position_ids`.
+    local_position_ids: Vec<u64>,
+}
+
+impl PositionIdRenumbering {
+    pub(super) fn new(program: &Program) -> Self {
+        let mut local_ids = HashMap::new();
+        let mut global_ids = Vec::new();
+        let local_position_ids = position_ids(program)
+            .into_iter()
+            .map(|id| {
+                *local_ids.entry(id).or_insert_with(|| {
+                    global_ids.push(id);
+                    global_ids.len() as u64
+                })
+            })
+            .collect();
+        PositionIdRenumbering {
+            local_ids,
+            global_ids,
+            local_position_ids,
+        }
+    }
+
+    /// The position ids of the program after renumbering, which are equal for
+    /// two encodings of the same program.
+    pub fn local_position_ids(&self) -> &[u64] {
+        &self.local_position_ids
+    }
+
+    pub fn to_local(&self, global_id: u64) -> Option<u64> {
+        self.local_ids.get(&global_id).copied()
+    }
+
+    pub fn to_global(&self, local_id: u64) -> Option<u64> {
+        let index = local_id.checked_sub(1)?;
+        self.global_ids.get(index as usize).copied()
+    }
+}
+
+#[derive(Default)]
+struct PositionCollector {
+    ids: Vec<u64>,
+}
+
+impl PositionCollector {
+    fn collect_method(&mut self, method: &CfgMethod) {
+        method.walk_statements(|stmt| StmtWalker::walk(self, stmt));
+        method.walk_successors(|successor| {
+            if let Successor::GotoSwitch(conditional_targets, _) = successor {
+                for (expr, _) in conditional_targets {
+                    ExprWalker::walk(self, expr);
+                }
+            }
+        });
+    }
+
+    fn add(&mut self, pos: &Position) {
+        if !pos.is_default() {
+            self.ids.push(pos.id());
+        }
+    }
+}
+
+impl ExprWalker for PositionCollector {
+    fn walk(&mut self, expr: &Expr) {
+        self.add(&expr.pos());
+        default_walk_expr(self, expr);
+    }
+}
+
+impl StmtWalker for PositionCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_exhale(&mut self, expr: &Expr, pos: &Position) {
+        self.add(pos);
+        self.walk_expr(expr);
+    }
+
+    fn walk_assert(&mut self, expr: &Expr, pos: &Position) {
+        self.add(pos);
+        self.walk_expr(expr);
+    }
+
+    fn walk_fold(
+        &mut self,
+        _predicate_name: &str,
+        args: &[Expr],
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        pos: &Position,
+    ) {
+        self.add(pos);
+        for arg in args {
+            self.walk_expr(arg);
+        }
+    }
+
+    fn walk_obtain(&mut self, expr: &Expr, pos: &Position) {
+        self.add(pos);
+        self.walk_expr(expr);
+    }
+
+    fn walk_package_magic_wand(
+        &mut self,
+        wand: &Expr,
+        body: &[Stmt],
+        _label: &str,
+        _vars: &[LocalVar],
+        pos: &Position,
+    ) {
+        self.add(pos);
+        self.walk_expr(wand);
+        for statement in body {
+            StmtWalker::walk(self, statement);
+        }
+    }
+
+    fn walk_apply_magic_wand(&mut self, wand: &Expr, pos: &Position) {
+        self.add(pos);
+        self.walk_expr(wand);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::position_ids;
+    use crate::vir::{
+        ast::*,
+        cfg::{CfgMethod, Successor},
+        Program,
+    };
+
+    #[test]
+    fn collects_statement_and_expression_positions() {
+        let x = LocalVar::new("x", Type::Int);
+        let condition =
+            Expr::gt_cmp(Expr::local(x.clone()), 0i32.into()).set_pos(Position::new(1, 2, 7));
+        let mut method = CfgMethod::new("m".to_string(), 0, vec![], vec![x]);
+        let start = method.add_block(
+            "start",
+            vec![
+                Stmt::Assert(condition.clone(), Position::new(1, 1, 5)),
+                Stmt::Inhale(condition),
+            ],
+        );
+        method.set_successor(start, Successor::Return);
+        let program = Program {
+            name: "program".to_string(),
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![],
+            viper_predicates: vec![],
+        };
+        assert_eq!(position_ids(&program), [5, 7, 7]);
+    }
+}
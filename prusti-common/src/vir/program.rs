@@ -1,4 +1,8 @@
-use super::low_to_viper::ToViper;
+use super::{
+    low_to_viper::ToViper,
+    position_ids::PositionIdRenumbering,
+    to_viper_text::{ToViperText, ViperTextWriter},
+};
 use viper::{self, AstFactory};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash)]
@@ -20,6 +24,28 @@ impl Program {
             Program::Low(program) => program.name = name,
         }
     }
+    /// Split the program into one program per method, each containing only
+    /// the definitions that the method transitively depends on. Programs of
+    /// the core proof already contain a single procedure and are returned
+    /// unchanged.
+    pub fn split_by_method(self) -> Vec<Program> {
+        match self {
+            Program::Legacy(program) => super::slicing::split_by_method(program)
+                .into_iter()
+                .map(Program::Legacy)
+                .collect(),
+            Program::Low(program) => vec![Program::Low(program)],
+        }
+    }
+    /// The renumbering of the ids of the positions in the program, which the
+    /// derived `Hash` ignores for legacy programs. The positions of the core
+    /// proof are already part of its hash, so it has no renumbering.
+    pub fn position_id_renumbering(&self) -> Option<PositionIdRenumbering> {
+        match self {
+            Program::Legacy(program) => Some(PositionIdRenumbering::new(program)),
+            Program::Low(_) => None,
+        }
+    }
 }
 
 impl<'v> ToViper<'v, viper::Program<'v>> for Program {
@@ -30,3 +56,12 @@ impl<'v> ToViper<'v, viper::Program<'v>> for Program {
         }
     }
 }
+
+impl ToViperText for Program {
+    fn write_viper_text(&self, writer: &mut ViperTextWriter) {
+        match self {
+            Program::Legacy(program) => program.write_viper_text(writer),
+            Program::Low(program) => program.write_viper_text(writer),
+        }
+    }
+}
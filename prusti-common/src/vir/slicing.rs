@@ -0,0 +1,508 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Splits a program into one program per method that contains only the
+//! definitions the method transitively depends on.
+//!
+//! The verification cache is keyed by the hash of the verification request,
+//! so slicing makes the key depend only on the method and its dependencies.
+//! Definitions that an unrelated method added to the encoder's global state
+//! (e.g. builtin methods or domain functions) no longer invalidate the cache.
+
+use crate::vir::{
+    ast::*,
+    cfg::{CfgMethod, Successor},
+    Program,
+};
+use std::collections::HashSet;
+use vir::common::identifier::WithIdentifier;
+
+/// Returns one program per method of `program`. Programs with a single method
+/// keep their name, otherwise the method name is appended. The functions and
+/// predicates that no method uses still have to be checked, so they are put
+/// into one additional program without methods. A program without methods is
+/// returned unchanged.
+pub(super) fn split_by_method(program: Program) -> Vec<Program> {
+    if program.methods.is_empty() {
+        return vec![program];
+    }
+    let Program {
+        name,
+        domains,
+        fields,
+        builtin_methods,
+        methods,
+        functions,
+        viper_predicates,
+    } = program;
+    let split = methods.len() > 1;
+    let definitions = Definitions {
+        domains: &domains,
+        fields: &fields,
+        builtin_methods: &builtin_methods,
+        functions: &functions,
+        predicates: &viper_predicates,
+    };
+    let mut programs: Vec<_> = methods
+        .into_iter()
+        .map(|method| {
+            let mut collector = DependencyCollector::default();
+            collector.collect_method(&method);
+            collector.close(&definitions);
+            let name = if split {
+                format!("{}__{}", name, method.name())
+            } else {
+                name.clone()
+            };
+            collector.into_program(name, vec![method], &definitions)
+        })
+        .collect();
+    let mut remainder = DependencyCollector::default();
+    for function in &functions {
+        if !programs
+            .iter()
+            .any(|program| program.functions.iter().any(|f| f.name == function.name))
+        {
+            remainder.use_function(&function.name);
+        }
+    }
+    for predicate in &viper_predicates {
+        let identifier = predicate.get_identifier();
+        if predicate.body().is_some()
+            && !programs.iter().any(|program| {
+                program
+                    .viper_predicates
+                    .iter()
+                    .any(|p| p.get_identifier() == identifier)
+            })
+        {
+            remainder.use_predicate(&identifier);
+        }
+    }
+    if !remainder.pending_functions.is_empty() || !remainder.pending_predicates.is_empty() {
+        remainder.close(&definitions);
+        programs.push(remainder.into_program(format!("{}__remainder", name), vec![], &definitions));
+    }
+    programs
+}
+
+struct Definitions<'a> {
+    domains: &'a [Domain],
+    fields: &'a [Field],
+    builtin_methods: &'a [BodylessMethod],
+    functions: &'a [Function],
+    predicates: &'a [Predicate],
+}
+
+#[derive(Default)]
+struct DependencyCollector {
+    domains: HashSet<String>,
+    fields: HashSet<String>,
+    builtin_methods: HashSet<String>,
+    functions: HashSet<String>,
+    predicates: HashSet<String>,
+    /// Functions, predicates and domains whose definitions still have to be
+    /// walked.
+    pending_functions: Vec<String>,
+    pending_predicates: Vec<String>,
+    pending_domains: Vec<String>,
+}
+
+impl DependencyCollector {
+    fn collect_method(&mut self, method: &CfgMethod) {
+        for local_var in method.local_vars.iter().chain(method.get_formal_returns()) {
+            ExprWalker::walk_local_var(self, local_var);
+        }
+        method.walk_statements(|stmt| StmtWalker::walk(self, stmt));
+        method.walk_successors(|successor| {
+            if let Successor::GotoSwitch(conditional_targets, _) = successor {
+                for (expr, _) in conditional_targets {
+                    ExprWalker::walk(self, expr);
+                }
+            }
+        });
+    }
+
+    /// Walks the definitions of all used functions, predicates and domains
+    /// until no new dependencies are found.
+    fn close(&mut self, definitions: &Definitions) {
+        loop {
+            if let Some(name) = self.pending_functions.pop() {
+                if let Some(function) = definitions.functions.iter().find(|f| f.name == name) {
+                    for arg in &function.formal_args {
+                        ExprWalker::walk_local_var(self, arg);
+                    }
+                    ExprWalker::walk_type(self, &function.return_type);
+                    for expr in function
+                        .pres
+                        .iter()
+                        .chain(&function.posts)
+                        .chain(&function.body)
+                    {
+                        ExprWalker::walk(self, expr);
+                    }
+                }
+            } else if let Some(name) = self.pending_predicates.pop() {
+                let predicate = definitions
+                    .predicates
+                    .iter()
+                    .find(|p| p.get_identifier() == name);
+                if let Some(body) = predicate.and_then(|p| p.body()) {
+                    ExprWalker::walk(self, &body);
+                }
+            } else if let Some(name) = self.pending_domains.pop() {
+                if let Some(domain) = definitions.domains.iter().find(|d| d.name == name) {
+                    for function in &domain.functions {
+                        for arg in &function.formal_args {
+                            ExprWalker::walk_local_var(self, arg);
+                        }
+                        ExprWalker::walk_type(self, &function.return_type);
+                    }
+                    for axiom in &domain.axioms {
+                        ExprWalker::walk(self, &axiom.expr);
+                    }
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn into_program(
+        self,
+        name: String,
+        methods: Vec<CfgMethod>,
+        definitions: &Definitions,
+    ) -> Program {
+        let builtin_methods: Vec<_> = definitions
+            .builtin_methods
+            .iter()
+            .filter(|m| self.builtin_methods.contains(&m.name))
+            .cloned()
+            .collect();
+        // Builtin methods are bodyless, but their signatures may mention
+        // domain types.
+        let mut collector = self;
+        for builtin_method in &builtin_methods {
+            for arg in builtin_method
+                .formal_args
+                .iter()
+                .chain(&builtin_method.formal_returns)
+            {
+                ExprWalker::walk_local_var(&mut collector, arg);
+            }
+        }
+        collector.close(definitions);
+        Program {
+            name,
+            domains: definitions
+                .domains
+                .iter()
+                .filter(|d| collector.domains.contains(&d.name))
+                .cloned()
+                .collect(),
+            fields: definitions
+                .fields
+                .iter()
+                .filter(|f| collector.fields.contains(&f.name))
+                .cloned()
+                .collect(),
+            builtin_methods,
+            methods,
+            functions: definitions
+                .functions
+                .iter()
+                .filter(|f| collector.functions.contains(&f.name))
+                .cloned()
+                .collect(),
+            viper_predicates: definitions
+                .predicates
+                .iter()
+                .filter(|p| {
+                    // Bodyless predicates are the ones that the lowering
+                    // refers to implicitly, like `DeadBorrowToken$`.
+                    matches!(p, Predicate::Bodyless(..))
+                        || collector.predicates.contains(&p.get_identifier())
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn use_function(&mut self, name: &str) {
+        if self.functions.insert(name.to_string()) {
+            self.pending_functions.push(name.to_string());
+        }
+    }
+
+    fn use_predicate(&mut self, name: &str) {
+        if self.predicates.insert(name.to_string()) {
+            self.pending_predicates.push(name.to_string());
+        }
+    }
+
+    fn use_domain(&mut self, name: String) {
+        if self.domains.insert(name.clone()) {
+            self.pending_domains.push(name);
+        }
+    }
+}
+
+impl ExprWalker for DependencyCollector {
+    fn walk_type(&mut self, typ: &Type) {
+        match typ {
+            Type::Domain(name) => self.use_domain(name.clone()),
+            Type::Snapshot(name) => self.use_domain(format!("Snap${}", name)),
+            Type::Seq(elem_ty) => ExprWalker::walk_type(self, elem_ty),
+            Type::Int | Type::Bool | Type::Float(_) | Type::BitVector(_) | Type::TypedRef(_) => {}
+        }
+    }
+
+    fn walk_variant(&mut self, base: &Expr, variant: &Field, _pos: &Position) {
+        self.fields.insert(variant.name.clone());
+        ExprWalker::walk(self, base);
+        ExprWalker::walk_type(self, &variant.typ);
+    }
+
+    fn walk_field(&mut self, receiver: &Expr, field: &Field, _pos: &Position) {
+        self.fields.insert(field.name.clone());
+        ExprWalker::walk(self, receiver);
+        ExprWalker::walk_type(self, &field.typ);
+    }
+
+    fn walk_predicate_access_predicate(
+        &mut self,
+        name: &str,
+        arg: &Expr,
+        _perm_amount: PermAmount,
+        _pos: &Position,
+    ) {
+        self.use_predicate(name);
+        ExprWalker::walk(self, arg)
+    }
+
+    fn walk_unfolding(
+        &mut self,
+        name: &str,
+        args: &[Expr],
+        body: &Expr,
+        _perm: PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        _pos: &Position,
+    ) {
+        self.use_predicate(name);
+        for arg in args {
+            ExprWalker::walk(self, arg);
+        }
+        ExprWalker::walk(self, body);
+    }
+
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &[Expr],
+        formal_args: &[LocalVar],
+        return_type: &Type,
+        _pos: &Position,
+    ) {
+        self.use_function(name);
+        for arg in args {
+            ExprWalker::walk(self, arg)
+        }
+        for arg in formal_args {
+            ExprWalker::walk_local_var(self, arg);
+        }
+        ExprWalker::walk_type(self, return_type);
+    }
+
+    fn walk_domain_func_app(&mut self, func: &DomainFunc, args: &[Expr], _pos: &Position) {
+        self.use_domain(func.domain_name.clone());
+        for arg in args {
+            ExprWalker::walk(self, arg)
+        }
+        for arg in &func.formal_args {
+            ExprWalker::walk_local_var(self, arg)
+        }
+        ExprWalker::walk_type(self, &func.return_type);
+    }
+}
+
+impl StmtWalker for DependencyCollector {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_local_var(&mut self, local_var: &LocalVar) {
+        ExprWalker::walk_local_var(self, local_var);
+    }
+
+    fn walk_method_call(&mut self, method_name: &str, args: &[Expr], targets: &[LocalVar]) {
+        self.builtin_methods.insert(method_name.to_string());
+        for arg in args {
+            self.walk_expr(arg);
+        }
+        for target in targets {
+            StmtWalker::walk_local_var(self, target);
+        }
+    }
+
+    fn walk_fold(
+        &mut self,
+        predicate_name: &str,
+        args: &[Expr],
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+        _pos: &Position,
+    ) {
+        self.use_predicate(predicate_name);
+        for arg in args {
+            self.walk_expr(arg);
+        }
+    }
+
+    fn walk_unfold(
+        &mut self,
+        predicate_name: &str,
+        args: &[Expr],
+        _perm: &PermAmount,
+        _variant: &MaybeEnumVariantIndex,
+    ) {
+        self.use_predicate(predicate_name);
+        for arg in args {
+            self.walk_expr(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_by_method;
+    use crate::vir::{
+        ast::*,
+        cfg::{CfgMethod, Successor},
+        Program,
+    };
+
+    fn local(name: &str) -> LocalVar {
+        LocalVar::new(name, Type::TypedRef("i32".to_string()))
+    }
+
+    fn method(name: &str, stmts: Vec<Stmt>) -> CfgMethod {
+        let mut method = CfgMethod::new(name.to_string(), 0, vec![], vec![local("_1")]);
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, Successor::Return);
+        method
+    }
+
+    fn function(name: &str, body: Expr) -> Function {
+        Function {
+            name: name.to_string(),
+            formal_args: vec![],
+            return_type: Type::Int,
+            pres: vec![],
+            posts: vec![],
+            body: Some(body),
+        }
+    }
+
+    fn call(name: &str) -> Expr {
+        Expr::func_app(
+            name.to_string(),
+            vec![],
+            vec![],
+            Type::Int,
+            Position::default(),
+        )
+    }
+
+    fn program(methods: Vec<CfgMethod>) -> Program {
+        Program {
+            name: "program".to_string(),
+            domains: vec![],
+            fields: vec![
+                Field::new("first", Type::Int),
+                Field::new("second", Type::Int),
+            ],
+            builtin_methods: vec![],
+            methods,
+            functions: vec![
+                function("outer", call("inner")),
+                function("inner", 0i32.into()),
+                function("unused", 1i32.into()),
+            ],
+            viper_predicates: vec![],
+        }
+    }
+
+    fn names<T>(items: &[T], name: impl Fn(&T) -> &str) -> Vec<&str> {
+        items.iter().map(name).collect()
+    }
+
+    #[test]
+    fn slices_keep_only_dependencies() {
+        let first = Expr::local(local("_1")).field(Field::new("first", Type::Int));
+        let second = Expr::local(local("_1")).field(Field::new("second", Type::Int));
+        let slices = split_by_method(program(vec![
+            method(
+                "m_first",
+                vec![Stmt::Assert(
+                    Expr::eq_cmp(first, call("outer")),
+                    Position::default(),
+                )],
+            ),
+            method(
+                "m_second",
+                vec![Stmt::Inhale(Expr::gt_cmp(second, 0i32.into()))],
+            ),
+        ]));
+        assert_eq!(slices.len(), 3);
+        assert_eq!(slices[0].name, "program__m_first");
+        assert_eq!(names(&slices[0].fields, |f| &f.name), ["first"]);
+        assert_eq!(names(&slices[0].functions, |f| &f.name), ["outer", "inner"]);
+        assert_eq!(slices[1].name, "program__m_second");
+        assert_eq!(names(&slices[1].fields, |f| &f.name), ["second"]);
+        assert!(slices[1].functions.is_empty());
+        assert_eq!(slices[2].name, "program__remainder");
+        assert!(slices[2].methods.is_empty());
+        assert_eq!(names(&slices[2].functions, |f| &f.name), ["unused"]);
+    }
+
+    #[test]
+    fn single_method_keeps_name() {
+        let slices = split_by_method(program(vec![method("m", vec![])]));
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[0].name, "program");
+        assert!(slices[0].fields.is_empty());
+        assert!(slices[0].functions.is_empty());
+        assert_eq!(slices[1].name, "program__remainder");
+        assert_eq!(
+            names(&slices[1].functions, |f| &f.name),
+            ["outer", "inner", "unused"]
+        );
+    }
+
+    #[test]
+    fn no_remainder_if_all_functions_are_used() {
+        let slices = split_by_method(program(vec![method(
+            "m",
+            vec![Stmt::Inhale(Expr::eq_cmp(call("outer"), call("unused")))],
+        )]));
+        assert_eq!(slices.len(), 1);
+        assert_eq!(
+            names(&slices[0].functions, |f| &f.name),
+            ["outer", "inner", "unused"]
+        );
+    }
+
+    #[test]
+    fn program_without_methods_is_unchanged() {
+        let slices = split_by_method(program(vec![]));
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].name, "program");
+        assert_eq!(slices[0].fields.len(), 2);
+        assert_eq!(slices[0].functions.len(), 3);
+    }
+}
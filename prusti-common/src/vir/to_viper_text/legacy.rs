@@ -0,0 +1,604 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Mirrors the JNI lowering in `to_viper.rs`.
+
+use super::{
+    comment, join, method_header, with_position, write_read_permission_function, ToViperText,
+    ViperTextWriter,
+};
+use crate::{
+    config,
+    vir::{
+        ast::*,
+        borrows::borrow_id,
+        cfg::{CfgBlock, CfgMethod, Successor, RETURN_LABEL},
+        Program,
+    },
+};
+use vir::common::identifier::WithIdentifier;
+
+impl ToViperText for Program {
+    fn write_viper_text(&self, writer: &mut ViperTextWriter) {
+        writer.line(comment(&format!("program {}", self.name)));
+        for domain in &self.domains {
+            writer.empty_line();
+            write_domain(writer, domain);
+        }
+        writer.empty_line();
+        for field in &self.fields {
+            writer.line(format!(
+                "field {}: {}",
+                field.name,
+                type_to_text(&field.typ)
+            ));
+        }
+        for function in &self.functions {
+            writer.empty_line();
+            write_function(writer, function);
+        }
+        writer.empty_line();
+        write_read_permission_function(writer);
+        for predicate in &self.viper_predicates {
+            writer.empty_line();
+            write_predicate(writer, predicate);
+        }
+        if config::verify_only_preamble() {
+            return;
+        }
+        for method in &self.methods {
+            writer.empty_line();
+            write_cfg_method(writer, method);
+        }
+        for method in &self.builtin_methods {
+            writer.empty_line();
+            writer.line(method_header(
+                &method.name,
+                declarations_to_text(&method.formal_args),
+                declarations_to_text(&method.formal_returns),
+            ));
+        }
+    }
+}
+
+impl ToViperText for CfgMethod {
+    fn write_viper_text(&self, writer: &mut ViperTextWriter) {
+        write_cfg_method(writer, self);
+    }
+}
+
+fn write_domain(writer: &mut ViperTextWriter, domain: &Domain) {
+    let type_vars = if domain.type_vars.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", join(domain.type_vars.iter().map(type_to_text)))
+    };
+    writer.block(format!("domain {}{}", domain.name, type_vars), |writer| {
+        for function in &domain.functions {
+            writer.line(format!(
+                "{}function {}({}): {}",
+                if function.unique { "unique " } else { "" },
+                function.get_identifier(),
+                declarations_to_text(&function.formal_args),
+                type_to_text(&function.return_type)
+            ));
+        }
+        for axiom in &domain.axioms {
+            writer.block(format!("axiom {}", axiom.name), |writer| {
+                writer.line(expr_to_text(&axiom.expr));
+            });
+        }
+    });
+}
+
+fn write_function(writer: &mut ViperTextWriter, function: &Function) {
+    writer.line(format!(
+        "function {}({}): {}",
+        function.get_identifier(),
+        declarations_to_text(&function.formal_args),
+        type_to_text(&function.return_type)
+    ));
+    for pre in &function.pres {
+        writer.line(format!("  requires {}", expr_to_text(pre)));
+    }
+    for post in &function.posts {
+        writer.line(format!("  ensures {}", expr_to_text(post)));
+    }
+    if let Some(body) = &function.body {
+        writer.line("{");
+        writer.indented(|writer| writer.line(expr_to_text(body)));
+        writer.line("}");
+    }
+}
+
+fn write_predicate(writer: &mut ViperTextWriter, predicate: &Predicate) {
+    let (name, this, body) = match predicate {
+        Predicate::Struct(predicate) => (&predicate.name, &predicate.this, predicate.body.clone()),
+        Predicate::Enum(predicate) => (&predicate.name, &predicate.this, Some(predicate.body())),
+        Predicate::Bodyless(name, this) => (name, this, None),
+    };
+    let header = format!("predicate {}({})", name, declaration_to_text(this));
+    if let Some(body) = body {
+        writer.block(header, |writer| writer.line(expr_to_text(&body)));
+    } else {
+        writer.line(header);
+    }
+}
+
+fn write_cfg_method(writer: &mut ViperTextWriter, method: &CfgMethod) {
+    let header = method_header(
+        &method.name(),
+        String::new(),
+        declarations_to_text(method.get_formal_returns()),
+    );
+    writer.block(header, |writer| {
+        for local_var in &method.local_vars {
+            writer.line(format!("var {}", declaration_to_text(local_var)));
+        }
+        // Sort blocks by label, except for the first block
+        let labels = method.basic_blocks_labels();
+        let mut blocks: Vec<_> = method.basic_blocks.iter().enumerate().skip(1).collect();
+        blocks.sort_by_key(|(index, _)| labels[*index].clone());
+        blocks.insert(0, (0, &method.basic_blocks[0]));
+        for (index, block) in blocks {
+            write_block(writer, labels, block, index);
+        }
+        writer.line(format!("label {}", RETURN_LABEL));
+    });
+}
+
+fn write_block(writer: &mut ViperTextWriter, labels: &[String], block: &CfgBlock, index: usize) {
+    writer.line(format!("label {}", labels[index]));
+    for stmt in &block.stmts {
+        write_stmt(writer, stmt);
+    }
+    match block.successor {
+        Successor::Undefined => panic!("CFG block '{}' has no successor.", labels[index]),
+        Successor::Return => writer.line(format!("goto {}", RETURN_LABEL)),
+        Successor::Goto(target) => writer.line(format!("goto {}", labels[target.index()])),
+        Successor::GotoSwitch(ref successors, ref default_target) => {
+            for (test, target) in successors {
+                writer.block(format!("if ({})", expr_to_text(test)), |writer| {
+                    writer.line(format!("goto {}", labels[target.index()]));
+                });
+            }
+            writer.line(format!("goto {}", labels[default_target.index()]));
+        }
+    }
+}
+
+fn write_stmt(writer: &mut ViperTextWriter, stmt: &Stmt) {
+    match stmt {
+        Stmt::Comment(text) => writer.line(comment(text)),
+        Stmt::Label(label) => writer.line(format!("label {}", label)),
+        Stmt::Inhale(expr) => writer.line(format!("inhale {}", expr_to_text(expr))),
+        Stmt::Exhale(expr, pos) => {
+            writer.line(positioned(format!("exhale {}", expr_to_text(expr)), pos))
+        }
+        Stmt::Assert(expr, pos) => {
+            writer.line(positioned(format!("assert {}", expr_to_text(expr)), pos))
+        }
+        Stmt::MethodCall(method_name, args, targets) => {
+            let call = format!("{}({})", method_name, exprs_to_text(args));
+            if targets.is_empty() {
+                writer.line(call);
+            } else {
+                let targets = join(targets.iter().map(|target| local_to_text(target)));
+                writer.line(format!("{} := {}", targets, call));
+            }
+        }
+        Stmt::Assign(lhs, rhs, _) => {
+            writer.line(format!("{} := {}", expr_to_text(lhs), expr_to_text(rhs)))
+        }
+        Stmt::Fold(pred_name, args, perm, _, pos) => writer.line(positioned(
+            format!(
+                "fold acc({}({}), {})",
+                pred_name,
+                exprs_to_text(args),
+                perm_to_text(*perm)
+            ),
+            pos,
+        )),
+        Stmt::Unfold(pred_name, args, perm, _) => writer.line(format!(
+            "unfold acc({}({}), {})",
+            pred_name,
+            exprs_to_text(args),
+            perm_to_text(*perm)
+        )),
+        Stmt::Obtain(..)
+        | Stmt::BeginFrame
+        | Stmt::EndFrame
+        | Stmt::TransferPerm(..)
+        | Stmt::ExpireBorrows(_)
+        | Stmt::Downcast(..) => writer.line(comment(stmt.to_string().trim_end())),
+        Stmt::PackageMagicWand(wand, package_stmts, _, vars, pos) => {
+            let header = positioned(format!("package {}", expr_to_text(wand)), pos);
+            writer.block(header, |writer| {
+                for var in vars {
+                    writer.line(format!("var {}", declaration_to_text(var)));
+                }
+                for stmt in package_stmts {
+                    write_stmt_in_package(writer, stmt);
+                }
+            });
+        }
+        Stmt::ApplyMagicWand(wand, pos) => {
+            if let Expr::MagicWand(_, _, Some(borrow), _) = wand {
+                writer.line(positioned(
+                    format!("inhale {}", dead_borrow_token(borrow_id(*borrow) as isize)),
+                    pos,
+                ));
+            } else {
+                unreachable!("applied magic wand without a borrow: {}", wand);
+            }
+            writer.line(positioned(format!("apply {}", expr_to_text(wand)), pos));
+        }
+        Stmt::If(guard, then_stmts, else_stmts) => {
+            write_if(writer, guard, then_stmts, else_stmts, write_stmt)
+        }
+    }
+}
+
+/// When packaging a magic wand, Silicon needs help in showing that it has
+/// access to the needed paths (see the JNI lowering).
+fn write_stmt_in_package(writer: &mut ViperTextWriter, stmt: &Stmt) {
+    let write_footprint_asserts = |writer: &mut ViperTextWriter, expr: &Expr| {
+        for access in expr.compute_footprint(PermAmount::Read) {
+            writer.line(format!("assert {}", expr_to_text(&access)));
+        }
+    };
+    match stmt {
+        Stmt::Assign(_, rhs, _) => {
+            write_footprint_asserts(writer, rhs);
+            write_stmt(writer, stmt);
+        }
+        Stmt::Exhale(expr, _) => {
+            write_footprint_asserts(writer, expr);
+            write_stmt(writer, stmt);
+        }
+        Stmt::Fold(_, args, ..) => {
+            assert_eq!(args.len(), 1);
+            write_footprint_asserts(writer, &args[0]);
+            write_stmt(writer, stmt);
+        }
+        Stmt::If(guard, then_stmts, else_stmts) => {
+            write_if(writer, guard, then_stmts, else_stmts, write_stmt_in_package)
+        }
+        _ => write_stmt(writer, stmt),
+    }
+}
+
+fn write_if(
+    writer: &mut ViperTextWriter,
+    guard: &Expr,
+    then_stmts: &[Stmt],
+    else_stmts: &[Stmt],
+    write: fn(&mut ViperTextWriter, &Stmt),
+) {
+    writer.line(format!("if ({}) {{", expr_to_text(guard)));
+    writer.indented(|writer| {
+        for stmt in then_stmts {
+            write(writer, stmt);
+        }
+    });
+    writer.line("} else {");
+    writer.indented(|writer| {
+        for stmt in else_stmts {
+            write(writer, stmt);
+        }
+    });
+    writer.line("}");
+}
+
+fn positioned(statement: String, pos: &Position) -> String {
+    with_position(statement, pos.line(), pos.column(), pos.id())
+}
+
+fn type_to_text(typ: &Type) -> String {
+    match typ {
+        Type::Int => "Int".to_string(),
+        Type::Bool => "Bool".to_string(),
+        Type::TypedRef(_) => "Ref".to_string(),
+        Type::Domain(name) => name.clone(),
+        Type::Snapshot(name) => format!("Snap${}", name),
+        Type::Seq(elem_ty) => format!("Seq[{}]", type_to_text(elem_ty)),
+        Type::Float(float) => float_to_text(*float).to_string(),
+        Type::BitVector(bv) => bitvector_to_text(*bv),
+    }
+}
+
+fn float_to_text(float: Float) -> &'static str {
+    match float {
+        Float::F32 => "Float32",
+        Float::F64 => "Float64",
+    }
+}
+
+fn bitvector_to_text(bv: BitVector) -> String {
+    match bv {
+        BitVector::Signed(size) | BitVector::Unsigned(size) => size.to_string(),
+    }
+}
+
+fn local_to_text(local: &LocalVar) -> String {
+    if local.name == "__result" {
+        "result".to_string()
+    } else {
+        local.name.clone()
+    }
+}
+
+fn declaration_to_text(local: &LocalVar) -> String {
+    format!("{}: {}", local.name, type_to_text(&local.typ))
+}
+
+fn declarations_to_text(locals: &[LocalVar]) -> String {
+    join(locals.iter().map(declaration_to_text))
+}
+
+fn exprs_to_text(exprs: &[Expr]) -> String {
+    join(exprs.iter().map(expr_to_text))
+}
+
+fn perm_to_text(perm: PermAmount) -> String {
+    match perm {
+        PermAmount::Write => "write".to_string(),
+        PermAmount::Read => "read$()".to_string(),
+        PermAmount::Remaining => "(write - read$())".to_string(),
+    }
+}
+
+fn dead_borrow_token(borrow: isize) -> String {
+    format!("acc(DeadBorrowToken$({}), write)", borrow)
+}
+
+fn binary_op_to_text(op: BinaryOpKind) -> &'static str {
+    match op {
+        BinaryOpKind::EqCmp => "==",
+        BinaryOpKind::NeCmp => "!=",
+        BinaryOpKind::GtCmp => ">",
+        BinaryOpKind::GeCmp => ">=",
+        BinaryOpKind::LtCmp => "<",
+        BinaryOpKind::LeCmp => "<=",
+        BinaryOpKind::Add => "+",
+        BinaryOpKind::Sub => "-",
+        BinaryOpKind::Mul => "*",
+        BinaryOpKind::Div => "\\",
+        BinaryOpKind::Mod => "%",
+        BinaryOpKind::And => "&&",
+        BinaryOpKind::Or => "||",
+        BinaryOpKind::Implies => "==>",
+        _ => unreachable!("illegal binary operation {} for mathematical integers", op),
+    }
+}
+
+/// The name of the uninterpreted function used for a float or bitvector
+/// operation, e.g. `Float32_add` or `BV32_shl`.
+fn backend_op_to_text(ty: &str, op: &str, args: &[&Expr]) -> String {
+    format!(
+        "{}_{}({})",
+        ty,
+        op,
+        join(args.iter().map(|arg| expr_to_text(arg)))
+    )
+}
+
+fn backend_op_name(op: BinaryOpKind) -> &'static str {
+    match op {
+        BinaryOpKind::Add => "add",
+        BinaryOpKind::Sub => "sub",
+        BinaryOpKind::Mul => "mul",
+        BinaryOpKind::Div => "div",
+        BinaryOpKind::EqCmp => "eq",
+        BinaryOpKind::GtCmp => "gt",
+        BinaryOpKind::GeCmp => "geq",
+        BinaryOpKind::LtCmp => "lt",
+        BinaryOpKind::LeCmp => "leq",
+        BinaryOpKind::Min => "min",
+        BinaryOpKind::Max => "max",
+        BinaryOpKind::BitAnd => "and",
+        BinaryOpKind::BitOr => "or",
+        BinaryOpKind::BitXor => "xor",
+        BinaryOpKind::Shl => "shl",
+        BinaryOpKind::LShr => "lshr",
+        BinaryOpKind::AShr => "ashr",
+        _ => unreachable!("illegal binary operation for floats or bitvectors: {}", op),
+    }
+}
+
+fn expr_to_text(expr: &Expr) -> String {
+    match expr {
+        Expr::Local(local_var, _) => local_to_text(local_var),
+        Expr::Variant(base, field, _) | Expr::Field(base, field, _) => {
+            format!("{}.{}", expr_to_text(base), field.name)
+        }
+        Expr::AddrOf(..) => unreachable!(),
+        Expr::Const(value, _) => const_to_text(value),
+        Expr::LabelledOld(label, expr, _) => format!("old[{}]({})", label, expr_to_text(expr)),
+        Expr::MagicWand(lhs, rhs, maybe_borrow, _) => {
+            let borrow = maybe_borrow.map_or(-1, |borrow| borrow_id(borrow) as isize);
+            format!(
+                "({} && {}) --* ({})",
+                dead_borrow_token(borrow),
+                expr_to_text(lhs),
+                expr_to_text(rhs)
+            )
+        }
+        Expr::PredicateAccessPredicate(predicate_name, arg, perm, _) => format!(
+            "acc({}({}), {})",
+            predicate_name,
+            expr_to_text(arg),
+            perm_to_text(*perm)
+        ),
+        Expr::FieldAccessPredicate(loc, perm, _) => {
+            format!("acc({}, {})", expr_to_text(loc), perm_to_text(*perm))
+        }
+        Expr::UnaryOp(op, arg, _) => match arg.get_type() {
+            Type::Float(float) => {
+                let op = match op {
+                    UnaryOpKind::Minus => "neg",
+                    UnaryOpKind::IsNaN => "isNaN",
+                    _ => unreachable!("illegal unary operation for floats: {}", op),
+                };
+                backend_op_to_text(float_to_text(*float), op, &[arg.as_ref()])
+            }
+            Type::BitVector(bv) => {
+                let op = match op {
+                    UnaryOpKind::Not => "not",
+                    UnaryOpKind::Minus => "neg",
+                    _ => unreachable!("illegal unary operation for bitvectors: {}", op),
+                };
+                backend_op_to_text(&bitvector_to_text(*bv), op, &[arg.as_ref()])
+            }
+            typ => match op {
+                UnaryOpKind::Not => format!("!({})", expr_to_text(arg)),
+                UnaryOpKind::Minus => format!("-({})", expr_to_text(arg)),
+                _ => unreachable!("illegal unary operation {} for type {}", op, typ),
+            },
+        },
+        Expr::BinOp(op, left, right, _) => match left.get_maybe_type() {
+            Some(Type::Float(float)) => backend_op_to_text(
+                float_to_text(*float),
+                backend_op_name(*op),
+                &[left.as_ref(), right.as_ref()],
+            ),
+            Some(Type::BitVector(bv)) => match op {
+                BinaryOpKind::EqCmp | BinaryOpKind::NeCmp => format!(
+                    "({}) {} ({})",
+                    expr_to_text(left),
+                    binary_op_to_text(*op),
+                    expr_to_text(right)
+                ),
+                BinaryOpKind::GtCmp
+                | BinaryOpKind::GeCmp
+                | BinaryOpKind::LtCmp
+                | BinaryOpKind::LeCmp => format!(
+                    "({}) {} ({})",
+                    bitvector_to_int(*bv, left),
+                    binary_op_to_text(*op),
+                    bitvector_to_int(*bv, right)
+                ),
+                _ => backend_op_to_text(
+                    &bitvector_to_text(*bv),
+                    backend_op_name(*op),
+                    &[left.as_ref(), right.as_ref()],
+                ),
+            },
+            _ => format!(
+                "({}) {} ({})",
+                expr_to_text(left),
+                binary_op_to_text(*op),
+                expr_to_text(right)
+            ),
+        },
+        Expr::ContainerOp(op_kind, left, right, _) => match op_kind {
+            ContainerOpKind::SeqIndex => format!("{}[{}]", expr_to_text(left), expr_to_text(right)),
+            ContainerOpKind::SeqConcat => {
+                format!("({}) ++ ({})", expr_to_text(left), expr_to_text(right))
+            }
+            ContainerOpKind::SeqLen => format!("|{}|", expr_to_text(left)),
+        },
+        Expr::Seq(ty, elems, _) => {
+            if elems.is_empty() {
+                format!("{}()", type_to_text(ty))
+            } else {
+                format!("Seq({})", exprs_to_text(elems))
+            }
+        }
+        Expr::Unfolding(predicate_name, args, expr, perm, _, _) => format!(
+            "(unfolding acc({}({}), {}) in {})",
+            predicate_name,
+            exprs_to_text(args),
+            perm_to_text(*perm),
+            expr_to_text(expr)
+        ),
+        Expr::Cond(guard, left, right, _) => format!(
+            "(({}) ? ({}) : ({}))",
+            expr_to_text(guard),
+            expr_to_text(left),
+            expr_to_text(right)
+        ),
+        Expr::ForAll(vars, triggers, body, _) => quantifier_to_text("forall", vars, triggers, body),
+        Expr::Exists(vars, triggers, body, _) => quantifier_to_text("exists", vars, triggers, body),
+        Expr::LetExpr(var, expr, body, _) => format!(
+            "(let {} == ({}) in {})",
+            var.name,
+            expr_to_text(expr),
+            expr_to_text(body)
+        ),
+        Expr::FuncApp(function_name, args, ..) => {
+            format!("{}({})", function_name, exprs_to_text(args))
+        }
+        Expr::DomainFuncApp(function, args, _) => {
+            format!("{}({})", function.get_identifier(), exprs_to_text(args))
+        }
+        Expr::InhaleExhale(inhale_expr, exhale_expr, _) => format!(
+            "[{}, {}]",
+            expr_to_text(inhale_expr),
+            expr_to_text(exhale_expr)
+        ),
+        Expr::Downcast(base, ..) => expr_to_text(base),
+        Expr::SnapApp(..) => unreachable!("unpatched snapshot operation"),
+        Expr::Cast(kind, base, _) => match kind {
+            CastKind::BVIntoInt(bv) => bitvector_to_int(*bv, base),
+            CastKind::IntIntoBV(bv) => {
+                format!("int_to_{}({})", bitvector_to_text(*bv), expr_to_text(base))
+            }
+        },
+    }
+}
+
+/// Converts a bitvector to a mathematical integer, interpreting it as a
+/// two's complement number if it is signed.
+fn bitvector_to_int(bv: BitVector, value: &Expr) -> String {
+    match bv {
+        BitVector::Unsigned(size) => format!("{}_to_int({})", size, expr_to_text(value)),
+        BitVector::Signed(size) => {
+            let (signed_max, unsigned_max) = match size {
+                BitVectorSize::BV8 => (i8::MAX as u128, u8::MAX as u128),
+                BitVectorSize::BV16 => (i16::MAX as u128, u16::MAX as u128),
+                BitVectorSize::BV32 => (i32::MAX as u128, u32::MAX as u128),
+                BitVectorSize::BV64 => (i64::MAX as u128, u64::MAX as u128),
+                BitVectorSize::BV128 => (i128::MAX as u128, u128::MAX),
+            };
+            let value = format!("{}_to_int({})", size, expr_to_text(value));
+            format!(
+                "(({} < {}) ? (({} - {}) - 1) : ({}))",
+                signed_max, value, value, unsigned_max, value
+            )
+        }
+    }
+}
+
+fn quantifier_to_text(kind: &str, vars: &[LocalVar], triggers: &[Trigger], body: &Expr) -> String {
+    let triggers: String = triggers
+        .iter()
+        .map(|trigger| format!(" {{ {} }}", exprs_to_text(trigger.elements())))
+        .collect();
+    format!(
+        "({} {} ::{} {})",
+        kind,
+        declarations_to_text(vars),
+        triggers,
+        expr_to_text(body)
+    )
+}
+
+fn const_to_text(value: &Const) -> String {
+    match value {
+        Const::Bool(value) => value.to_string(),
+        Const::Int(value) => value.to_string(),
+        Const::BigInt(value) => value.clone(),
+        Const::Float(FloatConst::F32(bits)) => format!("Float32_from_bits({})", bits),
+        Const::Float(FloatConst::F64(bits)) => format!("Float64_from_bits({})", bits),
+        Const::BitVector(bv_const) => format!(
+            "{}_from_int({})",
+            bitvector_to_text(bv_const.typ),
+            bv_const.value
+        ),
+        Const::FnPtr => "null".to_string(),
+    }
+}
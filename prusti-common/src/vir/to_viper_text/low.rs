@@ -0,0 +1,418 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Mirrors the JNI lowering in `low_to_viper`.
+
+use super::{
+    comment, join, method_header, with_position, write_read_permission_function, ToViperText,
+    ViperTextWriter,
+};
+use crate::config;
+use vir::{
+    legacy::RETURN_LABEL,
+    low::{
+        ast::{
+            expression::{
+                BinaryOpKind, ConstantValue, ContainerOpKind, Expression, QuantifierKind,
+                UnaryOpKind,
+            },
+            function::FunctionDecl,
+            position::Position,
+            predicate::PredicateDecl,
+            statement::Statement,
+            ty::{BitVector, BitVectorSize, Float, Type},
+            variable::VariableDecl,
+            PermAmount,
+        },
+        cfg::{procedure::Successor, MethodDecl, ProcedureDecl},
+        program::Program,
+        DomainDecl,
+    },
+};
+
+impl ToViperText for Program {
+    fn write_viper_text(&self, writer: &mut ViperTextWriter) {
+        writer.line(comment(&format!("program {}", self.name)));
+        for domain in &self.domains {
+            writer.empty_line();
+            write_domain(writer, domain);
+        }
+        // FIXME: These should be domain functions (see the JNI lowering).
+        for function in &self.functions {
+            writer.empty_line();
+            write_function(writer, function);
+        }
+        writer.empty_line();
+        write_read_permission_function(writer);
+        for predicate in &self.predicates {
+            writer.empty_line();
+            write_predicate(writer, predicate);
+        }
+        if config::verify_only_preamble() {
+            return;
+        }
+        for procedure in &self.procedures {
+            writer.empty_line();
+            write_procedure(writer, procedure);
+        }
+        for method in &self.methods {
+            writer.empty_line();
+            write_method(writer, method);
+        }
+    }
+}
+
+fn write_domain(writer: &mut ViperTextWriter, domain: &DomainDecl) {
+    writer.block(format!("domain {}", domain.name), |writer| {
+        for function in &domain.functions {
+            writer.line(format!(
+                "function {}({}): {}",
+                function.name,
+                declarations_to_text(&function.parameters),
+                type_to_text(&function.return_type)
+            ));
+        }
+        for axiom in &domain.axioms {
+            writer.block(format!("axiom {}", axiom.name), |writer| {
+                writer.line(expression_to_text(&axiom.body));
+            });
+        }
+    });
+}
+
+fn write_function(writer: &mut ViperTextWriter, function: &FunctionDecl) {
+    writer.line(format!(
+        "function {}({}): {}",
+        function.name,
+        declarations_to_text(&function.parameters),
+        type_to_text(&function.return_type)
+    ));
+    for pre in &function.pres {
+        writer.line(format!("  requires {}", expression_to_text(pre)));
+    }
+    for post in &function.posts {
+        writer.line(format!("  ensures {}", expression_to_text(post)));
+    }
+    if let Some(body) = &function.body {
+        writer.line("{");
+        writer.indented(|writer| writer.line(expression_to_text(body)));
+        writer.line("}");
+    }
+}
+
+fn write_predicate(writer: &mut ViperTextWriter, predicate: &PredicateDecl) {
+    let header = format!(
+        "predicate {}({})",
+        predicate.name,
+        declarations_to_text(&predicate.parameters)
+    );
+    if let Some(body) = &predicate.body {
+        writer.block(header, |writer| writer.line(expression_to_text(body)));
+    } else {
+        writer.line(header);
+    }
+}
+
+fn write_procedure(writer: &mut ViperTextWriter, procedure: &ProcedureDecl) {
+    writer.block(format!("method {}()", procedure.name), |writer| {
+        for local in &procedure.locals {
+            writer.line(format!("var {}", declaration_to_text(local)));
+        }
+        for block in &procedure.basic_blocks {
+            writer.line(format!("label {}", block.label.name));
+            write_statements(writer, &block.statements);
+            match &block.successor {
+                Successor::Goto(target) => writer.line(format!("goto {}", target.name)),
+                Successor::GotoSwitch(targets) => {
+                    for (test, target) in targets {
+                        writer.block(format!("if ({})", expression_to_text(test)), |writer| {
+                            writer.line(format!("goto {}", target.name));
+                        });
+                    }
+                    writer.line("assert false");
+                }
+                Successor::Return => writer.line(format!("goto {}", RETURN_LABEL)),
+            }
+        }
+        writer.line(format!("label {}", RETURN_LABEL));
+    });
+}
+
+fn write_method(writer: &mut ViperTextWriter, method: &MethodDecl) {
+    writer.line(method_header(
+        &method.name,
+        declarations_to_text(&method.parameters),
+        declarations_to_text(&method.targets),
+    ));
+    for pre in &method.pres {
+        writer.line(format!("  requires {}", expression_to_text(pre)));
+    }
+    for post in &method.posts {
+        writer.line(format!("  ensures {}", expression_to_text(post)));
+    }
+    if let Some(body) = &method.body {
+        writer.line("{");
+        writer.indented(|writer| write_statements(writer, body));
+        writer.line("}");
+    }
+}
+
+fn write_statements(writer: &mut ViperTextWriter, statements: &[Statement]) {
+    for statement in statements {
+        write_statement(writer, statement);
+    }
+}
+
+fn write_statement(writer: &mut ViperTextWriter, statement: &Statement) {
+    let (text, position) = match statement {
+        Statement::Comment(statement) => return writer.line(comment(&statement.comment)),
+        Statement::Assume(statement) => (
+            format!("inhale {}", expression_to_text(&statement.expression)),
+            statement.position,
+        ),
+        Statement::Assert(statement) => (
+            format!("assert {}", expression_to_text(&statement.expression)),
+            statement.position,
+        ),
+        Statement::Inhale(statement) => (
+            format!("inhale {}", expression_to_text(&statement.expression)),
+            statement.position,
+        ),
+        Statement::Exhale(statement) => (
+            format!("exhale {}", expression_to_text(&statement.expression)),
+            statement.position,
+        ),
+        Statement::Fold(statement) => (
+            format!("fold {}", expression_to_text(&statement.expression)),
+            statement.position,
+        ),
+        Statement::Unfold(statement) => (
+            format!("unfold {}", expression_to_text(&statement.expression)),
+            statement.position,
+        ),
+        Statement::MethodCall(statement) => {
+            let call = format!(
+                "{}({})",
+                statement.method_name,
+                expressions_to_text(&statement.arguments)
+            );
+            let text = if statement.targets.is_empty() {
+                call
+            } else {
+                format!("{} := {}", expressions_to_text(&statement.targets), call)
+            };
+            (text, statement.position)
+        }
+        Statement::Assign(statement) => (
+            format!(
+                "{} := {}",
+                statement.target.name,
+                expression_to_text(&statement.value)
+            ),
+            statement.position,
+        ),
+        Statement::Conditional(statement) => {
+            writer.line(positioned(
+                format!("if ({}) {{", expression_to_text(&statement.guard)),
+                &statement.position,
+            ));
+            writer.indented(|writer| write_statements(writer, &statement.then_branch));
+            writer.line("} else {");
+            writer.indented(|writer| write_statements(writer, &statement.else_branch));
+            writer.line("}");
+            return;
+        }
+    };
+    writer.line(positioned(text, &position));
+}
+
+fn positioned(statement: String, position: &Position) -> String {
+    with_position(statement, position.line, position.column, position.id)
+}
+
+fn type_to_text(ty: &Type) -> String {
+    match ty {
+        Type::Int => "Int".to_string(),
+        Type::Bool => "Bool".to_string(),
+        Type::Ref => "Ref".to_string(),
+        Type::Domain(ty) => ty.name.clone(),
+        Type::Seq(ty) => format!("Seq[{}]", type_to_text(&ty.element_type)),
+        Type::Float(Float::F32) => "Float32".to_string(),
+        Type::Float(Float::F64) => "Float64".to_string(),
+        Type::BitVector(BitVector::Signed(size) | BitVector::Unsigned(size)) => match size {
+            BitVectorSize::BV8 => "BV8".to_string(),
+            BitVectorSize::BV16 => "BV16".to_string(),
+            BitVectorSize::BV32 => "BV32".to_string(),
+            BitVectorSize::BV64 => "BV64".to_string(),
+            BitVectorSize::BV128 => "BV128".to_string(),
+        },
+    }
+}
+
+fn declaration_to_text(variable: &VariableDecl) -> String {
+    format!("{}: {}", variable.name, type_to_text(&variable.ty))
+}
+
+fn declarations_to_text(variables: &[VariableDecl]) -> String {
+    join(variables.iter().map(declaration_to_text))
+}
+
+fn expressions_to_text(expressions: &[Expression]) -> String {
+    join(expressions.iter().map(expression_to_text))
+}
+
+fn permission_to_text(permission: &PermAmount) -> &'static str {
+    match permission {
+        PermAmount::Write => "write",
+        PermAmount::Read => "read$()",
+        PermAmount::Remaining => "(write - read$())",
+    }
+}
+
+fn binary_op_to_text(op_kind: BinaryOpKind) -> &'static str {
+    match op_kind {
+        BinaryOpKind::EqCmp => "==",
+        BinaryOpKind::NeCmp => "!=",
+        BinaryOpKind::GtCmp => ">",
+        BinaryOpKind::GeCmp => ">=",
+        BinaryOpKind::LtCmp => "<",
+        BinaryOpKind::LeCmp => "<=",
+        BinaryOpKind::Add => "+",
+        BinaryOpKind::Sub => "-",
+        BinaryOpKind::Mul => "*",
+        BinaryOpKind::Div => "\\",
+        BinaryOpKind::Mod => "%",
+        BinaryOpKind::And => "&&",
+        BinaryOpKind::Or => "||",
+        BinaryOpKind::Implies => "==>",
+    }
+}
+
+fn expression_to_text(expression: &Expression) -> String {
+    match expression {
+        Expression::Local(expression) => {
+            if expression.variable.name == "__result" {
+                "result".to_string()
+            } else {
+                expression.variable.name.clone()
+            }
+        }
+        Expression::Field(expression) => format!(
+            "{}.{}",
+            expression_to_text(&expression.base),
+            expression.field.name
+        ),
+        Expression::LabelledOld(expression) => match &expression.label {
+            Some(label) => format!("old[{}]({})", label, expression_to_text(&expression.base)),
+            None => format!("old({})", expression_to_text(&expression.base)),
+        },
+        Expression::Constant(expression) => match &expression.value {
+            ConstantValue::Bool(value) => value.to_string(),
+            ConstantValue::Int(value) => value.to_string(),
+            ConstantValue::BigInt(value) => value.clone(),
+        },
+        Expression::MagicWand(expression) => format!(
+            "({}) --* ({})",
+            expression_to_text(&expression.left),
+            expression_to_text(&expression.right)
+        ),
+        Expression::PredicateAccessPredicate(expression) => format!(
+            "acc({}({}), {})",
+            expression.name,
+            expressions_to_text(&expression.arguments),
+            permission_to_text(&expression.permission)
+        ),
+        Expression::FieldAccessPredicate(expression) => format!(
+            "acc({}, {})",
+            expression_to_text(&expression.base),
+            permission_to_text(&expression.permission)
+        ),
+        Expression::Unfolding(expression) => format!(
+            "(unfolding acc({}({}), {}) in {})",
+            expression.predicate,
+            expressions_to_text(&expression.arguments),
+            permission_to_text(&expression.permission),
+            expression_to_text(&expression.base)
+        ),
+        Expression::UnaryOp(expression) => {
+            let op = match expression.op_kind {
+                UnaryOpKind::Not => "!",
+                UnaryOpKind::Minus => "-",
+            };
+            format!("{}({})", op, expression_to_text(&expression.argument))
+        }
+        Expression::BinaryOp(expression) => format!(
+            "({}) {} ({})",
+            expression_to_text(&expression.left),
+            binary_op_to_text(expression.op_kind),
+            expression_to_text(&expression.right)
+        ),
+        Expression::ContainerOp(expression) => {
+            let left = expression_to_text(&expression.left);
+            match expression.op_kind {
+                ContainerOpKind::SeqIndex => {
+                    format!("{}[{}]", left, expression_to_text(&expression.right))
+                }
+                ContainerOpKind::SeqConcat => {
+                    format!("({}) ++ ({})", left, expression_to_text(&expression.right))
+                }
+                ContainerOpKind::SeqLen => format!("|{}|", left),
+            }
+        }
+        Expression::Seq(expression) => {
+            if expression.elements.is_empty() {
+                format!("{}()", type_to_text(&expression.ty))
+            } else {
+                format!("Seq({})", expressions_to_text(&expression.elements))
+            }
+        }
+        Expression::Conditional(expression) => format!(
+            "(({}) ? ({}) : ({}))",
+            expression_to_text(&expression.guard),
+            expression_to_text(&expression.then_expr),
+            expression_to_text(&expression.else_expr)
+        ),
+        Expression::Quantifier(expression) => {
+            let kind = match expression.kind {
+                QuantifierKind::ForAll => "forall",
+                QuantifierKind::Exists => "exists",
+            };
+            let triggers: String = expression
+                .triggers
+                .iter()
+                .map(|trigger| format!(" {{ {} }}", expressions_to_text(&trigger.terms)))
+                .collect();
+            format!(
+                "({} {} ::{} {})",
+                kind,
+                declarations_to_text(&expression.variables),
+                triggers,
+                expression_to_text(&expression.body)
+            )
+        }
+        Expression::LetExpr(expression) => format!(
+            "(let {} == ({}) in {})",
+            expression.variable.name,
+            expression_to_text(&expression.def),
+            expression_to_text(&expression.body)
+        ),
+        Expression::FuncApp(expression) => format!(
+            "{}({})",
+            expression.function_name,
+            expressions_to_text(&expression.arguments)
+        ),
+        Expression::DomainFuncApp(expression) => format!(
+            "{}({})",
+            expression.function_name,
+            expressions_to_text(&expression.arguments)
+        ),
+        Expression::InhaleExhale(expression) => format!(
+            "[{}, {}]",
+            expression_to_text(&expression.inhale_expression),
+            expression_to_text(&expression.exhale_expression)
+        ),
+    }
+}
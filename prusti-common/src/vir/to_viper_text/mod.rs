@@ -0,0 +1,260 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A printer that renders VIR programs as Viper source code without going
+//! through the JVM.
+//!
+//! The output is meant for inspecting encodings and for golden tests. Source
+//! positions are preserved as `// pos line:column #id` comments. Floating
+//! point and bitvector operations are rendered as applications of
+//! uninterpreted functions, because Viper has no surface syntax for the
+//! backend types that the JNI lowering uses.
+
+mod legacy;
+mod low;
+
+pub trait ToViperText {
+    fn write_viper_text(&self, writer: &mut ViperTextWriter);
+
+    fn to_viper_text(&self) -> String {
+        let mut writer = ViperTextWriter::default();
+        self.write_viper_text(&mut writer);
+        writer.finish()
+    }
+}
+
+/// Collects indented lines of Viper source.
+#[derive(Default)]
+pub struct ViperTextWriter {
+    buffer: String,
+    indent: usize,
+}
+
+impl ViperTextWriter {
+    pub fn line(&mut self, text: impl AsRef<str>) {
+        for line in text.as_ref().lines() {
+            if !line.is_empty() {
+                for _ in 0..self.indent {
+                    self.buffer.push_str("  ");
+                }
+                self.buffer.push_str(line);
+            }
+            self.buffer.push('\n');
+        }
+    }
+
+    pub fn empty_line(&mut self) {
+        self.buffer.push('\n');
+    }
+
+    /// Writes `header {`, the lines produced by `body` one level deeper,
+    /// and the closing brace.
+    pub fn block(&mut self, header: impl AsRef<str>, body: impl FnOnce(&mut Self)) {
+        self.line(format!("{} {{", header.as_ref()));
+        self.indented(body);
+        self.line("}");
+    }
+
+    pub fn indented(&mut self, body: impl FnOnce(&mut Self)) {
+        self.indent += 1;
+        body(self);
+        self.indent -= 1;
+    }
+
+    pub fn finish(self) -> String {
+        self.buffer
+    }
+}
+
+fn comment(text: &str) -> String {
+    format!("// {}", text.replace('\n', " "))
+}
+
+fn with_position(statement: String, line: i32, column: i32, id: u64) -> String {
+    if line == 0 && column == 0 && id == 0 {
+        statement
+    } else {
+        format!("{} // pos {}:{} #{}", statement, line, column, id)
+    }
+}
+
+/// The header of a method; like Viper's pretty printer, it leaves out an
+/// empty `returns` clause.
+fn method_header(name: &str, parameters: String, returns: String) -> String {
+    if returns.is_empty() {
+        format!("method {}({})", name, parameters)
+    } else {
+        format!("method {}({}) returns ({})", name, parameters, returns)
+    }
+}
+
+fn join(items: impl IntoIterator<Item = String>) -> String {
+    items.into_iter().collect::<Vec<_>>().join(", ")
+}
+
+/// The Viper declaration of the symbolic read permission amount `read$()`.
+fn write_read_permission_function(writer: &mut ViperTextWriter) {
+    writer.line("function read$(): Perm");
+    writer.line("  ensures none < result");
+    writer.line("  ensures result < write");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToViperText;
+    use crate::vir::{
+        ast::{Expr, Field, Function, LocalVar, PermAmount, Position, Stmt, Type},
+        cfg::{CfgMethod, Successor},
+        Program,
+    };
+    use vir::low::{
+        ast::{
+            expression::{BinaryOp, BinaryOpKind, Constant, ConstantValue, Expression, Local},
+            position::Position as LowPosition,
+            statement::{Assert, Comment, Conditional, Statement},
+            ty::Type as LowType,
+            variable::VariableDecl,
+        },
+        cfg::{
+            procedure::{BasicBlock, ProcedureDecl, Successor as LowSuccessor},
+            Label,
+        },
+        program::Program as LowProgram,
+    };
+
+    #[test]
+    fn legacy_program() {
+        let value_field = Field::new("val_int", Type::Int);
+        let x = LocalVar::new("_1", Type::TypedRef("i32".to_string()));
+        let x_value = Expr::local(x.clone()).field(value_field.clone());
+        let mut method = CfgMethod::new("m_positive".to_string(), 0, vec![], vec![x]);
+        let start = method.add_block(
+            "start",
+            vec![
+                Stmt::comment("check the value"),
+                Stmt::Inhale(Expr::acc_permission(x_value.clone(), PermAmount::Write)),
+                Stmt::Assert(Expr::gt_cmp(x_value, 0i32.into()), Position::new(3, 5, 42)),
+            ],
+        );
+        method.set_successor(start, Successor::Return);
+        let double = LocalVar::new("x", Type::Int);
+        let program = Program {
+            name: "positive".to_string(),
+            domains: vec![],
+            fields: vec![value_field],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![Function {
+                name: "double".to_string(),
+                formal_args: vec![double.clone()],
+                return_type: Type::Int,
+                pres: vec![],
+                posts: vec![],
+                body: Some(Expr::add(Expr::local(double.clone()), Expr::local(double))),
+            }],
+            viper_predicates: vec![],
+        };
+        let expected = r#"// program positive
+
+field val_int: Int
+
+function double(x: Int): Int
+{
+  (x) + (x)
+}
+
+function read$(): Perm
+  ensures none < result
+  ensures result < write
+
+method m_positive() {
+  var _1: Ref
+  label start
+  // check the value
+  inhale acc(_1.val_int, write)
+  assert (_1.val_int) > (0) // pos 3:5 #42
+  goto end_of_method
+  label end_of_method
+}
+"#;
+        assert_eq!(program.to_viper_text(), expected);
+    }
+
+    #[test]
+    fn low_program() {
+        let x = VariableDecl {
+            name: "x".to_string(),
+            ty: LowType::Int,
+        };
+        let x_positive = Expression::BinaryOp(BinaryOp {
+            op_kind: BinaryOpKind::GtCmp,
+            left: Box::new(Expression::Local(Local {
+                variable: x.clone(),
+                position: LowPosition::default(),
+            })),
+            right: Box::new(Expression::Constant(Constant {
+                value: ConstantValue::Int(0),
+                ty: LowType::Int,
+                position: LowPosition::default(),
+            })),
+            position: LowPosition::default(),
+        });
+        let start = Label {
+            name: "start".to_string(),
+        };
+        let program = LowProgram {
+            name: "positive".to_string(),
+            domains: vec![],
+            predicates: vec![],
+            functions: vec![],
+            methods: vec![],
+            procedures: vec![ProcedureDecl {
+                name: "m_positive".to_string(),
+                locals: vec![x],
+                basic_blocks: vec![BasicBlock {
+                    label: start.clone(),
+                    statements: vec![
+                        Statement::Conditional(Conditional {
+                            guard: x_positive.clone(),
+                            then_branch: vec![Statement::Comment(Comment {
+                                comment: "x is positive".to_string(),
+                            })],
+                            else_branch: vec![],
+                            position: LowPosition::default(),
+                        }),
+                        Statement::Assert(Assert {
+                            expression: x_positive.clone(),
+                            position: LowPosition::new(3, 5, 42),
+                        }),
+                    ],
+                    successor: LowSuccessor::GotoSwitch(vec![(x_positive, start)]),
+                }],
+            }],
+        };
+        let expected = r#"// program positive
+
+function read$(): Perm
+  ensures none < result
+  ensures result < write
+
+method m_positive() {
+  var x: Int
+  label start
+  if ((x) > (0)) {
+    // x is positive
+  } else {
+  }
+  assert (x) > (0) // pos 3:5 #42
+  if ((x) > (0)) {
+    goto start
+  }
+  assert false
+  label end_of_method
+}
+"#;
+        assert_eq!(program.to_viper_text(), expected);
+    }
+}
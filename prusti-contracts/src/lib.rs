@@ -107,4 +107,34 @@ pub fn exists<T, F>(_trigger_set: T, _closure: F) -> bool {
     true
 }
 
+macro_rules! specification_entailments {
+    ($($name:ident($($arg:ident),*);)*) => {$(
+        /// This function is used to state that the given closure or function
+        /// pointer satisfies the given precondition and postcondition.
+        pub fn $name<$($arg,)* R, F, P, Q>(
+            _once: bool,
+            _closure: &F,
+            _precondition: P,
+            _postcondition: Q,
+        ) -> bool
+        where
+            F: FnOnce($($arg),*) -> R,
+            P: Fn($($arg),*) -> bool,
+            Q: Fn($($arg,)* R) -> bool,
+        {
+            true
+        }
+    )*};
+}
+
+specification_entailments! {
+    specification_entailment_0();
+    specification_entailment_1(A0);
+    specification_entailment_2(A0, A1);
+    specification_entailment_3(A0, A1, A2);
+    specification_entailment_4(A0, A1, A2, A3);
+    specification_entailment_5(A0, A1, A2, A3, A4);
+    specification_entailment_6(A0, A1, A2, A3, A4, A5);
+}
+
 pub use private::*;
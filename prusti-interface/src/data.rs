@@ -8,6 +8,7 @@
 //! its environment.
 
 use rustc_hir::def_id::DefId;
+use serde::Serialize;
 
 /// A unique identifier of the Rust procedure.
 pub type ProcedureDefId = DefId;
@@ -28,3 +29,17 @@ pub enum VerificationResult {
     /// the verifier.
     Failure,
 }
+
+/// How many of the procedures of a verification task verified, failed, use
+/// features that Prusti does not support or are trusted.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+pub struct VerificationSummary {
+    pub verified: usize,
+    pub failed: usize,
+    pub unsupported: usize,
+    /// Trusted procedures, whose bodies are not verified.
+    pub trusted: usize,
+    /// Number of overflow checks that were not encoded because the interval
+    /// analysis proved them.
+    pub discharged_overflow_checks: usize,
+}
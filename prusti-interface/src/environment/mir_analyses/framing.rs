@@ -0,0 +1,113 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! This module computes which places can be framed across a set of
+//! statements, e.g. across a loop body, using the framing analysis of the
+//! `analysis` crate.
+
+use crate::utils::is_prefix;
+use analysis::domains::FramingAnalysis;
+use log::debug;
+use prusti_common::Stopwatch;
+use rustc_borrowck::BodyWithBorrowckFacts;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+use rustc_middle::{mir, ty::TyCtxt};
+
+/// The places that can be framed across each statement and terminator.
+pub struct FramingInfo<'tcx> {
+    /// The places of which all the permission can be framed.
+    owned: FxHashMap<mir::Location, Vec<mir::Place<'tcx>>>,
+    /// The places of which a read permission can be framed.
+    accessible: FxHashMap<mir::Location, Vec<mir::Place<'tcx>>>,
+}
+
+impl<'tcx> FramingInfo<'tcx> {
+    /// The places whose value is not changed by any of the statements and
+    /// terminators at `locations`. If `owned` is true, the places are also
+    /// not read.
+    pub fn get_framed_across(
+        &self,
+        locations: impl IntoIterator<Item = mir::Location>,
+        owned: bool,
+    ) -> Vec<mir::Place<'tcx>> {
+        let framed = if owned { &self.owned } else { &self.accessible };
+        let mut locations = locations.into_iter();
+        let mut result = match locations.next().and_then(|location| framed.get(&location)) {
+            Some(places) => places.clone(),
+            None => return vec![],
+        };
+        for location in locations {
+            let places = match framed.get(&location) {
+                Some(places) => places,
+                None => return vec![],
+            };
+            // Keep the places framed at both locations. The framing analysis
+            // might split a place in its fields at some locations only.
+            let mut intersection: Vec<_> = result
+                .iter()
+                .filter(|place| places.iter().any(|other| is_prefix(place, other)))
+                .chain(
+                    places
+                        .iter()
+                        .filter(|place| result.iter().any(|other| is_prefix(place, other))),
+                )
+                .cloned()
+                .collect();
+            intersection.sort_by_key(|place| (place.local, place.projection.len()));
+            result = vec![];
+            for place in intersection {
+                if !result.iter().any(|other| is_prefix(&place, other)) {
+                    result.push(place);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Returns `None` if the analysis fails.
+pub fn compute_framing<'a, 'tcx: 'a>(
+    def_id: DefId,
+    body_with_facts: &'a BodyWithBorrowckFacts<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> Option<FramingInfo<'tcx>> {
+    let stopwatch = Stopwatch::start_debug("prusti-client", "framing analysis");
+    let body = &body_with_facts.body;
+    let analysis = FramingAnalysis::new(tcx, def_id, body_with_facts);
+    let pointwise_state = match analysis.run_analysis() {
+        Ok(pointwise_state) => pointwise_state,
+        Err(error) => {
+            debug!(
+                "Framing analysis of the function at {:?} failed: {}",
+                body.span,
+                error.to_pretty_str(body)
+            );
+            return None;
+        }
+    };
+
+    let mut result = FramingInfo {
+        owned: FxHashMap::default(),
+        accessible: FxHashMap::default(),
+    };
+    for (block, block_data) in body.basic_blocks().iter_enumerated() {
+        for statement_index in 0..=block_data.statements.len() {
+            let location = mir::Location {
+                block,
+                statement_index,
+            };
+            if let Some(state) = pointwise_state.lookup_before(location) {
+                let owned = state.get_framed_owned().iter().map(|place| **place);
+                result.owned.insert(location, owned.collect());
+                let accessible = state.get_framed_accessible().iter().map(|place| **place);
+                result.accessible.insert(location, accessible.collect());
+            }
+        }
+    }
+    stopwatch.finish();
+    Some(result)
+}
@@ -7,4 +7,6 @@
 //! This module defines various MIR analyses.
 //! Consider using the new `analysis` crate instead of this module.
 
+pub mod framing;
 pub mod initialization;
+pub mod overflow;
@@ -0,0 +1,60 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! This module finds the arithmetic operations of a MIR body that cannot
+//! overflow, using the interval analysis of the `analysis` crate.
+
+use analysis::{abstract_interpretation::FixpointEngine, domains::IntervalAnalysis};
+use log::debug;
+use prusti_common::Stopwatch;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir::def_id::DefId;
+use rustc_middle::{mir, ty::TyCtxt};
+
+/// Returns the locations of the `CheckedBinaryOp` assignments whose
+/// operation provably does not overflow. If the analysis fails, no
+/// operation is considered safe.
+pub fn compute_overflow_free_operations<'a, 'tcx: 'a>(
+    def_id: DefId,
+    body: &'a mir::Body<'tcx>,
+    tcx: TyCtxt<'tcx>,
+) -> FxHashSet<mir::Location> {
+    let stopwatch = Stopwatch::start_debug("prusti-client", "interval analysis");
+    let analysis = IntervalAnalysis::new(tcx, def_id, body);
+    let pointwise_state = match analysis.run_fwd_analysis() {
+        Ok(pointwise_state) => pointwise_state,
+        Err(error) => {
+            debug!(
+                "Interval analysis of the function at {:?} failed: {}",
+                body.span,
+                error.to_pretty_str(body)
+            );
+            return FxHashSet::default();
+        }
+    };
+
+    let mut result = FxHashSet::default();
+    for (bb, bb_data) in body.basic_blocks().iter_enumerated() {
+        for (statement_index, statement) in bb_data.statements.iter().enumerate() {
+            if let mir::StatementKind::Assign(box (
+                _,
+                mir::Rvalue::CheckedBinaryOp(op, box (left, right)),
+            )) = &statement.kind
+            {
+                let location = mir::Location {
+                    block: bb,
+                    statement_index,
+                };
+                let state = pointwise_state.lookup_before(location);
+                if matches!(state, Some(state) if state.cannot_overflow(*op, left, right)) {
+                    result.insert(location);
+                }
+            }
+        }
+    }
+    stopwatch.finish();
+    result
+}
@@ -1,5 +1,6 @@
 mod to_text;
 
+pub use self::to_text::escape_html;
 pub(super) use self::to_text::{
     loan_containment_to_text, loan_to_text, loans_to_text, to_sorted_text, ToText,
 };
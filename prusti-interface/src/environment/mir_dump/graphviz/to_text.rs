@@ -10,11 +10,19 @@ pub(in super::super::super) fn to_sorted_text<S: ToText>(texts: &[S]) -> Vec<Str
     strings
 }
 
-fn escape_html<S: ToString>(s: S) -> String {
+/// Escapes the characters that have a special meaning in HTML text and attributes.
+pub fn escape_html<S: ToString>(s: S) -> String {
     s.to_string()
         .replace('&', "&amp;")
         .replace('>', "&gt;")
         .replace('<', "&lt;")
+        .replace('"', "&quot;")
+}
+
+/// Like `escape_html`, but also escapes the characters that have a special meaning in the
+/// labels of Graphviz.
+fn escape_graphviz_html<S: ToString>(s: S) -> String {
+    escape_html(s)
         .replace('{', "\\{")
         .replace('}', "\\}")
         .replace('\n', "<br/>")
@@ -126,18 +134,18 @@ impl ToText for rustc_middle::mir::Location {
 
 impl<'tcx> ToText for rustc_middle::mir::Statement<'tcx> {
     fn to_text(&self) -> String {
-        escape_html(format!("{:?}", self))
+        escape_graphviz_html(format!("{:?}", self))
     }
 }
 
 impl<'tcx> ToText for rustc_middle::mir::Terminator<'tcx> {
     fn to_text(&self) -> String {
-        escape_html(format!("{:?}", self.kind))
+        escape_graphviz_html(format!("{:?}", self.kind))
     }
 }
 
 impl<'tcx> ToText for rustc_middle::ty::Ty<'tcx> {
     fn to_text(&self) -> String {
-        escape_html(format!("{:?}", self))
+        escape_graphviz_html(format!("{:?}", self))
     }
 }
@@ -0,0 +1,39 @@
+use rustc_middle::mir;
+use rustc_span::Span;
+
+/// A MIR statement or terminator as shown in the debug dumps.
+pub struct MirStatement {
+    pub location: mir::Location,
+    /// The statement as printed by its `Debug` implementation.
+    pub text: String,
+    pub span: Span,
+}
+
+/// Lists the statements and terminators of `mir` in the order of the basic
+/// blocks. A terminator has the location after the last statement of its block.
+pub fn mir_statements(mir: &mir::Body<'_>) -> Vec<MirStatement> {
+    let mut statements = Vec::new();
+    for (block, data) in mir.basic_blocks().iter_enumerated() {
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            statements.push(MirStatement {
+                location: mir::Location {
+                    block,
+                    statement_index,
+                },
+                text: format!("{:?}", statement),
+                span: statement.source_info.span,
+            });
+        }
+        if let Some(terminator) = &data.terminator {
+            statements.push(MirStatement {
+                location: mir::Location {
+                    block,
+                    statement_index: data.statements.len(),
+                },
+                text: format!("{:?}", terminator.kind),
+                span: terminator.source_info.span,
+            });
+        }
+    }
+    statements
+}
@@ -4,8 +4,14 @@ use rustc_span::def_id::DefId;
 
 mod graphviz;
 pub mod lifetimes;
+mod listing;
 mod mir;
 
+pub use self::{
+    graphviz::escape_html,
+    listing::{mir_statements, MirStatement},
+};
+
 pub(crate) fn dump_mir_info(env: &Environment<'_>, def_id: DefId) {
     eprintln!("def_id: {:?}", def_id);
     let local_def_id = def_id.expect_local();
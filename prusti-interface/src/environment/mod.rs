@@ -9,6 +9,8 @@
 
 
 use rustc_middle::mir;
+use rustc_hir as hir;
+use rustc_hir::def::DefKind;
 use rustc_hir::hir_id::HirId;
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_middle::ty::{self, TyCtxt};
@@ -46,6 +48,7 @@ pub use self::loops::{PlaceAccess, PlaceAccessKind, ProcedureLoops};
 pub use self::loops_utils::*;
 pub use self::procedure::{BasicBlockIndex, Procedure};
 use self::borrowck::facts::BorrowckFacts;
+use self::mir_analyses::framing::{compute_framing, FramingInfo};
 // use config;
 use crate::data::ProcedureDefId;
 // use syntax::codemap::CodeMap;
@@ -60,6 +63,8 @@ pub struct Environment<'tcx> {
     bodies: RefCell<HashMap<LocalDefId, Rc<mir::Body<'tcx>>>>,
     /// Cached borrowck information.
     borrowck_facts: RefCell<HashMap<LocalDefId, Rc<BorrowckFacts>>>,
+    /// Cached results of the framing analysis.
+    framing: RefCell<HashMap<LocalDefId, Option<Rc<FramingInfo<'tcx>>>>>,
     tcx: TyCtxt<'tcx>,
 }
 
@@ -70,6 +75,7 @@ impl<'tcx> Environment<'tcx> {
             tcx,
             bodies: RefCell::new(HashMap::new()),
             borrowck_facts: RefCell::new(HashMap::new()),
+            framing: RefCell::new(HashMap::new()),
         }
     }
 
@@ -91,6 +97,28 @@ impl<'tcx> Environment<'tcx> {
             .to_string()
     }
 
+    /// Returns `test` for a test harness and otherwise the crate type, like
+    /// `lib` or `bin`
+    pub fn crate_kind(&self) -> String {
+        let sess = self.tcx.sess;
+        if sess.opts.test {
+            "test".to_string()
+        } else {
+            sess.crate_types()
+                .iter()
+                .map(|crate_type| crate_type.to_string())
+                .collect::<Vec<_>>()
+                .join("-")
+        }
+    }
+
+    /// Returns a hash that distinguishes the crates that cargo compiles with
+    /// the same name and kind, like the test harnesses of a library and of a
+    /// binary
+    pub fn crate_hash(&self) -> u64 {
+        self.tcx.sess.local_stable_crate_id().to_u64()
+    }
+
     /// Returns the typing context
     pub fn tcx(&self) -> TyCtxt<'tcx> {
         self.tcx
@@ -255,6 +283,12 @@ impl<'tcx> Environment<'tcx> {
             let body_with_facts = unsafe {
                 self::mir_storage::retrieve_mir_body(self.tcx, def_id)
             };
+            // The framing analysis needs the borrowck facts together with
+            // the body, so it cannot be computed lazily.
+            if prusti_common::config::loop_framing_invariants() {
+                let framing = compute_framing(def_id.to_def_id(), &body_with_facts, self.tcx);
+                self.framing.borrow_mut().insert(def_id, framing.map(Rc::new));
+            }
             let body = body_with_facts.body;
             let facts = BorrowckFacts {
                 input_facts: RefCell::new(Some(body_with_facts.input_facts)),
@@ -282,6 +316,13 @@ impl<'tcx> Environment<'tcx> {
         borrowck_facts.get(&def_id).cloned()
     }
 
+    /// Get the result of the framing analysis of a local procedure, if the
+    /// analysis is enabled and succeeded.
+    pub fn local_mir_framing(&self, def_id: LocalDefId) -> Option<Rc<FramingInfo<'tcx>>> {
+        self.local_mir(def_id);
+        self.framing.borrow().get(&def_id).cloned().flatten()
+    }
+
     /// Get the MIR body of an external procedure.
     pub fn external_mir<'a>(&self, def_id: DefId) -> &'a mir::Body<'tcx> {
         self.tcx().optimized_mir(def_id)
@@ -320,6 +361,26 @@ impl<'tcx> Environment<'tcx> {
             .is_some()
     }
 
+    /// Returns true iff `def_id` is an `async fn`.
+    pub fn is_async_fn(&self, def_id: ProcedureDefId) -> bool {
+        matches!(self.tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+            && self.tcx.asyncness(def_id) == hir::IsAsync::Async
+    }
+
+    /// If `def_id` is the generator that implements the body of an
+    /// `async fn`, returns the `DefId` of the `async fn`.
+    pub fn get_async_fn_of_body(&self, def_id: ProcedureDefId) -> Option<ProcedureDefId> {
+        if !def_id.is_local() || !self.tcx.is_closure(def_id) {
+            return None;
+        }
+        match self.tcx.generator_kind(def_id) {
+            Some(hir::GeneratorKind::Async(hir::AsyncGeneratorKind::Fn)) => {
+                Some(self.tcx.typeck_root_def_id(def_id))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns the `DefId` of the corresponding trait method
     pub fn find_trait_method(&self, impl_def_id: ProcedureDefId) -> Option<DefId> {
         self.tcx
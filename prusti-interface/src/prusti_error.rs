@@ -78,6 +78,15 @@ impl PrustiError {
         error
     }
 
+    /// Report that the verifier ran out of time while verifying the Rust code
+    pub fn timeout<S: ToString>(message: S, span: MultiSpan) -> Self {
+        check_message(message.to_string());
+        PrustiError::new(
+            format!("[Prusti: verification timed out] {}", message.to_string()),
+            span
+        )
+    }
+
     /// Report an unsupported feature of the verified Rust code (e.g. dereferencing raw pointers)
     pub fn unsupported<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
@@ -5,7 +5,13 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use prusti_launch::get_rust_toolchain_channel;
-use std::{path::PathBuf, process::Command};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 fn main() {
     if let Err(code) = process(std::env::args().skip(1)) {
@@ -26,28 +32,316 @@ where
 
     // Remove the leading "prusti" argument when `cargo-prusti` is invocated
     // as `cargo prusti` (note the space)
-    let clean_args = args.skip_while(|x| x == "prusti");
+    let clean_args: Vec<String> = args.skip_while(|x| x == "prusti").collect();
+    // The arguments after `--` are passed to the compiler.
+    let (cargo_args, compiler_args) = clean_args.split_at(
+        clean_args
+            .iter()
+            .position(|arg| arg == "--")
+            .unwrap_or(clean_args.len()),
+    );
+    // `--summary` is our own flag, which cargo does not know.
+    let show_summary = cargo_args.iter().any(|arg| arg == "--summary");
+    let cargo_args: Vec<String> = cargo_args
+        .iter()
+        .filter(|arg| *arg != "--summary")
+        .cloned()
+        .collect();
 
     let cargo_path = std::env::var("CARGO_PATH").unwrap_or_else(|_| "cargo".to_string());
     let mut cargo_target =
         PathBuf::from(std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string()));
     cargo_target.push("verify");
+    // The compiler does not necessarily run in the current directory.
+    let cargo_target = std::env::current_dir()
+        .expect("current directory invalid")
+        .join(cargo_target);
+
+    let workspace = match read_workspace(&cargo_path, &cargo_args) {
+        Ok(workspace) => workspace,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return Err(1);
+        }
+    };
+    let config_dir = cargo_target.join("config");
+    let summary_dir = cargo_target.join("summary");
+    if let Err(error) = write_package_configs(&workspace, &cargo_args, &config_dir, &summary_dir) {
+        eprintln!("error: {}", error);
+        return Err(1);
+    }
+
     let exit_status = Command::new(cargo_path)
         .arg("check")
-        .args(clean_args)
+        .args(&cargo_args)
+        .args(compiler_args)
         .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
         .env("RUSTC_WRAPPER", prusti_rustc_path)
+        .env("CARGO_PRUSTI_CONFIG_DIR", &config_dir)
         .env("DEFAULT_PRUSTI_QUIET", "true")
         .env("DEFAULT_PRUSTI_FULL_COMPILATION", "true")
         .env("DEFAULT_PRUSTI_LOG_DIR", cargo_target.join("log"))
         .env("DEFAULT_PRUSTI_CACHE_PATH", cargo_target.join("cache.bin"))
-        .env("CARGO_TARGET_DIR", cargo_target)
+        .env("CARGO_TARGET_DIR", &cargo_target)
         .status()
         .expect("could not run cargo");
 
+    if show_summary {
+        print_summary(&workspace, &summary_dir);
+    }
+
     if exit_status.success() {
         Ok(())
     } else {
         Err(exit_status.code().unwrap_or(-1))
     }
 }
+
+/// The subset of the output of `cargo metadata` that we need.
+#[derive(Deserialize)]
+struct Workspace {
+    workspace_root: PathBuf,
+    workspace_members: Vec<String>,
+    packages: Vec<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    #[serde(default)]
+    metadata: Option<serde_json::Value>,
+}
+
+impl Workspace {
+    fn members(&self) -> impl Iterator<Item = &Package> {
+        self.packages
+            .iter()
+            .filter(|package| self.workspace_members.contains(&package.id))
+    }
+}
+
+/// Read the workspace with `cargo metadata`. The error is the message to
+/// report, for example the error of cargo when there is no `Cargo.toml`.
+fn read_workspace(cargo_path: &str, args: &[String]) -> Result<Workspace, String> {
+    let mut cmd = Command::new(cargo_path);
+    cmd.arg("metadata")
+        .arg("--no-deps")
+        .arg("--format-version")
+        .arg("1");
+    if let Some(manifest_path) = arg_values(args, "--manifest-path").next() {
+        cmd.arg("--manifest-path").arg(manifest_path);
+    }
+    let output = cmd
+        .output()
+        .map_err(|error| format!("could not run cargo metadata: {}", error))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(stderr.strip_prefix("error: ").unwrap_or(stderr).to_string());
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|error| format!("could not parse the output of cargo metadata: {}", error))
+}
+
+/// Iterate over the values of a cargo flag that is given as `--flag value` or
+/// `--flag=value`, or additionally as `-fvalue` for a short flag `-f`.
+fn arg_values<'a>(args: &'a [String], flag: &'a str) -> impl Iterator<Item = &'a str> {
+    let is_short = !flag.starts_with("--");
+    args.iter().enumerate().filter_map(move |(index, arg)| {
+        if arg == flag {
+            args.get(index + 1).map(String::as_str)
+        } else {
+            let rest = arg.strip_prefix(flag)?;
+            match rest.strip_prefix('=') {
+                Some(value) => Some(value),
+                None if is_short && !rest.is_empty() => Some(rest),
+                None => None,
+            }
+        }
+    })
+}
+
+/// The names of the workspace members selected with `-p`/`--package` and
+/// `--exclude`. Without `-p`, all members are selected.
+fn selected_packages(workspace: &Workspace, args: &[String]) -> HashSet<String> {
+    // Package specs may contain a version, like `foo:1.0.0` or `foo@1.0.0`.
+    let spec_name = |spec: &str| {
+        spec.split(|c| c == ':' || c == '@')
+            .next()
+            .unwrap()
+            .to_string()
+    };
+    let mut selected: HashSet<String> = arg_values(args, "--package")
+        .chain(arg_values(args, "-p"))
+        .map(spec_name)
+        .collect();
+    if selected.is_empty() {
+        selected = workspace
+            .members()
+            .map(|package| package.name.clone())
+            .collect();
+    }
+    for excluded in arg_values(args, "--exclude") {
+        selected.remove(&spec_name(excluded));
+    }
+    selected
+}
+
+/// Read the workspace-level `Prusti.toml`, or the file given by
+/// `PRUSTI_CONFIG`. The error is the message to report.
+fn read_workspace_config(workspace: &Workspace) -> Result<toml::value::Table, String> {
+    let path = std::env::var("PRUSTI_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| workspace.workspace_root.join("Prusti.toml"));
+    match fs::read_to_string(&path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|error| format!("could not parse {}: {}", path.display(), error)),
+        Err(_) => Ok(toml::value::Table::new()),
+    }
+}
+
+/// Write, for each workspace member, a Prusti configuration file that
+/// combines the workspace-level `Prusti.toml` with the package's
+/// `[package.metadata.prusti]` table. `prusti-rustc` passes it to Prusti via
+/// `PRUSTI_CONFIG`, so the keys are the same as in `Prusti.toml`. The error
+/// is the message to report.
+fn write_package_configs(
+    workspace: &Workspace,
+    args: &[String],
+    config_dir: &Path,
+    summary_dir: &Path,
+) -> Result<(), String> {
+    let workspace_config = read_workspace_config(workspace)?;
+    let selected = selected_packages(workspace, args);
+    fs::create_dir_all(config_dir)
+        .map_err(|error| format!("could not create {}: {}", config_dir.display(), error))?;
+    for package in workspace.members() {
+        let mut config = workspace_config.clone();
+        if let Some(metadata) = package
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("prusti"))
+        {
+            let package_config: toml::value::Table = serde_json::from_value(metadata.clone())
+                .map_err(|error| {
+                    format!(
+                        "invalid [package.metadata.prusti] table in package {}: {}",
+                        package.name, error
+                    )
+                })?;
+            config.extend(package_config);
+        }
+        let package_summary_dir = summary_dir.join(&package.name);
+        if !selected.contains(&package.name) {
+            config.insert("no_verify".to_string(), toml::Value::Boolean(true));
+            let _ = fs::remove_dir_all(&package_summary_dir);
+        }
+        config.insert(
+            "verification_summary_dir".to_string(),
+            toml::Value::String(package_summary_dir.to_string_lossy().to_string()),
+        );
+        let config_path = config_dir.join(format!("{}.toml", package.name));
+        let content = toml::to_string(&config).map_err(|error| {
+            format!(
+                "could not serialize the configuration of package {}: {}",
+                package.name, error
+            )
+        })?;
+        fs::write(&config_path, content)
+            .map_err(|error| format!("could not write {}: {}", config_path.display(), error))?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct VerificationSummary {
+    #[serde(rename = "crate")]
+    crate_name: String,
+    kind: String,
+    verified: usize,
+    failed: usize,
+    unsupported: usize,
+    trusted: usize,
+}
+
+/// Read the summaries that Prusti wrote for the crates of a package, keyed by
+/// crate name and kind.
+fn read_summaries(dir: &Path) -> BTreeMap<(String, String), VerificationSummary> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return BTreeMap::new(),
+    };
+    entries
+        .filter_map(|entry| fs::read(entry.ok()?.path()).ok())
+        .filter_map(|content| serde_json::from_slice::<VerificationSummary>(&content).ok())
+        .map(|summary| ((summary.crate_name.clone(), summary.kind.clone()), summary))
+        .collect()
+}
+
+/// Print, per crate of each workspace member, how many items verified,
+/// failed, use unsupported features or are trusted. Crates that cargo did not
+/// need to check again keep the summary of their last verification.
+fn print_summary(workspace: &Workspace, summary_dir: &Path) {
+    let mut rows: Vec<[String; 7]> = vec![];
+    let mut members: Vec<_> = workspace.members().collect();
+    members.sort_by(|first, second| first.name.cmp(&second.name));
+    for package in members {
+        let summaries = read_summaries(&summary_dir.join(&package.name));
+        if summaries.is_empty() {
+            let mut row = [(); 7].map(|_| "-".to_string());
+            row[0] = package.name.clone();
+            rows.push(row);
+        }
+        for ((crate_name, kind), summary) in summaries {
+            rows.push([
+                package.name.clone(),
+                crate_name,
+                kind,
+                summary.verified.to_string(),
+                summary.failed.to_string(),
+                summary.unsupported.to_string(),
+                summary.trusted.to_string(),
+            ]);
+        }
+    }
+    let header = [
+        "package",
+        "crate",
+        "kind",
+        "verified",
+        "failed",
+        "unsupported",
+        "trusted",
+    ];
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .chain(std::iter::once(header[column].len()))
+                .max()
+                .unwrap()
+        })
+        .collect();
+    let print_row = |row: &[&str]| {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(column, (cell, &width))| {
+                // Names are aligned to the left, counts to the right.
+                if column < 3 {
+                    format!("{:<width$}", cell)
+                } else {
+                    format!("{:>width$}", cell)
+                }
+            })
+            .collect();
+        eprintln!("{}", cells.join("  ").trim_end());
+    };
+    eprintln!();
+    print_row(&header);
+    for row in &rows {
+        print_row(&row.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+}
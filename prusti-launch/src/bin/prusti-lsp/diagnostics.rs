@@ -0,0 +1,198 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversion of the compiler's JSON diagnostics (`--error-format=json`) to
+//! LSP diagnostics.
+
+use crate::{path_to_uri, position::utf16_column};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    level: String,
+    spans: Vec<RustcSpan>,
+    children: Vec<RustcDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: u64,
+    line_end: u64,
+    column_start: u64,
+    column_end: u64,
+    is_primary: bool,
+    label: Option<String>,
+    /// The source lines of the span.
+    #[serde(default)]
+    text: Vec<RustcSpanLine>,
+}
+
+#[derive(Deserialize)]
+struct RustcSpanLine {
+    text: String,
+}
+
+impl RustcSpan {
+    fn path(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(&self.file_name)
+    }
+
+    /// LSP positions are zero-based and count UTF-16 code units, the
+    /// compiler's are one-based and count characters.
+    fn range(&self) -> Value {
+        let column = |line: Option<&RustcSpanLine>, column: u64| {
+            let column = column.saturating_sub(1) as usize;
+            line.map_or(column, |line| utf16_column(&line.text, column))
+        };
+        json!({
+            "start": {
+                "line": self.line_start.saturating_sub(1),
+                "character": column(self.text.first(), self.column_start),
+            },
+            "end": {
+                "line": self.line_end.saturating_sub(1),
+                "character": column(self.text.last(), self.column_end),
+            },
+        })
+    }
+
+    fn location(&self, base_dir: &Path) -> Value {
+        json!({
+            "uri": path_to_uri(&self.path(base_dir)),
+            "range": self.range(),
+        })
+    }
+}
+
+fn severity(level: &str) -> u64 {
+    match level {
+        "error" | "error: internal compiler error" => 1,
+        "warning" => 2,
+        "note" | "failure-note" => 3,
+        _ => 4,
+    }
+}
+
+/// Parse the compiler's standard error and group the diagnostics by the file
+/// of their primary span. Relative file names are resolved against
+/// `base_dir`, the working directory of the compiler.
+pub fn parse_diagnostics(stderr: &str, base_dir: &Path) -> HashMap<PathBuf, Vec<Value>> {
+    let mut diagnostics: HashMap<PathBuf, Vec<Value>> = HashMap::new();
+    for line in stderr.lines() {
+        let diagnostic: RustcDiagnostic = match serde_json::from_str(line) {
+            Ok(diagnostic) => diagnostic,
+            // Prusti prints some messages that are not diagnostics.
+            Err(_) => continue,
+        };
+        // Summaries like "aborting due to previous error" have no span.
+        let primary_span = match diagnostic.spans.iter().find(|span| span.is_primary) {
+            Some(span) => span,
+            None => continue,
+        };
+        let mut message = diagnostic.message.clone();
+        let mut related_information = vec![];
+        for span in &diagnostic.spans {
+            if let Some(label) = &span.label {
+                if !span.is_primary {
+                    related_information.push(json!({
+                        "location": span.location(base_dir),
+                        "message": label,
+                    }));
+                }
+            }
+        }
+        // Notes and help messages, including counterexamples, are children.
+        for child in &diagnostic.children {
+            let child_message = format!("{}: {}", child.level, child.message);
+            if child.spans.is_empty() {
+                message.push('\n');
+                message.push_str(&child_message);
+            }
+            for span in &child.spans {
+                related_information.push(json!({
+                    "location": span.location(base_dir),
+                    "message": child_message,
+                }));
+            }
+        }
+        diagnostics
+            .entry(primary_span.path(base_dir))
+            .or_default()
+            .push(json!({
+                "range": primary_span.range(),
+                "severity": severity(&diagnostic.level),
+                "source": "prusti",
+                "message": message,
+                "relatedInformation": related_information,
+            }));
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STDERR: &str = r#"Verification of 1 items...
+{"message":"[Prusti: verification error] the asserted expression might not hold","level":"error","spans":[{"file_name":"src/lib.rs","line_start":3,"line_end":3,"column_start":5,"column_end":21,"is_primary":true,"label":null,"text":[{"text":"    /* é */ assert!(x);","highlight_start":5,"highlight_end":21}]}],"children":[{"message":"counterexample for \"x\"","level":"note","spans":[{"file_name":"src/lib.rs","line_start":1,"line_end":1,"column_start":9,"column_end":10,"is_primary":true,"label":null,"text":[]}],"children":[]},{"message":"the failing assertion is here","level":"help","spans":[],"children":[]}]}
+{"message":"aborting due to previous error","level":"error","spans":[],"children":[]}
+"#;
+
+    #[test]
+    fn converts_errors_with_their_notes() {
+        let diagnostics = parse_diagnostics(STDERR, Path::new("/crate"));
+        assert_eq!(diagnostics.len(), 1);
+        let file_diagnostics = &diagnostics[Path::new("/crate/src/lib.rs")];
+        assert_eq!(file_diagnostics.len(), 1);
+        let diagnostic = &file_diagnostics[0];
+        assert_eq!(diagnostic["severity"], 1);
+        assert_eq!(
+            diagnostic["message"],
+            "[Prusti: verification error] the asserted expression might not hold\n\
+             help: the failing assertion is here"
+        );
+        // The `é` is a single UTF-16 unit, so the columns do not change.
+        assert_eq!(
+            diagnostic["range"],
+            json!({
+                "start": { "line": 2, "character": 4 },
+                "end": { "line": 2, "character": 20 },
+            })
+        );
+        let related_information = diagnostic["relatedInformation"].as_array().unwrap();
+        assert_eq!(related_information.len(), 1);
+        assert_eq!(
+            related_information[0]["message"],
+            "note: counterexample for \"x\""
+        );
+        assert_eq!(
+            related_information[0]["location"]["range"]["start"],
+            json!({ "line": 0, "character": 8 })
+        );
+    }
+
+    #[test]
+    fn counts_utf16_code_units() {
+        let stderr = r#"{"message":"m","level":"warning","spans":[{"file_name":"/a.rs","line_start":1,"line_end":1,"column_start":9,"column_end":12,"is_primary":true,"label":null,"text":[{"text":"let 🦀🦀 = foo;","highlight_start":9,"highlight_end":12}]}],"children":[]}"#;
+        let diagnostics = parse_diagnostics(stderr, Path::new("/"));
+        let diagnostic = &diagnostics[Path::new("/a.rs")][0];
+        assert_eq!(diagnostic["severity"], 2);
+        assert_eq!(
+            diagnostic["range"],
+            json!({
+                "start": { "line": 0, "character": 10 },
+                "end": { "line": 0, "character": 13 },
+            })
+        );
+    }
+}
@@ -0,0 +1,255 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Hovers that show the contract of the function under the cursor.
+//!
+//! The contracts are the ones that Prusti reported for the last verification
+//! of the open documents (see the `CONTRACT_REPORT_PATH` flag), so a hover is
+//! available once the document has been verified.
+
+use crate::position::byte_offset;
+use serde::Deserialize;
+
+/// The contract of a function, as reported by Prusti.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Contract {
+    pub name: String,
+    pub path: String,
+    pub signature: Option<String>,
+    pub attributes: Vec<String>,
+}
+
+/// Parse a contract report. A missing or malformed report has no contracts.
+pub fn parse_contracts(report: &str) -> Vec<Contract> {
+    serde_json::from_str(report).unwrap_or_default()
+}
+
+/// Compute the Markdown hover for the identifier at the given zero-based
+/// line and UTF-16 column of `text`, using the first contract with its name.
+pub fn hover<'a>(
+    text: &str,
+    line: usize,
+    character: usize,
+    contracts: impl IntoIterator<Item = &'a Contract>,
+) -> Option<String> {
+    let line_start = line_start(text, line)?;
+    let line_text = text[line_start..].lines().next().unwrap_or_default();
+    let offset = byte_offset(line_text, character);
+    if !is_code(text, line_start + offset) {
+        return None;
+    }
+    let name = identifier_at(line_text, offset)?;
+    let contract = contracts
+        .into_iter()
+        .find(|contract| contract.name == name)?;
+    let signature = contract
+        .signature
+        .clone()
+        .unwrap_or_else(|| format!("fn {}", contract.name));
+    Some(format!(
+        "```rust\n{}\n{}\n```\n\n`{}`",
+        contract.attributes.join("\n"),
+        signature,
+        contract.path
+    ))
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The byte offset at which the zero-based `line` of `text` starts.
+fn line_start(text: &str, line: usize) -> Option<usize> {
+    if line == 0 {
+        return Some(0);
+    }
+    text.match_indices('\n')
+        .nth(line - 1)
+        .map(|(offset, _)| offset + 1)
+}
+
+fn identifier_at(line: &str, offset: usize) -> Option<&str> {
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_identifier_char(c))
+        .last()
+        .map_or(offset, |(start, _)| start);
+    let end = line[offset..]
+        .char_indices()
+        .find(|&(_, c)| !is_identifier_char(c))
+        .map_or(line.len(), |(end, _)| offset + end);
+    let identifier = &line[start..end];
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_numeric()) {
+        None
+    } else {
+        Some(identifier)
+    }
+}
+
+/// Whether the byte `offset` of `text` is in code, as opposed to inside a
+/// comment or a string or character literal.
+fn is_code(text: &str, offset: usize) -> bool {
+    enum State {
+        Code,
+        LineComment,
+        /// The nesting depth of the block comments.
+        BlockComment(usize),
+        Str,
+        /// The number of `#` that close the raw string.
+        RawStr(usize),
+        Char,
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let at = |index: usize| chars.get(index).map(|&(_, c)| c);
+    let mut state = State::Code;
+    let mut index = 0;
+    while index < chars.len() && chars[index].0 < offset {
+        let c = chars[index].1;
+        index += 1;
+        state = match state {
+            State::Code => match c {
+                '/' if at(index) == Some('/') => State::LineComment,
+                '/' if at(index) == Some('*') => {
+                    index += 1;
+                    State::BlockComment(1)
+                }
+                '"' => State::Str,
+                'r' if matches!(at(index), Some('#') | Some('"'))
+                    && (index < 2
+                        || at(index - 2) == Some('b')
+                        || !at(index - 2).map_or(false, is_identifier_char)) =>
+                {
+                    let hashes = (index..chars.len())
+                        .take_while(|&i| at(i) == Some('#'))
+                        .count();
+                    if at(index + hashes) == Some('"') {
+                        index += hashes + 1;
+                        State::RawStr(hashes)
+                    } else {
+                        State::Code
+                    }
+                }
+                // A lifetime unless the literal is closed after one
+                // character or starts with an escape.
+                '\'' => match (at(index), at(index + 1)) {
+                    (Some('\\'), _) | (Some(_), Some('\'')) => State::Char,
+                    _ => State::Code,
+                },
+                _ => State::Code,
+            },
+            State::LineComment if c == '\n' => State::Code,
+            State::LineComment => State::LineComment,
+            State::BlockComment(depth) => match (c, at(index)) {
+                ('*', Some('/')) => {
+                    index += 1;
+                    if depth == 1 {
+                        State::Code
+                    } else {
+                        State::BlockComment(depth - 1)
+                    }
+                }
+                ('/', Some('*')) => {
+                    index += 1;
+                    State::BlockComment(depth + 1)
+                }
+                _ => State::BlockComment(depth),
+            },
+            State::Str | State::Char if c == '\\' => {
+                index += 1;
+                state
+            }
+            State::Str if c == '"' => State::Code,
+            State::Char if c == '\'' => State::Code,
+            State::RawStr(hashes)
+                if c == '"' && (index..index + hashes).all(|i| at(i) == Some('#')) =>
+            {
+                index += hashes;
+                State::Code
+            }
+            state => state,
+        };
+    }
+    matches!(state, State::Code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r#"use prusti_contracts::*;
+
+#[pure]
+#[ensures(result == x)]
+fn id(x: u32) -> u32 { x }
+
+fn test() {
+    // id is not called here
+    let s = "id";
+    let c = 'i'; let 🦀 = id(1);
+}
+"#;
+
+    fn contracts() -> Vec<Contract> {
+        parse_contracts(
+            r##"[{
+                "name": "id",
+                "path": "id",
+                "file": "test.rs",
+                "line": 5,
+                "column": 4,
+                "signature": "fn id(x: u32) -> u32",
+                "attributes": ["#[pure]", "#[ensures(result == x)]"]
+            }]"##,
+        )
+    }
+
+    #[test]
+    fn shows_the_reported_contract() {
+        let contracts = contracts();
+        let expected =
+            "```rust\n#[pure]\n#[ensures(result == x)]\nfn id(x: u32) -> u32\n```\n\n`id`";
+        assert_eq!(hover(TEXT, 4, 3, &contracts).as_deref(), Some(expected));
+        assert_eq!(hover(TEXT, 4, 5, &contracts).as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn uses_utf16_columns() {
+        let contracts = contracts();
+        // The crab takes two UTF-16 units, so `id` starts at column 26
+        // instead of 25.
+        assert!(hover(TEXT, 9, 26, &contracts).is_some());
+        assert!(hover(TEXT, 9, 25, &contracts).is_none());
+    }
+
+    #[test]
+    fn ignores_comments_and_strings() {
+        let contracts = contracts();
+        assert!(hover(TEXT, 7, 7, &contracts).is_none());
+        assert!(hover(TEXT, 8, 13, &contracts).is_none());
+    }
+
+    #[test]
+    fn needs_a_contract() {
+        assert!(hover(TEXT, 6, 4, &contracts()).is_none());
+        assert!(hover(TEXT, 4, 3, &[]).is_none());
+        assert!(parse_contracts("not json").is_empty());
+    }
+
+    #[test]
+    fn recognizes_code() {
+        let code_at_end = |text: &str| is_code(text, text.len() - 1);
+        assert!(code_at_end(
+            "let x = 'a'; let y: &'static str = \"\\\"\"; x"
+        ));
+        assert!(code_at_end("/* /* nested */ */ x"));
+        assert!(code_at_end("r#\"\"quoted\"\"# x"));
+        assert!(!code_at_end("/* /* nested */ x"));
+        assert!(!code_at_end("r#\"\" x"));
+        assert!(!code_at_end("let c = 'x'"));
+        assert!(!code_at_end("// comment"));
+    }
+}
@@ -0,0 +1,447 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A Language Server Protocol front-end for Prusti.
+//!
+//! The language server talks LSP over stdio. Whenever a Rust document is
+//! opened or saved, it runs `prusti-rustc` on it in a background thread and
+//! publishes the reported errors, with their notes and counterexamples, as
+//! diagnostics. The contracts that Prusti reports are shown in hovers. All
+//! runs share one `prusti-server` that is started on the first verification
+//! and lives as long as the language server, so that the JVM and the
+//! verification cache are reused.
+
+mod diagnostics;
+mod hover;
+mod position;
+mod transport;
+
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs, io,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+fn main() {
+    let current_executable_dir = env::current_exe()
+        .expect("current executable path invalid")
+        .parent()
+        .expect("failed to obtain the folder of the current executable")
+        .to_path_buf();
+
+    let mut server = LanguageServer::new(&current_executable_dir);
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    loop {
+        let message = match transport::read_message(&mut reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            // The malformed message has been consumed, so the next one can
+            // still be read.
+            Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                send(&json!({
+                    "jsonrpc": "2.0",
+                    "id": Value::Null,
+                    "error": {
+                        "code": -32700,
+                        "message": format!("Parse error: {}", error),
+                    },
+                }));
+                continue;
+            }
+            Err(error) => {
+                eprintln!("Could not read LSP message: {}", error);
+                std::process::exit(1);
+            }
+        };
+        if let Some(exit_code) = server.handle_message(message) {
+            std::process::exit(exit_code);
+        }
+    }
+}
+
+/// Convert a file path to a `file://` URI.
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Convert a `file://` URI to a file path.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let mut bytes = vec![];
+    let mut iter = encoded.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex: String = iter.by_ref().take(2).map(char::from).collect();
+            bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // Windows paths are encoded as `/C:/...`.
+    if cfg!(windows) {
+        Some(PathBuf::from(path.trim_start_matches('/')))
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+/// A `prusti-server` child process, killed when dropped.
+struct PrustiServer {
+    child: Child,
+    address: String,
+}
+
+impl PrustiServer {
+    fn spawn(prusti_server_path: &Path) -> io::Result<Self> {
+        let mut child = Command::new(prusti_server_path)
+            .arg("--port")
+            .arg("0")
+            .env("PRUSTI_LOG", "warn")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut port = None;
+        let mut line = String::new();
+        while stdout.read_line(&mut line)? > 0 {
+            if let Some(value) = line.trim().strip_prefix("port: ") {
+                port = Some(value.to_string());
+                break;
+            }
+            line.clear();
+        }
+        // Keep draining the output so that the server never blocks on it.
+        thread::spawn(move || io::copy(&mut stdout.into_inner(), &mut io::sink()));
+        match port {
+            Some(port) => Ok(PrustiServer {
+                child,
+                address: format!("localhost:{}", port),
+            }),
+            None => {
+                let _ = child.kill();
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "prusti-server exited without reporting its port",
+                ))
+            }
+        }
+    }
+}
+
+impl Drop for PrustiServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+struct LanguageServer {
+    prusti_rustc_path: PathBuf,
+    prusti_server_path: PathBuf,
+    prusti_server: Option<PrustiServer>,
+    /// The text of the open documents, by URI.
+    documents: HashMap<String, String>,
+    /// The results of the verification runs, shared with the threads that
+    /// run them.
+    results: Arc<Mutex<VerificationResults>>,
+    /// The number of verification runs started so far.
+    runs: u64,
+    shutdown_requested: bool,
+}
+
+#[derive(Default)]
+struct VerificationResults {
+    /// The latest run of each document. The results of older runs, and of
+    /// runs of closed documents, are discarded.
+    latest_runs: HashMap<String, u64>,
+    /// For each verified document, the URIs that its last run published
+    /// diagnostics for, so that they can be cleared by the next run.
+    published: HashMap<String, HashSet<String>>,
+    /// The contracts that the last run of each document reported.
+    contracts: HashMap<String, Vec<hover::Contract>>,
+}
+
+impl LanguageServer {
+    fn new(current_executable_dir: &Path) -> Self {
+        let mut prusti_rustc_path = current_executable_dir.join("prusti-rustc");
+        let mut prusti_server_path = current_executable_dir.join("prusti-server");
+        if cfg!(windows) {
+            prusti_rustc_path.set_extension("exe");
+            prusti_server_path.set_extension("exe");
+        }
+        LanguageServer {
+            prusti_rustc_path,
+            prusti_server_path,
+            prusti_server: None,
+            documents: HashMap::new(),
+            results: Arc::default(),
+            runs: 0,
+            shutdown_requested: false,
+        }
+    }
+
+    /// Handle a request or notification. Returns the exit code once the
+    /// client asks the server to exit.
+    fn handle_message(&mut self, message: Value) -> Option<i32> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": {
+                        "openClose": true,
+                        // Full synchronization.
+                        "change": 1,
+                        "save": { "includeText": false },
+                    },
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "prusti-lsp" },
+            })),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                self.prusti_server = None;
+                self.results.lock().unwrap().latest_runs.clear();
+                Ok(Value::Null)
+            }
+            "exit" => return Some(if self.shutdown_requested { 0 } else { 1 }),
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), text.to_string());
+                self.verify(uri);
+                Ok(Value::Null)
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|changes| changes.last()) {
+                    let text = text["text"].as_str().unwrap_or_default();
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                Ok(Value::Null)
+            }
+            "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.verify(uri);
+                Ok(Value::Null)
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                let mut results = self.results.lock().unwrap();
+                results.latest_runs.remove(uri);
+                results.contracts.remove(uri);
+                for published_uri in results.published.remove(uri).unwrap_or_default() {
+                    publish_diagnostics(&published_uri, vec![]);
+                }
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => Ok(self.hover(params)),
+            _ => Err(json!({
+                "code": -32601,
+                "message": format!("unsupported method {}", method),
+            })),
+        };
+        // Notifications have no id and must not be answered.
+        if let Some(id) = id {
+            let response = match result {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            };
+            send(&response);
+        }
+        None
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or_default() as usize;
+        let text = match self.documents.get(uri) {
+            Some(text) => text,
+            None => return Value::Null,
+        };
+        let results = self.results.lock().unwrap();
+        // Prefer the contracts reported for the document itself.
+        let own_contracts = results.contracts.get(uri).into_iter().flatten();
+        let other_contracts = results
+            .contracts
+            .iter()
+            .filter(|(other_uri, _)| other_uri.as_str() != uri)
+            .flat_map(|(_, contracts)| contracts);
+        match hover::hover(text, line, character, own_contracts.chain(other_contracts)) {
+            Some(contents) => json!({
+                "contents": { "kind": "markdown", "value": contents },
+            }),
+            None => Value::Null,
+        }
+    }
+
+    fn prusti_server_address(&mut self) -> Option<String> {
+        if self.prusti_server.is_none() {
+            match PrustiServer::spawn(&self.prusti_server_path) {
+                Ok(server) => self.prusti_server = Some(server),
+                Err(error) => {
+                    show_error(&format!("Could not start prusti-server: {}", error));
+                    return None;
+                }
+            }
+        }
+        self.prusti_server
+            .as_ref()
+            .map(|server| server.address.clone())
+    }
+
+    /// Run `prusti-rustc` on the document in a background thread, which
+    /// publishes the diagnostics and records the reported contracts.
+    fn verify(&mut self, uri: &str) {
+        let path = match uri_to_path(uri) {
+            Some(path) if path.extension() == Some("rs".as_ref()) => path,
+            _ => return,
+        };
+        let working_dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        self.runs += 1;
+        let run = self.runs;
+        let out_dir = env::temp_dir().join("prusti-lsp");
+        let contract_report_path =
+            out_dir.join(format!("contracts-{}-{}.json", std::process::id(), run));
+        let mut cmd = Command::new(&self.prusti_rustc_path);
+        cmd.arg("--edition=2018")
+            .arg("--crate-type=lib")
+            .arg("--error-format=json")
+            .arg("--out-dir")
+            .arg(&out_dir)
+            .arg(&path)
+            .current_dir(&working_dir)
+            .env("PRUSTI_QUIET", "true")
+            .env("PRUSTI_CONTRACT_REPORT_PATH", &contract_report_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        if let Some(address) = self.prusti_server_address() {
+            // A verification of the document supersedes its previous ones.
+            cmd.env("PRUSTI_SERVER_ADDRESS", address)
+                .env("PRUSTI_SERVER_SESSION", uri);
+        }
+        self.results
+            .lock()
+            .unwrap()
+            .latest_runs
+            .insert(uri.to_string(), run);
+        let results = self.results.clone();
+        let uri = uri.to_string();
+        thread::spawn(move || {
+            let stderr = match cmd.spawn() {
+                Ok(mut child) => {
+                    let mut stderr = String::new();
+                    if let Some(mut pipe) = child.stderr.take() {
+                        let _ = pipe.read_to_string(&mut stderr);
+                    }
+                    let _ = child.wait();
+                    stderr
+                }
+                Err(error) => {
+                    show_error(&format!("Could not run prusti-rustc: {}", error));
+                    return;
+                }
+            };
+            let contracts = fs::read_to_string(&contract_report_path)
+                .map(|report| hover::parse_contracts(&report))
+                .unwrap_or_default();
+            let _ = fs::remove_file(&contract_report_path);
+            let diagnostics = diagnostics::parse_diagnostics(&stderr, &working_dir);
+            results
+                .lock()
+                .unwrap()
+                .finish_run(&uri, run, &path, diagnostics, contracts);
+        });
+    }
+}
+
+impl VerificationResults {
+    /// Publish the diagnostics and record the contracts of a run of the
+    /// document at `path`, unless a newer run of the document was started.
+    fn finish_run(
+        &mut self,
+        uri: &str,
+        run: u64,
+        path: &Path,
+        mut diagnostics: HashMap<PathBuf, Vec<Value>>,
+        contracts: Vec<hover::Contract>,
+    ) {
+        if self.latest_runs.get(uri) != Some(&run) {
+            return;
+        }
+        // Always publish for the document itself so that fixed errors disappear.
+        diagnostics.entry(path.to_path_buf()).or_default();
+        let previous = self.published.remove(uri).unwrap_or_default();
+        let mut published = HashSet::new();
+        for (file, file_diagnostics) in diagnostics {
+            // Reuse the client's URI for the document, its encoding may differ.
+            let file_uri = if file == path {
+                uri.to_string()
+            } else {
+                path_to_uri(&file)
+            };
+            publish_diagnostics(&file_uri, file_diagnostics);
+            published.insert(file_uri);
+        }
+        for stale_uri in previous.difference(&published) {
+            publish_diagnostics(stale_uri, vec![]);
+        }
+        self.published.insert(uri.to_string(), published);
+        self.contracts.insert(uri.to_string(), contracts);
+    }
+}
+
+fn send(message: &Value) {
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+    transport::write_message(&mut writer, message).expect("Could not write LSP message");
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) {
+    send(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    }));
+}
+
+fn show_error(message: &str) {
+    send(&json!({
+        "jsonrpc": "2.0",
+        "method": "window/showMessage",
+        // Error
+        "params": { "type": 1, "message": message },
+    }));
+}
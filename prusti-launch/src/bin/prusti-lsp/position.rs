@@ -0,0 +1,62 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! LSP counts the characters of a line in UTF-16 code units, while the
+//! compiler counts Unicode scalar values and Rust strings are indexed by byte.
+
+/// The UTF-16 column of the character at the zero-based `char_column` of
+/// `line`. Columns past the end of the line are counted as one unit per
+/// character.
+pub fn utf16_column(line: &str, char_column: usize) -> usize {
+    line.chars()
+        .chain(std::iter::repeat(' '))
+        .take(char_column)
+        .map(char::len_utf16)
+        .sum()
+}
+
+/// The byte offset in `line` of the zero-based UTF-16 `column`, clamped to the
+/// end of the line. A column inside a surrogate pair is rounded down.
+pub fn byte_offset(line: &str, column: usize) -> usize {
+    let mut units = 0;
+    for (offset, c) in line.char_indices() {
+        units += c.len_utf16();
+        if units > column {
+            return offset;
+        }
+    }
+    line.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_columns_are_unchanged() {
+        assert_eq!(utf16_column("let x = 1;", 4), 4);
+        assert_eq!(byte_offset("let x = 1;", 4), 4);
+    }
+
+    #[test]
+    fn non_ascii_columns() {
+        // `é` is one UTF-16 unit and two bytes, `🦀` two units and four bytes.
+        let line = "é🦀 = x";
+        assert_eq!(utf16_column(line, 1), 1);
+        assert_eq!(utf16_column(line, 2), 3);
+        assert_eq!(utf16_column(line, 6), 7);
+        assert_eq!(byte_offset(line, 1), 2);
+        assert_eq!(byte_offset(line, 2), 2);
+        assert_eq!(byte_offset(line, 3), 6);
+        assert_eq!(byte_offset(line, 6), 9);
+    }
+
+    #[test]
+    fn columns_past_the_end() {
+        assert_eq!(utf16_column("🦀", 3), 4);
+        assert_eq!(byte_offset("🦀", 5), 4);
+    }
+}
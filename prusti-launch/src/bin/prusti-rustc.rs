@@ -77,6 +77,18 @@ fn process(mut args: Vec<String>) -> Result<(), i32> {
         }
     };
 
+    // `cargo prusti` writes the configuration of each workspace member to a
+    // separate file.
+    if let (Ok(config_dir), Ok(package_name)) = (
+        env::var("CARGO_PRUSTI_CONFIG_DIR"),
+        env::var("CARGO_PKG_NAME"),
+    ) {
+        let config_path = Path::new(&config_dir).join(format!("{}.toml", package_name));
+        if config_path.is_file() {
+            cmd.env("PRUSTI_CONFIG", config_path);
+        }
+    }
+
     // Setting RUSTC_WRAPPER causes Cargo to pass 'rustc' as the first argument.
     // We're invoking the compiler programmatically, so we ignore this
     if !args.is_empty() && Path::new(&args[0]).file_stem() == Some("rustc".as_ref()) {
@@ -5,9 +5,11 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use glob::glob;
+use serde_json::{json, Value};
 use std::{
-    io::{BufRead, BufReader},
-    path::PathBuf,
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
 };
 
@@ -211,3 +213,194 @@ fn test_prusti_rustc_with_server() {
         cmd
     });
 }
+
+#[test]
+fn test_prusti_lsp() {
+    let prusti_lsp = find_executable_path("prusti-lsp");
+
+    let messages = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+        r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ];
+    let mut lsp_child = Command::new(&prusti_lsp)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start prusti-lsp");
+    {
+        let stdin = lsp_child.stdin.as_mut().unwrap();
+        for message in messages {
+            write!(
+                stdin,
+                "Content-Length: {}\r\n\r\n{}",
+                message.len(),
+                message
+            )
+            .unwrap();
+        }
+    }
+    let output = lsp_child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "prusti-lsp failed: {}", stdout);
+    assert!(
+        stdout.contains(r#""hoverProvider":true"#),
+        "unexpected output: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(r#"{"id":2,"jsonrpc":"2.0","result":null}"#),
+        "unexpected output: {}",
+        stdout
+    );
+}
+
+fn send_lsp_message(writer: &mut impl Write, message: &Value) {
+    let content = message.to_string();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )
+    .unwrap();
+    writer.flush().unwrap();
+}
+
+/// Read LSP messages until one satisfies `predicate`, and return it.
+fn read_lsp_message_until(reader: &mut impl BufRead, predicate: impl Fn(&Value) -> bool) -> Value {
+    loop {
+        let mut content_length = None;
+        loop {
+            let mut header = String::new();
+            let read = reader.read_line(&mut header).unwrap();
+            assert!(read > 0, "prusti-lsp closed its output");
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(length) = header.strip_prefix("Content-Length: ") {
+                content_length = Some(length.parse::<usize>().unwrap());
+            }
+        }
+        let mut content = vec![0; content_length.expect("missing Content-Length header")];
+        reader.read_exact(&mut content).unwrap();
+        let message: Value = serde_json::from_slice(&content).unwrap();
+        if predicate(&message) {
+            return message;
+        }
+    }
+}
+
+fn lsp_document_uri(path: &Path) -> String {
+    format!(
+        "file://{}",
+        env::current_dir().unwrap().join(path).display()
+    )
+}
+
+#[test]
+fn test_prusti_lsp_verification() {
+    let prusti_lsp = find_executable_path("prusti-lsp");
+
+    let mut lsp_child = Command::new(&prusti_lsp)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to start prusti-lsp");
+    let mut stdin = lsp_child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(lsp_child.stdout.take().unwrap());
+    let _lsp_child_guard = ChildGuard(lsp_child);
+    let open_document = |stdin: &mut _, path: &Path| {
+        let uri = lsp_document_uri(path);
+        send_lsp_message(
+            stdin,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": {
+                    "uri": uri,
+                    "languageId": "rust",
+                    "version": 1,
+                    "text": fs::read_to_string(path).unwrap(),
+                }},
+            }),
+        );
+        uri
+    };
+    let is_diagnostics_of = |uri: &str, message: &Value| {
+        message["method"] == "textDocument/publishDiagnostics" && message["params"]["uri"] == uri
+    };
+
+    send_lsp_message(
+        &mut stdin,
+        &json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+    );
+    read_lsp_message_until(&mut stdout, |message| message["id"] == 1);
+
+    // A failing program is reported as a diagnostic on the assertion.
+    let failing_uri = open_document(&mut stdin, Path::new("tests/fail/assert_false.rs"));
+    let diagnostics = read_lsp_message_until(&mut stdout, |message| {
+        is_diagnostics_of(&failing_uri, message)
+    });
+    let diagnostics = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(
+        diagnostics.len(),
+        1,
+        "unexpected diagnostics: {:?}",
+        diagnostics
+    );
+    assert!(
+        diagnostics[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("the asserted expression might not hold"),
+        "unexpected diagnostic: {}",
+        diagnostics[0]
+    );
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 1, "character": 4 })
+    );
+
+    // A verified program has no diagnostics, and its contracts are shown on
+    // hover.
+    let verified_uri = open_document(&mut stdin, Path::new("tests/pass/with_specs.rs"));
+    let diagnostics = read_lsp_message_until(&mut stdout, |message| {
+        is_diagnostics_of(&verified_uri, message)
+    });
+    assert_eq!(diagnostics["params"]["diagnostics"], json!([]));
+    send_lsp_message(
+        &mut stdin,
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "textDocument/hover",
+            "params": {
+                "textDocument": { "uri": verified_uri },
+                "position": { "line": 6, "character": 3 },
+            },
+        }),
+    );
+    let hover = read_lsp_message_until(&mut stdout, |message| message["id"] == 2);
+    let contents = hover["result"]["contents"]["value"]
+        .as_str()
+        .unwrap_or_default();
+    for expected in [
+        "#[pure]",
+        "#[trusted]",
+        "#[requires(x == 42)]",
+        "#[ensures(result == 42)]",
+        "fn id(x: u32) -> u32",
+    ] {
+        assert!(contents.contains(expected), "unexpected hover: {}", hover);
+    }
+
+    // A malformed message is answered with a parse error.
+    write!(stdin, "Content-Length: 5\r\n\r\n{{oops").unwrap();
+    stdin.flush().unwrap();
+    let error = read_lsp_message_until(&mut stdout, |message| message.get("error").is_some());
+    assert_eq!(error["error"]["code"], -32700);
+    assert_eq!(error["id"], Value::Null);
+}
@@ -0,0 +1,346 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Self-contained archives of verification requests, to reproduce the
+//! results of Prusti on another machine.
+//!
+//! A bundle is a gzipped tarball with the following content:
+//! - `manifest.json`: the Prusti and Viper versions, and the program name,
+//!   the backend configuration and the result of each request;
+//! - `requests/<index>.bin`: the requests, serialized with bincode;
+//! - any other file added with `Bundle::add_file`, such as the configuration
+//!   of Prusti or MIR dumps.
+
+use crate::{
+    process_verification_request, worker_pool::NoCache, CancellationToken, VerificationRequest,
+    ViperBackendConfig,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use prusti_common::{config, Stopwatch};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+};
+use viper::{VerificationBackend, VerificationContext, VerificationResult, Viper};
+
+const MANIFEST_PATH: &str = "manifest.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub prusti_version: String,
+    /// The backend version, if the requests were verified locally. A server
+    /// might use another version.
+    pub viper_version: Option<String>,
+    pub requests: Vec<BundledRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledRequest {
+    pub program_name: String,
+    pub backend_config: ViperBackendConfig,
+    /// The path of the serialized request in the bundle.
+    pub path: String,
+    /// `None` if the verification did not finish, e.g. because it crashed.
+    pub result: Option<VerificationResult>,
+}
+
+pub struct Bundle {
+    manifest: BundleManifest,
+    requests: Vec<VerificationRequest>,
+    /// Other files of the bundle, by path.
+    files: Vec<(String, String)>,
+}
+
+impl Bundle {
+    pub fn new(prusti_version: String) -> Self {
+        Bundle {
+            manifest: BundleManifest {
+                prusti_version,
+                viper_version: None,
+                requests: vec![],
+            },
+            requests: vec![],
+            files: vec![],
+        }
+    }
+
+    pub fn manifest(&self) -> &BundleManifest {
+        &self.manifest
+    }
+
+    pub fn set_viper_version(&mut self, viper_version: String) {
+        self.manifest.viper_version = Some(viper_version);
+    }
+
+    /// Add a request without result. Returns the index of the request.
+    pub fn add_request(&mut self, request: VerificationRequest) -> usize {
+        let index = self.requests.len();
+        self.manifest.requests.push(BundledRequest {
+            program_name: request.program.get_name().to_string(),
+            backend_config: request.backend_config.clone(),
+            path: format!("requests/{}.bin", index),
+            result: None,
+        });
+        self.requests.push(request);
+        index
+    }
+
+    pub fn set_result(&mut self, index: usize, result: VerificationResult) {
+        self.manifest.requests[index].result = Some(result);
+    }
+
+    /// Add a text file to the bundle, replacing any file with the same path.
+    pub fn add_file(&mut self, path: String, contents: String) {
+        self.files.retain(|(other_path, _)| other_path != &path);
+        self.files.push((path, contents));
+    }
+
+    /// The requests together with their entry in the manifest.
+    pub fn requests(&self) -> impl Iterator<Item = (&BundledRequest, &VerificationRequest)> {
+        self.manifest.requests.iter().zip(&self.requests)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let encoder = GzEncoder::new(File::create(path)?, Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        let manifest = serde_json::to_vec_pretty(&self.manifest)?;
+        append(&mut archive, MANIFEST_PATH, &manifest)?;
+        for (entry, request) in self.requests() {
+            let request = bincode::serialize(request)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            append(&mut archive, &entry.path, &request)?;
+        }
+        for (file_path, contents) in &self.files {
+            append(&mut archive, file_path, contents.as_bytes())?;
+        }
+        archive.into_inner()?.finish()?.flush()
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+        let mut manifest = None;
+        let mut files = HashMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.to_string_lossy().into_owned();
+            let mut data = vec![];
+            entry.read_to_end(&mut data)?;
+            if entry_path == MANIFEST_PATH {
+                manifest = Some(serde_json::from_slice::<BundleManifest>(&data)?);
+            } else {
+                files.insert(entry_path, data);
+            }
+        }
+        let manifest = manifest.ok_or_else(|| invalid_bundle("the manifest is missing"))?;
+        let mut requests = vec![];
+        for entry in &manifest.requests {
+            let data = files
+                .remove(&entry.path)
+                .ok_or_else(|| invalid_bundle(&format!("{} is missing", entry.path)))?;
+            let request = bincode::deserialize(&data)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            requests.push(request);
+        }
+        let mut files: Vec<_> = files
+            .into_iter()
+            .map(|(path, data)| (path, String::from_utf8_lossy(&data).into_owned()))
+            .collect();
+        files.sort();
+        Ok(Bundle {
+            manifest,
+            requests,
+            files,
+        })
+    }
+}
+
+fn append<W: Write>(archive: &mut tar::Builder<W>, path: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, path, data)
+}
+
+fn invalid_bundle(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid bundle: {}", message),
+    )
+}
+
+/// The name and the version of a backend, as reported by Viper.
+pub fn backend_version(
+    verification_context: &VerificationContext,
+    backend: VerificationBackend,
+) -> String {
+    verification_context
+        .new_ast_utils()
+        .with_local_frame(16, || {
+            verification_context
+                .new_verifier(backend, None)
+                .version()
+                .to_string()
+        })
+}
+
+/// Verify the requests of a bundle again with a local Viper and compare the
+/// results with the recorded ones. Returns whether all results are the same.
+pub fn replay_bundle(path: &Path) -> io::Result<bool> {
+    let bundle = Bundle::read(path)?;
+    let manifest = bundle.manifest();
+    println!("Bundle created by Prusti {}", manifest.prusti_version);
+
+    let stopwatch = Stopwatch::start("prusti-server", "JVM startup");
+    let viper = Viper::new_with_args(config::extra_jvm_args());
+    let verification_context = viper.attach_current_thread();
+    stopwatch.finish();
+
+    if let Some((entry, _)) = bundle.requests().next() {
+        let version = backend_version(&verification_context, entry.backend_config.backend);
+        println!(
+            "Recorded Viper version: {}, replayed with: {}",
+            manifest.viper_version.as_deref().unwrap_or("<unknown>"),
+            version
+        );
+    }
+
+    let mut all_same = true;
+    for (entry, request) in bundle.requests() {
+        let result = process_verification_request(
+            &verification_context,
+            request.clone(),
+            NoCache,
+            &CancellationToken::default(),
+        );
+        match &entry.result {
+            Some(recorded) if recorded == &result => {
+                println!("{}: same result", entry.program_name);
+            }
+            Some(recorded) => {
+                all_same = false;
+                println!("{}: different result", entry.program_name);
+                println!("  recorded: {:?}", recorded);
+                println!("  replayed: {:?}", result);
+            }
+            None => {
+                all_same = false;
+                println!(
+                    "{}: the recorded verification did not finish",
+                    entry.program_name
+                );
+                println!("  replayed: {:?}", result);
+            }
+        }
+    }
+    Ok(all_same)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prusti_common::vir::{self, program::Program};
+    use std::{env, fs, path::PathBuf};
+
+    fn request(name: &str) -> VerificationRequest {
+        VerificationRequest {
+            program: Program::Legacy(vir::Program {
+                name: name.to_string(),
+                domains: vec![],
+                fields: vec![],
+                builtin_methods: vec![],
+                methods: vec![],
+                functions: vec![],
+                viper_predicates: vec![],
+            }),
+            backend_config: Default::default(),
+            session: Some("session".to_string()),
+            priority: 3,
+        }
+    }
+
+    fn bundle_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!(
+            "prusti-bundle-{}-{}.tar.gz",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn write_then_read() {
+        let mut bundle = Bundle::new("1.2.3".to_string());
+        bundle.set_viper_version("Silicon 1.1".to_string());
+        let first = bundle.add_request(request("first"));
+        let second = bundle.add_request(request("second"));
+        bundle.set_result(first, VerificationResult::Success);
+        bundle.add_file(
+            "config.txt".to_string(),
+            "check_overflows = true".to_string(),
+        );
+        bundle.add_file("mir/main.mir".to_string(), "old".to_string());
+        bundle.add_file("mir/main.mir".to_string(), "fn main() -> ()".to_string());
+
+        let path = bundle_path("round-trip");
+        bundle.write(&path).unwrap();
+        let read = Bundle::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let manifest = read.manifest();
+        assert_eq!(manifest.prusti_version, "1.2.3");
+        assert_eq!(manifest.viper_version.as_deref(), Some("Silicon 1.1"));
+        let requests: Vec<_> = read.requests().collect();
+        assert_eq!(requests.len(), 2);
+        for ((entry, request), (index, name)) in requests
+            .into_iter()
+            .zip([(first, "first"), (second, "second")])
+        {
+            assert_eq!(entry.program_name, name);
+            assert_eq!(entry.path, format!("requests/{}.bin", index));
+            assert_eq!(entry.backend_config, ViperBackendConfig::default());
+            assert_eq!(request.program.get_name(), name);
+            assert_eq!(request.session.as_deref(), Some("session"));
+            assert_eq!(request.priority, 3);
+        }
+        assert_eq!(
+            read.manifest.requests[first].result,
+            Some(VerificationResult::Success)
+        );
+        assert_eq!(read.manifest.requests[second].result, None);
+        assert_eq!(
+            read.files,
+            [
+                (
+                    "config.txt".to_string(),
+                    "check_overflows = true".to_string()
+                ),
+                ("mir/main.mir".to_string(), "fn main() -> ()".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_reports_missing_requests() {
+        let mut bundle = Bundle::new("1.2.3".to_string());
+        bundle.add_request(request("first"));
+        // The manifest lists the request, but its file is not written.
+        bundle.requests.clear();
+
+        let path = bundle_path("missing-request");
+        bundle.write(&path).unwrap();
+        let error = Bundle::read(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "invalid bundle: requests/0.bin is missing"
+        );
+    }
+}
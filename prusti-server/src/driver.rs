@@ -5,6 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use clap::{App, Arg};
+use std::{path::Path, process};
 
 fn main() {
     env_logger::init_from_env(env_logger::Env::new().filter_or("PRUSTI_LOG", "info"));
@@ -19,8 +20,41 @@ fn main() {
                 .takes_value(true)
                 .value_name("PORT"),
         )
+        .arg(
+            Arg::with_name("worker")
+                .long("worker")
+                .help("Runs a verifier worker that connects to the server on the given port. Used internally by the server.")
+                .hidden(true)
+                .takes_value(true)
+                .value_name("PORT"),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .help("Verifies the requests of a bundle written by Prusti with PRUSTI_EXPORT_BUNDLE and compares the results with the recorded ones, instead of running a server.")
+                .takes_value(true)
+                .value_name("FILE"),
+        )
         .get_matches();
 
+    if let Some(server_port) = matches.value_of("worker") {
+        let server_port = server_port.parse().expect("Invalid port provided");
+        prusti_server::run_worker(server_port);
+        return;
+    }
+
+    if let Some(bundle_path) = matches.value_of("replay") {
+        match prusti_server::replay_bundle(Path::new(bundle_path)) {
+            Ok(true) => {}
+            Ok(false) => process::exit(1),
+            Err(error) => {
+                eprintln!("Could not read the bundle {}: {}", bundle_path, error);
+                process::exit(2);
+            }
+        }
+        return;
+    }
+
     let port = matches
         .value_of("port")
         .unwrap()
@@ -4,15 +4,22 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod bundle;
 mod client;
+mod metrics;
 mod process_verification;
+mod scheduler;
 mod server;
 mod verification_request;
+mod worker_pool;
 
+pub use bundle::*;
 pub use client::*;
 pub use process_verification::*;
+pub use scheduler::*;
 pub use server::*;
 pub use verification_request::*;
+pub use worker_pool::*;
 
 // Futures returned by `Client` need to be executed in a compatible tokio runtime.
 pub use tokio;
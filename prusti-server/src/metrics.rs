@@ -0,0 +1,216 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Metrics of the server, exported in the Prometheus text format by the
+//! `/metrics` endpoint.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+use viper::{PersistentCache, VerificationBackend, VerificationResult};
+
+/// Upper bounds, in seconds, of the buckets of the latency histograms.
+const LATENCY_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+#[derive(Default)]
+pub struct ServerMetrics {
+    /// Number of requests that were received but not answered yet.
+    in_flight: AtomicUsize,
+    data: Mutex<MetricsData>,
+}
+
+#[derive(Default)]
+struct MetricsData {
+    /// Number of answered requests, by backend and result.
+    requests: BTreeMap<(String, &'static str), u64>,
+    /// Time taken to answer the requests, by backend.
+    latencies: BTreeMap<String, Histogram>,
+}
+
+struct Histogram {
+    /// Number of observations in each bucket of `LATENCY_BUCKETS`, not
+    /// cumulative.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            buckets: vec![0; LATENCY_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(index) = LATENCY_BUCKETS.iter().position(|&bound| value <= bound) {
+            self.buckets[index] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// The label of a verification result in the metrics.
+fn result_kind(result: &VerificationResult) -> &'static str {
+    match result {
+        VerificationResult::Success => "success",
+        VerificationResult::Failure(_) => "failure",
+        VerificationResult::ConsistencyErrors(_) => "consistency_errors",
+        VerificationResult::JavaException(_) => "java_exception",
+        VerificationResult::Timeout => "timeout",
+        VerificationResult::Cancelled => "cancelled",
+    }
+}
+
+impl ServerMetrics {
+    pub fn request_started(&self) {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Record a request answered after `duration`, as measured by the
+    /// `Stopwatch` of the request.
+    pub fn request_finished(
+        &self,
+        backend: VerificationBackend,
+        result: &VerificationResult,
+        duration: Duration,
+    ) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        let mut data = self.data.lock().unwrap();
+        *data
+            .requests
+            .entry((backend.to_string(), result_kind(result)))
+            .or_default() += 1;
+        data.latencies
+            .entry(backend.to_string())
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self, cache: &PersistentCache, queued: usize, active: usize) -> String {
+        let data = self.data.lock().unwrap();
+        let mut text = String::new();
+
+        header(
+            &mut text,
+            "prusti_server_requests_total",
+            "counter",
+            "Number of answered verification requests, by backend and result.",
+        );
+        for ((backend, result), count) in &data.requests {
+            writeln!(
+                text,
+                "prusti_server_requests_total{{backend=\"{}\",result=\"{}\"}} {}",
+                backend, result, count
+            )
+            .unwrap();
+        }
+
+        header(
+            &mut text,
+            "prusti_server_queued_requests",
+            "gauge",
+            "Number of requests waiting to be verified.",
+        );
+        writeln!(text, "prusti_server_queued_requests {}", queued).unwrap();
+        header(
+            &mut text,
+            "prusti_server_active_requests",
+            "gauge",
+            "Number of requests being verified.",
+        );
+        writeln!(text, "prusti_server_active_requests {}", active).unwrap();
+
+        let hits = cache.hits();
+        let misses = cache.misses();
+        header(
+            &mut text,
+            "prusti_server_cache_hits_total",
+            "counter",
+            "Number of requests answered from the verification cache.",
+        );
+        writeln!(text, "prusti_server_cache_hits_total {}", hits).unwrap();
+        header(
+            &mut text,
+            "prusti_server_cache_misses_total",
+            "counter",
+            "Number of requests not found in the verification cache.",
+        );
+        writeln!(text, "prusti_server_cache_misses_total {}", misses).unwrap();
+        header(
+            &mut text,
+            "prusti_server_cache_hit_ratio",
+            "gauge",
+            "Fraction of the cache lookups that found a result.",
+        );
+        let ratio = if hits + misses == 0 {
+            0.0
+        } else {
+            hits as f64 / (hits + misses) as f64
+        };
+        writeln!(text, "prusti_server_cache_hit_ratio {}", ratio).unwrap();
+
+        header(
+            &mut text,
+            "prusti_server_verification_duration_seconds",
+            "histogram",
+            "Time taken to answer a verification request, by backend.",
+        );
+        for (backend, histogram) in &data.latencies {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                writeln!(
+                    text,
+                    "prusti_server_verification_duration_seconds_bucket{{backend=\"{}\",le=\"{}\"}} {}",
+                    backend, bound, cumulative
+                )
+                .unwrap();
+            }
+            writeln!(
+                text,
+                "prusti_server_verification_duration_seconds_bucket{{backend=\"{}\",le=\"+Inf\"}} {}",
+                backend, histogram.count
+            )
+            .unwrap();
+            writeln!(
+                text,
+                "prusti_server_verification_duration_seconds_sum{{backend=\"{}\"}} {}",
+                backend, histogram.sum
+            )
+            .unwrap();
+            writeln!(
+                text,
+                "prusti_server_verification_duration_seconds_count{{backend=\"{}\"}} {}",
+                backend, histogram.count
+            )
+            .unwrap();
+        }
+
+        text
+    }
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(text, "# HELP {} {}", name, help).unwrap();
+    writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+}
@@ -4,7 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::{VerificationRequest, ViperBackendConfig};
+use crate::{CancellationToken, VerificationRequest, ViperBackendConfig};
 use log::info;
 use prusti_common::{config, report::log::report, vir::ToViper, Stopwatch};
 use std::{fs::create_dir_all, path::PathBuf};
@@ -13,7 +13,8 @@ use viper::{Cache, VerificationBackend, VerificationContext};
 pub fn process_verification_request<'v, 't: 'v>(
     verification_context: &'v VerificationContext<'t>,
     request: VerificationRequest,
-    cache: impl Cache,
+    mut cache: impl Cache,
+    cancellation: &CancellationToken,
 ) -> viper::VerificationResult {
     let ast_utils = verification_context.new_ast_utils();
 
@@ -51,7 +52,10 @@ pub fn process_verification_request<'v, 't: 'v>(
 
     // Early return in case of cache hit
     if config::enable_cache() {
-        if let Some(result) = cache.get(hash) {
+        if let Some(result) = cache
+            .get(hash)
+            .and_then(|result| request.result_from_cache(result))
+        {
             if config::dump_viper_program() {
                 ast_utils.with_local_frame(16, || {
                     let _ = build_or_dump_viper_program();
@@ -67,13 +71,20 @@ pub fn process_verification_request<'v, 't: 'v>(
         // Create a new verifier each time.
         // Workaround for https://github.com/viperproject/prusti-dev/issues/744
         let mut stopwatch = Stopwatch::start("prusti-server", "verifier startup");
-        let verifier = new_viper_verifier(verification_context, request.backend_config);
+        let verifier = new_viper_verifier(verification_context, request.backend_config.clone());
+        if !cancellation.set_verifier(verifier.stop_handle()) {
+            return viper::VerificationResult::Cancelled;
+        }
 
         stopwatch.start_next("verification");
         let result = verifier.verify(viper_program);
+        cancellation.clear_verifier();
 
-        if config::enable_cache() {
-            cache.insert(hash, result.clone());
+        // A cancelled verification says nothing about the program.
+        if config::enable_cache() && !matches!(result, viper::VerificationResult::Cancelled) {
+            if let Some(cached_result) = request.result_for_cache(&result) {
+                cache.insert(hash, cached_result);
+            }
         }
 
         result
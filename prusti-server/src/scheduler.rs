@@ -0,0 +1,341 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Prioritisation and cancellation of the requests of the server.
+//!
+//! A request waits in the queue of the scheduler until one of the verifiers
+//! is free and no queued request has a higher priority. A request of a
+//! session supersedes the unanswered requests of the same session for a
+//! program of the same name: queued ones are dropped from the queue, and
+//! ongoing ones are stopped through their `CancellationToken`.
+
+use crate::VerificationRequest;
+use log::info;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Condvar, Mutex},
+};
+use viper::VerifierStopHandle;
+
+/// Cancels the verification of a request, possibly from another thread.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<Mutex<CancellationState>>,
+}
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: bool,
+    /// The verifier of the ongoing verification, if any.
+    verifier: Option<VerifierStopHandle>,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancelled = true;
+        if let Some(verifier) = state.verifier.take() {
+            verifier.stop();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().cancelled
+    }
+
+    /// Register the verifier of the ongoing verification, to stop it when the
+    /// request is cancelled. Returns false if the request is already
+    /// cancelled.
+    pub fn set_verifier(&self, verifier: VerifierStopHandle) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.cancelled {
+            return false;
+        }
+        state.verifier = Some(verifier);
+        true
+    }
+
+    /// Forget the verifier of a finished verification.
+    pub fn clear_verifier(&self) {
+        self.state.lock().unwrap().verifier = None;
+    }
+}
+
+/// A request registered with the scheduler.
+pub struct Ticket {
+    id: u64,
+    priority: i32,
+    /// The session and the program name of the request.
+    key: Option<(String, String)>,
+    cancellation: CancellationToken,
+}
+
+pub struct Scheduler {
+    /// The number of requests that can be verified concurrently.
+    capacity: usize,
+    state: Mutex<SchedulerState>,
+    state_changed: Condvar,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    next_id: u64,
+    /// The number of requests being verified.
+    running: usize,
+    /// The queued requests, by decreasing priority and then by arrival.
+    queue: BTreeSet<(Reverse<i32>, u64)>,
+    /// The latest request of each session and program name.
+    latest: HashMap<(String, String), (u64, CancellationToken)>,
+}
+
+/// The permission to verify a request, given back to the scheduler when
+/// dropped.
+pub struct Permit<'a> {
+    scheduler: &'a Scheduler,
+    ticket: Ticket,
+}
+
+impl<'a> Permit<'a> {
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.ticket.cancellation
+    }
+}
+
+impl Scheduler {
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the scheduler needs to verify at least one request"
+        );
+        Scheduler {
+            capacity,
+            state: Mutex::new(SchedulerState::default()),
+            state_changed: Condvar::new(),
+        }
+    }
+
+    /// Register a request, cancelling the requests that it supersedes.
+    pub fn register(&self, request: &VerificationRequest) -> Ticket {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        let cancellation = CancellationToken::default();
+        let key = request
+            .session
+            .as_ref()
+            .map(|session| (session.clone(), request.program.get_name().to_string()));
+        let superseded = key
+            .as_ref()
+            .and_then(|key| state.latest.insert(key.clone(), (id, cancellation.clone())));
+        drop(state);
+        if let Some((_, superseded)) = superseded {
+            let (session, program) = key.as_ref().unwrap();
+            info!(
+                "Cancelling the request of session {} for {}, which was superseded",
+                session, program
+            );
+            // Stopping the verifier can take a while, so the scheduler is not
+            // locked meanwhile.
+            superseded.cancel();
+            // Wake up the request if it is queued. Locking makes sure that it
+            // is not between checking for cancellation and waiting.
+            let _state = self.state.lock().unwrap();
+            self.state_changed.notify_all();
+        }
+        Ticket {
+            id,
+            priority: request.priority,
+            key,
+            cancellation,
+        }
+    }
+
+    /// Wait until the request can be verified. Returns `None` if the request
+    /// was cancelled while it was queued.
+    pub fn acquire(&self, ticket: Ticket) -> Option<Permit<'_>> {
+        let entry = (Reverse(ticket.priority), ticket.id);
+        let mut state = self.state.lock().unwrap();
+        state.queue.insert(entry);
+        loop {
+            if ticket.cancellation.is_cancelled() {
+                state.queue.remove(&entry);
+                drop(state);
+                self.finish(&ticket);
+                return None;
+            }
+            if state.running < self.capacity && state.queue.iter().next() == Some(&entry) {
+                state.queue.remove(&entry);
+                state.running += 1;
+                return Some(Permit {
+                    scheduler: self,
+                    ticket,
+                });
+            }
+            state = self.state_changed.wait(state).unwrap();
+        }
+    }
+
+    /// The number of requests waiting for a verifier.
+    pub fn queue_length(&self) -> usize {
+        self.state.lock().unwrap().queue.len()
+    }
+
+    /// Forget a request that was answered.
+    fn finish(&self, ticket: &Ticket) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(key) = &ticket.key {
+            if matches!(state.latest.get(key), Some((id, _)) if *id == ticket.id) {
+                state.latest.remove(key);
+            }
+        }
+        // The next request in the queue might be allowed to run now.
+        self.state_changed.notify_all();
+    }
+}
+
+impl<'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().running -= 1;
+        self.scheduler.finish(&self.ticket);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prusti_common::vir::{self, program::Program};
+    use std::{sync::Arc, thread, time::Duration};
+
+    fn request(session: Option<&str>, name: &str, priority: i32) -> VerificationRequest {
+        VerificationRequest {
+            program: Program::Legacy(vir::Program {
+                name: name.to_string(),
+                domains: vec![],
+                fields: vec![],
+                builtin_methods: vec![],
+                methods: vec![],
+                functions: vec![],
+                viper_predicates: vec![],
+            }),
+            backend_config: Default::default(),
+            session: session.map(str::to_string),
+            priority,
+        }
+    }
+
+    /// Wait until `length` requests are queued.
+    fn wait_for_queue_length(scheduler: &Scheduler, length: usize) {
+        while scheduler.queue_length() != length {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn cancellation_token_is_shared_by_clones() {
+        let token = CancellationToken::default();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        // A cancelled token is not cancelled again.
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn queued_requests_run_by_priority_then_arrival() {
+        let scheduler = Arc::new(Scheduler::new(1));
+        let running = scheduler
+            .acquire(scheduler.register(&request(None, "running", 0)))
+            .unwrap();
+        let order = Arc::new(Mutex::new(vec![]));
+        let threads: Vec<_> = [("low", 0), ("high", 5), ("second high", 5)]
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, priority))| {
+                let ticket = scheduler.register(&request(None, name, priority));
+                let scheduler = Arc::clone(&scheduler);
+                let order = Arc::clone(&order);
+                let thread = thread::spawn(move || {
+                    let _permit = scheduler.acquire(ticket).unwrap();
+                    order.lock().unwrap().push(name);
+                });
+                wait_for_queue_length(&scheduler, index + 1);
+                thread
+            })
+            .collect();
+        drop(running);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["high", "second high", "low"]);
+        assert_eq!(scheduler.queue_length(), 0);
+    }
+
+    #[test]
+    fn requests_run_up_to_the_capacity() {
+        let scheduler = Scheduler::new(2);
+        let first = scheduler.acquire(scheduler.register(&request(None, "first", 0)));
+        let second = scheduler.acquire(scheduler.register(&request(None, "second", 0)));
+        assert!(first.is_some() && second.is_some());
+        assert_eq!(scheduler.state.lock().unwrap().running, 2);
+        drop(first);
+        assert_eq!(scheduler.state.lock().unwrap().running, 1);
+    }
+
+    #[test]
+    fn superseded_request_is_cancelled_before_it_runs() {
+        let scheduler = Scheduler::new(1);
+        let old = scheduler.register(&request(Some("session"), "program", 0));
+        let new = scheduler.register(&request(Some("session"), "program", 0));
+        assert!(old.cancellation.is_cancelled());
+        assert!(!new.cancellation.is_cancelled());
+        assert!(scheduler.acquire(old).is_none());
+        let permit = scheduler.acquire(new).unwrap();
+        assert!(!permit.cancellation().is_cancelled());
+        drop(permit);
+        assert!(scheduler.state.lock().unwrap().latest.is_empty());
+    }
+
+    #[test]
+    fn superseded_request_leaves_the_queue() {
+        let scheduler = Arc::new(Scheduler::new(1));
+        let running = scheduler
+            .acquire(scheduler.register(&request(None, "running", 0)))
+            .unwrap();
+        let queued = scheduler.register(&request(Some("session"), "program", 0));
+        let thread = {
+            let scheduler = Arc::clone(&scheduler);
+            thread::spawn(move || scheduler.acquire(queued).is_none())
+        };
+        wait_for_queue_length(&scheduler, 1);
+        let _new = scheduler.register(&request(Some("session"), "program", 0));
+        assert!(thread.join().unwrap(), "the superseded request ran");
+        assert_eq!(scheduler.queue_length(), 0);
+        drop(running);
+    }
+
+    #[test]
+    fn superseded_request_is_cancelled_while_it_runs() {
+        let scheduler = Scheduler::new(1);
+        let permit = scheduler
+            .acquire(scheduler.register(&request(Some("session"), "program", 0)))
+            .unwrap();
+        // Requests of other sessions or for other programs do not supersede it.
+        let other_session = scheduler.register(&request(Some("other"), "program", 0));
+        let other_program = scheduler.register(&request(Some("session"), "other", 0));
+        let no_session = scheduler.register(&request(None, "program", 0));
+        assert!(!permit.cancellation().is_cancelled());
+
+        let new = scheduler.register(&request(Some("session"), "program", 0));
+        assert!(permit.cancellation().is_cancelled());
+        drop(permit);
+        for ticket in [other_session, other_program, no_session, new] {
+            assert!(scheduler.acquire(ticket).is_some());
+        }
+    }
+}
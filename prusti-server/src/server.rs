@@ -4,24 +4,77 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::{process_verification_request, VerificationRequest};
+use crate::{
+    metrics::ServerMetrics, process_verification_request, CancellationToken, Scheduler,
+    VerificationRequest, WorkerHealth, WorkerPool,
+};
 use log::info;
 use prusti_common::{config, Stopwatch};
+use serde::Serialize;
 use std::{
+    env,
     net::{Ipv4Addr, SocketAddr},
     sync::{mpsc, Arc, Mutex},
     thread,
 };
-use tokio::runtime::Builder;
-use viper::{PersistentCache, Viper};
+use tokio::{runtime::Builder, sync::oneshot};
+use viper::{Cache, PersistentCache, Viper};
 use warp::Filter;
 
 #[derive(Debug)]
 struct BincodeReject(bincode::Error);
 impl warp::reject::Reject for BincodeReject {}
 
+/// How the server runs the verifier.
+#[derive(Clone)]
+enum ServerVerifier {
+    /// In the server process, one request at a time.
+    InProcess(Arc<Viper>),
+    /// In a pool of worker processes.
+    Workers(Arc<WorkerPool>),
+}
+
+impl ServerVerifier {
+    /// The number of requests that can be verified concurrently.
+    fn capacity(&self) -> usize {
+        match self {
+            // Viper is not thread safe yet (Silicon issue #578).
+            ServerVerifier::InProcess(_) => 1,
+            ServerVerifier::Workers(pool) => pool.size(),
+        }
+    }
+}
+
+/// The number of queued requests and the number of requests being verified.
+fn load(scheduler: &Scheduler, metrics: &ServerMetrics) -> (usize, usize) {
+    let queued = scheduler.queue_length();
+    (queued, metrics.in_flight().saturating_sub(queued))
+}
+
+/// The state of the server reported by the `/status` endpoint.
+#[derive(Serialize)]
+struct ServerStatus {
+    /// Whether a JVM is running. Workers start their JVM on their first
+    /// request.
+    jvm_up: bool,
+    queue_length: usize,
+    active_requests: usize,
+    workers: Vec<WorkerHealth>,
+}
+
 pub fn start_server_on_port(port: u16) {
-    listen_on_port_with_address_callback(port, move |address| {
+    let workers = config::server_workers().unwrap_or(0);
+    let verifier = if workers == 0 {
+        new_in_process_verifier()
+    } else {
+        let executable = env::current_exe().expect("current executable path invalid");
+        ServerVerifier::Workers(Arc::new(WorkerPool::new(
+            executable,
+            workers,
+            config::server_worker_memory_limit(),
+        )))
+    };
+    listen_on_port_with_address_callback(port, verifier, move |address| {
         if port == 0 {
             return;
         }
@@ -37,8 +90,11 @@ pub fn start_server_on_port(port: u16) {
 pub fn spawn_server_thread() -> SocketAddr {
     let (sender, receiver) = mpsc::channel();
     thread::spawn(move || {
+        // This server runs in the process of the caller, which cannot act
+        // as a worker.
         listen_on_port_with_address_callback(
             0, // ask system for port
+            new_in_process_verifier(),
             move |address| sender.send(address).unwrap(),
         );
     });
@@ -46,30 +102,112 @@ pub fn spawn_server_thread() -> SocketAddr {
     receiver.recv().unwrap()
 }
 
-fn listen_on_port_with_address_callback<F>(port: u16, address_callback: F) -> !
-where
-    F: FnOnce(SocketAddr),
-{
+fn new_in_process_verifier() -> ServerVerifier {
     let stopwatch = Stopwatch::start("prusti-server", "JVM startup");
     let viper = Arc::new(Viper::new_with_args(config::extra_jvm_args()));
     stopwatch.finish();
+    ServerVerifier::InProcess(viper)
+}
 
-    let cache_data = PersistentCache::load_cache(config::cache_path());
-    let cache = Arc::new(Mutex::new(cache_data));
-    let build_verification_request_handler = |viper_arc: Arc<Viper>, cache| {
-        move |request: VerificationRequest| {
+fn verify(
+    verifier: &ServerVerifier,
+    request: VerificationRequest,
+    mut cache: &Arc<Mutex<PersistentCache>>,
+    cancellation: &CancellationToken,
+) -> viper::VerificationResult {
+    match verifier {
+        ServerVerifier::InProcess(viper) => {
             let stopwatch = Stopwatch::start("prusti-server", "attach thread to JVM");
-            let viper_thread = viper_arc.attach_current_thread();
+            let viper_thread = viper.attach_current_thread();
             stopwatch.finish();
-            process_verification_request(&viper_thread, request, &cache)
+            process_verification_request(&viper_thread, request, cache, cancellation)
         }
-    };
+        ServerVerifier::Workers(pool) => {
+            // The cache is shared between the workers, so it is checked here.
+            let use_cache = config::enable_cache() && !config::print_hash();
+            let hash = request.get_hash();
+            if use_cache {
+                if let Some(result) = cache
+                    .get(hash)
+                    .and_then(|result| request.result_from_cache(result))
+                {
+                    return result;
+                }
+            }
+            let result = pool.verify(&request, cancellation);
+            // Do not cache failures of the workers, they might be transient.
+            if use_cache
+                && !matches!(
+                    result,
+                    viper::VerificationResult::JavaException(_)
+                        | viper::VerificationResult::Cancelled
+                )
+            {
+                if let Some(cached_result) = request.result_for_cache(&result) {
+                    cache.insert(hash, cached_result);
+                }
+            }
+            result
+        }
+    }
+}
+
+fn listen_on_port_with_address_callback<F>(port: u16, verifier: ServerVerifier, address_callback: F)
+where
+    F: FnOnce(SocketAddr),
+{
+    let cache_data = PersistentCache::load_cache(config::cache_path());
+    let cache = Arc::new(Mutex::new(cache_data));
+    let metrics = Arc::new(ServerMetrics::default());
+    let scheduler = Arc::new(Scheduler::new(verifier.capacity()));
+    let build_verification_request_handler =
+        |verifier: ServerVerifier, cache, metrics, scheduler| {
+            move |request: VerificationRequest| {
+                let verifier = verifier.clone();
+                let cache: Arc<Mutex<PersistentCache>> = Arc::clone(&cache);
+                let metrics: Arc<ServerMetrics> = Arc::clone(&metrics);
+                let scheduler: Arc<Scheduler> = Arc::clone(&scheduler);
+                async move {
+                    metrics.request_started();
+                    let backend = request.backend_config.backend;
+                    let stopwatch = Stopwatch::start("prusti-server", "verification request");
+                    // Cancel the requests that this one supersedes right away, but
+                    // wait for a verifier on a separate thread, so that the server
+                    // keeps accepting requests meanwhile.
+                    let ticket = scheduler.register(&request);
+                    let (sender, receiver) = oneshot::channel();
+                    let thread_cache = Arc::clone(&cache);
+                    thread::spawn(move || {
+                        let result = match scheduler.acquire(ticket) {
+                            Some(permit) => {
+                                verify(&verifier, request, &thread_cache, permit.cancellation())
+                            }
+                            None => viper::VerificationResult::Cancelled,
+                        };
+                        let _ = sender.send(result);
+                    });
+                    let result = receiver.await.expect("the verification thread panicked");
+                    metrics.request_finished(backend, &result, stopwatch.finish());
+                    if config::enable_cache() {
+                        let cache = cache.lock().unwrap();
+                        info!(
+                            "Verification cache: {} hits, {} misses",
+                            cache.hits(),
+                            cache.misses()
+                        );
+                    }
+                    Ok::<_, warp::Rejection>(result)
+                }
+            }
+        };
 
     let json_verify = warp::path!("json" / "verify")
         .and(warp::body::json())
-        .map(build_verification_request_handler(
-            viper.clone(),
+        .and_then(build_verification_request_handler(
+            verifier.clone(),
             cache.clone(),
+            metrics.clone(),
+            scheduler.clone(),
         ))
         .map(|response| warp::reply::json(&response));
 
@@ -81,26 +219,92 @@ where
                 warp::reject::custom(BincodeReject(err))
             })
         })
-        .map(build_verification_request_handler(viper, cache.clone()))
+        .and_then(build_verification_request_handler(
+            verifier.clone(),
+            cache.clone(),
+            metrics.clone(),
+            scheduler.clone(),
+        ))
         .map(|result| {
             warp::http::Response::new(
                 bincode::serialize(&result).expect("could not encode verification result"),
             )
         });
 
+    let status_verifier = verifier.clone();
+    let status_metrics = metrics.clone();
+    let status_scheduler = scheduler.clone();
+    let status = warp::get()
+        .and(warp::path("status"))
+        .and(warp::path::end())
+        .map(move || {
+            let (queue_length, active_requests) = load(&status_scheduler, &status_metrics);
+            let status = match &status_verifier {
+                ServerVerifier::InProcess(_) => ServerStatus {
+                    jvm_up: true,
+                    queue_length,
+                    active_requests,
+                    workers: vec![],
+                },
+                ServerVerifier::Workers(pool) => {
+                    let workers = pool.health();
+                    ServerStatus {
+                        jvm_up: workers.iter().any(|worker| worker.pid.is_some()),
+                        queue_length,
+                        active_requests,
+                        workers,
+                    }
+                }
+            };
+            warp::reply::json(&status)
+        });
+
+    let metrics_cache = cache.clone();
+    let metrics_endpoint = warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .map(move || {
+            let (queued, active) = load(&scheduler, &metrics);
+            let text = metrics.to_prometheus(&metrics_cache.lock().unwrap(), queued, active);
+            warp::reply::with_header(text, "content-type", "text/plain; version=0.0.4")
+        });
+
+    let save_cache_cache = cache.clone();
     let save_cache = warp::post()
         .and(warp::path("save"))
         .and(warp::path::end())
         .map(move || {
-            cache.lock().unwrap().save();
+            save_cache_cache.lock().unwrap().save();
             warp::reply::html("Saved")
         });
 
-    let endpoints = json_verify.or(bincode_verify).or(save_cache);
+    // The sender is taken by the first shutdown request.
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let shutdown_sender = Arc::new(Mutex::new(Some(shutdown_sender)));
+    let shutdown_cache = cache.clone();
+    let shutdown = warp::post()
+        .and(warp::path("shutdown"))
+        .and(warp::path::end())
+        .map(move || {
+            info!("Prusti Server received a shutdown request");
+            shutdown_cache.lock().unwrap().save();
+            if let Some(sender) = shutdown_sender.lock().unwrap().take() {
+                let _ = sender.send(());
+            }
+            warp::reply::html("Shutting down")
+        });
 
-    // Here we use a single thread because
-    // 1. Viper is not thread safe yet (Silicon issue #578), and
-    // 2. By default Silicon already uses as many cores as possible.
+    let endpoints = json_verify
+        .or(bincode_verify)
+        .or(save_cache)
+        .or(status)
+        .or(metrics_endpoint)
+        .or(shutdown);
+
+    // Here we use a single thread because requests are verified on separate
+    // threads, at most as many at a time as the verifier allows (see
+    // `ServerVerifier::capacity`). By default Silicon already uses as many
+    // cores as possible.
     let mut runtime = Builder::new()
         .basic_scheduler()
         .thread_name("prusti-server")
@@ -110,8 +314,14 @@ where
 
     runtime.block_on(async {
         info!("Prusti Server binding to port {}", port);
-        let (address, server_loop) =
-            warp::serve(endpoints).bind_ephemeral((Ipv4Addr::LOCALHOST, port));
+        // Requests that are being answered when the server shuts down are
+        // completed first.
+        let (address, server_loop) = warp::serve(endpoints).bind_with_graceful_shutdown(
+            (Ipv4Addr::LOCALHOST, port),
+            async move {
+                shutdown_receiver.await.ok();
+            },
+        );
 
         println!("port: {}", address.port());
         address_callback(address);
@@ -120,5 +330,7 @@ where
         server_loop.await
     });
 
-    unreachable!("The server unexpectedly stopped.");
+    // Results of requests answered during the shutdown are saved too.
+    cache.lock().unwrap().save();
+    info!("Prusti Server stopped");
 }
@@ -11,20 +11,101 @@ use std::{
     hash::{Hash, Hasher},
     str::FromStr,
 };
-use viper::{self, VerificationBackend};
+use viper::{self, VerificationBackend, VerificationResult};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerificationRequest {
     pub program: Program,
     pub backend_config: ViperBackendConfig,
+    /// The client session that sent the request. A request supersedes the
+    /// unanswered requests of the same session for a program of the same
+    /// name, which are cancelled.
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Queued requests with a higher priority are verified first.
+    #[serde(default)]
+    pub priority: i32,
 }
 
 impl VerificationRequest {
+    pub fn new(program: Program, backend_config: ViperBackendConfig) -> Self {
+        VerificationRequest {
+            program,
+            backend_config,
+            session: config::server_session(),
+            priority: config::server_priority(),
+        }
+    }
+
+    /// The hash of the request, used as the key of the verification cache.
+    /// The session and the priority do not affect the result. The position
+    /// ids are included because the errors of a cached result refer to them.
+    /// They are renumbered, so that the key does not depend on the positions
+    /// that the encoder created for other programs.
     pub(crate) fn get_hash(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
-        self.hash(&mut hasher);
+        self.program.hash(&mut hasher);
+        if let Some(renumbering) = self.program.position_id_renumbering() {
+            renumbering.local_position_ids().hash(&mut hasher);
+        }
+        self.backend_config.hash(&mut hasher);
         hasher.finish()
     }
+
+    /// Translates a result of this request into the renumbered position ids
+    /// that the cache stores. Returns `None` if the result refers to a
+    /// position that is not in the program and therefore cannot be cached.
+    pub(crate) fn result_for_cache(
+        &self,
+        result: &VerificationResult,
+    ) -> Option<VerificationResult> {
+        match self.program.position_id_renumbering() {
+            Some(renumbering) => map_position_ids(result.clone(), |id| renumbering.to_local(id)),
+            None => Some(result.clone()),
+        }
+    }
+
+    /// Translates a cached result back into the position ids of this request.
+    /// Returns `None` if the cached result does not fit the request.
+    pub(crate) fn result_from_cache(
+        &self,
+        result: VerificationResult,
+    ) -> Option<VerificationResult> {
+        match self.program.position_id_renumbering() {
+            Some(renumbering) => map_position_ids(result, |id| renumbering.to_global(id)),
+            None => Some(result),
+        }
+    }
+}
+
+/// Applies `map` to the position ids of the errors in `result`. The default
+/// position id `0` is kept as it is.
+fn map_position_ids(
+    result: VerificationResult,
+    map: impl Fn(u64) -> Option<u64>,
+) -> Option<VerificationResult> {
+    let map_id = |pos_id: Option<String>| -> Option<Option<String>> {
+        match pos_id {
+            None => Some(None),
+            Some(pos_id) => {
+                let id: u64 = pos_id.parse().ok()?;
+                let mapped_id = if id == 0 { 0 } else { map(id)? };
+                Some(Some(mapped_id.to_string()))
+            }
+        }
+    };
+    match result {
+        VerificationResult::Failure(errors) => errors
+            .into_iter()
+            .map(|mut error| {
+                error.pos_id = map_id(error.pos_id)?;
+                error.reason_pos_id = map_id(error.reason_pos_id)?;
+                Some(error)
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(VerificationResult::Failure),
+        result => Some(result),
+    }
 }
 
 /// The configuration for the viper backend, (i.e. verifier).
@@ -50,6 +131,11 @@ impl Default for ViperBackendConfig {
                     verifier_args.push("mapped".to_string());
                 }
 
+                if let Some(timeout) = config::method_timeout() {
+                    verifier_args.push("--timeout".to_string());
+                    verifier_args.push(timeout.to_string());
+                }
+
                 verifier_args.extend(vec![
                     "--assertTimeout".to_string(),
                     config::assert_timeout().to_string(),
@@ -67,3 +153,107 @@ impl Default for ViperBackendConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prusti_common::vir::{
+        self,
+        ast::*,
+        cfg::{CfgMethod, Successor},
+        program::Program,
+    };
+    use std::collections::HashMap;
+    use viper::VerificationError;
+
+    /// A method that asserts `x > 0` once at each of the given positions.
+    fn method(name: &str, position_ids: &[u64]) -> CfgMethod {
+        let x = LocalVar::new("x", Type::Int);
+        let mut method = CfgMethod::new(name.to_string(), 0, vec![], vec![x.clone()]);
+        let stmts = position_ids
+            .iter()
+            .map(|&id| {
+                Stmt::Assert(
+                    Expr::gt_cmp(Expr::local(x.clone()), 0i32.into()),
+                    Position::new(1, 1, id),
+                )
+            })
+            .collect();
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, Successor::Return);
+        method
+    }
+
+    /// The request for the slice of `method_name` of a program with `methods`.
+    fn request(methods: Vec<CfgMethod>, method_name: &str) -> VerificationRequest {
+        let program = Program::Legacy(vir::Program {
+            name: "program".to_string(),
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods,
+            functions: vec![],
+            viper_predicates: vec![],
+        });
+        let slice = program
+            .split_by_method()
+            .into_iter()
+            .find(|slice| slice.get_name().ends_with(method_name))
+            .unwrap();
+        VerificationRequest {
+            program: slice,
+            backend_config: ViperBackendConfig {
+                backend: VerificationBackend::Silicon,
+                verifier_args: vec![],
+            },
+            session: None,
+            priority: 0,
+        }
+    }
+
+    fn failure_at(pos_id: u64) -> VerificationResult {
+        VerificationResult::Failure(vec![VerificationError::new(
+            "assert.failed:assertion.false".to_string(),
+            Some(pos_id.to_string()),
+            None,
+            "Assert might fail.".to_string(),
+            None,
+        )])
+    }
+
+    #[test]
+    fn unchanged_method_hits_the_cache_after_an_edit_elsewhere() {
+        let before = vec![method("m_edited", &[1]), method("m_unchanged", &[2, 3, 2])];
+        // The edited method got a new position, which shifts the ids of all
+        // positions created after it.
+        let after = vec![
+            method("m_edited", &[1, 2]),
+            method("m_unchanged", &[3, 4, 3]),
+        ];
+        assert_ne!(
+            request(before.clone(), "m_edited").get_hash(),
+            request(after.clone(), "m_edited").get_hash()
+        );
+        let before = request(before, "m_unchanged");
+        let after = request(after, "m_unchanged");
+        assert_eq!(before.get_hash(), after.get_hash());
+
+        let mut cache = HashMap::new();
+        let cached_result = before.result_for_cache(&failure_at(3)).unwrap();
+        cache.insert(before.get_hash(), cached_result);
+        let cached_result = cache.remove(&after.get_hash()).unwrap();
+        match after.result_from_cache(cached_result) {
+            Some(VerificationResult::Failure(errors)) => {
+                assert_eq!(errors[0].pos_id.as_deref(), Some("4"));
+            }
+            other => panic!("unexpected cached result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn result_with_an_unknown_position_is_not_cached() {
+        let request = request(vec![method("m", &[1])], "m");
+        assert!(request.result_for_cache(&failure_at(2)).is_none());
+        assert!(request.result_for_cache(&failure_at(1)).is_some());
+    }
+}
@@ -0,0 +1,430 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Verification in child processes of the server.
+//!
+//! Each worker is a `prusti-server-driver --worker <PORT>` process that owns
+//! its own JVM and connects back to the server on the given port. Requests
+//! and results are exchanged as length-prefixed bincode messages. A worker
+//! that crashes, throws a Java exception or uses too much memory is killed
+//! and restarted, and the request it was verifying is retried once. A request
+//! that is cancelled while a worker verifies it is cancelled in the worker
+//! too, which stops its verifier.
+
+use crate::{process_verification_request, CancellationToken, VerificationRequest};
+use log::{info, warn};
+use prusti_common::{config, Stopwatch};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{mpsc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+use viper::{Cache, JavaException, VerificationResult, Viper};
+
+/// How often a busy worker is checked for crashes and its memory usage.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The health of a worker, as reported by the `/status` endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerHealth {
+    pub id: usize,
+    /// The process id of the worker, if it is running.
+    pub pid: Option<u32>,
+    pub busy: bool,
+    /// Number of requests that the worker answered.
+    pub requests: u64,
+    /// Number of times that the worker was restarted after a failure.
+    pub restarts: u64,
+    pub last_failure: Option<String>,
+}
+
+/// A worker process and the connection to it.
+struct Worker {
+    child: Child,
+    stream: TcpStream,
+}
+
+/// A place in the pool. The worker is started lazily, so that a worker that
+/// failed is only restarted when it is needed again.
+struct Slot {
+    id: usize,
+    worker: Option<Worker>,
+}
+
+/// A message from the server to a worker. The server sends requests by
+/// reference.
+#[derive(Serialize, Deserialize)]
+enum WorkerMessage<Request> {
+    Verify(Request),
+    /// Cancel the request being verified.
+    Cancel,
+}
+
+/// Why a request could not be verified by a worker.
+enum WorkerFailure {
+    Crash(String),
+    Exception(JavaException),
+}
+
+pub struct WorkerPool {
+    executable: PathBuf,
+    /// The memory limit of a worker, in bytes.
+    memory_limit: Option<u64>,
+    idle: Mutex<Vec<Slot>>,
+    idle_changed: Condvar,
+    health: Mutex<Vec<WorkerHealth>>,
+}
+
+impl WorkerPool {
+    /// Create a pool of `size` workers running `executable`. The workers are
+    /// started when they receive their first request.
+    pub fn new(executable: PathBuf, size: usize, memory_limit_mib: Option<u64>) -> Self {
+        assert!(size > 0, "the worker pool needs at least one worker");
+        WorkerPool {
+            executable,
+            memory_limit: memory_limit_mib.map(|limit| limit * 1024 * 1024),
+            idle: Mutex::new(
+                (0..size)
+                    .rev()
+                    .map(|id| Slot { id, worker: None })
+                    .collect(),
+            ),
+            idle_changed: Condvar::new(),
+            health: Mutex::new(
+                (0..size)
+                    .map(|id| WorkerHealth {
+                        id,
+                        pid: None,
+                        busy: false,
+                        requests: 0,
+                        restarts: 0,
+                        last_failure: None,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn health(&self) -> Vec<WorkerHealth> {
+        self.health.lock().unwrap().clone()
+    }
+
+    /// The number of workers, which is the number of requests that the pool
+    /// verifies concurrently.
+    pub fn size(&self) -> usize {
+        self.health.lock().unwrap().len()
+    }
+
+    /// Verify the request on the next idle worker, blocking until one is
+    /// available.
+    pub fn verify(
+        &self,
+        request: &VerificationRequest,
+        cancellation: &CancellationToken,
+    ) -> VerificationResult {
+        let mut slot = self.acquire();
+        self.update_health(slot.id, |health| health.busy = true);
+        let mut result = None;
+        for attempt in 0..2 {
+            if cancellation.is_cancelled() {
+                result = Some(VerificationResult::Cancelled);
+                break;
+            }
+            match self.verify_in_slot(&mut slot, request, cancellation) {
+                Ok(verification_result) => {
+                    result = Some(verification_result);
+                    break;
+                }
+                Err(failure) => {
+                    let message = match &failure {
+                        WorkerFailure::Crash(message) => message.clone(),
+                        WorkerFailure::Exception(exception) => exception.to_string(),
+                    };
+                    warn!(
+                        "Verifier worker {} failed on {} (attempt {}): {}",
+                        slot.id,
+                        request.program.get_name(),
+                        attempt + 1,
+                        message
+                    );
+                    // Dropping the worker kills it; it is restarted on demand.
+                    slot.worker = None;
+                    self.update_health(slot.id, |health| {
+                        health.pid = None;
+                        health.restarts += 1;
+                        health.last_failure = Some(message.clone());
+                    });
+                    result = Some(match failure {
+                        WorkerFailure::Exception(exception) => {
+                            VerificationResult::JavaException(exception)
+                        }
+                        WorkerFailure::Crash(message) => {
+                            VerificationResult::JavaException(JavaException::new(
+                                format!("the verifier worker crashed: {}", message),
+                                String::new(),
+                            ))
+                        }
+                    });
+                }
+            }
+        }
+        self.update_health(slot.id, |health| health.busy = false);
+        self.release(slot);
+        result.unwrap()
+    }
+
+    fn verify_in_slot(
+        &self,
+        slot: &mut Slot,
+        request: &VerificationRequest,
+        cancellation: &CancellationToken,
+    ) -> Result<VerificationResult, WorkerFailure> {
+        if slot.worker.is_none() {
+            let worker = Worker::spawn(&self.executable, slot.id).map_err(|error| {
+                WorkerFailure::Crash(format!("could not start the worker: {}", error))
+            })?;
+            let pid = worker.child.id();
+            info!("Started verifier worker {} with pid {}", slot.id, pid);
+            self.update_health(slot.id, |health| health.pid = Some(pid));
+            slot.worker = Some(worker);
+        }
+        let worker = slot.worker.as_mut().unwrap();
+        let result = worker
+            .verify(request, cancellation, self.memory_limit)
+            .map_err(|error| WorkerFailure::Crash(error.to_string()))?;
+        self.update_health(slot.id, |health| health.requests += 1);
+        match result {
+            // The JVM of the worker might be in a bad state.
+            VerificationResult::JavaException(exception) => {
+                Err(WorkerFailure::Exception(exception))
+            }
+            result => Ok(result),
+        }
+    }
+
+    fn acquire(&self) -> Slot {
+        let mut idle = self.idle.lock().unwrap();
+        loop {
+            if let Some(slot) = idle.pop() {
+                return slot;
+            }
+            idle = self.idle_changed.wait(idle).unwrap();
+        }
+    }
+
+    fn release(&self, slot: Slot) {
+        self.idle.lock().unwrap().push(slot);
+        self.idle_changed.notify_one();
+    }
+
+    fn update_health<F: FnOnce(&mut WorkerHealth)>(&self, id: usize, update: F) {
+        update(&mut self.health.lock().unwrap()[id]);
+    }
+}
+
+impl Worker {
+    fn spawn(executable: &Path, id: usize) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let mut child = Command::new(executable)
+            .arg("--worker")
+            .arg(listener.local_addr()?.port().to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        // The standard output of the server is reserved for its port, so the
+        // output of the worker, like that of its verifier, goes to the log.
+        let stdout = child.stdout.take().unwrap();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => info!("Verifier worker {}: {}", id, line),
+                    Err(_) => break,
+                }
+            }
+        });
+        listener.set_nonblocking(true)?;
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    return Ok(Worker { child, stream });
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    if let Some(status) = child.try_wait()? {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("the worker exited with {} before connecting", status),
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(error) => {
+                    let _ = child.kill();
+                    return Err(error);
+                }
+            }
+        }
+    }
+
+    fn verify(
+        &mut self,
+        request: &VerificationRequest,
+        cancellation: &CancellationToken,
+        memory_limit: Option<u64>,
+    ) -> io::Result<VerificationResult> {
+        write_message(&mut self.stream, &WorkerMessage::Verify(request))?;
+        // Wait for the result, checking periodically that the worker is
+        // still alive and within its memory limit, and whether the request
+        // was cancelled.
+        self.stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        let mut byte = [0];
+        let mut cancel_sent = false;
+        loop {
+            if !cancel_sent && cancellation.is_cancelled() {
+                // The worker answers with a cancelled result.
+                write_message(&mut self.stream, &WorkerMessage::<()>::Cancel)?;
+                cancel_sent = true;
+            }
+            match self.stream.peek(&mut byte) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "the worker closed the connection",
+                    ))
+                }
+                Ok(_) => break,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if let Some(status) = self.child.try_wait()? {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("the worker exited with {}", status),
+                        ));
+                    }
+                    if let (Some(limit), Some(memory)) =
+                        (memory_limit, resident_memory(self.child.id()))
+                    {
+                        if memory > limit {
+                            return Err(io::Error::new(
+                                io::ErrorKind::Other,
+                                format!(
+                                    "the worker exceeded its memory limit ({} MiB)",
+                                    memory / 1024 / 1024
+                                ),
+                            ));
+                        }
+                    }
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        self.stream.set_read_timeout(None)?;
+        read_message(&mut self.stream)
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The resident memory of a process, in bytes.
+#[cfg(target_os = "linux")]
+fn resident_memory(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory(_pid: u32) -> Option<u64> {
+    None
+}
+
+fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let bytes = bincode::serialize(message)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<T> {
+    let mut length = [0; 8];
+    reader.read_exact(&mut length)?;
+    let mut bytes = vec![0; u64::from_le_bytes(length) as usize];
+    reader.read_exact(&mut bytes)?;
+    bincode::deserialize(&bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// A cache that never stores results. Used by the workers, whose results are
+/// cached by the server, which shares the cache between all workers.
+pub(crate) struct NoCache;
+
+impl Cache for NoCache {
+    fn get(&mut self, _request: u64) -> Option<VerificationResult> {
+        None
+    }
+    fn insert(self, _request: u64, _result: VerificationResult) -> Option<VerificationResult> {
+        None
+    }
+}
+
+/// The main loop of a worker process: connect to the server on `port` and
+/// verify the requests it sends until it closes the connection.
+pub fn run_worker(port: u16) {
+    let mut stream =
+        TcpStream::connect((Ipv4Addr::LOCALHOST, port)).expect("could not connect to the server");
+
+    let stopwatch = Stopwatch::start("prusti-server", "JVM startup");
+    let viper = Viper::new_with_args(config::extra_jvm_args());
+    let viper_thread = viper.attach_current_thread();
+    stopwatch.finish();
+
+    // Messages are read on a separate thread, so that a cancellation is
+    // received while the request is being verified. The server sends the
+    // next request only after receiving the result of the previous one, so a
+    // cancellation always concerns the last request received.
+    let (request_sender, request_receiver) =
+        mpsc::channel::<(VerificationRequest, CancellationToken)>();
+    let mut reader = stream.try_clone().expect("could not clone the connection");
+    thread::spawn(move || {
+        let mut current_cancellation = CancellationToken::default();
+        while let Ok(message) = read_message(&mut reader) {
+            match message {
+                WorkerMessage::Verify(request) => {
+                    current_cancellation = CancellationToken::default();
+                    if request_sender
+                        .send((request, current_cancellation.clone()))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                WorkerMessage::Cancel => current_cancellation.cancel(),
+            }
+        }
+    });
+
+    for (request, cancellation) in request_receiver {
+        let result = process_verification_request(&viper_thread, request, NoCache, &cancellation);
+        if write_message(&mut stream, &result).is_err() {
+            break;
+        }
+    }
+}
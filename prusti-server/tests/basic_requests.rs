@@ -41,6 +41,42 @@ fn empty_program() {
     }
 }
 
+#[test]
+fn status_and_metrics() {
+    let result = process_program(|_| ());
+    assert!(matches!(result, VerificationResult::Success));
+
+    let status = get("status");
+    assert!(
+        status.contains("\"jvm_up\":true"),
+        "unexpected status {}",
+        status
+    );
+
+    let metrics = get("metrics");
+    assert!(
+        metrics.contains("prusti_server_requests_total{backend=\"Silicon\",result=\"success\"}"),
+        "unexpected metrics {}",
+        metrics
+    );
+    assert!(
+        metrics.contains("prusti_server_verification_duration_seconds_count{backend=\"Silicon\"}"),
+        "unexpected metrics {}",
+        metrics
+    );
+}
+
+fn get(path: &str) -> String {
+    let url = format!("http://{}/{}", *SERVER_ADDRESS, path);
+    Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .expect("failed to construct Tokio runtime")
+        .block_on(async { reqwest::get(&url).await?.error_for_status()?.text().await })
+        .expect("Request failed")
+}
+
 fn process_program<F>(configure: F) -> VerificationResult
 where
     F: FnOnce(&mut Program),
@@ -58,10 +94,10 @@ where
     };
     configure(&mut program);
 
-    let request = VerificationRequest {
-        program: prusti_common::vir::program::Program::Legacy(program),
-        backend_config: Default::default(),
-    };
+    let request = VerificationRequest::new(
+        prusti_common::vir::program::Program::Legacy(program),
+        Default::default(),
+    );
 
     Builder::new()
         .basic_scheduler()
@@ -0,0 +1,92 @@
+use prusti_common::vir::*;
+use prusti_server::{CancellationToken, VerificationRequest, WorkerPool};
+use std::{env, path::PathBuf, process::Command};
+use viper::VerificationResult;
+
+/// test that a worker that was killed while idle is restarted and that the
+/// request is retried on the new worker
+#[cfg(unix)]
+#[test]
+fn restarts_a_killed_worker() {
+    let pool = WorkerPool::new(driver_path(), 1, None);
+    assert!(matches!(verify(&pool), VerificationResult::Success));
+    let pid = pool.health()[0].pid.expect("the worker is running");
+
+    let status = Command::new("kill")
+        .arg("-9")
+        .arg(pid.to_string())
+        .status()
+        .expect("failed to run kill");
+    assert!(status.success());
+
+    assert!(matches!(verify(&pool), VerificationResult::Success));
+    let health = &pool.health()[0];
+    assert_eq!(health.restarts, 1);
+    assert_eq!(health.requests, 2);
+    assert!(health.last_failure.is_some());
+    assert_ne!(health.pid, Some(pid));
+}
+
+/// test that a request whose worker fails twice is reported as a crash of the
+/// worker, and that the worker was restarted after each failure
+#[cfg(target_os = "linux")]
+#[test]
+fn reports_a_worker_that_fails_twice() {
+    // No worker fits into 1 MiB, so every attempt fails.
+    let pool = WorkerPool::new(driver_path(), 1, Some(1));
+    match verify(&pool) {
+        VerificationResult::JavaException(exception) => assert!(
+            exception.to_string().contains("memory limit"),
+            "unexpected exception {}",
+            exception
+        ),
+        other => panic!("worker crash not reported, instead found {:?}", other),
+    }
+    let health = &pool.health()[0];
+    assert_eq!(health.restarts, 2);
+    assert_eq!(health.requests, 0);
+    assert!(!health.busy);
+    assert_eq!(health.pid, None);
+}
+
+/// test that a cancelled request is not sent to a worker
+#[test]
+fn does_not_verify_cancelled_requests() {
+    let pool = WorkerPool::new(driver_path(), 1, None);
+    let cancellation = CancellationToken::default();
+    cancellation.cancel();
+    assert!(matches!(
+        pool.verify(&request(), &cancellation),
+        VerificationResult::Cancelled
+    ));
+    assert_eq!(pool.health()[0].pid, None);
+}
+
+fn driver_path() -> PathBuf {
+    env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .with_file_name("prusti-server-driver")
+        .with_extension(env::consts::EXE_EXTENSION)
+}
+
+fn verify(pool: &WorkerPool) -> VerificationResult {
+    pool.verify(&request(), &CancellationToken::default())
+}
+
+fn request() -> VerificationRequest {
+    let program = Program {
+        name: "dummy".to_string(),
+        domains: vec![],
+        fields: vec![],
+        builtin_methods: vec![],
+        methods: vec![],
+        functions: vec![],
+        viper_predicates: vec![],
+    };
+    VerificationRequest::new(
+        prusti_common::vir::program::Program::Legacy(program),
+        Default::default(),
+    )
+}
@@ -322,7 +322,7 @@ impl PrustiTokenStream {
                             // TODO: assert empty afterwards ...
                             .map(|stream| stream.parse())
                             .collect::<Result<Vec<_>, _>>()?,
-                    );
+                    )?;
                     continue;
                 }
 
@@ -491,74 +491,65 @@ enum PrustiToken {
     CallDesc(Span, bool),
 }
 
+/// The largest number of arguments of a closure or a function pointer that
+/// can be the target of a specification entailment. It has to match the
+/// number of `specification_entailment_*` functions in `prusti_contracts`.
+const MAX_SPEC_ENT_ARGS: usize = 6;
+
 fn translate_spec_ent(
     span: Span,
     once: bool,
     cl_expr: TokenStream,
     cl_args: Vec<TokenStream>,
     contract: Vec<ClosureSpec<TokenStream>>,
-) -> TokenStream {
+) -> syn::Result<TokenStream> {
     let once = if once {
         quote_spanned! { span => true }
     } else {
         quote_spanned! { span => false }
     };
 
-    // TODO: move extraction function generation into "fn_type_extractor"
-    let arg_count = cl_args.len();
-    let generics_args = (0..arg_count)
-        .map(|i| TokenTree::Ident(proc_macro2::Ident::new(&format!("GA{}", i), span)))
-        .collect::<Vec<_>>();
-    let generic_res = TokenTree::Ident(proc_macro2::Ident::new("GR", span));
-
-    let extract_args = (0..arg_count)
-        .map(|i| TokenTree::Ident(proc_macro2::Ident::new(&format!("__extract_arg{}", i), span)))
-        .collect::<Vec<_>>();
-    let extract_args_decl = extract_args.iter()
-        .zip(generics_args.iter())
-        .map(|(ident, arg_type)| quote_spanned! { span =>
-            #[prusti::spec_only]
-            fn #ident<
-                #(#generics_args),* ,
-                #generic_res,
-                F: FnOnce( #(#generics_args),* ) -> #generic_res
-            >(_f: &F) -> #arg_type { unreachable!() }
-        })
-        .collect::<Vec<_>>();
+    if cl_args.len() > MAX_SPEC_ENT_ARGS {
+        return error(
+            span,
+            &format!("specification entailments support at most {} arguments", MAX_SPEC_ENT_ARGS),
+        );
+    }
+    // The arity-specific function fixes the signatures of the specification
+    // closures, so that `result` does not need a type annotation.
+    let entailment = proc_macro2::Ident::new(
+        &format!("specification_entailment_{}", cl_args.len()),
+        span,
+    );
 
-    let preconds = contract.iter()
-        .filter_map(|spec| match spec {
-            ClosureSpec::Requires(stream) => Some(stream.clone()),
-            _ => None,
-        })
-        .collect::<Vec<_>>();
-    let postconds = contract.into_iter()
-        .filter_map(|spec| match spec {
-            ClosureSpec::Ensures(stream) => Some(stream),
-            _ => None,
-        })
-        .collect::<Vec<_>>();
+    let mut preconds = vec![];
+    let mut postconds = vec![];
+    for spec in contract {
+        match spec {
+            ClosureSpec::Requires(stream) => preconds.push(stream),
+            ClosureSpec::Ensures(stream) => postconds.push(stream),
+        }
+    }
+    let conjoin = |conjuncts: Vec<TokenStream>| {
+        conjuncts
+            .into_iter()
+            .map(|conjunct| quote_spanned! { span => ((#conjunct): bool) })
+            .reduce(|conjunction, conjunct| quote_spanned! { span => #conjunction && #conjunct })
+            .unwrap_or_else(|| quote_spanned! { span => true })
+    };
+    let precondition = conjoin(preconds);
+    let postcondition = conjoin(postconds);
 
     // TODO: figure out `outer`
 
-    quote_spanned! { span => {
-        let __cl_ref = & #cl_expr;
-        #(#extract_args_decl)*
-        #[prusti::spec_only]
-        fn __extract_res<
-            #(#generics_args),* ,
-            #generic_res,
-            F: FnOnce( #(#generics_args),* ) -> #generic_res
-        >(_f: &F) -> #generic_res { unreachable!() }
-        #( let #cl_args = #extract_args(__cl_ref); )*
-        let result = __extract_res(__cl_ref);
-        specification_entailment(
+    Ok(quote_spanned! { span =>
+        #entailment(
             #once,
-            __cl_ref,
-            ( #( #[prusti::spec_only] || -> bool { ((#preconds): bool) }, )* ),
-            ( #( #[prusti::spec_only] || -> bool { ((#postconds): bool) }, )* ),
+            & #cl_expr,
+            #[prusti::spec_only] | #(#cl_args),* | -> bool { #precondition },
+            #[prusti::spec_only] | #(#cl_args,)* result | -> bool { #postcondition },
         )
-    } }
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,12 @@
+use prusti_contracts::*;
+
+#[ensures(result == 1)]
+pub fn one() -> u32 {
+    1
+}
+
+#[trusted]
+#[ensures(result == 2)]
+pub fn two() -> u32 {
+    unimplemented!()
+}
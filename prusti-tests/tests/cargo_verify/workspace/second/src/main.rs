@@ -0,0 +1,4 @@
+fn main() {
+    assert!(first::one() + first::two() == 3);
+    assert!(false);
+}
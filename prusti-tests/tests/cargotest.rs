@@ -95,6 +95,11 @@ error: could not compile `foo` due to previous error
 /// For more details on the special syntax allowed in the `output.*` files, check the documentation
 /// of `cargo_test_support`: <https://doc.crates.io/contrib/tests/writing.html>.
 fn test_local_project<T: Into<PathBuf>>(project_name: T) {
+    test_local_project_with_args(project_name, &[]);
+}
+
+/// Like `test_local_project`, but passes additional arguments to `cargo-prusti`.
+fn test_local_project_with_args<T: Into<PathBuf>>(project_name: T, args: &[&str]) {
     let mut project_builder = project().no_manifest();
     let relative_project_path = Path::new("tests/cargo_verify").join(project_name.into());
     let project_path = fs::canonicalize(&relative_project_path).unwrap_or_else(|_| {
@@ -161,7 +166,7 @@ fn test_local_project<T: Into<PathBuf>>(project_name: T) {
 
     // Set the expected exit status, stdout and stderr
     let mut test_builder = project.process(cargo_prusti_path());
-    test_builder.arg("--quiet");
+    test_builder.arg("--quiet").args(args);
     let opt_expected_stdout = fs::read_to_string(project_path.join("output.stdout")).ok();
     let opt_expected_stderr = fs::read_to_string(project_path.join("output.stderr")).ok();
     if let Some(ref expected_stdout) = opt_expected_stdout {
@@ -227,4 +232,54 @@ fn test_overflow_checks() {
     test_local_project("overflow_checks");
 }
 
+#[cargo_test]
+fn test_workspace_summary() {
+    test_local_project_with_args("workspace", &["--summary"]);
+}
+
+#[cargo_test]
+fn test_missing_manifest() {
+    let p = project().no_manifest().build();
+    p.process(cargo_prusti_path())
+        .arg("--manifest-path")
+        .arg("missing/Cargo.toml")
+        .with_status(1)
+        .with_stderr("error: manifest path `missing/Cargo.toml` does not exist")
+        .run();
+}
+
+#[cargo_test]
+fn test_malformed_prusti_toml() {
+    let p = project()
+        .file("src/main.rs", "fn main() {}")
+        .file("Prusti.toml", "check_overflows = ")
+        .build();
+    p.process(cargo_prusti_path())
+        .with_status(1)
+        .with_stderr("error: could not parse [..]Prusti.toml: [..]")
+        .run();
+}
+
+#[cargo_test]
+fn test_invalid_package_metadata() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [package.metadata]
+                prusti = "check_overflows"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+    p.process(cargo_prusti_path())
+        .with_status(1)
+        .with_stderr("error: invalid [package.metadata.prusti] table in package foo: [..]")
+        .run();
+}
+
 // TODO: automatically create a test for each folder in `test/cargo_verify`.
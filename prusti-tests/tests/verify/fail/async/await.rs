@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+async fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+async fn calls_increment() {
+    let y = increment(5).await;
+    assert!(y == 5); //~ ERROR the asserted expression might not hold
+}
+
+#[requires(x < 100)]
+async fn wrong_precondition(x: u32) -> u32 {
+    increment(x + 1).await //~ ERROR precondition might not hold
+}
+
+fn main() {}
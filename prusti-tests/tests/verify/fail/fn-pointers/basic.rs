@@ -0,0 +1,29 @@
+use prusti_contracts::*;
+
+#[requires(op |= |a: i32| [
+    requires(a >= 0 && a < 100),
+    ensures(result > a)
+])]
+fn apply(op: fn(i32) -> i32) -> i32 {
+    op(-1) //~ ERROR precondition might not hold
+}
+
+#[requires(op |= |a: i32| [
+    ensures(result > a)
+])]
+fn check(op: fn(i32) -> i32) -> i32 {
+    let r = op(5);
+    assert!(r > 6); //~ ERROR the asserted expression might not hold
+    r
+}
+
+#[requires(a >= 0 && a < 100)]
+#[ensures(result == a + 1)]
+fn inc(a: i32) -> i32 {
+    a + 1
+}
+
+fn main() {
+    apply(inc);
+    check(inc); //~ ERROR precondition might not hold
+}
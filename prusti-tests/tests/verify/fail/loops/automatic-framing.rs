@@ -0,0 +1,22 @@
+// compile-flags: -Ploop_framing_invariants=true
+
+use prusti_contracts::*;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+// The loop writes `p.x`, so its old value must not be framed.
+fn modified_field() {
+    let mut p = Point { x: 1, y: 2 };
+    let mut i = 0;
+    while i < 10 {
+        p.x = i;
+        i += 1;
+    }
+    assert!(p.y == 2);
+    assert!(p.x == 1); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+trait Shape {
+    #[ensures(result > 0)]
+    fn area(&self) -> u32;
+}
+
+struct Square;
+
+#[refine_trait_spec]
+impl Shape for Square {
+    #[ensures(result > 0)]
+    fn area(&self) -> u32 {
+        1
+    }
+}
+
+fn area_of(shape: &dyn Shape) -> u32 {
+    let area = shape.area();
+    // The trait specification says nothing about the concrete value.
+    assert!(area == 1); //~ ERROR the asserted expression might not hold
+    area
+}
+
+fn main() {
+    let square = Square;
+    area_of(&square);
+}
@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+async fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+async fn await_either(b: bool) -> u32 {
+    let future = if b { increment(1) } else { increment(2) };
+    future.await //~ ERROR awaiting a future that is assigned in more than one place is not supported
+}
+
+fn main() {}
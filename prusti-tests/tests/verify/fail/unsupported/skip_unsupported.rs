@@ -0,0 +1,21 @@
+// compile-flags: -Pskip_unsupported_features=true
+
+use prusti_contracts::*;
+
+trait Shape {
+    #[ensures(result > 0)]
+    fn area(&self) -> u32;
+}
+
+// Trait objects are partially supported, so this function is still verified.
+fn partially_supported(shape: &dyn Shape) {
+    let area = shape.area();
+    assert!(area > 1); //~ ERROR the asserted expression might not hold
+}
+
+fn unsupported(p: *const i32) -> bool { //~ WARNING this function will be ignored because it is not supported by Prusti
+    assert!(false);
+    p.is_null()
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+// compile-flags: -Preport_support_status=true -Psupport_status_only=true
+
+use prusti_contracts::*;
+
+union Foo {
+    a: u32,
+    b: i32,
+}
+
+fn raw_pointer(p: *const i32) -> bool { //~ ERROR raw pointers are not supported
+    p.is_null()
+}
+
+fn union(x: Foo) -> u32 { //~ ERROR unions are not supported
+    unsafe { x.a }
+}
+
+fn two_phase_borrow(v: &mut Vec<usize>) {
+    v.push(v.len()); //~ ERROR two-phase borrows are not supported
+}
+
+#[pure]
+fn pure_with_loop(n: u32) -> u32 { //~ ERROR loops in pure functions are not supported
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+trait Counter {
+    fn increment(&mut self);
+}
+
+struct Simple(u32);
+
+impl Counter for Simple {
+    fn increment(&mut self) {}
+}
+
+fn main() {
+    let mut c = Simple(0);
+    let _d: &mut dyn Counter = &mut c; //~ ERROR unsizing a mutable reference into a trait object is not supported
+}
@@ -1,3 +1,3 @@
 fn main() {
-    let _ = vec![1]; //~ ERROR unsizing a pointer or reference value is not supported
+    let _ = vec![1]; //~ ERROR unsizing a box of an array into a box of a slice is not supported
 }
\ No newline at end of file
@@ -0,0 +1,25 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+async fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[ensures(result == a || result == b)]
+#[ensures(result >= a && result >= b)]
+async fn max(a: i32, b: i32) -> i32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[requires(*x < 100)]
+#[ensures(*x == old(*x) + 1)]
+async fn increment_in_place(x: &mut u32) {
+    *x += 1;
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+async fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[ensures(result >= a && result >= b)]
+async fn max(a: i32, b: i32) -> i32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[requires(x < 50)]
+#[ensures(result == x + 2)]
+async fn increment_twice(x: u32) -> u32 {
+    let y = increment(x).await;
+    assert!(y == x + 1);
+    increment(y).await
+}
+
+async fn use_max() {
+    let future = max(3, 5);
+    let m = future.await;
+    assert!(m >= 5);
+}
+
+fn main() {}
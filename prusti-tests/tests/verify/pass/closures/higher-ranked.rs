@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+#[trusted]
+#[ensures(result <= items.len())]
+fn count_matching<T, F>(items: &[T], predicate: F) -> usize
+    where F: for<'a> Fn(&'a T) -> bool
+{
+    items.iter().filter(|item| predicate(item)).count()
+}
+
+#[ensures(result <= items.len())]
+fn count_forward<T, F>(items: &[T], predicate: F) -> usize
+    where F: for<'a> Fn(&'a T) -> bool
+{
+    count_matching(items, predicate)
+}
+
+#[ensures(result == x)]
+fn first<'a, 'b: 'a>(x: &'a i32, _y: &'b i32) -> &'a i32 {
+    x
+}
+
+fn main() {
+    let _ = (0..1).filter(|_| true);
+    let items = [1, 2, 3];
+    let count = count_forward(&items, |x| *x > 1);
+    assert!(count <= 3);
+    let a = 1;
+    let b = 2;
+    let r = first(&a, &b);
+    assert!(*r == 1);
+}
@@ -0,0 +1,34 @@
+use prusti_contracts::*;
+
+#[requires(op |= |a: i32, b: i32| [
+    requires(a >= 0 && a <= 100),
+    requires(b >= 0 && b <= 100),
+    ensures(result >= a)
+])]
+#[ensures(result >= 7)]
+fn apply(op: fn(i32, i32) -> i32) -> i32 {
+    op(7, 9)
+}
+
+#[requires(a >= 0 && a <= 100)]
+#[requires(b >= 0 && b <= 100)]
+#[ensures(result == a + b)]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[requires(a >= 0)]
+#[ensures(result == a)]
+fn first(a: i32, _b: i32) -> i32 {
+    a
+}
+
+fn main() {
+    let r = apply(add);
+    assert!(r >= 7);
+    let op: fn(i32, i32) -> i32 = first;
+    let r = apply(op);
+    assert!(r >= 7);
+    let s = op(3, 4);
+    assert!(s == 3);
+}
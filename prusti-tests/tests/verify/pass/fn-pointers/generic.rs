@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+#[trusted]
+fn identity<T>(x: T) -> T {
+    x
+}
+
+fn call_through_pointer<U>(x: U) -> U {
+    let op: fn(U) -> U = identity;
+    op(x)
+}
+
+#[requires(a >= 0 && a <= 100)]
+#[ensures(result == a + 1)]
+fn increment(a: i32) -> i32 {
+    a + 1
+}
+
+fn main() {
+    let op: fn(i32) -> i32 = increment;
+    let r = op(4);
+    assert!(r == 5);
+    let _ = call_through_pointer(3);
+}
@@ -0,0 +1,42 @@
+// compile-flags: -Ploop_framing_invariants=true
+
+use prusti_contracts::*;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn untouched_field() {
+    let mut p = Point { x: 1, y: 2 };
+    let mut i = 0;
+    while i < 10 {
+        p.y += 1;
+        i += 1;
+    }
+    assert!(p.x == 1);
+}
+
+#[requires(limit.y == 3)]
+fn read_only(limit: Point) {
+    let mut i = 0;
+    while i < limit.x {
+        i += 1;
+    }
+    assert!(limit.y == 3);
+}
+
+#[requires(p.x == 5)]
+fn untouched_argument(p: Point) -> i32 {
+    let mut res = 0;
+    let mut i = 0;
+    while i < 3 {
+        body_invariant!(res == i);
+        res += 1;
+        i += 1;
+    }
+    assert!(p.x == 5);
+    res
+}
+
+fn main() {}
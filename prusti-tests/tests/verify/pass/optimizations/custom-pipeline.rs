@@ -0,0 +1,24 @@
+// Run some of the method optimizations, in another order than the default one.
+// compile-flags: -Poptimization_passes=clean_cfg,remove_unused_vars,purify_vars,remove_empty_if
+
+use prusti_contracts::*;
+
+#[pure]
+fn double(x: u32) -> u64 {
+    2 * (x as u64)
+}
+
+#[ensures(result == double(x) + 1)]
+fn odd(x: u32) -> u64 {
+    let _copy = x;
+    if x == 0 {
+        1
+    } else {
+        double(x) + 1
+    }
+}
+
+fn main() {
+    let y = odd(3);
+    assert!(y == 7);
+}
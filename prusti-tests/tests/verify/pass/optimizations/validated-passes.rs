@@ -0,0 +1,48 @@
+// Check that the default optimization passes do not break the methods.
+// compile-flags: -Pvalidate_optimizations=true -Penable_purification_optimization=true
+
+use prusti_contracts::*;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[pure]
+fn norm(p: &Point) -> i64 {
+    abs(p.x as i64) + abs(p.y as i64)
+}
+
+#[pure]
+fn abs(x: i64) -> i64 {
+    if x < 0 { -x } else { x }
+}
+
+#[requires(p.x < 1000 && p.y < 1000)]
+#[ensures(norm(p) >= 0)]
+fn shift(p: &mut Point) {
+    let _unused = p.x;
+    if p.x < 0 {
+        p.x += 1;
+    } else {
+        p.y += 1;
+    }
+}
+
+#[requires(n >= 0)]
+#[ensures(result == n)]
+fn count(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        body_invariant!(i < n);
+        i += 1;
+    }
+    i
+}
+
+fn main() {
+    let mut p = Point { x: -3, y: 4 };
+    shift(&mut p);
+    assert!(p.x == -2);
+    assert!(count(5) == 5);
+}
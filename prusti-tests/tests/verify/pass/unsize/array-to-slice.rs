@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+fn main() {}
+
+#[ensures(result == s.len())]
+fn len(s: &[i32]) -> usize {
+    s.len()
+}
+
+#[pure]
+fn pure_len(s: &[i32]) -> usize {
+    s.len()
+}
+
+fn test_impure() {
+    let a = [1, 2, 3];
+    let n = len(&a);
+    assert!(n == 3);
+}
+
+fn test_pure() {
+    let a = [1, 2, 3, 4];
+    assert!(pure_len(&a) == 4);
+}
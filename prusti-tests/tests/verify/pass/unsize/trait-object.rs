@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+trait Shape {
+    #[ensures(result > 0)]
+    fn area(&self) -> u32;
+}
+
+struct Square;
+
+#[refine_trait_spec]
+impl Shape for Square {
+    #[ensures(result > 0)]
+    fn area(&self) -> u32 {
+        1
+    }
+}
+
+fn area_of(shape: &dyn Shape) -> u32 {
+    let area = shape.area();
+    assert!(area > 0);
+    area
+}
+
+fn boxed_area(shape: Box<dyn Shape>) -> u32 {
+    let area = shape.area();
+    assert!(area > 0);
+    area
+}
+
+fn main() {
+    let square = Square;
+    let shape: &dyn Shape = &square;
+    let area = area_of(shape);
+    assert!(area > 0);
+    let area = boxed_area(Box::new(Square));
+    assert!(area > 0);
+}
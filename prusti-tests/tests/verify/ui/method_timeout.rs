@@ -0,0 +1,19 @@
+// compile-flags: -Pmethod_timeout=1
+
+use prusti_contracts::*;
+
+// Proving that there is no solution requires nonlinear reasoning that takes
+// far longer than the time budget of the method.
+#[requires(0 < a && 0 < b && 0 < c)]
+#[requires(a * a * a + b * b * b == c * c * c)]
+#[ensures(false)]
+fn hard(a: u32, b: u32, c: u32) {}
+
+// The sibling method is verified in its own program, so it is not affected by
+// the timeout of `hard`.
+#[ensures(result == a + 1)]
+fn easy(a: u32) -> u32 {
+    a + 1
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+// compile-flags: -Punsafe_core_proof=true
+
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+struct Pair {
+    first: u8,
+    second: u8,
+}
+
+union IntOrBool {
+    int: u8,
+    boolean: bool,
+}
+
+union PairOrInt {
+    pair: Pair,
+    int: u16,
+}
+
+fn read_other_field(value: u8) -> bool {
+    let union = IntOrBool { int: value };
+    unsafe { union.boolean } //~ ERROR the read union field might not be the one that was written last
+}
+
+fn read_unknown_field(value: IntOrBool) -> bool {
+    unsafe { value.boolean } //~ ERROR the read union field might not be the one that was written last
+}
+
+fn write_nested_field(mut value: PairOrInt) -> PairOrInt {
+    unsafe {
+        value.pair.first = 1; //~ ERROR writing into a nested field of a union is not supported
+    }
+    value
+}
+
+fn main() {}
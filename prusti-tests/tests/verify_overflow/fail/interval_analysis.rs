@@ -0,0 +1,17 @@
+// compile-flags: -Pinterval_analysis=true
+
+use prusti_contracts::*;
+
+fn increment(x: u8) -> u8 {
+    x + 1 //~ ERROR: assertion might fail with "attempt to add with overflow"
+}
+
+fn increment_small(x: u8) -> u8 {
+    if x < 255 {
+        x + 1
+    } else {
+        x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+/// The same function as in `pass/interval_analysis.rs`, which verifies only
+/// with the interval analysis: the loop forgets the value of `x`.
+fn reset_in_loop(n: u32) -> u8 {
+    let mut i = 0;
+    let mut x = 0u8;
+    while i < n {
+        i += 1;
+        x = 5;
+    }
+    x + 1 //~ ERROR: assertion might fail with "attempt to add with overflow"
+}
+
+fn main() {}
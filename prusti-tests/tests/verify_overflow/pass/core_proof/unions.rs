@@ -0,0 +1,44 @@
+// compile-flags: -Punsafe_core_proof=true
+
+use prusti_contracts::*;
+
+union IntOrFloat {
+    int: u32,
+    float: f32,
+}
+
+union IntOrBool {
+    int: u8,
+    boolean: bool,
+}
+
+fn identity(value: IntOrFloat) -> IntOrFloat {
+    value
+}
+
+fn move_around(value: IntOrFloat) -> IntOrFloat {
+    let copy = value;
+    let other = identity(copy);
+    other
+}
+
+fn read_written_field(value: bool) -> bool {
+    let union = IntOrBool { boolean: value };
+    unsafe { union.boolean }
+}
+
+fn read_overwritten_field(value: bool) -> bool {
+    let mut union = IntOrBool { int: 1 };
+    union.boolean = value;
+    unsafe { union.boolean }
+}
+
+fn read_int_field(value: IntOrBool) -> u8 {
+    unsafe { value.int }
+}
+
+fn read_float_field(value: IntOrFloat) -> f32 {
+    unsafe { value.float }
+}
+
+fn main() {}
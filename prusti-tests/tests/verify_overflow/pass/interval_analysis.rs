@@ -0,0 +1,40 @@
+// compile-flags: -Pinterval_analysis=true
+
+use prusti_contracts::*;
+
+fn sum_up_to(n: u32) -> u32 {
+    let mut i = 0;
+    let mut count = 0u8;
+    while i < n {
+        i += 1;
+        if count < 100 {
+            count += 1;
+        }
+    }
+    i
+}
+
+fn clamp(x: i32) -> i32 {
+    let res = if x > 10 {
+        10
+    } else if x < -10 {
+        -10
+    } else {
+        x
+    };
+    res * 2
+}
+
+/// Without the interval analysis, the loop forgets the value of `x` and the
+/// addition after the loop might overflow.
+fn reset_in_loop(n: u32) -> u8 {
+    let mut i = 0;
+    let mut x = 0u8;
+    while i < n {
+        i += 1;
+        x = 5;
+    }
+    x + 1
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// compile-flags: -Pinterval_analysis=true -Pquiet=false
+// normalize-stderr-test: "(?s)^.*Prusti version: [^\n]*\n" -> ""
+
+use prusti_contracts::*;
+
+// Both additions are discharged: `i` is smaller than `n` in the loop, and `x`
+// is at most 5 after it.
+fn reset_in_loop(n: u32) -> u8 {
+    let mut i = 0;
+    let mut x = 0u8;
+    while i < n {
+        i += 1;
+        x = 5;
+    }
+    x + 1
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+// compile-flags: -Punsafe_core_proof=true -Ponly_memory_safety=true -Pprint_permission_inference=true
+
+use prusti_contracts::*;
+
+struct T {
+    f: u32,
+}
+
+fn branch(b: bool) {
+    let mut a = T { f: 4 };
+    if b {
+        a.f = 5;
+    }
+    let _c = a;
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// Check that a failure to obtain a permission is explained by the borrow that took it.
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+pub struct A {
+    inner: usize,
+}
+pub struct B {
+    inner: [A],
+}
+
+impl B {
+    #[requires(index < self.inner.len())]
+    pub fn get_mut(&mut self, index: usize) -> &mut A {
+        //~^ ERROR Prusti encountered an unexpected internal error
+        //~| NOTE We would appreciate a bug report
+        //~| NOTE cannot generate fold-unfold Viper statements
+        &mut self.inner[index] //~ NOTE was lost because it is borrowed here
+    }
+}
+
+fn main() {}
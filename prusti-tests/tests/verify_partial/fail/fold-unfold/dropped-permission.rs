@@ -0,0 +1,25 @@
+// Check that a failure to obtain a permission is explained by the join of the branches that
+// dropped it.
+extern crate prusti_contracts;
+use prusti_contracts::*;
+
+#[derive(Clone, Copy)]
+pub struct A {
+    inner: usize,
+}
+pub struct B {
+    inner: [A],
+}
+
+#[requires(b.inner.len() > 1)]
+pub fn update(b: &mut B, first: bool) {
+    //~^ ERROR Prusti encountered an unexpected internal error
+    //~| NOTE We would appreciate a bug report
+    //~| NOTE cannot generate fold-unfold Viper statements
+    if first {
+        let a = &mut b.inner[0];
+        a.inner = 1;
+    } //~ NOTE was lost where these branches join
+}
+
+fn main() {}
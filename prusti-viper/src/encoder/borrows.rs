@@ -413,6 +413,15 @@ impl<'tcx> TypeVisitor<'tcx> for BorrowInfoCollectingVisitor<'tcx> {
         // Do nothing.
         Ok(())
     }
+
+    fn visit_fn_ptr(
+        &mut self,
+        sig: ty::PolyFnSig<'tcx>,
+    ) -> Result<(), Self::Error> {
+        trace!("visit_fn_ptr({:?}) current_path={:?}", sig, self.current_path);
+        // A function pointer does not hold the references of its signature.
+        Ok(())
+    }
 }
 
 pub fn compute_procedure_contract<'p, 'a, 'tcx>(
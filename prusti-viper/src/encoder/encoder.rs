@@ -8,7 +8,7 @@ use ::log::{info, debug, trace};
 use crate::encoder::borrows::{compute_procedure_contract, ProcedureContract, ProcedureContractMirDef};
 use crate::encoder::builtin_encoder::BuiltinEncoder;
 use crate::encoder::builtin_encoder::BuiltinMethodKind;
-use crate::encoder::errors::{ErrorManager, SpannedEncodingError, EncodingError};
+use crate::encoder::errors::{ErrorManager, SpannedEncodingError, EncodingError, EncodingErrorKind};
 use crate::encoder::foldunfold;
 use crate::encoder::places;
 use crate::encoder::procedure_encoder::ProcedureEncoder;
@@ -82,6 +82,9 @@ pub struct Encoder<'v, 'tcx: 'v> {
     spec_functions: RefCell<FxHashMap<ProcedureDefId, Vec<vir::FunctionIdentifier>>>,
     type_discriminant_funcs: RefCell<FxHashMap<String, vir::FunctionIdentifier>>,
     type_cast_functions: RefCell<FxHashMap<(ty::Ty<'tcx>, ty::Ty<'tcx>), vir::FunctionIdentifier>>,
+    /// The abstract functions stating the precondition (`false`) and the
+    /// postcondition (`true`) of calls through a function pointer type.
+    fn_pointer_contract_functions: RefCell<FxHashMap<(ty::Ty<'tcx>, bool), (String, vir::FunctionIdentifier)>>,
     pub(super) snapshot_encoder_state: SnapshotEncoderState,
     pub(super) mirror_encoder: RefCell<MirrorEncoder>,
     array_types_encoder: RefCell<ArrayTypesEncoder<'tcx>>,
@@ -90,6 +93,10 @@ pub struct Encoder<'v, 'tcx: 'v> {
     vir_program_before_foldunfold_writer: Option<RefCell<Box<dyn Write>>>,
     vir_program_before_viper_writer: Option<RefCell<Box<dyn Write>>>,
     encoding_errors_counter: RefCell<usize>,
+    discharged_overflow_checks_counter: RefCell<usize>,
+    /// The procedures whose encoding failed, mapped to whether it failed
+    /// because of an unsupported feature.
+    failed_encodings: RefCell<FxHashMap<ProcedureDefId, bool>>,
     name_interner: RefCell<NameInterner>,
     /// Maps locals to the local of their discriminant.
     discriminants_info: RefCell<FxHashMap<(ProcedureDefId, String), Vec<String>>>,
@@ -153,6 +160,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             spec_functions: RefCell::new(FxHashMap::default()),
             type_discriminant_funcs: RefCell::new(FxHashMap::default()),
             type_cast_functions: RefCell::new(FxHashMap::default()),
+            fn_pointer_contract_functions: RefCell::new(FxHashMap::default()),
             closures_collector: RefCell::new(SpecsClosuresCollector::new()),
             encoding_queue: RefCell::new(vec![]),
             vir_program_before_foldunfold_writer,
@@ -161,6 +169,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             mirror_encoder: RefCell::new(MirrorEncoder::new()),
             array_types_encoder: RefCell::new(ArrayTypesEncoder::new()),
             encoding_errors_counter: RefCell::new(0),
+            discharged_overflow_checks_counter: RefCell::new(0),
+            failed_encodings: RefCell::new(FxHashMap::default()),
             name_interner: RefCell::new(NameInterner::new()),
             discriminants_info: RefCell::new(FxHashMap::default()),
             is_encoding_trigger: Cell::new(false),
@@ -240,6 +250,27 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         *self.encoding_errors_counter.borrow()
     }
 
+    pub(in crate::encoder) fn register_discharged_overflow_check(&self) {
+        self.discharged_overflow_checks_counter.borrow_mut().add_assign(1);
+    }
+
+    pub fn count_discharged_overflow_checks(&self) -> usize {
+        *self.discharged_overflow_checks_counter.borrow()
+    }
+
+    /// Register an error that prevented the procedure from being encoded.
+    fn register_procedure_encoding_error(&self, proc_def_id: ProcedureDefId, encoding_error: SpannedEncodingError) {
+        let is_unsupported = matches!(encoding_error.kind(), EncodingErrorKind::Unsupported(_));
+        self.failed_encodings.borrow_mut().insert(proc_def_id, is_unsupported);
+        self.register_encoding_error(encoding_error);
+    }
+
+    /// If the encoding of the procedure failed, returns whether it failed
+    /// because of an unsupported feature.
+    pub fn failed_encoding(&self, proc_def_id: ProcedureDefId) -> Option<bool> {
+        self.failed_encodings.borrow().get(&proc_def_id).copied()
+    }
+
     pub(super) fn get_mirror_domain(&self) -> Option<vir::Domain> {
         self.mirror_encoder.borrow().get_domain().cloned()
     }
@@ -354,11 +385,20 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         tymap: SubstMap<'tcx>,// TODO: Simplify these params
         substs: ty::subst::SubstsRef<'tcx>,
     ) -> EncodingResult<ProcedureContract<'tcx>> {
-        let spec = self.env()
-            .find_impl_of_trait_method_call(proc_def_id, substs)
-            .and_then(|impl_def_id| self.get_procedure_specs(impl_def_id))
-            .or_else(|| self.get_procedure_specs(proc_def_id)) // Fallback to trait spec
-            .unwrap_or_else(typed::ProcedureSpecification::empty);
+        let spec = self.get_call_site_specification(proc_def_id, substs);
+        let spec = if self.env().is_async_fn(proc_def_id) {
+            // Calling an `async fn` only constructs the future, so the
+            // postconditions (which describe the output of the future) do not
+            // hold after the call. They are assumed when the future is
+            // awaited, see `get_procedure_contract_for_await`.
+            typed::ProcedureSpecification {
+                posts: typed::SpecificationItem::Empty,
+                pledges: typed::SpecificationItem::Empty,
+                ..spec
+            }
+        } else {
+            spec
+        };
 
         let contract = compute_procedure_contract(
             proc_def_id,
@@ -369,6 +409,40 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(contract.to_call_site_contract(args, target))
     }
 
+    /// The contract of the `async fn` `proc_def_id` at the point where the
+    /// future returned by a call with `args` is awaited. `target` is the
+    /// local that stores the output of the future.
+    pub fn get_procedure_contract_for_await(
+        &self,
+        proc_def_id: ProcedureDefId,
+        args: &[places::Local],
+        target: places::Local,
+        tymap: SubstMap<'tcx>,
+        substs: ty::subst::SubstsRef<'tcx>,
+    ) -> EncodingResult<ProcedureContract<'tcx>> {
+        debug_assert!(self.env().is_async_fn(proc_def_id));
+        let spec = self.get_call_site_specification(proc_def_id, substs);
+        let contract = compute_procedure_contract(
+            proc_def_id,
+            self.env(),
+            typed::SpecificationSet::Procedure(spec),
+            Some(&tymap)
+        )?;
+        Ok(contract.to_call_site_contract(args, target))
+    }
+
+    fn get_call_site_specification(
+        &self,
+        proc_def_id: ProcedureDefId,
+        substs: ty::subst::SubstsRef<'tcx>,
+    ) -> typed::ProcedureSpecification {
+        self.env()
+            .find_impl_of_trait_method_call(proc_def_id, substs)
+            .and_then(|impl_def_id| self.get_procedure_specs(impl_def_id))
+            .or_else(|| self.get_procedure_specs(proc_def_id)) // Fallback to trait spec
+            .unwrap_or_else(typed::ProcedureSpecification::empty)
+    }
+
     /// Encodes a value in a field if the base expression is a reference or
     /// a primitive types.
     /// For composed data structures, the base expression is returned.
@@ -376,6 +450,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         match ty.kind() {
             ty::TyKind::Adt(_, _)
             | ty::TyKind::Closure(_, _)
+            | ty::TyKind::Generator(..)
             | ty::TyKind::Array(..)
             | ty::TyKind::Tuple(_) => {
                 Ok(base) // don't use a field for tuples and ADTs
@@ -505,10 +580,14 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(function_name)
     }
 
+    /// Returns the name and the type arguments of the unsizing function.
     pub fn encode_unsize_function_use(&self, src_ty: ty::Ty<'tcx>, dst_ty: ty::Ty<'tcx>, tymap: &SubstMap<'tcx>)
-        -> EncodingResult<String>
+        -> EncodingResult<(String, Vec<vir::Type>)>
     {
         trace!("encode_unsize_function_use(src_ty={:?}, dst_ty={:?})", src_ty, dst_ty);
+        if dst_ty.is_trait() {
+            return self.encode_trait_object_unsize_function_use(src_ty, dst_ty, tymap);
+        }
         // at some point we may want to add support for other types of unsizing calls?
         assert!(matches!(src_ty.kind(), ty::TyKind::Array(..)));
         assert!(matches!(dst_ty.kind(), ty::TyKind::Slice(..)));
@@ -551,7 +630,103 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             let identifier = self.insert_function(function);
             self.type_cast_functions.borrow_mut().insert((src_ty, dst_ty), identifier);
         }
-        Ok(function_name)
+        Ok((function_name, vec![]))
+    }
+
+    /// A trait object is opaque, so the snapshot of the unsized value is
+    /// unrelated to the snapshot of the original one.
+    fn encode_trait_object_unsize_function_use(
+        &self,
+        src_ty: ty::Ty<'tcx>,
+        dst_ty: ty::Ty<'tcx>,
+        tymap: &SubstMap<'tcx>,
+    ) -> EncodingResult<(String, Vec<vir::Type>)> {
+        let src_snap_ty = self.encode_snapshot_type(src_ty, tymap)?;
+        let dst_snap_ty = self.encode_snapshot_type(dst_ty, tymap)?;
+        let type_arguments = vec![src_snap_ty.clone(), dst_snap_ty.clone()];
+        let function_name = format!(
+            "builtin$unsize${}${}",
+            self.encode_type(src_ty)?.name(),
+            self.encode_type(dst_ty)?.name(),
+        );
+        if !self.type_cast_functions.borrow().contains_key(&(src_ty, dst_ty)) {
+            let function = vir::Function {
+                name: function_name.clone(),
+                type_arguments: type_arguments.clone(),
+                formal_args: vec![vir_local!{ object: {src_snap_ty} }],
+                return_type: dst_snap_ty,
+                pres: vec![],
+                posts: vec![],
+                body: None,
+            };
+            let identifier = self.insert_function(function);
+            self.type_cast_functions.borrow_mut().insert((src_ty, dst_ty), identifier);
+        }
+        Ok((function_name, type_arguments))
+    }
+
+    /// Encodes a call of the abstract function that holds if the function
+    /// behind `fn_ptr` satisfies its precondition (if `result` is `None`) or
+    /// its postcondition (otherwise) for the given arguments. All expressions
+    /// are snapshots.
+    pub fn encode_fn_pointer_contract_call(
+        &self,
+        fn_ptr_ty: ty::Ty<'tcx>,
+        fn_ptr: vir::Expr,
+        mut args: Vec<vir::Expr>,
+        result: Option<vir::Expr>,
+        tymap: &SubstMap<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        trace!("encode_fn_pointer_contract_call(fn_ptr_ty={:?})", fn_ptr_ty);
+        let tcx = self.env().tcx();
+        let fn_sig = tcx.erase_late_bound_regions(fn_ptr_ty.fn_sig(tcx));
+        let mut formal_args = vec![vir::LocalVar::new(
+            "fn_ptr",
+            self.encode_snapshot_type(fn_ptr_ty, tymap)?,
+        )];
+        for (arg_idx, arg_ty) in fn_sig.inputs().iter().enumerate() {
+            formal_args.push(vir::LocalVar::new(
+                format!("arg{}", arg_idx),
+                self.encode_snapshot_type(*arg_ty, tymap)?,
+            ));
+        }
+        let is_postcondition = result.is_some();
+        if is_postcondition {
+            formal_args.push(vir::LocalVar::new(
+                "result",
+                self.encode_snapshot_type(fn_sig.output(), tymap)?,
+            ));
+        }
+        let key = (fn_ptr_ty, is_postcondition);
+        if !self.fn_pointer_contract_functions.borrow().contains_key(&key) {
+            let function_name = format!(
+                "fn_ptr${}${}",
+                if is_postcondition { "post" } else { "pre" },
+                self.fn_pointer_contract_functions.borrow().len(),
+            );
+            let function = vir::Function {
+                name: function_name.clone(),
+                type_arguments: vec![], // FIXME: This is probably wrong.
+                formal_args: formal_args.clone(),
+                return_type: vir::Type::Bool,
+                pres: vec![],
+                posts: vec![],
+                body: None,
+            };
+            let identifier = self.insert_function(function);
+            self.fn_pointer_contract_functions.borrow_mut().insert(key, (function_name, identifier));
+        }
+        let function_name = self.fn_pointer_contract_functions.borrow()[&key].0.clone();
+        args.insert(0, fn_ptr);
+        args.extend(result);
+        Ok(vir::Expr::func_app(
+            function_name,
+            vec![],
+            args,
+            formal_args,
+            vir::Type::Bool,
+            vir::Position::default(),
+        ))
     }
 
     /// This encodes the Rust function as a Viper method for verification. It
@@ -786,7 +961,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
 
             if config::unsafe_core_proof() {
                 if let Err(error) = self.encode_lifetimes_core_proof(proc_def_id) {
-                    self.register_encoding_error(error);
+                    self.register_procedure_encoding_error(proc_def_id, error);
                     debug!("Error encoding function: {:?}", proc_def_id);
                 }
                 continue;
@@ -800,7 +975,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 // TODO: Make sure that this encoded function does not end up in
                 // the Viper file because that would be unsound.
                 if let Err(error) = self.encode_pure_function_def(proc_def_id, &SubstMap::default(), &ty::List::empty()) {
-                    self.register_encoding_error(error);
+                    self.register_procedure_encoding_error(proc_def_id, error);
                     debug!("Error encoding function: {:?}", proc_def_id);
                     // Skip encoding the function as a method.
                     continue;
@@ -811,14 +986,21 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                     "Trusted procedure will not be encoded or verified: {:?}",
                     proc_def_id
                 );
+            } else if self.env().is_async_fn(proc_def_id) {
+                // The body of an `async fn` only constructs the future; the
+                // actual body is verified as the corresponding generator.
+                debug!(
+                    "Async function will be verified via its generator: {:?}",
+                    proc_def_id
+                );
             } else if let Err(error) = self.encode_procedure(proc_def_id) {
-                self.register_encoding_error(error);
+                self.register_procedure_encoding_error(proc_def_id, error);
                 debug!("Error encoding function: {:?}", proc_def_id);
             } else {
                 match self.finalize_viper_program(proc_name, proc_def_id) {
                     Ok(program) => self.programs.push(program),
                     Err(error) => {
-                        self.register_encoding_error(error);
+                        self.register_procedure_encoding_error(proc_def_id, error);
                         debug!("Error finalizing program: {:?}", proc_def_id);
                     }
                 }
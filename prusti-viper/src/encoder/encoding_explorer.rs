@@ -0,0 +1,512 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A self-contained HTML page that shows the MIR of a procedure, its VIR CFG
+//! and the resulting Viper method side by side.
+//!
+//! The three columns are linked by the ids of the VIR positions. A MIR
+//! statement is linked to every position whose span it most tightly
+//! encloses. A VIR statement is linked to the positions of the statement and
+//! of its expressions. A Viper line is linked to the position that the text
+//! emitter printed after it. Clicking any of them highlights everything that
+//! shares one of its positions.
+//!
+//! The Viper column shows the method before the optimizations that run on
+//! whole programs, so it can differ from the verified program.
+
+use crate::encoder::Encoder;
+use prusti_common::{
+    report::log,
+    vir::{CfgMethod as LegacyCfgMethod, ToViperText},
+};
+use prusti_interface::environment::mir_dump::{escape_html, mir_statements, MirStatement};
+use rustc_middle::mir;
+use rustc_span::{source_map::SourceMap, Span};
+use std::{collections::BTreeSet, io::Write};
+use vir_crate::polymorphic::{self as vir, ExprWalker, StmtWalker};
+
+/// Writes the explorer page of `method`, which is the final encoding of the
+/// procedure whose MIR is `mir`.
+pub(super) fn dump_encoding_explorer(
+    encoder: &Encoder,
+    mir: &mir::Body,
+    method: &vir::CfgMethod,
+    source_filename: &str,
+) {
+    let codemap = encoder.env().codemap();
+    let statements = mir_statements(mir);
+    let vir_blocks = collect_vir_blocks(method);
+    let viper_lines: Vec<(String, Option<u64>)> = LegacyCfgMethod::from(method.clone())
+        .to_viper_text()
+        .lines()
+        .map(|line| (line.to_string(), position_id_of_line(line)))
+        .collect();
+
+    let mut position_ids = BTreeSet::new();
+    for block in &vir_blocks {
+        for (_, ids) in &block.stmts {
+            position_ids.extend(ids);
+        }
+        position_ids.extend(&block.successor.1);
+    }
+    position_ids.extend(viper_lines.iter().filter_map(|(_, id)| *id));
+
+    let mut mir_positions: Vec<BTreeSet<u64>> = vec![BTreeSet::new(); statements.len()];
+    {
+        let error_manager = encoder.error_manager();
+        for &pos_id in &position_ids {
+            let span = error_manager
+                .get_span(pos_id)
+                .and_then(|span| span.primary_span())
+                .map(|span| span.source_callsite());
+            if let Some(span) = span {
+                for index in enclosing_statements(&statements, span) {
+                    mir_positions[index].insert(pos_id);
+                }
+            }
+        }
+    }
+
+    log::report_with_writer(
+        "encoding_explorer",
+        format!("{}.{}.html", source_filename, method.name()),
+        |writer| {
+            write_page(
+                writer,
+                codemap,
+                &method.name(),
+                &statements,
+                &mir_positions,
+                &vir_blocks,
+                &viper_lines,
+            )
+            .unwrap()
+        },
+    );
+}
+
+struct VirBlock {
+    label: String,
+    stmts: Vec<(vir::Stmt, BTreeSet<u64>)>,
+    successor: (String, BTreeSet<u64>),
+}
+
+fn collect_vir_blocks(method: &vir::CfgMethod) -> Vec<VirBlock> {
+    let labels = method.basic_blocks_labels();
+    method
+        .basic_blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| {
+            let stmts = block
+                .stmts
+                .iter()
+                .map(|stmt| {
+                    let mut collector = PositionCollector::default();
+                    StmtWalker::walk(&mut collector, stmt);
+                    (stmt.clone(), collector.ids)
+                })
+                .collect();
+            let mut collector = PositionCollector::default();
+            if let vir::Successor::GotoSwitch(ref targets, _) = block.successor {
+                for (guard, _) in targets {
+                    ExprWalker::walk(&mut collector, guard);
+                }
+            }
+            let successor_text = match block.successor {
+                vir::Successor::Undefined => "undefined".to_string(),
+                vir::Successor::Return => "return".to_string(),
+                vir::Successor::Goto(target) => format!("goto {}", labels[target.index()]),
+                vir::Successor::GotoSwitch(ref targets, ref default_target) => targets
+                    .iter()
+                    .map(|(guard, target)| {
+                        format!("if ({}) goto {}", guard, labels[target.index()])
+                    })
+                    .chain(std::iter::once(format!(
+                        "goto {}",
+                        labels[default_target.index()]
+                    )))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            };
+            VirBlock {
+                label: format!("{} (cfg:{})", labels[index], index),
+                stmts,
+                successor: (successor_text, collector.ids),
+            }
+        })
+        .collect()
+}
+
+/// Collects the ids of the non-default positions of statements and
+/// expressions.
+#[derive(Default)]
+struct PositionCollector {
+    ids: BTreeSet<u64>,
+}
+
+impl PositionCollector {
+    fn add(&mut self, position: &vir::Position) {
+        if position.id() != 0 {
+            self.ids.insert(position.id());
+        }
+    }
+}
+
+impl ExprWalker for PositionCollector {
+    fn walk(&mut self, expr: &vir::Expr) {
+        self.add(&expr.pos());
+        vir::default_walk_expr(self, expr);
+    }
+}
+
+impl StmtWalker for PositionCollector {
+    fn walk_expr(&mut self, expr: &vir::Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_exhale(&mut self, statement: &vir::Exhale) {
+        let vir::Exhale { expr, position } = statement;
+        self.add(position);
+        self.walk_expr(expr);
+    }
+
+    fn walk_assert(&mut self, statement: &vir::Assert) {
+        let vir::Assert { expr, position } = statement;
+        self.add(position);
+        self.walk_expr(expr);
+    }
+
+    fn walk_fold(&mut self, statement: &vir::Fold) {
+        let vir::Fold {
+            arguments,
+            position,
+            ..
+        } = statement;
+        self.add(position);
+        for arg in arguments {
+            self.walk_expr(arg);
+        }
+    }
+
+    fn walk_obtain(&mut self, statement: &vir::Obtain) {
+        let vir::Obtain { expr, position } = statement;
+        self.add(position);
+        self.walk_expr(expr);
+    }
+
+    fn walk_package_magic_wand(&mut self, statement: &vir::PackageMagicWand) {
+        let vir::PackageMagicWand {
+            magic_wand,
+            package_stmts,
+            position,
+            ..
+        } = statement;
+        self.add(position);
+        self.walk_expr(magic_wand);
+        for statement in package_stmts {
+            StmtWalker::walk(self, statement);
+        }
+    }
+
+    fn walk_apply_magic_wand(&mut self, statement: &vir::ApplyMagicWand) {
+        let vir::ApplyMagicWand {
+            magic_wand,
+            position,
+        } = statement;
+        self.add(position);
+        self.walk_expr(magic_wand);
+    }
+}
+
+/// Parses the `// pos line:column #id` suffix that the Viper text emitter
+/// appends to positioned statements.
+fn position_id_of_line(line: &str) -> Option<u64> {
+    let (_, position) = line.rsplit_once(" // pos ")?;
+    let (_, id) = position.rsplit_once('#')?;
+    id.parse().ok().filter(|&id| id != 0)
+}
+
+/// The indices of the MIR statements with the smallest spans that contain
+/// `span`. If no statement contains it, the statements that overlap it.
+fn enclosing_statements(statements: &[MirStatement], span: Span) -> Vec<usize> {
+    if span.is_dummy() {
+        return vec![];
+    }
+    let spans: Vec<Span> = statements
+        .iter()
+        .map(|statement| statement.span.source_callsite())
+        .collect();
+    let length = |span: &Span| span.hi().0 - span.lo().0;
+    let enclosing_length = spans
+        .iter()
+        .filter(|statement_span| !statement_span.is_dummy() && statement_span.contains(span))
+        .map(length)
+        .min();
+    spans
+        .iter()
+        .enumerate()
+        .filter(|(_, statement_span)| {
+            !statement_span.is_dummy()
+                && match enclosing_length {
+                    Some(enclosing_length) => {
+                        statement_span.contains(span) && length(*statement_span) == enclosing_length
+                    }
+                    None => statement_span.overlaps(span),
+                }
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn pos_attribute(ids: &BTreeSet<u64>) -> String {
+    if ids.is_empty() {
+        String::new()
+    } else {
+        let ids: Vec<_> = ids.iter().map(|id| id.to_string()).collect();
+        format!(" data-pos=\"{}\"", ids.join(" "))
+    }
+}
+
+fn span_to_text(codemap: &SourceMap, span: Span) -> String {
+    if span.is_dummy() {
+        return String::new();
+    }
+    let location = codemap.lookup_char_pos(span.lo());
+    let snippet = codemap.span_to_snippet(span).unwrap_or_default();
+    let mut first_line: String = snippet
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(60)
+        .collect();
+    if first_line.len() < snippet.len() {
+        first_line.push('…');
+    }
+    format!("{}:{} {}", location.line, location.col.0 + 1, first_line)
+}
+
+const STYLE: &str = "
+body { margin: 0; font-family: monospace; font-size: 12px; }
+h1 { font-size: 14px; margin: 8px; }
+.columns { display: flex; height: calc(100vh - 40px); }
+.column { flex: 1; overflow: auto; border-left: 1px solid #ccc; padding: 0 8px; }
+.column h2 { font-size: 13px; position: sticky; top: 0; background: white; margin: 0; padding: 4px 0; }
+.block { margin-bottom: 8px; border: 1px solid #ccc; }
+.block-label { background: #ddd; padding: 2px 4px; }
+.item { white-space: pre-wrap; padding: 1px 4px; }
+.item[data-pos] { cursor: pointer; }
+.item[data-pos]:hover { background: #eef; }
+.location { color: #888; }
+.span { color: steelblue; }
+.comment { color: darkorange; }
+.successor { border-top: 1px solid #ccc; color: #555; }
+.highlight, .item[data-pos].highlight:hover { background: #ffe680; }
+";
+
+const SCRIPT: &str = "
+var items = document.querySelectorAll('[data-pos]');
+items.forEach(function (item) {
+  item.addEventListener('click', function () {
+    var ids = item.dataset.pos.split(' ');
+    items.forEach(function (other) {
+      var linked = other.dataset.pos.split(' ').some(function (id) {
+        return ids.indexOf(id) >= 0;
+      });
+      other.classList.toggle('highlight', linked);
+    });
+    document.querySelectorAll('.column').forEach(function (column) {
+      var first = column.querySelector('.highlight');
+      if (first && first !== item) {
+        first.scrollIntoView({ block: 'center' });
+      }
+    });
+  });
+});
+";
+
+fn write_page(
+    writer: &mut dyn Write,
+    codemap: &SourceMap,
+    method_name: &str,
+    statements: &[MirStatement],
+    mir_positions: &[BTreeSet<u64>],
+    vir_blocks: &[VirBlock],
+    viper_lines: &[(String, Option<u64>)],
+) -> std::io::Result<()> {
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html><head><meta charset=\"utf-8\">")?;
+    writeln!(writer, "<title>{}</title>", escape_html(method_name))?;
+    writeln!(writer, "<style>{}</style></head><body>", STYLE)?;
+    writeln!(writer, "<h1>Method {}</h1>", escape_html(method_name))?;
+    writeln!(writer, "<div class=\"columns\">")?;
+
+    writeln!(writer, "<div class=\"column\"><h2>MIR</h2>")?;
+    let mut current_block = None;
+    for (statement, ids) in statements.iter().zip(mir_positions) {
+        if current_block != Some(statement.location.block) {
+            if current_block.is_some() {
+                writeln!(writer, "</div>")?;
+            }
+            current_block = Some(statement.location.block);
+            writeln!(
+                writer,
+                "<div class=\"block\"><div class=\"block-label\">{:?}</div>",
+                statement.location.block
+            )?;
+        }
+        writeln!(
+            writer,
+            "<div class=\"item\"{}><span class=\"location\">{:?}</span> {}\n<span class=\"span\">{}</span></div>",
+            pos_attribute(ids),
+            statement.location,
+            escape_html(&statement.text),
+            escape_html(span_to_text(codemap, statement.span.source_callsite())),
+        )?;
+    }
+    if current_block.is_some() {
+        writeln!(writer, "</div>")?;
+    }
+    writeln!(writer, "</div>")?;
+
+    writeln!(writer, "<div class=\"column\"><h2>VIR CFG</h2>")?;
+    for block in vir_blocks {
+        writeln!(
+            writer,
+            "<div class=\"block\"><div class=\"block-label\">{}</div>",
+            escape_html(&block.label)
+        )?;
+        for (stmt, ids) in &block.stmts {
+            writeln!(
+                writer,
+                "<div class=\"item{}\"{}>{}</div>",
+                if stmt.is_comment() { " comment" } else { "" },
+                pos_attribute(ids),
+                escape_html(stmt),
+            )?;
+        }
+        let (successor, ids) = &block.successor;
+        writeln!(
+            writer,
+            "<div class=\"item successor\"{}>{}</div></div>",
+            pos_attribute(ids),
+            escape_html(successor),
+        )?;
+    }
+    writeln!(writer, "</div>")?;
+
+    writeln!(
+        writer,
+        "<div class=\"column\"><h2>Viper (before the whole-program optimizations)</h2>"
+    )?;
+    for (line, id) in viper_lines {
+        writeln!(
+            writer,
+            "<div class=\"item\"{}>{}</div>",
+            pos_attribute(&id.iter().copied().collect()),
+            escape_html(line),
+        )?;
+    }
+    writeln!(writer, "</div>")?;
+
+    writeln!(writer, "</div><script>{}</script></body></html>", SCRIPT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_span::{source_map::FilePathMapping, BytePos};
+
+    fn span(lo: u32, hi: u32) -> Span {
+        Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+    }
+
+    fn statements(spans: &[Span]) -> Vec<MirStatement> {
+        spans
+            .iter()
+            .enumerate()
+            .map(|(statement_index, &span)| MirStatement {
+                location: mir::Location {
+                    block: mir::START_BLOCK,
+                    statement_index,
+                },
+                text: format!("statement {}", statement_index),
+                span,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_the_position_of_a_viper_line() {
+        assert_eq!(
+            position_id_of_line("    assert _1 > 0 // pos 3:5 #42"),
+            Some(42)
+        );
+        assert_eq!(position_id_of_line("    assert _1 > 0 // pos 3:5 #0"), None);
+        assert_eq!(position_id_of_line("    assert _1 > 0 // pos 3:5"), None);
+        assert_eq!(position_id_of_line("    assert _1 > 0"), None);
+    }
+
+    #[test]
+    fn finds_the_tightest_enclosing_statements() {
+        rustc_span::create_default_session_globals_then(|| {
+            let statements = statements(&[
+                span(0, 20),
+                span(5, 10),
+                span(5, 10),
+                span(30, 40),
+                rustc_span::DUMMY_SP,
+            ]);
+            assert_eq!(enclosing_statements(&statements, span(6, 8)), [1, 2]);
+            assert_eq!(enclosing_statements(&statements, span(12, 14)), [0]);
+        });
+    }
+
+    #[test]
+    fn falls_back_to_the_overlapping_statements() {
+        rustc_span::create_default_session_globals_then(|| {
+            let statements = statements(&[span(0, 20), span(5, 10), span(30, 40)]);
+            assert_eq!(enclosing_statements(&statements, span(15, 35)), [0, 2]);
+            assert!(enclosing_statements(&statements, span(50, 60)).is_empty());
+            assert!(enclosing_statements(&statements, rustc_span::DUMMY_SP).is_empty());
+        });
+    }
+
+    #[test]
+    fn escapes_the_html_of_all_columns() {
+        rustc_span::create_default_session_globals_then(|| {
+            let codemap = SourceMap::new(FilePathMapping::empty());
+            let mut statements = statements(&[rustc_span::DUMMY_SP]);
+            statements[0].text = "_0 = <T as Tr>::f(&_1)".to_string();
+            let vir_blocks = [VirBlock {
+                label: "start<1>".to_string(),
+                stmts: vec![(vir::Stmt::comment("a < b && c"), BTreeSet::new())],
+                successor: ("goto \"end\"".to_string(), BTreeSet::from([7])),
+            }];
+            let viper_lines = [("  x := y && z < 1 // pos 1:1 #7".to_string(), Some(7))];
+            let mut page = Vec::new();
+            write_page(
+                &mut page,
+                &codemap,
+                "m<T>",
+                &statements,
+                &[BTreeSet::new()],
+                &vir_blocks,
+                &viper_lines,
+            )
+            .unwrap();
+            let page = String::from_utf8(page).unwrap();
+            assert!(page.contains("<title>m&lt;T&gt;</title>"));
+            assert!(page.contains("_0 = &lt;T as Tr&gt;::f(&amp;_1)"));
+            assert!(page.contains(">start&lt;1&gt;<"));
+            assert!(page.contains("a &lt; b &amp;&amp; c"));
+            assert!(page.contains(" data-pos=\"7\">goto &quot;end&quot;<"));
+            assert!(page.contains("x := y &amp;&amp; z &lt; 1"));
+            assert!(!page.contains("<T"));
+        });
+    }
+}
@@ -112,6 +112,9 @@ pub enum ErrorCtxt {
     AssertMethodPostconditionStrengthening(MultiSpan),
     /// A cast like `usize as u32`.
     TypeCast,
+    /// A Viper `assert` that checks that the read field of a union is the
+    /// one that was written last.
+    UnionFieldRead,
     /// A Viper `assert false` that encodes an unsupported feature
     Unsupported(String),
 }
@@ -168,6 +171,11 @@ impl<'tcx> ErrorManager<'tcx> {
         pos
     }
 
+    /// The span that was registered for the VIR position with the given id.
+    pub fn get_span(&self, pos_id: u64) -> Option<&MultiSpan> {
+        self.position_manager.source_span.get(&pos_id)
+    }
+
     pub fn get_def_id(&self, ver_error: &VerificationError) -> Option<ProcedureDefId> {
         ver_error.pos_id.as_ref()
             .and_then(|id| id.parse().ok())
@@ -515,6 +523,13 @@ impl<'tcx> ErrorManager<'tcx> {
                 ).set_failing_assertion(opt_cause_span)
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::UnionFieldRead) => {
+                PrustiError::verification(
+                    "the read union field might not be the one that was written last".to_string(),
+                    error_span,
+                ).set_failing_assertion(opt_cause_span)
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::Unsupported(ref reason)) => {
                 PrustiError::unsupported(
                     format!("an unsupported Rust feature might be reachable: {}.", reason),
@@ -4,7 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use rustc_span::MultiSpan;
+use rustc_span::{MultiSpan, Span};
 use log::trace;
 use prusti_interface::PrustiError;
 
@@ -16,13 +16,14 @@ use backtrace::Backtrace;
 pub struct SpannedEncodingError {
     pub(super) error: EncodingErrorKind,
     span: MultiSpan,
+    notes: Vec<(String, Option<Span>)>,
 }
 
 pub type SpannedEncodingResult<T> = Result<T, SpannedEncodingError>;
 
 impl From<SpannedEncodingError> for PrustiError {
     fn from(other: SpannedEncodingError) -> Self {
-        match other.error {
+        let error = match other.error {
             EncodingErrorKind::Unsupported(msg) => {
                 PrustiError::unsupported(msg, other.span)
             }
@@ -32,7 +33,10 @@ impl From<SpannedEncodingError> for PrustiError {
             EncodingErrorKind::Internal(msg) => {
                 PrustiError::internal(msg, other.span)
             }
-        }
+        };
+        other.notes.into_iter().fold(error, |error, (note, opt_span)| {
+            error.add_note(note, opt_span)
+        })
     }
 }
 
@@ -41,6 +45,7 @@ impl SpannedEncodingError {
         SpannedEncodingError {
             error,
             span: span.into(),
+            notes: vec![],
         }
     }
 
@@ -71,6 +76,12 @@ impl SpannedEncodingError {
         )
     }
 
+    #[must_use]
+    pub fn add_note<S: ToString>(mut self, message: S, opt_span: Option<Span>) -> Self {
+        self.notes.push((message.to_string(), opt_span));
+        self
+    }
+
     pub fn kind(&self) -> &EncodingErrorKind {
         &self.error
     }
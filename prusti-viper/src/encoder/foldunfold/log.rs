@@ -5,15 +5,96 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 //! The log keeps track of actions performed by the fold-unfold algorithm so that they can be
-//! undone when restoring borrowed permissions.
+//! undone when restoring borrowed permissions, and of the reasons why permissions were lost so
+//! that a failure to obtain a permission can be explained.
 
-use crate::encoder::foldunfold::{action::Action, perm::Perm, FoldUnfoldError};
+use crate::encoder::foldunfold::{action::Action, perm::Perm, state::State, FoldUnfoldError};
 use log::trace;
 use prusti_common::utils::to_string::ToString;
-use rustc_hash::FxHashMap;
-use std::{cmp::Ordering, rc::Rc, sync::RwLock};
+use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_middle::mir;
+use std::{cmp::Ordering, fmt, rc::Rc, sync::RwLock};
 use vir_crate::polymorphic as vir;
 
+/// Why the fold-unfold algorithm lost the permissions of a place.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PermissionLoss {
+    /// The place was mutably borrowed, or shared borrowed while having write permission.
+    Borrowed(vir::Expr, vir::borrows::Borrow),
+    /// The permission of the place was not available in all the branches that are joined at
+    /// the given block, so it was dropped.
+    DroppedAtJoin(vir::Expr, vir::CfgBlockIndex),
+}
+
+impl PermissionLoss {
+    pub fn place(&self) -> &vir::Expr {
+        match self {
+            PermissionLoss::Borrowed(place, _) | PermissionLoss::DroppedAtJoin(place, _) => place,
+        }
+    }
+
+    /// The MIR location at which the permission was lost, if it is known.
+    pub fn location(
+        &self,
+        borrow_locations: &FxHashMap<vir::borrows::Borrow, mir::Location>,
+        cfg_map: &FxHashMap<mir::BasicBlock, FxHashSet<vir::CfgBlockIndex>>,
+    ) -> Option<mir::Location> {
+        match self {
+            PermissionLoss::Borrowed(_, borrow) => borrow_locations.get(borrow).copied(),
+            PermissionLoss::DroppedAtJoin(_, join_block) => cfg_map
+                .iter()
+                .filter(|(_, cfg_blocks)| cfg_blocks.iter().any(|block| block.weak_eq(join_block)))
+                .map(|(&block, _)| block)
+                .min()
+                .map(|block| mir::Location {
+                    block,
+                    statement_index: 0,
+                }),
+        }
+    }
+
+    /// Whether the place has regained the permission that it lost, e.g. by being assigned to.
+    /// A shared borrow only takes the write permission of the place, so the read permission
+    /// that remains does not count.
+    fn is_regained(&self, state: &State) -> bool {
+        let place = self.place();
+        if !place.is_place() {
+            return false;
+        }
+        // A predicate permission to a prefix also covers the place.
+        let prefixes = place.all_prefixes();
+        let mut amounts = state.acc().get(place).into_iter().chain(
+            prefixes
+                .iter()
+                .filter_map(|prefix| state.pred().get(prefix)),
+        );
+        match self {
+            PermissionLoss::Borrowed(..) => amounts.any(|&amount| amount == vir::PermAmount::Write),
+            PermissionLoss::DroppedAtJoin(..) => amounts.next().is_some(),
+        }
+    }
+}
+
+impl fmt::Display for PermissionLoss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PermissionLoss::Borrowed(place, _) => {
+                write!(
+                    f,
+                    "the permission to {} was lost because it is borrowed here",
+                    place
+                )
+            }
+            PermissionLoss::DroppedAtJoin(place, _) => write!(
+                f,
+                "the permission to {} was lost where these branches join, because it is not \
+                available in all of them",
+                place
+            ),
+        }
+    }
+}
+
 // Note: Now every PathCtxt has its own EventLog, because a Borrow no longer unique
 // (e.g. we duplicate the evaluation of the loop condition in the encoding of loops).
 // The idea is to progressively transform this *log* into a *state*, removing past actions that are
@@ -43,6 +124,9 @@ pub(super) struct EventLog {
     /// `Write` to `Read` when creating a borrow.
     converted_to_read_places: FxHashMap<vir::borrows::Borrow, Vec<vir::Expr>>,
 
+    /// The places whose permissions were lost, in the order in which they were lost.
+    permission_losses: Vec<PermissionLoss>,
+
     /// A generator of unique IDs.
     id_generator: u32,
 }
@@ -54,6 +138,7 @@ impl EventLog {
             duplicated_reads: FxHashMap::default(),
             blocked_place: FxHashMap::default(),
             converted_to_read_places: FxHashMap::default(),
+            permission_losses: Vec::new(),
             id_generator: 0,
         }
     }
@@ -182,6 +267,31 @@ impl EventLog {
         self.duplicated_reads.remove(&borrow);
         self.blocked_place.remove(&borrow);
         self.converted_to_read_places.remove(&borrow);
+        self.permission_losses
+            .retain(|loss| !matches!(loss, PermissionLoss::Borrowed(_, b) if *b == borrow));
+    }
+
+    pub fn log_permission_loss(&mut self, loss: PermissionLoss) {
+        trace!("log_permission_loss: {:?}", loss);
+        if !self.permission_losses.contains(&loss) {
+            self.permission_losses.push(loss);
+        }
+    }
+
+    /// Forgets the losses of the places that have regained their permission in `state`, so that
+    /// they are not used to explain a later failure.
+    pub fn forget_regained_permissions(&mut self, state: &State) {
+        self.permission_losses
+            .retain(|loss| !loss.is_regained(state));
+    }
+
+    /// Returns the most recent loss of a permission to `place`, to one of its prefixes or to one
+    /// of its extensions.
+    pub fn get_permission_loss(&self, place: &vir::Expr) -> Option<&PermissionLoss> {
+        self.permission_losses.iter().rev().find(|loss| {
+            let lost_place = loss.place();
+            place.has_prefix(lost_place) || lost_place.has_prefix(place)
+        })
     }
 
     /// Join `other` into `self`
@@ -205,6 +315,9 @@ impl EventLog {
                 .entry(other_key)
                 .or_insert(other_value);
         }
+        for loss in other.permission_losses.drain(..) {
+            self.log_permission_loss(loss);
+        }
         self.id_generator = self.id_generator.max(other.id_generator);
         Ok(())
     }
@@ -37,12 +37,15 @@ mod requirements;
 mod semantics;
 mod state;
 
+pub use self::log::PermissionLoss;
+
 pub type Predicates = FxHashMap<vir::Type, vir::Predicate>;
 
 #[derive(Clone, Debug)]
 pub enum FoldUnfoldError {
-    /// The algorithm failed to obtain a permission
-    FailedToObtain(Perm),
+    /// The algorithm failed to obtain a permission, possibly because it was lost for a known
+    /// reason
+    FailedToObtain(Perm, Option<PermissionLoss>),
     /// The algorithm tried to generate a "folding .. in .." Viper expression
     RequiresFolding(
         vir::Type,
@@ -71,7 +74,7 @@ pub enum FoldUnfoldError {
 impl fmt::Display for FoldUnfoldError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            FoldUnfoldError::FailedToObtain(perm) => {
+            FoldUnfoldError::FailedToObtain(perm, _) => {
                 writeln!(f, "The required permission {} cannot be obtained.", perm)
             }
             FoldUnfoldError::RequiresFolding(_pred, args, frac, _variant, _pos) => {
@@ -771,7 +774,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec> for FoldUnf
                     .flatten()
                     .collect::<Vec<_>>();
                 let (mut join_actions, mut joined_pctxt) =
-                    self.prepend_join(vec![&then_pctxt, &else_pctxt])?;
+                    self.prepend_join(vec![&then_pctxt, &else_pctxt], curr_block_index)?;
                 else_stmts.extend(self.perform_prejoin_action(
                     &mut joined_pctxt,
                     curr_block_index,
@@ -790,6 +793,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec> for FoldUnf
                 })
             }
             _ => {
+                if let vir::Stmt::Assign(vir::Assign {
+                    ref source,
+                    kind: vir::AssignKind::MutableBorrow(borrow),
+                    ..
+                }) = stmt
+                {
+                    pctxt
+                        .log_mut()
+                        .log_permission_loss(PermissionLoss::Borrowed(source.clone(), borrow));
+                }
                 pctxt.apply_stmt(&stmt)?;
                 stmt
             }
@@ -887,6 +900,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec> for FoldUnf
                     pctxt
                         .log_mut()
                         .log_convertion_to_read(borrow, access.clone());
+                    pctxt
+                        .log_mut()
+                        .log_permission_loss(PermissionLoss::Borrowed(place.clone(), borrow));
                     let stmt = vir::Stmt::Exhale(vir::Exhale {
                         expr: access,
                         position: self.method_pos,
@@ -936,6 +952,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec> for FoldUnf
                     pctxt
                         .log_mut()
                         .log_convertion_to_read(borrow, access.clone());
+                    pctxt
+                        .log_mut()
+                        .log_permission_loss(PermissionLoss::Borrowed(place.clone(), borrow));
                     let stmt = vir::Stmt::Exhale(vir::Exhale {
                         expr: access,
                         position: self.method_pos,
@@ -1076,8 +1095,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec> for FoldUnf
     fn prepend_join(
         &mut self,
         bcs: Vec<&PathCtxt<'p>>,
+        curr_block_index: CfgBlockIndex,
     ) -> Result<(Vec<ActionVec>, PathCtxt<'p>), Self::Error> {
-        prepend_join(bcs)
+        prepend_join(bcs, curr_block_index)
     }
 
     /// Convert actions to statements and log them.
@@ -1098,6 +1118,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec> for FoldUnf
 
 pub(super) fn prepend_join<'p>(
     bcs: Vec<&PathCtxt<'p>>,
+    join_block: CfgBlockIndex,
 ) -> Result<(Vec<ActionVec>, PathCtxt<'p>), FoldUnfoldError> {
     trace!("[enter] prepend_join(..{})", &bcs.len());
     assert!(!bcs.is_empty());
@@ -1110,11 +1131,11 @@ pub(super) fn prepend_join<'p>(
         let right_pctxts = &bcs[mid..];
 
         // Join the subgroups
-        let (left_actions_vec, mut left_pctxt) = prepend_join(left_pctxts.to_vec())?;
-        let (right_actions_vec, right_pctxt) = prepend_join(right_pctxts.to_vec())?;
+        let (left_actions_vec, mut left_pctxt) = prepend_join(left_pctxts.to_vec(), join_block)?;
+        let (right_actions_vec, right_pctxt) = prepend_join(right_pctxts.to_vec(), join_block)?;
 
         // Join the recursive calls
-        let (merge_actions_left, merge_actions_right) = left_pctxt.join(right_pctxt)?;
+        let (merge_actions_left, merge_actions_right) = left_pctxt.join(right_pctxt, join_block)?;
         let merged_pctxt = left_pctxt;
 
         let mut branch_actions_vec: Vec<ActionVec> = vec![];
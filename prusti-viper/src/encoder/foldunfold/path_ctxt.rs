@@ -6,8 +6,15 @@
 
 use super::Predicates;
 use crate::encoder::foldunfold::{
-    action::*, footprint::*, log::EventLog, perm::*, places_utils::*, semantics::ApplyOnState,
-    state::*, FoldUnfoldError, FoldUnfoldError::FailedToObtain,
+    action::*,
+    footprint::*,
+    log::{EventLog, PermissionLoss},
+    perm::*,
+    places_utils::*,
+    semantics::ApplyOnState,
+    state::*,
+    FoldUnfoldError,
+    FoldUnfoldError::FailedToObtain,
 };
 use log::{debug, trace};
 use prusti_common::utils::to_string::ToString;
@@ -151,9 +158,13 @@ impl<'a> PathCtxt<'a> {
 
     /// left is self, right is other
     /// Note: this merges the event logs as well
+    ///
+    /// ``join_block`` – the block at which the branches join, used to explain the dropped
+    /// permissions.
     pub fn join(
         &mut self,
         mut other: PathCtxt,
+        join_block: vir::CfgBlockIndex,
     ) -> Result<(Vec<Action>, Vec<Action>), FoldUnfoldError> {
         let mut left_actions: Vec<Action> = vec![];
         let mut right_actions: Vec<Action> = vec![];
@@ -503,8 +514,21 @@ impl<'a> PathCtxt<'a> {
             self.state.check_consistency();
         }
 
+        // Remember why the dropped permissions are missing after the join
+        for action in left_actions.iter().chain(&right_actions) {
+            if let Action::Drop(perm, _) = action {
+                self.log_mut()
+                    .log_permission_loss(PermissionLoss::DroppedAtJoin(
+                        perm.get_place().clone(),
+                        join_block,
+                    ));
+            }
+        }
+
         // Merge the event logs
         self.log_mut().join(other.drain_log())?;
+        // The permissions that are available after the join were regained in all the branches
+        self.log.forget_regained_permissions(&self.state);
 
         Ok((left_actions, right_actions))
     }
@@ -737,6 +761,7 @@ Predicates: {{
         self.state.check_consistency();
 
         stmt.apply_on_state(&mut self.state, self.predicates)?;
+        self.log.forget_regained_permissions(&self.state);
 
         trace!("Acc state after: {{\n{}\n}}", self.state.display_acc());
         trace!("Pred state after: {{\n{}\n}}", self.state.display_pred());
@@ -768,7 +793,13 @@ Predicates: {{
 
         self.state.check_consistency();
 
-        let actions = self.obtain_all(permissions)?;
+        let actions = self.obtain_all(permissions).map_err(|error| match error {
+            FailedToObtain(perm, None) => {
+                let loss = self.log.get_permission_loss(perm.get_place()).cloned();
+                FailedToObtain(perm, loss)
+            }
+            error => error,
+        })?;
 
         trace!("Acc state after: {{\n{}\n}}", self.state.display_acc());
         trace!("Pred state after: {{\n{}\n}}", self.state.display_pred());
@@ -931,7 +962,7 @@ impl ObtainResult {
     pub fn get_actions(self) -> Result<Vec<Action>, FoldUnfoldError> {
         match self {
             ObtainResult::Success(actions) => Ok(actions),
-            ObtainResult::Failure(p) => Err(FailedToObtain(*p)),
+            ObtainResult::Failure(p) => Err(FailedToObtain(*p, None)),
         }
     }
 }
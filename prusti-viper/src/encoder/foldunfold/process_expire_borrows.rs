@@ -66,7 +66,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
         }
 
         // Merge all pctxts with reborrowed_nodes.is_empty() into one.
-        *surrounding_pctxt = construct_final_pctxt(dag, &mut cfg, &final_pctxt)?;
+        *surrounding_pctxt =
+            construct_final_pctxt(dag, &mut cfg, &final_pctxt, surrounding_block_index)?;
 
         let final_statements = generate_final_statements(&cfg, label);
         debug!(
@@ -206,7 +207,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
             incoming_pctxt.push(pctxt);
         }
         let incoming_pctxt_refs = incoming_pctxt.iter().collect();
-        let (actions, mut pctxt) = prepend_join(incoming_pctxt_refs)?;
+        let (actions, mut pctxt) = prepend_join(incoming_pctxt_refs, surrounding_block_index)?;
         for (&src_index, action) in curr_block.predecessors.iter().zip(&actions) {
             assert!(src_index < curr_block_index);
             if !action.is_empty() {
@@ -352,6 +353,7 @@ fn construct_final_pctxt<'p>(
     dag: &vir::borrows::DAG,
     cfg: &mut borrows::CFG,
     final_pctxt: &[Option<PathCtxt<'p>>],
+    surrounding_block_index: vir::CfgBlockIndex,
 ) -> Result<PathCtxt<'p>, FoldUnfoldError> {
     let final_blocks: Vec<_> = cfg
         .basic_blocks
@@ -364,7 +366,7 @@ fn construct_final_pctxt<'p>(
         .iter()
         .map(|i| final_pctxt[*i].as_ref().unwrap())
         .collect();
-    let (actions, mut final_pctxt) = prepend_join(final_pctxts)?;
+    let (actions, mut final_pctxt) = prepend_join(final_pctxts, surrounding_block_index)?;
     for (&i, action) in final_blocks.iter().zip(actions.iter()) {
         if !action.is_empty() {
             let mut stmts_to_add = Vec::new();
@@ -1,5 +1,8 @@
 use super::super::types::{create_value_field, interface::HighTypeEncoderInterfacePrivate};
-use crate::encoder::{errors::EncodingResult, high::lower::IntoPolymorphic};
+use crate::encoder::{
+    errors::{EncodingError, EncodingResult},
+    high::lower::IntoPolymorphic,
+};
 use vir_crate::{high as vir_high, polymorphic as vir_poly};
 use vir_poly::Predicate;
 
@@ -27,6 +30,9 @@ impl IntoPredicates for vir_high::TypeDecl {
             vir_high::TypeDecl::Tuple(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Struct(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Enum(ty_decl) => ty_decl.lower(ty, encoder),
+            vir_high::TypeDecl::Union(_) => {
+                Err(EncodingError::unsupported("unions are not supported"))
+            }
             vir_high::TypeDecl::Array(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Reference(ty_decl) => ty_decl.lower(ty, encoder),
             vir_high::TypeDecl::Never => construct_never_predicate(encoder),
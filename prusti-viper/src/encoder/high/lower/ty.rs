@@ -31,6 +31,7 @@ impl IntoPolymorphic<vir_poly::Type> for vir_high::Type {
             vir_high::Type::Closure(ty) => vir_poly::Type::TypedRef(ty.lower(encoder)),
             vir_high::Type::FunctionDef(ty) => vir_poly::Type::TypedRef(ty.lower(encoder)),
             vir_high::Type::Projection(ty) => vir_poly::Type::TypedRef(ty.lower(encoder)),
+            vir_high::Type::Trait(ty) => vir_poly::Type::TypedRef(ty.lower(encoder)),
             vir_high::Type::Unsupported(ty) => vir_poly::Type::TypedRef(ty.lower(encoder)),
         })
     }
@@ -133,6 +134,12 @@ impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Projection {
     }
 }
 
+impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Trait {
+    fn lower(&self, encoder: &impl HighTypeEncoderInterfacePrivate) -> vir_poly::TypedRef {
+        vir_poly::TypedRef::new(format!("dyn${}", self.name), self.arguments.lower(encoder))
+    }
+}
+
 impl IntoPolymorphic<vir_poly::TypedRef> for vir_high::ty::Unsupported {
     fn lower(&self, _encoder: &impl HighTypeEncoderInterfacePrivate) -> vir_poly::TypedRef {
         vir_poly::TypedRef::new(self.name.clone(), Vec::new())
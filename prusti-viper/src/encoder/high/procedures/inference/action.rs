@@ -1,4 +1,5 @@
 use super::permission::PermissionKind;
+use std::fmt;
 use vir_crate::{high as vir_high, middle as vir_mid};
 
 pub(in super::super) enum Action {
@@ -42,3 +43,18 @@ impl Action {
         })
     }
 }
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (operation, state) = match self {
+            Self::Unfold(state) => ("unfold", state),
+            Self::Fold(state) => ("fold", state),
+        };
+        write!(f, "{} {:?}({})", operation, state.kind, state.place)?;
+        if let Some(condition) = &state.condition {
+            let labels: Vec<_> = condition.iter().map(ToString::to_string).collect();
+            write!(f, " if came through {}", labels.join("→"))?;
+        }
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use vir_crate::{
 mod action;
 mod ensurer;
 mod permission;
+mod report;
 mod semantics;
 mod state;
 mod visitor;
@@ -0,0 +1,180 @@
+//! A listing of the permissions that the fold-unfold inference computed at
+//! each MIR location of a procedure, annotated with the Rust source lines.
+
+use super::{action::Action, state::FoldUnfoldState};
+use crate::encoder::Encoder;
+use prusti_common::{config, report::log};
+use std::fmt::Write;
+use vir_crate::{
+    common::position::Positioned,
+    high::{self as vir_high},
+    middle::{self as vir_mid},
+};
+
+#[derive(Default)]
+pub(super) struct PermissionReport {
+    procedure_name: String,
+    blocks: Vec<BlockEntry>,
+    /// The statement for which the inference did not finish.
+    unfinished_statement: Option<String>,
+}
+
+struct BlockEntry {
+    label: String,
+    entry_state: String,
+    locations: Vec<LocationEntry>,
+}
+
+/// The statements that encode a single MIR statement or terminator.
+struct LocationEntry {
+    /// The comment that the procedure encoder emits for the MIR location, or
+    /// `None` for statements that do not come from a MIR location.
+    mir_location: Option<String>,
+    /// The first non-default position of the statements.
+    position: Option<vir_high::Position>,
+    actions: Vec<String>,
+    state: Option<String>,
+}
+
+impl PermissionReport {
+    pub(super) fn new(procedure_name: String) -> Self {
+        Self {
+            procedure_name,
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn start_block(&mut self, label: &vir_mid::BasicBlockId, state: &FoldUnfoldState) {
+        self.blocks.push(BlockEntry {
+            label: label.to_string(),
+            entry_state: state.summary(),
+            locations: Vec::new(),
+        });
+    }
+
+    pub(super) fn start_statement(&mut self, statement: &vir_high::Statement) {
+        let block = self.blocks.last_mut().unwrap();
+        if let vir_high::Statement::Comment(vir_high::Comment { comment }) = statement {
+            if is_mir_location_comment(comment) {
+                block
+                    .locations
+                    .push(LocationEntry::new(Some(comment.clone())));
+            }
+            return;
+        }
+        if block.locations.is_empty() {
+            block.locations.push(LocationEntry::new(None));
+        }
+        let location = block.locations.last_mut().unwrap();
+        if location.position.is_none() && !statement.position().is_default() {
+            location.position = Some(statement.position());
+        }
+        self.unfinished_statement = Some(statement.to_string());
+    }
+
+    pub(super) fn record_actions(&mut self, actions: &[Action]) {
+        if let Some(location) = self.current_location() {
+            location
+                .actions
+                .extend(actions.iter().map(ToString::to_string));
+        }
+    }
+
+    pub(super) fn finish_statement(&mut self, state: &FoldUnfoldState) {
+        if let Some(location) = self.current_location() {
+            location.state = Some(state.summary());
+        }
+        self.unfinished_statement = None;
+    }
+
+    fn current_location(&mut self) -> Option<&mut LocationEntry> {
+        self.blocks
+            .last_mut()
+            .and_then(|block| block.locations.last_mut())
+    }
+
+    /// Prints the listing if `print_permission_inference` is enabled and
+    /// writes it to the log if `dump_debug_info` is enabled.
+    pub(super) fn emit(&self, encoder: &Encoder) {
+        let listing = self.render(encoder);
+        if config::print_permission_inference() {
+            println!("{}", listing);
+        }
+        if config::dump_debug_info() {
+            let source_filename = encoder.env().source_file_name();
+            log::report(
+                "permission_inference",
+                format!("{}.{}.txt", source_filename, self.procedure_name),
+                listing,
+            );
+        }
+    }
+
+    fn render(&self, encoder: &Encoder) -> String {
+        let mut listing = String::new();
+        writeln!(listing, "Inferred permissions of {}", self.procedure_name).unwrap();
+        let mut last_line = None;
+        for block in &self.blocks {
+            writeln!(listing).unwrap();
+            writeln!(listing, "{}:", block.label).unwrap();
+            writeln!(listing, "  entry: {}", block.entry_state).unwrap();
+            for location in &block.locations {
+                if let Some((line_number, line)) = location
+                    .position
+                    .and_then(|position| source_line(encoder, position))
+                {
+                    if last_line != Some(line_number) {
+                        writeln!(listing, "{:>5} | {}", line_number, line.trim_end()).unwrap();
+                        last_line = Some(line_number);
+                    }
+                }
+                if let Some(mir_location) = &location.mir_location {
+                    writeln!(listing, "  {}", mir_location).unwrap();
+                }
+                for action in &location.actions {
+                    writeln!(listing, "    {}", action).unwrap();
+                }
+                if let Some(state) = &location.state {
+                    writeln!(listing, "    permissions: {}", state).unwrap();
+                }
+            }
+        }
+        if let Some(statement) = &self.unfinished_statement {
+            writeln!(listing).unwrap();
+            writeln!(listing, "inference stopped at: {}", statement).unwrap();
+        }
+        listing
+    }
+}
+
+impl LocationEntry {
+    fn new(mir_location: Option<String>) -> Self {
+        Self {
+            mir_location,
+            position: None,
+            actions: Vec::new(),
+            state: None,
+        }
+    }
+}
+
+/// Whether the comment is the `bbN[M] statement` comment that the procedure
+/// encoder emits before the encoding of each MIR statement and terminator.
+fn is_mir_location_comment(comment: &str) -> bool {
+    comment.split_once(' ').map_or(false, |(location, _)| {
+        location.starts_with("bb") && location.ends_with(']')
+    })
+}
+
+/// The number and the text of the first source line of the span registered
+/// for `position`.
+fn source_line(encoder: &Encoder, position: vir_high::Position) -> Option<(usize, String)> {
+    let span = encoder
+        .error_manager()
+        .get_span(position.id())?
+        .primary_span()?
+        .source_callsite();
+    let file_and_line = encoder.env().codemap().lookup_line(span.lo()).ok()?;
+    let line = file_and_line.sf.get_line(file_and_line.line)?;
+    Some((file_and_line.line + 1, line.into_owned()))
+}
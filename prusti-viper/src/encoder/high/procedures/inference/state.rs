@@ -58,6 +58,24 @@ impl PredicateState {
         Ok(())
     }
 
+    fn summary(&self) -> String {
+        let permissions: Vec<_> = self
+            .owned_non_aliased
+            .iter()
+            .map(|place| format!("Owned({})", place))
+            .chain(
+                self.memory_block_stack
+                    .iter()
+                    .map(|place| format!("MemoryBlock({})", place)),
+            )
+            .collect();
+        if permissions.is_empty() {
+            "none".to_string()
+        } else {
+            permissions.join(", ")
+        }
+    }
+
     fn places_mut(&mut self, kind: PermissionKind) -> &mut BTreeSet<vir_high::Expression> {
         match kind {
             PermissionKind::MemoryBlock => &mut self.memory_block_stack,
@@ -187,6 +205,18 @@ impl FoldUnfoldState {
         Ok(())
     }
 
+    /// The permissions of the state on a single line, as shown in the
+    /// permission inference report.
+    pub(in super::super) fn summary(&self) -> String {
+        let mut summary = self.unconditional.summary();
+        for (condition, state) in &self.conditional {
+            summary.push_str("; if came through ");
+            self.debug_write_condition(condition, &mut summary).unwrap();
+            write!(summary, ": {}", state.summary()).unwrap();
+        }
+        summary
+    }
+
     fn debug_write_condition(
         &self,
         condition: &[vir_mid::BasicBlockId],
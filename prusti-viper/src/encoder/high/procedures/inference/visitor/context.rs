@@ -32,8 +32,10 @@ impl<'p, 'v, 'tcx> super::super::ensurer::Context for Visitor<'p, 'v, 'tcx> {
         let expansion = match type_decl {
             vir_high::TypeDecl::Bool
             | vir_high::TypeDecl::Int(_)
-            | vir_high::TypeDecl::Float(_) => {
-                // Primitive type. Convert.
+            | vir_high::TypeDecl::Float(_)
+            | vir_high::TypeDecl::Union(_) => {
+                // Primitive type or a union, whose fields overlap and
+                // therefore cannot be unfolded. Convert.
                 vec![(ExpandedPermissionKind::MemoryBlock, place.clone())]
             }
             vir_high::TypeDecl::TypeVar(_) => unimplemented!("ty: {}", ty),
@@ -107,6 +107,9 @@ impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
 
 impl<'p, 'v, 'tcx> Drop for Visitor<'p, 'v, 'tcx> {
     fn drop(&mut self) {
+        if let Some(report) = self.permission_report.take() {
+            report.emit(self.encoder);
+        }
         if self.graphviz_on_crash {
             let graph = self.render_crash_state();
             let source_filename = self.encoder.env().source_file_name();
@@ -3,9 +3,11 @@ use crate::encoder::{
     errors::SpannedEncodingResult,
     high::procedures::inference::{
         action::{Action, ActionState},
-        permission::PermissionKind,
+        permission::{Permission, PermissionKind},
+        report::PermissionReport,
         semantics::collect_permission_changes,
     },
+    mir::types::MirTypeEncoderInterface,
     Encoder,
 };
 use log::debug;
@@ -15,7 +17,7 @@ use rustc_hir::def_id::DefId;
 use std::collections::{btree_map::Entry, BTreeMap};
 use vir_crate::{
     common::position::Positioned,
-    high::{self as vir_high},
+    high::{self as vir_high, operations::ty::Typed},
     middle::{
         self as vir_mid,
         operations::{ToMiddleExpression, ToMiddleStatement},
@@ -39,6 +41,10 @@ pub(super) struct Visitor<'p, 'v, 'tcx> {
     current_statements: Vec<vir_mid::Statement>,
     /// Should we dump a Graphviz plot in case we crash during inference?
     graphviz_on_crash: bool,
+    /// The inferred permissions, if they should be reported. The report is
+    /// emitted when the visitor is dropped, so that it is also available if
+    /// the inference fails.
+    permission_report: Option<PermissionReport>,
 }
 
 impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
@@ -55,6 +61,7 @@ impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
             current_label: None,
             current_statements: Default::default(),
             graphviz_on_crash: config::dump_debug_info(),
+            permission_report: None,
         }
     }
 
@@ -64,6 +71,9 @@ impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
         entry_state: FoldUnfoldState,
     ) -> SpannedEncodingResult<vir_mid::ProcedureDecl> {
         self.procedure_name = Some(procedure.name.clone());
+        if config::print_permission_inference() || config::dump_debug_info() {
+            self.permission_report = Some(PermissionReport::new(procedure.name.clone()));
+        }
 
         let traversal_order = procedure.get_topological_sort();
         for (label, block) in &procedure.basic_blocks {
@@ -135,6 +145,9 @@ impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
                 .remove(self.current_label.as_ref().unwrap())
                 .unwrap()
         };
+        if let Some(report) = &mut self.permission_report {
+            report.start_block(self.current_label.as_ref().unwrap(), &state);
+        }
         for statement in old_block.statements {
             self.lower_statement(statement, &mut state)?;
         }
@@ -163,9 +176,17 @@ impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
             "Statement has default position: {}",
             statement
         );
+        if let Some(report) = &mut self.permission_report {
+            report.start_statement(&statement);
+        }
         let (consumed_permissions, produced_permissions) = collect_permission_changes(&statement)?;
+        let consumed_permissions = self.lift_permissions_to_unions(consumed_permissions)?;
+        let produced_permissions = self.lift_permissions_to_unions(produced_permissions)?;
         debug!("lower_statement {}: {:?}", statement, consumed_permissions);
         let actions = ensure_required_permissions(self, state, consumed_permissions.clone())?;
+        if let Some(report) = &mut self.permission_report {
+            report.record_actions(&actions);
+        }
         for action in actions {
             let statement = match action {
                 Action::Unfold(ActionState {
@@ -227,9 +248,45 @@ impl<'p, 'v, 'tcx> Visitor<'p, 'v, 'tcx> {
         new_block
             .statements
             .extend(std::mem::take(&mut self.current_statements));
+        if let Some(report) = &mut self.permission_report {
+            report.finish_statement(state);
+        }
         Ok(())
     }
 
+    /// The fields of a union overlap, so the permission to a union is never
+    /// split into permissions to its fields. Replaces the permissions to
+    /// places inside of a union with the permissions to the outermost union.
+    fn lift_permissions_to_unions(
+        &mut self,
+        permissions: Vec<Permission>,
+    ) -> SpannedEncodingResult<Vec<Permission>> {
+        permissions
+            .into_iter()
+            .map(|permission| match permission {
+                Permission::MemoryBlock(place) => {
+                    Ok(Permission::MemoryBlock(self.lift_place_to_union(place)?))
+                }
+                Permission::Owned(place) => Ok(Permission::Owned(self.lift_place_to_union(place)?)),
+            })
+            .collect()
+    }
+
+    fn lift_place_to_union(
+        &mut self,
+        place: vir_high::Expression,
+    ) -> SpannedEncodingResult<vir_high::Expression> {
+        let mut outermost_union = None;
+        let mut current = &place;
+        while let vir_high::Expression::Field(vir_high::Field { box base, .. }) = current {
+            if let vir_high::TypeDecl::Union(_) = self.encoder.encode_type_def(base.get_type())? {
+                outermost_union = Some(base.clone());
+            }
+            current = base;
+        }
+        Ok(outermost_union.unwrap_or(place))
+    }
+
     pub(crate) fn update_state_at_entry(
         &mut self,
         to_label: vir_mid::BasicBlockId,
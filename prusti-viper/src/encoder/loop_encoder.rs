@@ -4,6 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use prusti_interface::environment::mir_analyses::framing::FramingInfo;
 use prusti_interface::environment::mir_analyses::initialization::{
     compute_definitely_initialized, DefinitelyInitializedAnalysisResult,
 };
@@ -12,6 +13,7 @@ use prusti_interface::environment::{BasicBlockIndex, PermissionForest, Procedure
 use prusti_interface::utils;
 use rustc_middle::{mir, ty};
 use log::{trace, debug};
+use std::rc::Rc;
 
 pub enum LoopEncoderError {
     LoopInvariantInBranch(BasicBlockIndex),
@@ -21,12 +23,15 @@ pub struct LoopEncoder<'p, 'tcx: 'p> {
     procedure: &'p Procedure<'tcx>,
     tcx: ty::TyCtxt<'tcx>,
     initialization: DefinitelyInitializedAnalysisResult<'tcx>,
+    /// The result of the framing analysis, if enabled.
+    framing: Option<Rc<FramingInfo<'tcx>>>,
 }
 
 impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
     pub fn new(
         procedure: &'p Procedure<'tcx>,
         tcx: ty::TyCtxt<'tcx>,
+        framing: Option<Rc<FramingInfo<'tcx>>>,
     ) -> Self {
         LoopEncoder {
             procedure,
@@ -36,6 +41,7 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
                 procedure.get_mir(),
                 tcx,
             ),
+            framing,
         }
     }
 
@@ -130,6 +136,35 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
             .any(|def_init_place| utils::is_prefix(place, def_init_place))
     }
 
+    /// Get the places that are definitely initialised at the loop head and
+    /// that are not modified by the loop body, together with whether the loop
+    /// body does not even read them.
+    pub fn get_framed_places(&self, loop_head: BasicBlockIndex) -> Vec<(mir::Place<'tcx>, bool)> {
+        let framing = if let Some(framing) = &self.framing {
+            framing
+        } else {
+            return vec![];
+        };
+        let mir = self.mir();
+        let locations = || {
+            self.loops().get_loop_body(loop_head).iter().flat_map(move |&bb| {
+                (0..=mir[bb].statements.len()).map(move |statement_index| mir::Location {
+                    block: bb,
+                    statement_index,
+                })
+            })
+        };
+        let owned_places = framing.get_framed_across(locations(), true);
+        framing.get_framed_across(locations(), false)
+            .into_iter()
+            .filter(|place| self.is_definitely_initialised(place, loop_head))
+            .map(|place| {
+                let owned = owned_places.iter().any(|owned_place| utils::is_prefix(&place, owned_place));
+                (place, owned)
+            })
+            .collect()
+    }
+
     /// Return the block at whose end the loop invariant holds
     pub fn get_loop_invariant_block(
         &self,
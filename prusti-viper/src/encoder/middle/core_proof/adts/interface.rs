@@ -1,9 +1,23 @@
-use crate::encoder::{errors::SpannedEncodingResult, middle::core_proof::lowerer::Lowerer};
-use vir_crate::low::{self as vir_low};
+use crate::encoder::{
+    errors::{ErrorCtxt, SpannedEncodingResult},
+    high::types::HighTypeEncoderInterface,
+    middle::core_proof::{
+        addresses::AddressesInterface,
+        builtin_methods::BuiltinMethodsInterface,
+        lowerer::Lowerer,
+        places::PlacesInterface,
+        snapshots::{IntoSnapshot, SnapshotsInterface},
+    },
+    mir::errors::ErrorInterface,
+};
+use vir_crate::{
+    low::{self as vir_low},
+    middle::{self as vir_mid, operations::ty::Typed},
+};
 
 use super::types::{
-    constructor_call, constructor_name, destructor_call, destructor_name, BASE_VARIANT,
-    CONSTANT_FIELD, CONSTANT_VARIANT,
+    constructor_call, constructor_name, destructor_call, destructor_name, discriminant_name,
+    BASE_VARIANT, CONSTANT_FIELD, CONSTANT_VARIANT,
 };
 
 pub(in super::super) trait AdtsInterface {
@@ -32,6 +46,40 @@ pub(in super::super) trait AdtsInterface {
         variant: &str,
         field_name: &str,
     ) -> SpannedEncodingResult<String>;
+    fn adt_discriminant_name(&mut self, domain_name: &str) -> SpannedEncodingResult<String>;
+    fn adt_discriminant_call(
+        &mut self,
+        domain_name: &str,
+        argument: vir_low::Expression,
+    ) -> SpannedEncodingResult<vir_low::Expression>;
+    /// Check that every union field read by `place` is the field that was
+    /// written last. Reading fields of integer and float types is always
+    /// allowed because any bit pattern is a valid value of such a type. This
+    /// does not hold for `bool`.
+    #[allow(clippy::ptr_arg)] // Clippy false positive.
+    fn encode_union_field_read_checks(
+        &mut self,
+        statements: &mut Vec<vir_low::Statement>,
+        place: &vir_mid::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<()>;
+    /// Returns the outermost union that contains `place`, if there is one.
+    /// The fields of a union overlap, so the union is always accessed as a
+    /// single memory block.
+    fn outermost_union_containing(
+        &mut self,
+        place: &vir_mid::Expression,
+    ) -> SpannedEncodingResult<Option<vir_mid::Expression>>;
+    /// Write `value` into `target`. If `target` is inside of a union, the
+    /// whole union is written with its new snapshot.
+    #[allow(clippy::ptr_arg)] // Clippy false positive.
+    fn encode_write_place_through_unions(
+        &mut self,
+        statements: &mut Vec<vir_low::Statement>,
+        target: &vir_mid::Expression,
+        value: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<()>;
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> AdtsInterface for Lowerer<'p, 'v, 'tcx> {
@@ -82,4 +130,100 @@ impl<'p, 'v: 'p, 'tcx: 'v> AdtsInterface for Lowerer<'p, 'v, 'tcx> {
     ) -> SpannedEncodingResult<String> {
         Ok(destructor_name(domain_name, variant, field_name))
     }
+    fn adt_discriminant_name(&mut self, domain_name: &str) -> SpannedEncodingResult<String> {
+        Ok(discriminant_name(domain_name))
+    }
+    fn adt_discriminant_call(
+        &mut self,
+        domain_name: &str,
+        argument: vir_low::Expression,
+    ) -> SpannedEncodingResult<vir_low::Expression> {
+        Ok(vir_low::Expression::domain_function_call(
+            domain_name.to_string(),
+            discriminant_name(domain_name),
+            vec![argument],
+            vir_low::Type::Int,
+        ))
+    }
+    fn encode_union_field_read_checks(
+        &mut self,
+        statements: &mut Vec<vir_low::Statement>,
+        place: &vir_mid::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<()> {
+        use vir_low::macros::*;
+        if let Some(parent) = place.get_parent_ref() {
+            self.encode_union_field_read_checks(statements, parent, position)?;
+            let parent_type = parent.get_type();
+            if let vir_mid::TypeDecl::Union(decl) = self.encoder.get_type_decl_mid(parent_type)? {
+                let field = place.clone().unwrap_field().field;
+                if !(field.ty.is_int() || field.ty.is_float()) {
+                    let variant_index = decl.get_variant_index(&field.name).unwrap();
+                    let domain_name = self.encode_snapshot_domain_name(parent_type)?;
+                    let parent_snapshot = parent.create_snapshot(self)?;
+                    let discriminant = self.adt_discriminant_call(&domain_name, parent_snapshot)?;
+                    let position = self
+                        .encoder
+                        .change_error_context(position, ErrorCtxt::UnionFieldRead);
+                    statements.push(stmtp! {
+                        position => assert ([discriminant] == [variant_index.into()])
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+    fn outermost_union_containing(
+        &mut self,
+        place: &vir_mid::Expression,
+    ) -> SpannedEncodingResult<Option<vir_mid::Expression>> {
+        let mut outermost_union = None;
+        let mut current = place;
+        while let vir_mid::Expression::Field(vir_mid::Field { box base, .. }) = current {
+            if self.encoder.get_type_decl_mid(base.get_type())?.is_union() {
+                outermost_union = Some(base.clone());
+            }
+            current = base;
+        }
+        Ok(outermost_union)
+    }
+    fn encode_write_place_through_unions(
+        &mut self,
+        statements: &mut Vec<vir_low::Statement>,
+        target: &vir_mid::Expression,
+        value: vir_low::Expression,
+        position: vir_low::Position,
+    ) -> SpannedEncodingResult<()> {
+        use vir_low::macros::*;
+        if let Some(union_place) = self.outermost_union_containing(target)? {
+            // Writing a union field overwrites the whole union, so the new
+            // snapshot of the union is built from the written variant.
+            let field = match target {
+                vir_mid::Expression::Field(vir_mid::Field {
+                    box base, field, ..
+                }) if base == &union_place => field,
+                _ => unimplemented!("writing into a nested field of a union: {}", target),
+            };
+            let union_ty = union_place.get_type();
+            let domain_name = self.encode_snapshot_domain_name(union_ty)?;
+            let snapshot = constructor_call(&domain_name, &field.name, vec![value]);
+            self.encode_write_place_method(union_ty)?;
+            let place = self.encode_expression_as_place(&union_place)?;
+            let address = self.extract_root_address(&union_place)?;
+            statements.push(stmtp! { position =>
+                call write_place<union_ty>([place], [address], [snapshot.clone()])
+            });
+            self.encode_snapshot_update(statements, &union_place, snapshot, position)?;
+        } else {
+            let target_ty = target.get_type();
+            self.encode_write_place_method(target_ty)?;
+            let place = self.encode_expression_as_place(target)?;
+            let address = self.extract_root_address(target)?;
+            statements.push(stmtp! { position =>
+                call write_place<target_ty>([place], [address], [value.clone()])
+            });
+            self.encode_snapshot_update(statements, target, value, position)?;
+        }
+        Ok(())
+    }
 }
@@ -179,3 +179,7 @@ pub(super) fn constructor_name(domain_name: &str, variant: &str) -> String {
 pub(super) fn destructor_name(domain_name: &str, variant: &str, field_name: &str) -> String {
     format!("field${}${}${}", domain_name, variant, field_name)
 }
+
+pub(super) fn discriminant_name(domain_name: &str) -> String {
+    format!("discriminant${}", domain_name)
+}
@@ -3,8 +3,10 @@ use crate::encoder::{
     errors::SpannedEncodingResult,
     middle::core_proof::{
         addresses::AddressesInterface,
+        adts::AdtsInterface,
         block_markers::BlockMarkersInterface,
         builtin_methods::BuiltinMethodsInterface,
+        fold_unfold::FoldUnfoldInterface,
         lowerer::Lowerer,
         places::PlacesInterface,
         predicates_memory_block::PredicatesMemoryBlockInterface,
@@ -193,27 +195,71 @@ impl IntoLow for vir_mid::Statement {
                 let target_ty = statement.target.get_type();
                 let source_ty = statement.source.get_type();
                 assert_eq!(target_ty, source_ty);
-                lowerer.encode_move_place_method(target_ty)?;
-                let target_place = lowerer.encode_expression_as_place(&statement.target)?;
-                let target_address = lowerer.extract_root_address(&statement.target)?;
-                let source_place = lowerer.encode_expression_as_place(&statement.source)?;
-                let source_address = lowerer.extract_root_address(&statement.source)?;
                 let value = lowerer.lower_expression_into_snapshot(&statement.source)?;
-                let mut statements = vec![stmtp! { statement.position =>
-                    call move_place<target_ty>(
-                        [target_place],
-                        [target_address],
-                        [source_place],
-                        [source_address],
-                        [value.clone()]
-                    )
-                }];
-                lowerer.encode_snapshot_update(
+                let mut statements = Vec::new();
+                lowerer.encode_union_field_read_checks(
                     &mut statements,
-                    &statement.target,
-                    value,
+                    &statement.source,
                     statement.position,
                 )?;
+                let source_union = lowerer.outermost_union_containing(&statement.source)?;
+                let target_union = lowerer.outermost_union_containing(&statement.target)?;
+                if source_union.is_none() && target_union.is_none() {
+                    lowerer.encode_move_place_method(target_ty)?;
+                    let target_place = lowerer.encode_expression_as_place(&statement.target)?;
+                    let target_address = lowerer.extract_root_address(&statement.target)?;
+                    let source_place = lowerer.encode_expression_as_place(&statement.source)?;
+                    let source_address = lowerer.extract_root_address(&statement.source)?;
+                    statements.push(stmtp! { statement.position =>
+                        call move_place<target_ty>(
+                            [target_place],
+                            [target_address],
+                            [source_place],
+                            [source_address],
+                            [value.clone()]
+                        )
+                    });
+                    lowerer.encode_snapshot_update(
+                        &mut statements,
+                        &statement.target,
+                        value,
+                        statement.position,
+                    )?;
+                } else {
+                    // A union is a single memory block, so moving out of its
+                    // field turns the whole union into a memory block.
+                    let (source, source_value) = if let Some(source_union) = source_union {
+                        let snapshot = source_union.create_snapshot(lowerer)?;
+                        (source_union, snapshot)
+                    } else {
+                        (statement.source.clone(), value.clone())
+                    };
+                    let source_ty = source.get_type();
+                    let source_place = lowerer.encode_expression_as_place(&source)?;
+                    let source_address = lowerer.extract_root_address(&source)?;
+                    lowerer.encode_fully_unfold_owned_non_aliased(
+                        &mut statements,
+                        source_ty,
+                        source_place,
+                        &source_address,
+                        source_value,
+                        statement.position,
+                    )?;
+                    let source_block_address =
+                        lowerer.encode_expression_as_place_address(&source)?;
+                    lowerer.encode_fully_join_memory_block(
+                        &mut statements,
+                        source_ty,
+                        source_block_address,
+                        statement.position,
+                    )?;
+                    lowerer.encode_write_place_through_unions(
+                        &mut statements,
+                        &statement.target,
+                        value,
+                        statement.position,
+                    )?;
+                }
                 Ok(statements)
             }
             Self::CopyPlace(statement) => {
@@ -221,45 +267,80 @@ impl IntoLow for vir_mid::Statement {
                 let target_ty = statement.target.get_type();
                 let source_ty = statement.source.get_type();
                 assert_eq!(target_ty, source_ty);
-                lowerer.encode_copy_place_method(target_ty)?;
-                let target_place = lowerer.encode_expression_as_place(&statement.target)?;
-                let target_address = lowerer.extract_root_address(&statement.target)?;
-                let source_place = lowerer.encode_expression_as_place(&statement.source)?;
-                let source_address = lowerer.extract_root_address(&statement.source)?;
                 let value = lowerer.lower_expression_into_snapshot(&statement.source)?;
-                let mut statements = vec![stmtp! { statement.position =>
-                    call copy_place<target_ty>(
-                        [target_place],
-                        [target_address],
-                        [source_place],
-                        [source_address],
-                        [value.clone()]
-                    )
-                }];
-                lowerer.encode_snapshot_update(
+                let mut statements = Vec::new();
+                lowerer.encode_union_field_read_checks(
                     &mut statements,
-                    &statement.target,
-                    value,
+                    &statement.source,
                     statement.position,
                 )?;
+                let source_union = lowerer.outermost_union_containing(&statement.source)?;
+                let target_union = lowerer.outermost_union_containing(&statement.target)?;
+                if source_union.is_none() && target_union.is_none() {
+                    lowerer.encode_copy_place_method(target_ty)?;
+                    let target_place = lowerer.encode_expression_as_place(&statement.target)?;
+                    let target_address = lowerer.extract_root_address(&statement.target)?;
+                    let source_place = lowerer.encode_expression_as_place(&statement.source)?;
+                    let source_address = lowerer.extract_root_address(&statement.source)?;
+                    statements.push(stmtp! { statement.position =>
+                        call copy_place<target_ty>(
+                            [target_place],
+                            [target_address],
+                            [source_place],
+                            [source_address],
+                            [value.clone()]
+                        )
+                    });
+                    lowerer.encode_snapshot_update(
+                        &mut statements,
+                        &statement.target,
+                        value,
+                        statement.position,
+                    )?;
+                } else {
+                    // The source keeps its permission, so we unfold it only to
+                    // learn that the copied value is valid.
+                    let (source, source_value) = if let Some(source_union) = source_union {
+                        let snapshot = source_union.create_snapshot(lowerer)?;
+                        (source_union, snapshot)
+                    } else {
+                        (statement.source.clone(), value.clone())
+                    };
+                    let source_ty = source.get_type();
+                    let source_place = lowerer.encode_expression_as_place(&source)?;
+                    let source_address = lowerer.extract_root_address(&source)?;
+                    lowerer.encode_fully_unfold_owned_non_aliased(
+                        &mut statements,
+                        source_ty,
+                        source_place.clone(),
+                        &source_address,
+                        source_value.clone(),
+                        statement.position,
+                    )?;
+                    lowerer.encode_write_place_through_unions(
+                        &mut statements,
+                        &statement.target,
+                        value,
+                        statement.position,
+                    )?;
+                    lowerer.encode_fully_fold_owned_non_aliased(
+                        &mut statements,
+                        source_ty,
+                        source_place,
+                        &source_address,
+                        source_value,
+                        statement.position,
+                    )?;
+                }
                 Ok(statements)
             }
             Self::WritePlace(statement) => {
                 let target_ty = statement.target.get_type();
                 let source_ty = statement.value.get_type();
                 assert_eq!(target_ty, source_ty);
-                lowerer.encode_write_place_method(target_ty)?;
-                let target_place = lowerer.encode_expression_as_place(&statement.target)?;
-                let target_address = lowerer.extract_root_address(&statement.target)?;
                 let value = lowerer.lower_expression_into_snapshot(&statement.value)?;
-                let mut statements = vec![stmtp! { statement.position =>
-                    call write_place<target_ty>(
-                        [target_place],
-                        [target_address],
-                        [value.clone()]
-                    )
-                }];
-                lowerer.encode_snapshot_update(
+                let mut statements = Vec::new();
+                lowerer.encode_write_place_through_unions(
                     &mut statements,
                     &statement.target,
                     value,
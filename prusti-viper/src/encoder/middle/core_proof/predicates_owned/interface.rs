@@ -58,7 +58,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
         let bytes =
             self.encode_memory_block_bytes_expression(compute_address.clone(), size_of.clone())?;
         let predicate = match &type_decl {
-            vir_mid::TypeDecl::Bool | vir_mid::TypeDecl::Int(_) | vir_mid::TypeDecl::Float(_) => {
+            // A union is a single memory block; its fields are only typed
+            // views of the snapshot.
+            vir_mid::TypeDecl::Bool
+            | vir_mid::TypeDecl::Int(_)
+            | vir_mid::TypeDecl::Float(_)
+            | vir_mid::TypeDecl::Union(_) => {
                 predicate! {
                     OwnedNonAliased<ty>(place: Place, root_address: Address, snapshot: {snapshot_type})
                     {(
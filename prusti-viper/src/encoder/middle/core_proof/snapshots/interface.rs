@@ -186,6 +186,33 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
                     ))
                 }
                 vir_mid::TypeDecl::Enum(_) => unimplemented!("ty: {}", type_decl),
+                vir_mid::TypeDecl::Union(decl) => {
+                    // Writing a field of a union overwrites the whole memory
+                    // block, so nothing is copied. We only record which typed
+                    // view is now valid.
+                    let place_field = place.clone().unwrap_field();
+                    let variant_index = decl.get_variant_index(&place_field.field.name).unwrap();
+                    let domain_name = self.encode_snapshot_domain_name(parent_type)?;
+                    let discriminant =
+                        self.adt_discriminant_call(&domain_name, new_snapshot.clone())?;
+                    statements.push(
+                        stmtp! { position => assume ([discriminant] == [variant_index.into()])},
+                    );
+                    Ok((
+                        self.encode_field_snapshot(
+                            parent_type,
+                            &place_field.field,
+                            old_snapshot,
+                            Default::default(),
+                        )?,
+                        self.encode_field_snapshot(
+                            parent_type,
+                            &place_field.field,
+                            new_snapshot,
+                            Default::default(),
+                        )?,
+                    ))
+                }
                 vir_mid::TypeDecl::Array(_) => unimplemented!("ty: {}", type_decl),
                 vir_mid::TypeDecl::Reference(_) => unimplemented!("ty: {}", type_decl),
                 vir_mid::TypeDecl::Never => unimplemented!("ty: {}", type_decl),
@@ -391,9 +418,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> SnapshotsInterface for Lowerer<'p, 'v, 'tcx> {
     ) -> SpannedEncodingResult<vir_low::Expression> {
         let domain_name = self.encode_snapshot_domain_name(base_type)?;
         let return_type = field.ty.create_snapshot(self)?;
-        Ok(self
-            .adt_destructor_base_call(&domain_name, &field.name, return_type, base_snapshot)?
-            .set_default_position(position))
+        let field_snapshot = if self.encoder.get_type_decl_mid(base_type)?.is_union() {
+            // Each field of a union is a separate typed view of its memory.
+            let function_name =
+                self.adt_destructor_variant_name(&domain_name, &field.name, &field.name)?;
+            vir_low::Expression::domain_function_call(
+                domain_name,
+                function_name,
+                vec![base_snapshot],
+                return_type,
+            )
+        } else {
+            self.adt_destructor_base_call(&domain_name, &field.name, return_type, base_snapshot)?
+        };
+        Ok(field_snapshot.set_default_position(position))
     }
     fn encode_constant_snapshot(
         &mut self,
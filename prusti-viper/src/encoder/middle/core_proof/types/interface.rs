@@ -33,6 +33,10 @@ struct LoweredVariant {
     /// A constructor is representational if it can be used for constructing any
     /// valid instance of the ADT.
     is_representational: bool,
+    /// Whether the validity of the ADT implies the validity of all fields of
+    /// this constructor. This does not hold for the typed views of a union,
+    /// whose validity is axiomatized in `declare_union_discriminant`.
+    has_top_down_validity: bool,
 }
 
 #[derive(Default)]
@@ -51,6 +55,7 @@ impl LoweredVariants {
             constructor: AdtConstructor::constant(parameter_type),
             validity: Some(invariant),
             is_representational,
+            has_top_down_validity: true,
         });
     }
     fn add_enum_variant_no_inv(
@@ -62,6 +67,20 @@ impl LoweredVariants {
             constructor: AdtConstructor::variant(variant.to_string(), parameters),
             validity: None,
             is_representational: false,
+            has_top_down_validity: true,
+        });
+    }
+    fn add_union_variant_with_inv(
+        &mut self,
+        variant: impl ToString,
+        parameters: Vec<vir_low::VariableDecl>,
+        invariant: vir_low::Expression,
+    ) {
+        self.variants.push(LoweredVariant {
+            constructor: AdtConstructor::variant(variant.to_string(), parameters),
+            validity: Some(invariant),
+            is_representational: false,
+            has_top_down_validity: false,
         });
     }
     fn add_struct_with_inv(
@@ -73,6 +92,7 @@ impl LoweredVariants {
             constructor: AdtConstructor::base(parameters),
             validity: Some(invariant),
             is_representational: false,
+            has_top_down_validity: true,
         });
     }
     fn iter_constructors(&self) -> impl Iterator<Item = &AdtConstructor> {
@@ -147,6 +167,11 @@ trait Private {
         simplification_result: vir_low::Expression,
     ) -> SpannedEncodingResult<()>;
     fn declare_simplification_axioms(&mut self, ty: &vir_mid::Type) -> SpannedEncodingResult<()>;
+    fn declare_union_discriminant(
+        &mut self,
+        ty: &vir_mid::Type,
+        decl: &vir_mid::type_decl::Union,
+    ) -> SpannedEncodingResult<()>;
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
@@ -229,6 +254,19 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
                 }
                 constructors.add_struct_with_inv(parameters, true.into());
             }
+            vir_mid::TypeDecl::Union(decl) => {
+                // One constructor per typed view of the union.
+                for variant in &decl.variants {
+                    let mut parameters = Vec::new();
+                    for field in variant.iter_fields() {
+                        parameters.push(vir_low::VariableDecl::new(
+                            field.name.clone(),
+                            field.ty.create_snapshot(self)?,
+                        ));
+                    }
+                    constructors.add_union_variant_with_inv(&variant.name, parameters, true.into());
+                }
+            }
             _ => unimplemented!("type: {:?}", type_decl),
         };
         Ok(constructors)
@@ -287,11 +325,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
                     }
                 };
                 self.declare_axiom(&domain_name, axiom_bottom_up)?;
-                if variant
-                    .constructor
-                    .get_parameters()
-                    .iter()
-                    .any(|parameter| parameter.ty.is_domain())
+                if variant.has_top_down_validity
+                    && variant
+                        .constructor
+                        .get_parameters()
+                        .iter()
+                        .any(|parameter| parameter.ty.is_domain())
                 {
                     // The top-down axiom allows proving that any of the fields
                     // is valid if we know that the whole data strucure is
@@ -483,6 +522,98 @@ impl<'p, 'v: 'p, 'tcx: 'v> Private for Lowerer<'p, 'v, 'tcx> {
         }
         Ok(())
     }
+    /// The discriminant of a union snapshot tracks which typed view of the
+    /// union was written last.
+    fn declare_union_discriminant(
+        &mut self,
+        ty: &vir_mid::Type,
+        decl: &vir_mid::type_decl::Union,
+    ) -> SpannedEncodingResult<()> {
+        use vir_low::macros::*;
+        let domain_name = self.encode_snapshot_domain_name(ty)?;
+        let snapshot_type = ty.create_snapshot(self)?;
+        let discriminant_name = self.adt_discriminant_name(&domain_name)?;
+        self.insert_domain_function(
+            &domain_name,
+            vir_low::DomainFunctionDecl {
+                name: discriminant_name,
+                parameters: vars! { snapshot: {snapshot_type} },
+                return_type: vir_low::Type::Int,
+            },
+        )?;
+        for (variant_index, variant) in decl.variants.iter().enumerate() {
+            let mut parameters = Vec::new();
+            for field in variant.iter_fields() {
+                parameters.push(vir_low::VariableDecl::new(
+                    field.name.clone(),
+                    field.ty.create_snapshot(self)?,
+                ));
+            }
+            let constructor_call = vir_low::Expression::domain_function_call(
+                &domain_name,
+                self.adt_constructor_variant_name(&domain_name, &variant.name)?,
+                parameters
+                    .iter()
+                    .map(|parameter| parameter.clone().into())
+                    .collect(),
+                ty.create_snapshot(self)?,
+            );
+            let discriminant_call =
+                self.adt_discriminant_call(&domain_name, constructor_call.clone())?;
+            let body = vir_low::Expression::forall(
+                parameters,
+                vec![vir_low::Trigger::new(vec![constructor_call])],
+                expr! { [discriminant_call] == [variant_index.into()] },
+            );
+            let axiom = vir_low::DomainAxiomDecl {
+                name: format!("{}${}$discriminant_axiom", domain_name, variant.name),
+                body,
+            };
+            self.declare_axiom(&domain_name, axiom)?;
+            // Every bit pattern is a valid integer or float, so these views
+            // are valid whenever the union is. Other views are valid only if
+            // they were written last.
+            var_decls! { snapshot: {snapshot_type.clone()} };
+            let valid_union =
+                self.encode_snapshot_validity_expression(snapshot.clone().into(), ty)?;
+            for field in variant.iter_fields() {
+                let field_snapshot = vir_low::Expression::domain_function_call(
+                    &domain_name,
+                    self.adt_destructor_variant_name(&domain_name, &variant.name, &field.name)?,
+                    vec![snapshot.clone().into()],
+                    field.ty.create_snapshot(self)?,
+                );
+                let valid_field =
+                    self.encode_snapshot_validity_expression(field_snapshot, &field.ty)?;
+                let guard = if field.ty.is_int() || field.ty.is_float() {
+                    valid_union.clone()
+                } else {
+                    let discriminant_call =
+                        self.adt_discriminant_call(&domain_name, snapshot.clone().into())?;
+                    expr! {
+                        [valid_union.clone()] && ([discriminant_call] == [variant_index.into()])
+                    }
+                };
+                let body = vir_low::Expression::forall(
+                    vec![snapshot.clone()],
+                    vec![vir_low::Trigger::new(vec![
+                        valid_union.clone(),
+                        valid_field.clone(),
+                    ])],
+                    expr! { [guard] ==> [valid_field] },
+                );
+                let axiom = vir_low::DomainAxiomDecl {
+                    name: format!(
+                        "{}${}${}$validity_axiom",
+                        domain_name, variant.name, field.name
+                    ),
+                    body,
+                };
+                self.declare_axiom(&domain_name, axiom)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 pub(in super::super) trait TypesInterface {
@@ -519,6 +650,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> TypesInterface for Lowerer<'p, 'v, 'tcx> {
                 constructors.iter_variants(),
             )?;
             self.declare_simplification_axioms(ty)?;
+            if let vir_mid::TypeDecl::Union(decl) = &type_decl {
+                self.declare_union_discriminant(ty, decl)?;
+            }
             // 1. Snapshot.
             // 2. Address.
             // 3. Place.
@@ -16,9 +16,11 @@ pub(in super::super) trait TypeDeclWalker {
     ) -> SpannedEncodingResult<()> {
         let is_zst = lowerer.encoder.is_zst_mid(ty)?;
         match ty {
-            vir_mid::Type::Bool | vir_mid::Type::Int(_) | vir_mid::Type::Float(_) => {
-                self.before_primitive(ty, parameters, lowerer)
-            }
+            // A union is a single memory block, so it is treated as a primitive.
+            vir_mid::Type::Bool
+            | vir_mid::Type::Int(_)
+            | vir_mid::Type::Float(_)
+            | vir_mid::Type::Union(_) => self.before_primitive(ty, parameters, lowerer),
             // vir_mid::Type::TypeVar(TypeVar) => {},
             vir_mid::Type::Tuple(_) | vir_mid::Type::Struct(_)
                 if is_zst && Self::IS_ZST_PRIMITIVE =>
@@ -127,7 +129,10 @@ pub(in super::super) trait TypeDeclWalker {
         self.before(ty, &parameters, lowerer)?;
         let is_zst = lowerer.encoder.is_zst_mid(ty)?;
         match type_decl {
-            vir_mid::TypeDecl::Bool | vir_mid::TypeDecl::Int(_) | vir_mid::TypeDecl::Float(_) => {
+            vir_mid::TypeDecl::Bool
+            | vir_mid::TypeDecl::Int(_)
+            | vir_mid::TypeDecl::Float(_)
+            | vir_mid::TypeDecl::Union(_) => {
                 self.walk_primitive(ty, &parameters, lowerer)?;
             }
             // vir_mid::TypeDecl::TypeVar(TypeVar) => {},
@@ -161,9 +166,10 @@ pub(in super::super) trait TypeDeclWalker {
     ) -> SpannedEncodingResult<()> {
         let is_zst = lowerer.encoder.is_zst_mid(ty)?;
         match ty {
-            vir_mid::Type::Bool | vir_mid::Type::Int(_) | vir_mid::Type::Float(_) => {
-                self.after_primitive(ty, parameters, lowerer)
-            }
+            vir_mid::Type::Bool
+            | vir_mid::Type::Int(_)
+            | vir_mid::Type::Float(_)
+            | vir_mid::Type::Union(_) => self.after_primitive(ty, parameters, lowerer),
             // vir_mid::Type::TypeVar(TypeVar) => {},
             vir_mid::Type::Tuple(_) | vir_mid::Type::Struct(_)
                 if is_zst && Self::IS_ZST_PRIMITIVE =>
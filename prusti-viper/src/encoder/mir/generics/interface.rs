@@ -77,7 +77,7 @@ impl<'v, 'tcx: 'v> MirGenericsEncoderInterface<'tcx> for super::super::super::En
         } else {
             Vec::new()
         };
-        for generic in &generics.params {
+        for generic in generics.params.iter().filter(|param| is_type_param(param)) {
             let name = generic.name;
             let index = generics.param_def_id_to_index[&generic.def_id];
             parameters.push(self.encode_param(name, index));
@@ -95,7 +95,7 @@ impl<'v, 'tcx: 'v> MirGenericsEncoderInterface<'tcx> for super::super::super::En
         } else {
             Vec::new()
         };
-        for generic in &generics.params {
+        for generic in generics.params.iter().filter(|param| is_type_param(param)) {
             let name = generic.name;
             let index = generics.param_def_id_to_index[&generic.def_id];
             let type_var = ty::ParamTy { index, name };
@@ -116,3 +116,9 @@ impl<'v, 'tcx: 'v> MirGenericsEncoderInterface<'tcx> for super::super::super::En
         vir_high::ty::TypeVar::new(identifier)
     }
 }
+
+/// Lifetimes are erased during the encoding, so only type parameters become
+/// generic parameters.
+fn is_type_param(param: &ty::GenericParamDef) -> bool {
+    matches!(param.kind, ty::GenericParamDefKind::Type { .. })
+}
@@ -238,6 +238,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 ));
             }
             mir::StatementKind::Assign(box (target, source)) => {
+                self.check_union_field_write(location, *target, source)?;
                 let encoded_target = self.encoder.encode_place_high(self.mir, *target)?;
                 self.encode_statement_assign(block_builder, location, encoded_target, source)?;
             }
@@ -248,6 +249,41 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(())
     }
 
+    /// A write into a union field is supported only if it overwrites a field
+    /// of the union itself with a moved, copied, or constant value.
+    fn check_union_field_write(
+        &self,
+        location: mir::Location,
+        target: mir::Place<'tcx>,
+        source: &mir::Rvalue<'tcx>,
+    ) -> SpannedEncodingResult<()> {
+        let tcx = self.encoder.env().tcx();
+        for (index, (base, projection)) in target.iter_projections().enumerate() {
+            let is_union_field = matches!(projection, mir::ProjectionElem::Field(..))
+                && matches!(
+                    base.ty(self.mir, tcx).ty.kind(),
+                    ty::TyKind::Adt(adt_def, _) if adt_def.is_union()
+                );
+            if !is_union_field {
+                continue;
+            }
+            let span = self.encoder.get_span_of_location(self.mir, location);
+            if index + 1 != target.projection.len() {
+                return Err(SpannedEncodingError::unsupported(
+                    "writing into a nested field of a union is not supported",
+                    span,
+                ));
+            }
+            if !matches!(source, mir::Rvalue::Use(_)) {
+                return Err(SpannedEncodingError::unsupported(
+                    "only moved, copied, or constant values can be written into a union field",
+                    span,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     fn encode_statement_assign(
         &self,
         block_builder: &mut BasicBlockBuilder,
@@ -302,12 +338,25 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             mir::AggregateKind::Adt(adt_did, variant_index, substs, _, active_field_index) => {
                 let adt_def = self.encoder.env().tcx().adt_def(*adt_did);
                 assert!(
-                    active_field_index.is_none(),
+                    active_field_index.is_none() || adt_def.is_union(),
                     "field index should be set only for unions"
                 );
                 assert_eq!(variant_index.index(), 0, "variant for structs should be 0");
                 let encoded_adt_def = self.encoder.encode_adt_def(adt_def, substs, None)?;
                 match encoded_adt_def {
+                    vir_high::TypeDecl::Union(decl) => {
+                        // Only the active field of a union is initialized.
+                        let active_field_index = active_field_index.unwrap();
+                        assert_eq!(operands.len(), 1);
+                        let field = decl.variants[active_field_index].fields[0].clone();
+                        let encoded_target_with_field = encoded_target.field_no_pos(field);
+                        self.encode_assign_operand(
+                            block_builder,
+                            location,
+                            encoded_target_with_field,
+                            &operands[0],
+                        )?;
+                    }
                     vir_high::TypeDecl::Struct(decl) => {
                         assert_eq!(decl.fields.len(), operands.len());
                         for (field, operand) in decl.fields.into_iter().zip(operands.iter()) {
@@ -491,7 +491,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ExpressionBackwardInterpreter<'p, 'v, 'tcx> {
                     // TODO: check we are in a spec function
                     "prusti_contracts::exists"
                     | "prusti_contracts::forall"
-                    | "prusti_contracts::specification_entailment"
                     | "prusti_contracts::call_description" => {
                         let expr = self.encoder.encode_prusti_operation_high(
                             full_func_proc_name,
@@ -506,6 +505,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> ExpressionBackwardInterpreter<'p, 'v, 'tcx> {
                         state
                     }
 
+                    name if name.starts_with("prusti_contracts::specification_entailment_") => {
+                        let expr = self.encoder.encode_prusti_operation_high(
+                            full_func_proc_name,
+                            span,
+                            substs,
+                            encoded_args,
+                            self.caller_def_id,
+                            &self.tymap,
+                        )?;
+                        let mut state = states[target_block].clone();
+                        state.substitute_value(&encoded_lhs, expr);
+                        state
+                    }
+
                     _ => {
                         if self.encoder.is_pure(def_id) {
                             self.encode_call_generic(
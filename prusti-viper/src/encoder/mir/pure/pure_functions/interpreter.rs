@@ -602,7 +602,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                             "prusti_contracts::implication"
                             | "prusti_contracts::exists"
                             | "prusti_contracts::forall"
-                            | "prusti_contracts::specification_entailment"
                             | "prusti_contracts::call_description" => {
                                 let expr = self.encoder.encode_prusti_operation(
                                     full_func_proc_name,
@@ -617,6 +616,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state
                             }
 
+                            name if name
+                                .starts_with("prusti_contracts::specification_entailment_") =>
+                            {
+                                let expr = self.encoder.encode_prusti_operation(
+                                    full_func_proc_name,
+                                    span,
+                                    substs,
+                                    encoded_args,
+                                    self.caller_def_id,
+                                    &self.tymap,
+                                )?;
+                                let mut state = states[target_block].clone();
+                                state.substitute_value(&lhs_value, expr);
+                                state
+                            }
+
                             // simple function call
                             _ => {
                                 let own_substs = ty::List::identity_for_item(tcx, def_id);
@@ -1226,18 +1241,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                         let lhs_ty = lhs_ref_ty.peel_refs();
                         let rhs_ref_ty = self.mir_encoder.get_operand_ty(operand);
                         let rhs_ty = rhs_ref_ty.peel_refs();
-                        if lhs_ref_ty.is_slice() && rhs_ty.is_array() {
-                            let function_name = self.encoder.encode_unsize_function_use(rhs_ty, lhs_ty, &self.tymap)
-                                .unwrap_or_else(|error| unreachable!("error during unsizing slice to array: {:?}", error) );
+                        let is_array_to_slice = lhs_ref_ty.is_slice() && rhs_ty.is_array();
+                        let is_into_trait_object = lhs_ty.is_trait() && !rhs_ty.is_trait();
+                        if is_array_to_slice || is_into_trait_object {
+                            let (function_name, type_arguments) = self.encoder.encode_unsize_function_use(rhs_ty, lhs_ty, &self.tymap)
+                                .with_span(span)?;
                             let encoded_rhs = self.encoder.encode_snapshot_type(rhs_ty, &self.tymap).with_span(span)?;
                             let formal_args = vec![vir::LocalVar::new(
-                                String::from("array"),
+                                String::from(if is_array_to_slice { "array" } else { "object" }),
                                 encoded_rhs,
                             )];
                             let encoded_arg = self.encoder.encode_value_expr(self.encode_operand(operand).with_span(span)?.0, rhs_ref_ty).with_span(span)?;
                             let unsize_func = vir::Expr::func_app(
                                 function_name,
-                                Vec::new(),     // FIXME: This is probably wrong.
+                                type_arguments,
                                 vec![encoded_arg],
                                 formal_args,
                                 self.encoder.encode_snapshot_type(lhs_ty, &self.tymap).with_span(span)?,
@@ -6,7 +6,9 @@
 
 use crate::encoder::{
     encoder::SubstMap,
-    errors::{EncodingError, EncodingResult, SpannedEncodingResult, WithSpan},
+    errors::{
+        EncodingError, EncodingResult, SpannedEncodingError, SpannedEncodingResult, WithSpan,
+    },
     high::types::HighTypeEncoderInterface,
     mir::{
         pure::{specifications::utils::extract_closure_from_ty, PureFunctionEncoderInterface},
@@ -259,6 +261,134 @@ pub(super) fn encode_quantifier<'tcx>(
     }
 }
 
+pub(super) fn encode_spec_entailment<'tcx>(
+    encoder: &Encoder<'_, 'tcx>,
+    span: Span,
+    substs: ty::subst::SubstsRef<'tcx>,
+    encoded_args: Vec<vir_crate::polymorphic::Expr>,
+    parent_def_id: DefId,
+    tymap: &SubstMap<'tcx>,
+) -> SpannedEncodingResult<vir_crate::polymorphic::Expr> {
+    let tcx = encoder.env().tcx();
+
+    // Specification entailments are encoded as:
+    //   specification_entailment_N::<A_0, .., A_N-1, R, F, P, Q>(
+    //     once,
+    //     &f,
+    //     |a_0, .., a_N-1| -> bool { <precondition> },
+    //     |a_0, .., a_N-1, result| -> bool { <postcondition> },
+    //   )
+    // The entailment holds if the precondition implies the precondition of
+    // `f` and if the postcondition of `f` (together with the precondition)
+    // implies the postcondition.
+
+    let arg_count = substs.types().count() - 4;
+    let fn_ptr_ty = substs.type_at(arg_count + 1);
+    if !matches!(fn_ptr_ty.kind(), ty::TyKind::FnPtr(..)) {
+        return Err(SpannedEncodingError::unsupported(
+            "specification entailments are only supported for function pointers",
+            span,
+        ));
+    }
+    let (pre_def_id, _, _, _) = extract_closure_from_ty(tcx, substs.type_at(arg_count + 2));
+    let (post_def_id, _, post_args, _) =
+        extract_closure_from_ty(tcx, substs.type_at(arg_count + 3));
+
+    let mut encoded_qvars = vec![];
+    let mut bounds = vec![];
+    for (arg_idx, arg_ty) in post_args.into_iter().enumerate() {
+        let qvar_ty = encoder.encode_type(arg_ty).with_span(span)?;
+        let qvar_name = format!("_{}_ent_{}", arg_idx, encoder.encode_item_name(post_def_id));
+        let encoded_qvar = vir_crate::polymorphic::LocalVar::new(qvar_name, qvar_ty);
+        if config::check_overflows() {
+            bounds.push(
+                encoder
+                    .encode_type_bounds(&encoded_qvar.clone().into(), arg_ty)
+                    .into_iter()
+                    .conjoin(),
+            );
+        } else {
+            bounds.push(true.into());
+        }
+        encoded_qvars.push(encoded_qvar);
+    }
+    // The last argument of the postcondition closure is the result.
+    let result_qvar = encoded_qvars.pop().unwrap();
+    let result_bounds = bounds.pop().unwrap();
+    let arg_exprs: Vec<vir_crate::polymorphic::Expr> = encoded_qvars
+        .iter()
+        .cloned()
+        .map(vir_crate::polymorphic::Expr::local)
+        .collect();
+
+    let precondition = inline_closure(
+        encoder,
+        pre_def_id,
+        encoded_args[2].clone(),
+        encoded_qvars.clone(),
+        parent_def_id,
+        tymap,
+        &substs,
+    )?;
+    let mut post_qvars = encoded_qvars.clone();
+    post_qvars.push(result_qvar.clone());
+    let postcondition = inline_closure(
+        encoder,
+        post_def_id,
+        encoded_args[3].clone(),
+        post_qvars.clone(),
+        parent_def_id,
+        tymap,
+        &substs,
+    )?;
+
+    let fn_ptr = encoded_args[1].clone();
+    let pre_call = encoder
+        .encode_fn_pointer_contract_call(fn_ptr_ty, fn_ptr.clone(), arg_exprs.clone(), None, tymap)
+        .with_span(span)?;
+    let post_call = encoder
+        .encode_fn_pointer_contract_call(
+            fn_ptr_ty,
+            fn_ptr,
+            arg_exprs,
+            Some(vir_crate::polymorphic::Expr::local(result_qvar)),
+            tymap,
+        )
+        .with_span(span)?;
+
+    let precondition = bounds
+        .into_iter()
+        .chain(std::iter::once(precondition))
+        .conjoin();
+    let pre_entailment =
+        vir_crate::polymorphic::Expr::implies(precondition.clone(), pre_call.clone());
+    let pre_entailment = if encoded_qvars.is_empty() {
+        pre_entailment
+    } else {
+        vir_crate::polymorphic::Expr::forall(
+            encoded_qvars,
+            vec![vir_crate::polymorphic::Trigger::new(vec![pre_call])],
+            pre_entailment,
+        )
+    };
+    let post_entailment = vir_crate::polymorphic::Expr::forall(
+        post_qvars,
+        vec![vir_crate::polymorphic::Trigger::new(
+            vec![post_call.clone()],
+        )],
+        vir_crate::polymorphic::Expr::implies(
+            vec![precondition, result_bounds, post_call]
+                .into_iter()
+                .conjoin(),
+            postcondition,
+        ),
+    );
+    Ok(vir_crate::polymorphic::Expr::and(
+        pre_entailment,
+        post_entailment,
+    ))
+}
+
 fn find_quantifier_depth(expr: &vir_crate::polymorphic::Expr) -> usize {
     use vir_crate::polymorphic::ExprWalker;
     struct DepthChecker {
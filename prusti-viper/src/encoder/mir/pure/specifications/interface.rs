@@ -6,11 +6,13 @@
 
 use crate::encoder::{
     encoder::SubstMap,
-    errors::{SpannedEncodingResult, WithSpan},
+    errors::{SpannedEncodingError, SpannedEncodingResult, WithSpan},
     mir::pure::{
         specifications::{
             encoder_high::{encode_quantifier_high, inline_spec_item_high},
-            encoder_poly::{encode_quantifier, inline_closure, inline_spec_item},
+            encoder_poly::{
+                encode_quantifier, encode_spec_entailment, inline_closure, inline_spec_item,
+            },
         },
         PureEncodingContext, PureFunctionBackwardInterpreter,
     },
@@ -98,6 +100,12 @@ impl<'v, 'tcx: 'v> SpecificationEncoderInterface<'tcx> for crate::encoder::Encod
                 parent_def_id,
                 tymap,
             ),
+            name if name.starts_with("prusti_contracts::specification_entailment_") => {
+                Err(SpannedEncodingError::unsupported(
+                    "specification entailments are not supported in this encoding",
+                    span,
+                ))
+            }
             _ => unimplemented!(),
         }
     }
@@ -146,6 +154,9 @@ impl<'v, 'tcx: 'v> SpecificationEncoderInterface<'tcx> for crate::encoder::Encod
                 parent_def_id,
                 tymap,
             ),
+            name if name.starts_with("prusti_contracts::specification_entailment_") => {
+                encode_spec_entailment(self, span, substs, encoded_args, parent_def_id, tymap)
+            }
             _ => unimplemented!(),
         }
     }
@@ -20,7 +20,7 @@ pub(super) fn extract_closure_from_ty<'tcx>(
     match ty.kind() {
         ty::TyKind::Closure(def_id, substs) => {
             let cl_substs = substs.as_closure();
-            let sig = cl_substs.sig().no_bound_vars().unwrap();
+            let sig = tcx.erase_late_bound_regions(cl_substs.sig());
             (
                 *def_id,
                 tcx.def_span(*def_id),
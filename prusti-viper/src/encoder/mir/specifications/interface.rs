@@ -57,6 +57,8 @@ impl<'v, 'tcx: 'v> SpecificationsInterface for super::super::super::Encoder<'v,
     }
 
     fn is_trusted(&self, def_id: DefId) -> bool {
+        // The body of an `async fn` is trusted iff the `async fn` is.
+        let def_id = self.env().get_async_fn_of_body(def_id).unwrap_or(def_id);
         let result = self
             .specifications_state
             .specs
@@ -102,6 +104,9 @@ impl<'v, 'tcx: 'v> SpecificationsInterface for super::super::super::Encoder<'v,
     }
 
     fn get_procedure_specs(&self, def_id: DefId) -> Option<typed::ProcedureSpecification> {
+        // The body of an `async fn` is verified against the specification of
+        // the `async fn`.
+        let def_id = self.env().get_async_fn_of_body(def_id).unwrap_or(def_id);
         let mut specs = self.specifications_state.specs.borrow_mut();
         let spec = specs.get_and_refine_proc_spec(self.env(), def_id)?;
         Some(spec.clone())
@@ -50,13 +50,17 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
     }
 
     fn encode_union_name(&self, did: DefId) -> String {
-        format!("union${}", self.encoder.encode_item_name(did))
+        encode_union_name(self.encoder, did)
     }
 
     fn encode_closure_name(&self, did: DefId) -> String {
         format!("closure${}", self.encoder.encode_item_name(did))
     }
 
+    fn encode_generator_name(&self, did: DefId) -> String {
+        format!("generator${}", self.encoder.encode_item_name(did))
+    }
+
     fn encode_function_def_name(&self, did: DefId) -> String {
         format!("fndef${}", self.encoder.encode_item_name(did))
     }
@@ -157,6 +161,14 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 // self.encode_substs(substs),
             ),
 
+            // Generators are encoded like closures: a value of the generator
+            // type is a tuple of its upvars.
+            ty::TyKind::Generator(def_id, _substs, _) => vir::Type::closure(
+                self.encode_generator_name(*def_id),
+                // FIXME: We are currently ignoring type arguments.
+                // self.encode_substs(substs),
+            ),
+
             ty::TyKind::FnDef(def_id, _substs) => vir::Type::function_def(
                 self.encode_function_def_name(*def_id),
                 // FIXME: We are currently ignoring type arguments.
@@ -175,9 +187,20 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 self.encode_substs(substs),
             ),
 
-            ty::TyKind::Dynamic(..) => vir::Type::unsupported("dynamic".to_string()),
+            ty::TyKind::Dynamic(predicates, _) => {
+                if let Some(principal) = predicates.principal() {
+                    let trait_ref = principal.skip_binder();
+                    vir::Type::trait_(
+                        self.encoder.encode_item_name(trait_ref.def_id),
+                        self.encode_substs(trait_ref.substs),
+                    )
+                } else {
+                    // Trait objects of only auto traits such as `dyn Send`.
+                    vir::Type::unsupported("dynamic".to_string())
+                }
+            }
 
-            ty::TyKind::FnPtr(..) => vir::Type::unsupported("fnptr".to_string()),
+            ty::TyKind::FnPtr(..) => vir::Type::FnPointer,
 
             ty::TyKind::Foreign(..) => vir::Type::unsupported("foreign".to_string()),
 
@@ -317,6 +340,15 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 let name = self.encode_closure_name(*def_id);
                 vir::TypeDecl::closure(name, arguments)
             }
+            ty::TyKind::Generator(def_id, internal_substs, _) => {
+                let gen_substs = internal_substs.as_generator();
+                let arguments = gen_substs
+                    .upvar_tys()
+                    .filter_map(|ty| self.encoder.encode_type_high(ty).ok())
+                    .collect();
+                let name = self.encode_generator_name(*def_id);
+                vir::TypeDecl::closure(name, arguments)
+            }
             ty::TyKind::Array(elem_ty, size) => {
                 let array_len = self.compute_array_len(*size);
                 vir::TypeDecl::array(array_len, self.encoder.encode_type_high(*elem_ty)?)
@@ -706,6 +738,10 @@ fn encode_struct_name<'v, 'tcx: 'v>(encoder: &Encoder<'v, 'tcx>, did: DefId) ->
     format!("struct${}", encoder.encode_item_name(did))
 }
 
+fn encode_union_name<'v, 'tcx: 'v>(encoder: &Encoder<'v, 'tcx>, did: DefId) -> String {
+    format!("union${}", encoder.encode_item_name(did))
+}
+
 fn encode_variant<'v, 'tcx: 'v>(
     encoder: &Encoder<'v, 'tcx>,
     name: String,
@@ -747,10 +783,17 @@ pub(super) fn encode_adt_def<'v, 'tcx>(
     } else if adt_def.is_union() {
         debug!("ADT {:?} is a union", adt_def);
         assert!(variant_index.is_none());
-        Err(SpannedEncodingError::unsupported(
-            "unions are not supported",
-            encoder.env().get_def_span(adt_def.did()),
-        ))
+        let tcx = encoder.env().tcx();
+        let name = encode_union_name(encoder, adt_def.did());
+        let mut variants = Vec::new();
+        for field in &adt_def.non_enum_variant().fields {
+            let field_name = crate::encoder::encoder::encode_field_name(field.ident(tcx).as_str());
+            let field_ty = field.ty(tcx, substs);
+            let encoded_field =
+                vir::FieldDecl::new(field_name.clone(), encoder.encode_type_high(field_ty)?);
+            variants.push(vir::type_decl::Struct::new(field_name, vec![encoded_field]));
+        }
+        Ok(vir::TypeDecl::union_(name, variants))
     } else if adt_def.is_enum() {
         debug!("ADT {:?} is an enum", adt_def);
         let name = encode_struct_name(encoder, adt_def.did());
@@ -31,6 +31,7 @@ pub(crate) trait MirTypeEncoderInterface<'tcx> {
         viper_field_name: String,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<vir::Field>;
+    fn encode_upvar_field(&self, index: usize, ty: ty::Ty<'tcx>) -> EncodingResult<vir::Field>;
     fn encode_enum_variant_field(&self, index: &str) -> vir::Field;
     fn encode_discriminant_field(&self) -> vir::Field;
     fn encode_field(
@@ -91,6 +92,11 @@ impl<'v, 'tcx: 'v> MirTypeEncoderInterface<'tcx> for super::super::super::Encode
         let typ = self.encode_type(ty)?;
         Ok(vir::Field::new(viper_field_name, typ))
     }
+    /// Creates the field that stores the upvar ``index`` of a closure or a
+    /// generator.
+    fn encode_upvar_field(&self, index: usize, ty: ty::Ty<'tcx>) -> EncodingResult<vir::Field> {
+        self.encode_raw_ref_field(format!("closure_{}", index), ty)
+    }
     /// Creates a field that corresponds to the enum variant ``index``.
     fn encode_enum_variant_field(&self, index: &str) -> vir::Field {
         let name = format!("enum_{}", index);
@@ -118,6 +124,7 @@ impl<'v, 'tcx: 'v> MirTypeEncoderInterface<'tcx> for super::super::super::Encode
                     .ok_or_else(|| EncodingError::internal("not found variant"))?;
                 variant.fields[field.index()].clone()
             }
+            vir_high::TypeDecl::Union(item) => item.variants[field.index()].fields[0].clone(),
             vir_high::TypeDecl::Closure(item) => vir_high::FieldDecl::new(
                 format!("closure_{}", field.index()),
                 item.arguments[field.index()].clone(),
@@ -202,6 +202,24 @@ pub trait PlaceEncoder<'v, 'tcx: 'v> {
                         (encoded_projection, field_ty, None)
                     }
 
+                    ty::TyKind::Generator(def_id, ref generator_subst, _) => {
+                        debug!("def_id={:?} generator_subst {:?}", def_id, generator_subst);
+
+                        let field_ty = generator_subst.as_generator().upvar_tys().nth(field.index())
+                            .ok_or_else(|| EncodingError::internal(format!(
+                                "failed to obtain the type of the captured path #{} of generator {:?}",
+                                field.index(),
+                                base_ty,
+                            )))?;
+
+                        let encoded_field = self.encoder()
+                            .encode_upvar_field(field.index(), field_ty)?;
+                        let encoded_projection = encoded_base.field(encoded_field);
+                        debug!("encoded_projection: {:?}", encoded_projection);
+
+                        (encoded_projection, field_ty, None)
+                    }
+
                     x => {
                         return Err(EncodingError::internal(
                             format!("{} has no fields", utils::ty_to_string(x))
@@ -11,6 +11,7 @@ mod builtin_encoder;
 mod specs_closures_collector;
 #[allow(clippy::module_inception)]
 mod encoder;
+mod encoding_explorer;
 mod errors;
 mod foldunfold;
 mod initialisation;
@@ -19,6 +19,7 @@ use crate::encoder::mir_encoder::PRECONDITION_LABEL;
 use crate::encoder::mir_successor::MirSuccessor;
 use crate::encoder::places::{Local, LocalVariableManager, Place};
 use crate::encoder::Encoder;
+use crate::encoder::encoding_explorer::dump_encoding_explorer;
 use crate::encoder::snapshot::interface::SnapshotEncoderInterface;
 use prusti_common::{
     config,
@@ -41,6 +42,7 @@ use prusti_interface::{
             LoanPlaces, PoloniusInfo, PoloniusInfoError, ReborrowingDAG, ReborrowingDAGNode,
             ReborrowingKind, ReborrowingZombity,
         },
+        mir_analyses::overflow::compute_overflow_free_operations,
         BasicBlockIndex, PermissionKind, Procedure,
     },
 };
@@ -68,6 +70,42 @@ use crate::encoder::mir::specifications::SpecificationsInterface;
 use super::encoder::SubstMap;
 use super::high::generics::HighGenericsEncoderInterface;
 
+/// A call of an `async fn` whose future is stored in a local variable.
+#[derive(Clone)]
+struct AsyncFnCall<'tcx> {
+    location: mir::Location,
+    called_def_id: ProcedureDefId,
+    arguments: Vec<Local>,
+    /// Maps the fake argument locals to the expressions passed to the call.
+    fake_exprs: FxHashMap<vir::Expr, vir::Expr>,
+    /// The label of the state before the call.
+    pre_label: String,
+    tymap: SubstMap<'tcx>,
+    substs: SubstsRef<'tcx>,
+}
+
+/// The right-hand side of an assignment of a MIR local.
+#[derive(Clone, Copy)]
+enum LocalDefinition<'p, 'tcx> {
+    /// The local is the destination of a call of the function.
+    Call(ProcedureDefId, &'p [mir::Operand<'tcx>]),
+    Rvalue(&'p mir::Rvalue<'tcx>),
+}
+
+/// Returns the local moved or copied by `operand`, if there is one.
+fn operand_local(operand: &mir::Operand) -> Option<mir::Local> {
+    place_base_local(operand.place()?)
+}
+
+/// Returns the local of `place` if `place` is the local itself or its
+/// dereference, as in the reborrows of the desugaring of `.await`.
+fn place_base_local(place: mir::Place) -> Option<mir::Local> {
+    match place.projection[..] {
+        [] | [mir::ProjectionElem::Deref] => Some(place.local),
+        _ => None,
+    }
+}
+
 pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     encoder: &'p Encoder<'v, 'tcx>,
     proc_def_id: ProcedureDefId,
@@ -94,18 +132,27 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     ///  - regained array variable
     ///  - info about updated and restored array elements
     array_magic_wand_at: FxHashMap<mir::Location, (vir::Expr, vir::Expr, vir::Expr)>,
-    /// Labels for array equalities in loops
-    array_loop_old_label: FxHashMap<BasicBlockIndex, String>,
+    /// Labels of the states before loops, used by the snapshot equalities of loop invariants
+    loop_old_label: FxHashMap<BasicBlockIndex, String>,
     /// Slices created at certain locations
     slice_created_at: FxHashMap<mir::Location, vir::Expr>,
     // /// Contracts of functions called at given locations with map for replacing fake expressions.
     procedure_contracts:
         FxHashMap<mir::Location, (ProcedureContract<'tcx>, FxHashMap<vir::Expr, vir::Expr>)>,
+    /// Calls of `async fn`s by the location of the call, used to assume the
+    /// postcondition when the returned future is awaited.
+    async_fn_calls: FxHashMap<mir::Location, AsyncFnCall<'tcx>>,
+    /// Maps the locations where the output of a future is extracted by
+    /// `.await` to the locations of the `async fn` calls that created them.
+    awaited_async_fn_calls: FxHashMap<mir::Location, mir::Location>,
     // /// A map that stores local variables used to preserve the value of a place accross the loop
     // /// when we cannot do that by using permissions.
     pure_var_for_preserving_value_map: FxHashMap<BasicBlockIndex, FxHashMap<vir::Expr, vir::LocalVar>>,
     /// Information about which places are definitely initialised.
     init_info: InitInfo,
+    /// The arithmetic operations that the interval analysis proved not to
+    /// overflow.
+    overflow_free_operations: FxHashSet<mir::Location>,
     // /// Mapping from old expressions to ghost variables with which they were replaced.
     old_to_ghost_var: FxHashMap<vir::Expr, vir::Expr>,
     /// Ghost variables used inside package statements.
@@ -127,6 +174,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let mir_encoder = MirEncoder::new(encoder, mir, def_id);
         let init_info = InitInfo::new(mir, tcx, def_id, &mir_encoder)
             .with_default_span(procedure.get_span())?;
+        let overflow_free_operations = if config::check_overflows() && config::interval_analysis() {
+            compute_overflow_free_operations(def_id, mir, tcx)
+        } else {
+            FxHashSet::default()
+        };
 
         let cfg_method = vir::CfgMethod::new(
             // method name
@@ -148,7 +200,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             mir,
             cfg_method,
             locals: LocalVariableManager::new(&mir.local_decls),
-            loop_encoder: LoopEncoder::new(procedure, tcx),
+            loop_encoder: LoopEncoder::new(
+                procedure,
+                tcx,
+                encoder.env().local_mir_framing(def_id.expect_local()),
+            ),
             auxiliary_local_vars: FxHashMap::default(),
             mir_encoder,
             check_panics: config::check_panics(),
@@ -160,11 +216,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             cfg_blocks_map: FxHashMap::default(),
             magic_wand_at_location: FxHashMap::default(),
             array_magic_wand_at: FxHashMap::default(),
-            array_loop_old_label: FxHashMap::default(),
+            loop_old_label: FxHashMap::default(),
             slice_created_at: FxHashMap::default(),
             procedure_contracts: FxHashMap::default(),
+            async_fn_calls: FxHashMap::default(),
+            awaited_async_fn_calls: FxHashMap::default(),
             pure_var_for_preserving_value_map: FxHashMap::default(),
             init_info,
+            overflow_free_operations,
             old_to_ghost_var: FxHashMap::default(),
             old_ghost_vars: FxHashMap::default(),
             cached_loop_invariant_block: FxHashMap::default(),
@@ -246,6 +305,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         assert_one_magic_wand(procedure_contract.borrow_infos.len()).with_span(mir_span)?;
         self.procedure_contract = Some(procedure_contract);
 
+        // Find the `async fn` calls whose futures are awaited
+        self.awaited_async_fn_calls = self.collect_awaited_async_fn_calls()?;
+
         // Declare the formal return
         for local in self.mir.local_decls.indices().take(1) {
             let name = self.mir_encoder.encode_local_var_name(local);
@@ -408,6 +470,20 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     SpannedEncodingError::unsupported(msg, mir_span)
                 }
 
+                foldunfold::FoldUnfoldError::FailedToObtain(_, Some(ref loss)) => {
+                    let error = SpannedEncodingError::internal(
+                        format!(
+                            "cannot generate fold-unfold Viper statements. {}",
+                            foldunfold_error,
+                        ),
+                        mir_span,
+                    );
+                    let opt_loss_span = loss
+                        .location(&loan_locations, &self.cfg_blocks_map)
+                        .map(|location| self.mir.source_info(location).span);
+                    error.add_note(loss, opt_loss_span)
+                }
+
                 _ => SpannedEncodingError::internal(
                     format!(
                         "cannot generate fold-unfold Viper statements. {}",
@@ -428,6 +504,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 format!("{}.{}.dot", source_filename, method_name),
                 |writer| method_with_fold_unfold.to_graphviz(writer),
             );
+            dump_encoding_explorer(
+                self.encoder,
+                self.mir,
+                &method_with_fold_unfold,
+                &source_filename,
+            );
         }
 
         Ok(method_with_fold_unfold)
@@ -1065,12 +1147,24 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     let (encoded_lhs, pre_stmts) = self.postprocess_place_encoding(lhs_place_encoding, ArrayAccessKind::Mutable(None, location))
                         .with_span(span)?;
                     stmts.extend(pre_stmts);
-                    self.encode_assign(
-                        encoded_lhs,
+                    let mut assign_stmts = self.encode_assign(
+                        encoded_lhs.clone(),
                         rhs,
                         ty,
                         location,
-                    )?
+                    )?;
+                    if let Some(call_location) = self.awaited_async_fn_calls.get(&location) {
+                        let call = self.async_fn_calls.get(call_location).cloned().ok_or_else(|| {
+                            SpannedEncodingError::unsupported(
+                                "awaiting a future that is not created before the `.await` is not supported",
+                                span,
+                            )
+                        })?;
+                        assign_stmts.extend(
+                            self.encode_awaited_postcondition(&call, encoded_lhs, ty, location)?
+                        );
+                    }
+                    assign_stmts
                 }
             }
             ref x => unimplemented!("{:?}", x),
@@ -1206,12 +1300,34 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         *ty,
                         location,
                     )?
+                } else if ty.builtin_deref(true).map_or(false, |pointee| pointee.ty.is_trait()) {
+                    trace!("trait object: operand={:?}, ty={:?}", operand, ty);
+                    self.encode_assign_trait_object(
+                        encoded_lhs,
+                        operand,
+                        *ty,
+                        location,
+                    )?
+                } else if ty.is_box() && matches!(ty.boxed_ty().kind(), ty::TyKind::Slice(..)) {
+                    // Slices are encoded only behind references, so there is
+                    // no encoding of a boxed slice to unsize into.
+                    return Err(EncodingError::unsupported(
+                        "unsizing a box of an array into a box of a slice is not supported"
+                    )).with_span(span);
                 } else {
                     return Err(EncodingError::unsupported(
                         "unsizing a pointer or reference value is not supported"
                     )).with_span(span);
                 }
             }
+            mir::Rvalue::Cast(mir::CastKind::Pointer(ty::adjustment::PointerCast::ReifyFnPointer), ref operand, ty) => {
+                self.encode_assign_fn_pointer(
+                    encoded_lhs,
+                    operand,
+                    *ty,
+                    location,
+                )?
+            }
             mir::Rvalue::Cast(mir::CastKind::Pointer(_), _, _) => {
                 return Err(EncodingError::unsupported(
                     "raw pointers are not supported"
@@ -2279,9 +2395,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
 
-            TerminatorKind::Call { .. } => {
-                // Other kind of calls?
-                unimplemented!();
+            TerminatorKind::Call {
+                ref args,
+                ref destination,
+                func: mir::Operand::Copy(ref func_place) | mir::Operand::Move(ref func_place),
+                ..
+            } => {
+                stmts.extend(
+                    self.encode_fn_pointer_call(
+                        location,
+                        term.source_info.span,
+                        func_place,
+                        args,
+                        destination,
+                    )?
+                );
+                if let Some((_, target)) = destination {
+                    (stmts, MirSuccessor::Goto(*target))
+                } else {
+                    (stmts, MirSuccessor::Kill)
+                }
             }
 
             TerminatorKind::Assert {
@@ -2339,9 +2472,28 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 (stmts, MirSuccessor::Goto(target))
             }
 
+            TerminatorKind::Yield {
+                resume,
+                resume_arg,
+                ..
+            } => {
+                // Suspending the generator does not affect its upvars, but
+                // we know nothing about the value with which it is resumed.
+                // FIXME: Havoc the state of the generator that is reachable
+                // from other tasks.
+                let (encoded_resume_arg, pre_stmts, _, _) = self.encode_place(
+                    &resume_arg,
+                    ArrayAccessKind::Mutable(None, location),
+                    location,
+                )?;
+                stmts.extend(pre_stmts);
+                stmts.extend(self.encode_havoc_and_initialization(&encoded_resume_arg));
+                (stmts, MirSuccessor::Goto(resume))
+            }
+
+            TerminatorKind::GeneratorDrop => (stmts, MirSuccessor::Kill),
+
             TerminatorKind::Resume
-            | TerminatorKind::Yield { .. }
-            | TerminatorKind::GeneratorDrop
             | TerminatorKind::InlineAsm { .. } => unimplemented!("{:?}", term.kind),
         };
         Ok(result)
@@ -2938,6 +3090,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let pre_label = self.cfg_method.get_fresh_label_name();
         stmts.push(vir::Stmt::label(pre_label.clone()));
 
+        if self.encoder.env().is_async_fn(called_def_id) {
+            self.async_fn_calls.insert(location, AsyncFnCall {
+                location,
+                called_def_id,
+                arguments: arguments.clone(),
+                fake_exprs: fake_exprs.clone(),
+                pre_label: pre_label.clone(),
+                tymap: tymap.clone(),
+                substs,
+            });
+        }
+
         // Havoc and inhale variables that store constants
         for constant_arg in &constant_args {
             stmts.extend(self.encode_havoc_and_initialization(constant_arg));
@@ -3411,11 +3575,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let mut func_spec: Vec<vir::Expr> = vec![];
 
         // Encode functional specification
-        let encoded_args: Vec<vir::Expr> = contract
-            .args
-            .iter()
-            .map(|local| self.encode_prusti_local(*local).into())
-            .collect();
+        let encoded_args = self.encode_contract_args(contract)
+            .with_span(self.mir.span)?;
         for assertion in contract.functional_precondition() {
             // FIXME
             let value = self.encoder.encode_assertion(
@@ -3466,11 +3627,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Option<vir::Expr>, // postcondition strengthening
     )> {
         // Encode arguments and return
-        let encoded_args = self.procedure_contract()
-            .args
-            .iter()
-            .map(|local| self.encode_prusti_local(*local).into())
-            .collect::<Vec<_>>();
+        let encoded_args = self.encode_contract_args(self.procedure_contract())
+            .with_span(self.mir.span)?;
         let encoded_return = self
             .encode_prusti_local(self.procedure_contract().returned_value).into();
 
@@ -3646,11 +3804,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         };
 
         // Encode args and return.
-        let encoded_args: Vec<vir::Expr> = contract
-            .args
-            .iter()
-            .map(|local| self.encode_prusti_local(*local).into())
-            .collect();
+        let encoded_args = self.encode_contract_args(contract)?;
         let encoded_return: vir::Expr = self.encode_prusti_local(contract.returned_value).into();
 
         // Encode magic wands
@@ -3770,6 +3924,214 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Returns the types of the upvars of the generator that implements the
+    /// body of an `async fn`. These correspond to the arguments of the
+    /// `async fn`.
+    fn async_fn_body_upvar_tys(&self) -> impl Iterator<Item = ty::Ty<'tcx>> {
+        let generator_ty = self.locals.get_type(mir::Local::from_usize(1).into());
+        match generator_ty.kind() {
+            ty::TyKind::Generator(_, substs, _) => substs.as_generator().upvar_tys(),
+            _ => unreachable!("the first argument of a generator is {:?}", generator_ty),
+        }
+    }
+
+    /// Encode the arguments of `contract` as they are referred to by its
+    /// specification. For the body of an `async fn` these are the upvars of
+    /// the generator instead of the locals of the MIR body.
+    fn encode_contract_args(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+    ) -> EncodingResult<Vec<vir::Expr>> {
+        if contract.def_id == self.proc_def_id
+            && self.encoder.env().get_async_fn_of_body(self.proc_def_id).is_some()
+        {
+            let generator: vir::Expr = self
+                .encode_prusti_local(mir::Local::from_usize(1).into())
+                .into();
+            return self.async_fn_body_upvar_tys()
+                .enumerate()
+                .map(|(field_index, field_ty)| {
+                    let encoded_field = self.encoder.encode_upvar_field(field_index, field_ty)?;
+                    Ok(generator.clone().field(encoded_field))
+                })
+                .collect();
+        }
+        Ok(contract
+            .args
+            .iter()
+            .map(|local| self.encode_prusti_local(*local).into())
+            .collect())
+    }
+
+    /// Finds the statements that extract the output of the future returned
+    /// by an `async fn` call, that is, the assignments of the
+    /// `(_ as Poll::Ready).0` of the result of polling the future in the
+    /// desugaring of `.await`. Returns a map from the location of each such
+    /// statement to the location of the call.
+    ///
+    /// This runs before the blocks are encoded, so that the result does not
+    /// depend on the order in which the blocks are encoded. Each local on the
+    /// way from the polled future back to the call must be assigned exactly
+    /// once, otherwise the call that created the awaited future is ambiguous.
+    fn collect_awaited_async_fn_calls(
+        &self,
+    ) -> SpannedEncodingResult<FxHashMap<mir::Location, mir::Location>> {
+        let mir: &'p mir::Body<'tcx> = self.mir;
+        let lang_items = self.encoder.env().tcx().lang_items();
+        let mut definitions: FxHashMap<mir::Local, Vec<(mir::Location, LocalDefinition<'p, 'tcx>)>> =
+            FxHashMap::default();
+        for (block, block_data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in block_data.statements.iter().enumerate() {
+                if let mir::StatementKind::Assign(box (lhs, rhs)) = &statement.kind {
+                    if let Some(local) = lhs.as_local() {
+                        let location = mir::Location { block, statement_index };
+                        definitions.entry(local).or_default()
+                            .push((location, LocalDefinition::Rvalue(rhs)));
+                    }
+                }
+            }
+            if let TerminatorKind::Call {
+                func: mir::Operand::Constant(box mir::Constant { literal, .. }),
+                args,
+                destination: Some((target_place, _)),
+                ..
+            } = &block_data.terminator().kind {
+                if let (Some(local), ty::TyKind::FnDef(def_id, _)) =
+                    (target_place.as_local(), literal.ty().kind())
+                {
+                    let location = mir::Location {
+                        block,
+                        statement_index: block_data.statements.len(),
+                    };
+                    definitions.entry(local).or_default()
+                        .push((location, LocalDefinition::Call(*def_id, &args[..])));
+                }
+            }
+        }
+        let no_definitions = vec![];
+        let definitions_of = |local| definitions.get(&local).unwrap_or(&no_definitions);
+
+        let mut awaited_calls = FxHashMap::default();
+        for (block, block_data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in block_data.statements.iter().enumerate() {
+                let place = match &statement.kind {
+                    mir::StatementKind::Assign(box (
+                        _,
+                        mir::Rvalue::Use(mir::Operand::Move(place) | mir::Operand::Copy(place)),
+                    )) => place,
+                    _ => continue,
+                };
+                match place.projection[..] {
+                    // `Poll::Ready` is the first variant of `Poll`.
+                    [mir::ProjectionElem::Downcast(_, variant), mir::ProjectionElem::Field(field, _)]
+                        if variant.as_usize() == 0 && field.index() == 0 => {}
+                    _ => continue,
+                }
+                let poll_args = match definitions_of(place.local)[..] {
+                    [(_, LocalDefinition::Call(def_id, args))]
+                        if Some(def_id) == lang_items.future_poll_fn() => args,
+                    _ => continue,
+                };
+                let await_location = mir::Location { block, statement_index };
+                // Follow the pinned reference back to the future.
+                let mut local = poll_args.first().and_then(operand_local);
+                let mut visited = FxHashSet::default();
+                while let Some(current) = local.filter(|current| visited.insert(*current)) {
+                    local = match definitions_of(current)[..] {
+                        [] => None,
+                        [(call_location, LocalDefinition::Call(def_id, args))] => {
+                            if self.encoder.env().is_async_fn(def_id) {
+                                awaited_calls.insert(await_location, call_location);
+                                None
+                            } else if Some(def_id) == lang_items.new_unchecked_fn()
+                                || Some(def_id) == lang_items.into_future_fn()
+                            {
+                                args.first().and_then(operand_local)
+                            } else {
+                                None
+                            }
+                        }
+                        [(_, LocalDefinition::Rvalue(rhs))] => match rhs {
+                            mir::Rvalue::Use(mir::Operand::Move(source) | mir::Operand::Copy(source))
+                            | mir::Rvalue::Ref(_, _, source) => place_base_local(*source),
+                            _ => None,
+                        },
+                        _ => {
+                            return Err(SpannedEncodingError::unsupported(
+                                "awaiting a future that is assigned in more than one place is not supported",
+                                self.mir_encoder.get_span_of_location(await_location),
+                            ));
+                        }
+                    };
+                }
+            }
+        }
+        Ok(awaited_calls)
+    }
+
+    /// Assume the postcondition of the awaited `async fn` `call` for the
+    /// output of its future, which is stored in `encoded_target`.
+    fn encode_awaited_postcondition(
+        &mut self,
+        call: &AsyncFnCall<'tcx>,
+        encoded_target: vir::Expr,
+        target_ty: ty::Ty<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let output_local = self.locals.get_fresh(target_ty);
+        let contract = self.encoder.get_procedure_contract_for_await(
+            call.called_def_id,
+            &call.arguments,
+            output_local,
+            call.tymap.clone(),
+            call.substs,
+        ).with_span(span)?;
+        if !contract.borrow_infos.is_empty() {
+            return Err(SpannedEncodingError::unsupported(
+                "awaiting an async function that returns a reference is not supported",
+                span,
+            ));
+        }
+        let post_label = self.cfg_method.get_fresh_label_name();
+        let (_, _, post_invs_spec, post_func_spec, _, _) = self.encode_postcondition_expr(
+            Some(call.location),
+            &contract,
+            &call.pre_label,
+            &post_label,
+            None,
+            false,
+            None,
+            false,
+            &call.tymap,
+            &call.substs,
+        )?;
+        let mut fake_exprs = call.fake_exprs.clone();
+        fake_exprs.insert(
+            vir::Expr::local(self.encode_prusti_local(output_local)),
+            encoded_target,
+        );
+        let replace_fake_exprs = |mut expr: vir::Expr| -> vir::Expr {
+            for (fake_arg, arg_expr) in fake_exprs.iter() {
+                expr = expr.replace_place(fake_arg, arg_expr);
+            }
+            expr
+        };
+        Ok(vec![
+            vir::Stmt::comment(format!(
+                "Assume the postcondition of the call at {:?}",
+                call.location
+            )),
+            vir::Stmt::Inhale( vir::Inhale {
+                expr: replace_fake_exprs(post_invs_spec),
+            }),
+            vir::Stmt::Inhale( vir::Inhale {
+                expr: replace_fake_exprs(post_func_spec),
+            }),
+            vir::Stmt::label(post_label),
+        ])
+    }
+
     /// Wrap function arguments used in the postcondition into ``old``:
     ///
     /// +   For references wrap the base ``_1.var_ref``.
@@ -3781,11 +4143,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         contract: &ProcedureContract<'tcx>,
         encoded_args: &[vir::Expr],
     ) -> SpannedEncodingResult<vir::Expr> {
-        for (encoded_arg, &arg) in encoded_args.iter().zip(&contract.args) {
-            let ty = self.locals.get_type(arg);
+        let args: Vec<_> = if contract.def_id == self.proc_def_id
+            && self.encoder.env().get_async_fn_of_body(self.proc_def_id).is_some()
+        {
+            // The arguments of an `async fn` are the upvars of its generator,
+            // which are not locals of the MIR body.
+            self.async_fn_body_upvar_tys().map(|ty| (ty, None)).collect()
+        } else {
+            contract.args.iter().map(|&arg| (self.locals.get_type(arg), Some(arg))).collect()
+        };
+        for (encoded_arg, (ty, arg)) in encoded_args.iter().zip(args) {
             if is_reference(ty) {
                 // If the argument is a reference, we wrap _1.val_ref into old.
-                let arg_span = self.mir_encoder.get_local_span(arg.into());
+                let arg_span = arg.map_or(self.mir.span, |arg| {
+                    self.mir_encoder.get_local_span(arg.into())
+                });
                 let (encoded_deref, ..) = self
                     .mir_encoder
                     .encode_deref(encoded_arg.clone(), ty)
@@ -3796,8 +4168,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             } else {
                 // If the argument is not a reference, we wrap entire path into old.
                 assertion = assertion.fold_places(|place| {
-                    let base: vir::Expr = place.get_base().into();
-                    if encoded_arg == &base {
+                    if place.has_prefix(encoded_arg) {
                         place.old(pre_label)
                     } else {
                         place
@@ -3879,11 +4250,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
 
         // Encode args and return.
-        let encoded_args: Vec<vir::Expr> = contract
-            .args
-            .iter()
-            .map(|local| self.encode_prusti_local(*local).into())
-            .collect();
+        let encoded_args = self.encode_contract_args(contract)
+            .with_span(self.mir.span)?;
         trace!("encode_postcondition_expr: encoded_args {:?} ({:?}) as {:?}", contract.args,
                contract.args.iter().map(|a| self.locals.get_type(*a)).collect::<Vec<_>>(),
                encoded_args);
@@ -4477,22 +4845,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         })
     }
 
-    fn construct_value_preserving_array_equality(
+    fn construct_value_preserving_snapshot_equality(
         &mut self,
         loop_head: BasicBlockIndex,
-        array_base: vir::Expr,
+        place: vir::Expr,
     ) -> vir::Expr {
         // this label is inserted in encode_loop_invariant_exhale_stmts at the point
         // where the other "preserve equality" assignments are made
         use std::collections::hash_map::Entry::*;
-        let old_label = match self.array_loop_old_label.entry(loop_head) {
+        let old_label = match self.loop_old_label.entry(loop_head) {
             Occupied(lbl) => lbl.into_mut(),
             Vacant(v) => v.insert(self.cfg_method.get_fresh_label_name()),
         };
 
-        let snap_array = vir::Expr::snap_app(array_base);
-        let old_snap_array = vir::Expr::old(snap_array.clone(), old_label);
-        vir_expr!{ [snap_array] == [old_snap_array] }
+        let snap_place = vir::Expr::snap_app(place);
+        let old_snap_place = vir::Expr::old(snap_place.clone(), old_label);
+        vir_expr!{ [snap_place] == [old_snap_place] }
     }
 
     /// Arguments:
@@ -4669,7 +5037,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                                                 ));
                                             }
                                             ExprOrArrayBase::ArrayBase(b) => {
-                                                let eq = self.construct_value_preserving_array_equality(loop_head, b);
+                                                let eq = self.construct_value_preserving_snapshot_equality(loop_head, b);
                                                 // arrays can be mentioned multiple times, so we
                                                 // need to check here
                                                 if !equalities.contains(&eq) {
@@ -4693,6 +5061,42 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             }
         }
 
+        // frame the places that the loop does not modify
+        for (mir_place, owned) in self.loop_encoder.get_framed_places(loop_head) {
+            if !matches!(self.mir.local_kind(mir_place.local), mir::LocalKind::Arg | mir::LocalKind::Var)
+                || mir_place.projection.iter().any(|elem| !matches!(elem, mir::ProjectionElem::Field(..)))
+            {
+                continue;
+            }
+            let (encoded_place, ty, _) = self.mir_encoder.encode_place(&mir_place)?;
+            let encoded_place = match encoded_place.into_array_base() {
+                ExprOrArrayBase::Expr(e) => e,
+                ExprOrArrayBase::ArrayBase(_) | ExprOrArrayBase::SliceBase(_) => continue,
+            };
+            if matches!(ty.kind(), ty::TyKind::Ref(..) | ty::TyKind::RawPtr(..)) {
+                continue;
+            }
+            let base = encoded_place.get_base();
+            let is_local_in_invariant = permissions.iter()
+                .filter_map(|perm| perm.get_place())
+                .any(|place| place.get_base() == base);
+            if !is_local_in_invariant {
+                // The loop does not access the local at all.
+                let perm_amount = if owned { vir::PermAmount::Write } else { vir::PermAmount::Read };
+                permissions.push(vir::Expr::pred_permission(encoded_place.clone(), perm_amount).unwrap());
+            } else if !permissions.iter().any(|perm| {
+                matches!(perm, vir::Expr::PredicateAccessPredicate(..))
+                    && perm.get_place() == Some(&encoded_place)
+            }) {
+                // The snapshot of the place could not be taken.
+                continue;
+            }
+            let equality = self.construct_value_preserving_snapshot_equality(loop_head, encoded_place);
+            if !equalities.contains(&equality) {
+                equalities.push(equality);
+            }
+        }
+
         // put the collected maxima of array permissions into the final permissions array
         // Note that array access permissions are always predicate permissions, never the raw
         // LocalVar
@@ -4849,7 +5253,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             loop_head
         ))];
         if !after_loop_iteration {
-            if let Some(label) = self.array_loop_old_label.get(&loop_head) {
+            if let Some(label) = self.loop_old_label.get(&loop_head) {
                 stmts.push(vir::Stmt::label(label));
             }
             for (place, field) in &self.pure_var_for_preserving_value_map[&loop_head] {
@@ -5364,10 +5768,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             encoded_right.clone(),
             operand_ty,
         ).with_span(span)?;
-        let encoded_check =
+        let encoded_check = if self.overflow_free_operations.contains(&location) {
+            self.encoder.register_discharged_overflow_check();
+            false.into()
+        } else {
             self.mir_encoder
                 .encode_bin_op_check(op, encoded_left, encoded_right, operand_ty)
-                .with_span(span)?;
+                .with_span(span)?
+        };
         let field_types = if let ty::TyKind::Tuple(ref x) = ty.kind() {
             x
         } else {
@@ -5786,6 +6194,276 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(stmts)
     }
 
+    /// Encodes an unsizing coercion of a shared reference or a box into a
+    /// trait object. The trait object is opaque: the only facts known about
+    /// it are the ones provided by the specifications of the trait methods.
+    fn encode_assign_trait_object(
+        &mut self,
+        encoded_lhs: vir::Expr,
+        operand: &mir::Operand<'tcx>,
+        ty: ty::Ty<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        trace!("encode_assign_trait_object(lhs={:?}, operand={:?}, ty={:?})", encoded_lhs, operand, ty);
+        let span = self.mir_encoder.get_span_of_location(location);
+        let mut stmts = Vec::new();
+
+        let (object_ty, permission) = match ty.kind() {
+            ty::TyKind::Ref(_, object_ty, mir::Mutability::Not) => (*object_ty, vir::PermAmount::Read),
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_box() => (ty.boxed_ty(), vir::PermAmount::Write),
+            _ => {
+                // The underlying value could be modified through the trait
+                // object without us noticing.
+                return Err(SpannedEncodingError::unsupported(
+                    "unsizing a mutable reference into a trait object is not supported",
+                    span,
+                ));
+            }
+        };
+
+        // The value is moved into the trait object, so the permission to it
+        // is transferred from the source. A copied shared reference keeps its
+        // permission.
+        if let mir::Operand::Move(ref place) = operand {
+            let (src_place, src_ty, ..) = self.mir_encoder.encode_place(place).with_span(span)?;
+            let src_expr = src_place.try_into_expr().with_span(span)?;
+            let src_object_ty = match src_ty.kind() {
+                ty::TyKind::Ref(_, src_object_ty, _) => *src_object_ty,
+                _ => src_ty.boxed_ty(),
+            };
+            let src_ref_field = self.encoder.encode_dereference_field(src_object_ty).with_span(span)?;
+            let src_object_expr = src_expr.field(src_ref_field);
+            let src_object_perm = vir::Expr::pred_permission(src_object_expr.clone(), permission).unwrap();
+            let pos = self.register_error(span, ErrorCtxt::Unexpected);
+            stmts.push(vir::Stmt::Exhale( vir::Exhale {
+                expr: vir::Expr::and(
+                    vir::Expr::acc_permission(src_object_expr, vir::PermAmount::Write),
+                    src_object_perm,
+                ),
+                position: pos,
+            }));
+        }
+
+        stmts.extend(self.encode_havoc(&encoded_lhs));
+        let ref_field = self.encoder.encode_dereference_field(object_ty).with_span(span)?;
+        let object_expr = encoded_lhs.field(ref_field);
+        stmts.push(vir_stmt!{ inhale [vir::Expr::acc_permission(object_expr.clone(), vir::PermAmount::Write)] });
+        let object_perm = vir::Expr::pred_permission(object_expr, permission).unwrap();
+        stmts.push(vir_stmt!{ inhale [object_perm] });
+
+        Ok(stmts)
+    }
+
+    /// Encodes the coercion of a function item into a function pointer. The
+    /// function pointer is opaque, but calls through it are known to require
+    /// and ensure exactly the contract of the function item.
+    fn encode_assign_fn_pointer(
+        &mut self,
+        encoded_lhs: vir::Expr,
+        operand: &mir::Operand<'tcx>,
+        fn_ptr_ty: ty::Ty<'tcx>,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        trace!("encode_assign_fn_pointer(lhs={:?}, operand={:?})", encoded_lhs, operand);
+        let span = self.mir_encoder.get_span_of_location(location);
+        let (def_id, substs) = match self.mir_encoder.get_operand_ty(operand).kind() {
+            ty::TyKind::FnDef(def_id, substs) => (*def_id, *substs),
+            ty => unreachable!("{:?}", ty),
+        };
+        // The type of the function pointer belongs to the encoded procedure,
+        // while the contract belongs to the called function.
+        let own_tymap = self.own_tymap();
+        let tymap = SubstMap::build(self.encoder.env(), def_id, substs);
+        let called_def_id = self.encoder.env()
+            .find_impl_of_trait_method_call(def_id, substs)
+            .unwrap_or(def_id);
+        let contract = self.encoder.get_procedure_contract_for_def(called_def_id)
+            .with_span(span)?;
+
+        let tcx = self.encoder.env().tcx();
+        let fn_sig = tcx.erase_late_bound_regions(fn_ptr_ty.fn_sig(tcx));
+        let mut encoded_args = vec![];
+        for (arg_idx, arg_ty) in fn_sig.inputs().iter().enumerate() {
+            let arg_snap_ty = self.encoder.encode_snapshot_type(*arg_ty, &own_tymap).with_span(span)?;
+            encoded_args.push(vir::LocalVar::new(format!("_fn_ptr_arg_{}", arg_idx), arg_snap_ty));
+        }
+        let result_snap_ty = self.encoder.encode_snapshot_type(fn_sig.output(), &own_tymap).with_span(span)?;
+        let encoded_result = vir::LocalVar::new("_fn_ptr_result", result_snap_ty);
+        let arg_exprs: Vec<vir::Expr> = encoded_args.iter().cloned().map(vir::Expr::local).collect();
+        let result_expr = vir::Expr::local(encoded_result.clone());
+
+        let mut precondition = vec![];
+        for item in contract.functional_precondition() {
+            precondition.push(self.encoder.encode_assertion(
+                item,
+                None,
+                &arg_exprs,
+                None,
+                true,
+                self.proc_def_id,
+                &tymap,
+                &substs,
+            )?);
+        }
+        let mut postcondition = vec![];
+        for item in contract.functional_postcondition() {
+            postcondition.push(self.encoder.encode_assertion(
+                item,
+                None,
+                &arg_exprs,
+                Some(&result_expr),
+                true,
+                self.proc_def_id,
+                &tymap,
+                &substs,
+            )?);
+        }
+
+        let mut stmts = self.encode_havoc_and_initialization(&encoded_lhs);
+        let fn_ptr = self.encoder.patch_snapshots(vir::Expr::snap_app(encoded_lhs), &own_tymap)
+            .with_span(span)?;
+        let pre_call = self.encoder.encode_fn_pointer_contract_call(
+            fn_ptr_ty,
+            fn_ptr.clone(),
+            arg_exprs.clone(),
+            None,
+            &own_tymap,
+        ).with_span(span)?;
+        let post_call = self.encoder.encode_fn_pointer_contract_call(
+            fn_ptr_ty,
+            fn_ptr,
+            arg_exprs,
+            Some(result_expr),
+            &own_tymap,
+        ).with_span(span)?;
+        let mut pre_definition = vir::Expr::eq_cmp(pre_call.clone(), precondition.into_iter().conjoin());
+        if !encoded_args.is_empty() {
+            pre_definition = vir::Expr::forall(
+                encoded_args.clone(),
+                vec![vir::Trigger::new(vec![pre_call])],
+                pre_definition,
+            );
+        }
+        let mut post_vars = encoded_args;
+        post_vars.push(encoded_result);
+        let post_definition = vir::Expr::forall(
+            post_vars,
+            vec![vir::Trigger::new(vec![post_call.clone()])],
+            vir::Expr::eq_cmp(post_call, postcondition.into_iter().conjoin()),
+        );
+        stmts.push(vir_stmt!{ inhale [pre_definition] });
+        stmts.push(vir_stmt!{ inhale [post_definition] });
+        Ok(stmts)
+    }
+
+    /// The type substitutions of the encoded procedure, which map each of its
+    /// generic parameters to itself.
+    fn own_tymap(&self) -> SubstMap<'tcx> {
+        let tcx = self.encoder.env().tcx();
+        SubstMap::build(
+            self.encoder.env(),
+            self.proc_def_id,
+            ty::List::identity_for_item(tcx, self.proc_def_id),
+        )
+    }
+
+    /// Encodes a call through a function pointer. The call is checked against
+    /// the contract that the function pointer is known to satisfy.
+    fn encode_fn_pointer_call(
+        &mut self,
+        location: mir::Location,
+        call_site_span: rustc_span::Span,
+        func_place: &mir::Place<'tcx>,
+        mir_args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        trace!("encode_fn_pointer_call(func={:?}, args={:?})", func_place, mir_args);
+        let tymap = self.own_tymap();
+        let (encoded_func, mut stmts, fn_ptr_ty, _) = self.encode_place(
+            func_place,
+            ArrayAccessKind::Shared,
+            location,
+        )?;
+        if !matches!(fn_ptr_ty.kind(), ty::TyKind::FnPtr(..)) {
+            return Err(SpannedEncodingError::unsupported(
+                format!("calls of values of type {:?} are not supported", fn_ptr_ty),
+                call_site_span,
+            ));
+        }
+        let fn_ptr = self.encoder.patch_snapshots(vir::Expr::snap_app(encoded_func), &tymap)
+            .with_span(call_site_span)?;
+
+        let mut arg_exprs = vec![];
+        let mut moved_args = vec![];
+        for arg in mir_args {
+            let arg_expr = match arg {
+                mir::Operand::Move(place) | mir::Operand::Copy(place) => {
+                    let (encoded_place, place_stmts, ..) = self.encode_place(
+                        place,
+                        ArrayAccessKind::Shared,
+                        location,
+                    )?;
+                    stmts.extend(place_stmts);
+                    if let mir::Operand::Move(_) = arg {
+                        moved_args.push(encoded_place.clone());
+                    }
+                    vir::Expr::snap_app(encoded_place)
+                }
+                mir::Operand::Constant(_) => {
+                    self.mir_encoder.encode_operand_expr(arg).with_span(call_site_span)?
+                }
+            };
+            arg_exprs.push(
+                self.encoder.patch_snapshots(arg_expr, &tymap).with_span(call_site_span)?
+            );
+        }
+
+        let pre_call = self.encoder.encode_fn_pointer_contract_call(
+            fn_ptr_ty,
+            fn_ptr.clone(),
+            arg_exprs.clone(),
+            None,
+            &tymap,
+        ).with_span(call_site_span)?;
+        let pos = self.register_error(call_site_span, ErrorCtxt::ExhaleMethodPrecondition);
+        stmts.push(vir::Stmt::Assert( vir::Assert {
+            expr: pre_call,
+            position: pos,
+        }));
+
+        // The arguments that are moved into the call are consumed.
+        for moved_arg in moved_args {
+            let perm = vir::Expr::pred_permission(moved_arg, vir::PermAmount::Write).unwrap();
+            stmts.push(vir::Stmt::Exhale( vir::Exhale {
+                expr: perm,
+                position: pos,
+            }));
+        }
+
+        if let Some((ref target_place, _)) = destination {
+            let (encoded_target, pre_stmts, ..) = self.encode_place(
+                target_place,
+                ArrayAccessKind::Shared,
+                location,
+            )?;
+            stmts.extend(pre_stmts);
+            stmts.extend(self.encode_havoc_and_initialization(&encoded_target));
+            let result = self.encoder.patch_snapshots(vir::Expr::snap_app(encoded_target), &tymap)
+                .with_span(call_site_span)?;
+            let post_call = self.encoder.encode_fn_pointer_contract_call(
+                fn_ptr_ty,
+                fn_ptr,
+                arg_exprs,
+                Some(result),
+                &tymap,
+            ).with_span(call_site_span)?;
+            stmts.push(vir_stmt!{ inhale [post_call] });
+        } else {
+            stmts.push(vir_stmt!{ inhale [vir::Expr::from(false)] });
+        }
+        Ok(stmts)
+    }
+
     fn encode_assign_sequence_len(
         &mut self,
         encoded_lhs: vir::Expr,
@@ -6086,6 +6764,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
             ty::TyKind::Adt(_, _)
             | ty::TyKind::Closure(_, _)
+            | ty::TyKind::Generator(..)
+            | ty::TyKind::FnPtr(_)
             | ty::TyKind::Tuple(_)
             | ty::TyKind::Param(_)
             | ty::TyKind::Array(_, _) => {
@@ -6253,11 +6933,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
 
-            mir::AggregateKind::Generator(..) => {
-                return Err(SpannedEncodingError::unsupported(
-                    "construction of generators is not supported",
-                    span
-                ));
+            mir::AggregateKind::Generator(_def_id, substs, _) => {
+                // The state of a generator that has not been resumed yet
+                // consists only of its upvars.
+                let gen_substs = substs.as_generator();
+                for (field_index, field_ty) in gen_substs.upvar_tys().enumerate() {
+                    let operand = &operands[field_index];
+                    let encoded_field = self.encoder
+                        .encode_upvar_field(field_index, field_ty)
+                        .with_span(span)?;
+                    stmts.extend(self.encode_assign_operand(
+                        &dst.clone().field(encoded_field),
+                        operand,
+                        location,
+                    )?);
+                }
             }
         }
 
@@ -746,6 +746,32 @@ impl SnapshotEncoder {
                     predicate_type,
                 )
             }
+            ty::TyKind::Generator(_def_id, subst, _) => {
+                let gen_subst = subst.as_generator();
+                let mut fields = vec![];
+                for (field_num, field_ty) in gen_subst.upvar_tys().enumerate() {
+                    let field = encoder.encode_upvar_field(field_num, field_ty)?;
+                    fields.push(SnapshotField {
+                        name: field.name.clone(),
+                        access: self.snap_app(
+                            encoder,
+                            Expr::field(arg_expr.clone(), field),
+                            tymap,
+                        )?,
+                        mir_type: field_ty,
+                        typ: self.encode_type(encoder, field_ty, tymap)?,
+                    });
+                }
+                self.encode_complex(
+                    encoder,
+                    vec![SnapshotVariant {
+                        discriminant: -1,
+                        fields,
+                        name: None,
+                    }],
+                    predicate_type,
+                )
+            }
             ty::TyKind::Adt(adt_def, subst) if adt_def.is_struct() => {
                 let mut fields = vec![];
                 for field in adt_def.all_fields() {
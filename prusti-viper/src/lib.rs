@@ -30,4 +30,5 @@ extern crate rustc_hash;
 
 pub mod encoder;
 mod utils;
+pub mod validators;
 pub mod verifier;
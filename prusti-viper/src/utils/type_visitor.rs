@@ -6,7 +6,7 @@
 
 use rustc_hir::Mutability;
 use rustc_middle::ty::{
-    AdtDef, FieldDef, List, ParamTy, ProjectionTy, Region, Ty, TyCtxt,
+    AdtDef, FieldDef, List, ParamTy, PolyFnSig, ProjectionTy, Region, Ty, TyCtxt,
     TypeFlags, TyKind, IntTy, UintTy, FloatTy, VariantDef, subst::SubstsRef, Const
 };
 use rustc_hir::def_id::DefId;
@@ -67,9 +67,15 @@ pub trait TypeVisitor<'tcx>: Sized {
             TyKind::Closure(def_id, substs) => {
                 self.visit_closure(def_id, substs)
             }
+            TyKind::Generator(def_id, substs, _) => {
+                self.visit_generator(def_id, substs)
+            }
             TyKind::FnDef(def_id, substs) => {
                 self.visit_fndef(def_id, substs)
             }
+            TyKind::FnPtr(sig) => {
+                self.visit_fn_ptr(sig)
+            }
             TyKind::Array(ty, len) => {
                 self.visit_array(ty, len)
             }
@@ -197,6 +203,15 @@ pub trait TypeVisitor<'tcx>: Sized {
         walk_closure(self, def_id, substs)
     }
 
+    fn visit_generator(
+        &mut self,
+        def_id: DefId,
+        substs: SubstsRef<'tcx>
+    ) -> Result<(), Self::Error> {
+        trace!("visit_generator({:?})", def_id);
+        walk_generator(self, def_id, substs)
+    }
+
     fn visit_fndef(
         &mut self,
         def_id: DefId,
@@ -206,6 +221,14 @@ pub trait TypeVisitor<'tcx>: Sized {
         walk_fndef(self, def_id, substs)
     }
 
+    fn visit_fn_ptr(
+        &mut self,
+        sig: PolyFnSig<'tcx>,
+    ) -> Result<(), Self::Error> {
+        trace!("visit_fn_ptr({:?})", sig);
+        walk_fn_ptr(self, sig)
+    }
+
     fn visit_array(
         &mut self,
         ty: Ty<'tcx>,
@@ -288,17 +311,26 @@ pub fn walk_closure<'tcx, E, V: TypeVisitor<'tcx, Error = E>>(
     substs: SubstsRef<'tcx>
 ) -> Result<(), E> {
     let cl_substs = substs.as_closure();
-    // TODO: when are there bound typevars? can type visitor deal with generics?
-    let fn_sig =
-        cl_substs.sig()
-                 .no_bound_vars()
-                 .ok_or_else(|| visitor.unsupported("higher-ranked lifetimes and types are not supported"))?;
+    // Late-bound regions (e.g. of a closure taking `&T`) carry no information
+    // that we use, so we erase them.
+    let fn_sig = visitor.tcx().erase_late_bound_regions(cl_substs.sig());
     for ty in fn_sig.inputs() {
         visitor.visit_ty(*ty)?;
     }
     visitor.visit_ty(fn_sig.output())
 }
 
+pub fn walk_generator<'tcx, E, V: TypeVisitor<'tcx, Error = E>>(
+    visitor: &mut V,
+    _def_id: DefId,
+    substs: SubstsRef<'tcx>
+) -> Result<(), E> {
+    for ty in substs.as_generator().upvar_tys() {
+        visitor.visit_ty(ty)?;
+    }
+    Ok(())
+}
+
 pub fn walk_fndef<'tcx, E, V: TypeVisitor<'tcx, Error = E>>(
     visitor: &mut V,
     _def_id: DefId,
@@ -310,6 +342,17 @@ pub fn walk_fndef<'tcx, E, V: TypeVisitor<'tcx, Error = E>>(
     Ok(())
 }
 
+pub fn walk_fn_ptr<'tcx, E, V: TypeVisitor<'tcx, Error = E>>(
+    visitor: &mut V,
+    sig: PolyFnSig<'tcx>,
+) -> Result<(), E> {
+    let fn_sig = visitor.tcx().erase_late_bound_regions(sig);
+    for ty in fn_sig.inputs() {
+        visitor.visit_ty(*ty)?;
+    }
+    visitor.visit_ty(fn_sig.output())
+}
+
 pub fn walk_array<'tcx, E, V: TypeVisitor<'tcx, Error = E>>(
     visitor: &mut V,
     ty: Ty<'tcx>,
@@ -0,0 +1,205 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A pre-pass that finds, before the encoding, the Rust features used by a
+//! procedure that Prusti cannot (fully) encode.
+
+mod support_status;
+
+pub use self::support_status::{
+    FeatureReport, ProcedureSupportReport, SupportKind, SupportReport, SupportStatus,
+};
+
+use prusti_common::config;
+use prusti_interface::{data::ProcedureDefId, environment::Environment};
+use rustc_hash::FxHashSet;
+use rustc_middle::{
+    mir,
+    mir::visit::{PlaceContext, Visitor},
+    ty,
+    ty::subst::GenericArgKind,
+};
+use rustc_span::Span;
+
+pub struct Validator<'v, 'tcx> {
+    env: &'v Environment<'tcx>,
+}
+
+impl<'v, 'tcx> Validator<'v, 'tcx> {
+    pub fn new(env: &'v Environment<'tcx>) -> Self {
+        Validator { env }
+    }
+
+    pub fn procedure_support_status(&self, def_id: ProcedureDefId) -> SupportStatus {
+        self.support_status(def_id, false)
+    }
+
+    pub fn pure_function_support_status(&self, def_id: ProcedureDefId) -> SupportStatus {
+        self.support_status(def_id, true)
+    }
+
+    fn support_status(&self, def_id: ProcedureDefId, is_pure: bool) -> SupportStatus {
+        // The bodies of trusted procedures are not encoded.
+        if self.env.has_prusti_attribute(def_id, "trusted") {
+            return SupportStatus::default();
+        }
+        let body = self.env.local_mir(def_id.expect_local());
+        let mut visitor = SupportStatusVisitor {
+            body: &body,
+            status: SupportStatus::default(),
+            unsafe_core_proof: config::unsafe_core_proof(),
+        };
+        visitor.visit_body(&body);
+        let mut status = visitor.status;
+        if is_pure {
+            // Pure functions are encoded as Viper functions, which have
+            // neither loops nor a mutable heap.
+            for arg in body.args_iter() {
+                let local_decl = &body.local_decls[arg];
+                if local_decl.ty.walk().any(
+                    |arg| matches!(arg.unpack(), GenericArgKind::Type(ty) if ty.is_mutable_ptr()),
+                ) {
+                    status.unsupported(
+                        "mutable references in pure functions",
+                        local_decl.source_info.span,
+                    );
+                }
+            }
+            if body.is_cfg_cyclic() {
+                status.unsupported("loops in pure functions", body.span);
+            }
+        }
+        status
+    }
+}
+
+struct SupportStatusVisitor<'a, 'tcx> {
+    body: &'a mir::Body<'tcx>,
+    status: SupportStatus,
+    unsafe_core_proof: bool,
+}
+
+impl<'a, 'tcx> SupportStatusVisitor<'a, 'tcx> {
+    fn check_type(&mut self, ty: ty::Ty<'tcx>, span: Span) {
+        for arg in ty.walk() {
+            let ty = match arg.unpack() {
+                GenericArgKind::Type(ty) => ty,
+                _ => continue,
+            };
+            match ty.kind() {
+                // The core proof encoding has a model of raw pointers and unions.
+                ty::TyKind::RawPtr(_) if self.unsafe_core_proof => {
+                    self.status.partially_supported("raw pointers", span)
+                }
+                ty::TyKind::RawPtr(_) => self.status.unsupported("raw pointers", span),
+                ty::TyKind::Adt(adt_def, _) if adt_def.is_union() => {
+                    if self.unsafe_core_proof {
+                        self.status.partially_supported("unions", span)
+                    } else {
+                        self.status.unsupported("unions", span)
+                    }
+                }
+                ty::TyKind::Dynamic(..) => self.status.partially_supported("trait objects", span),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the place borrowed by the two-phase borrow `borrow` at
+    /// `location` is used before the borrow is activated. The fold-unfold
+    /// algorithm cannot handle such borrows, as in `v.push(v.len())`.
+    fn is_used_before_activation(
+        &self,
+        borrowed: mir::Local,
+        borrow: mir::Local,
+        location: mir::Location,
+    ) -> bool {
+        let mut visited = FxHashSet::default();
+        let mut location = location.successor_within_block();
+        loop {
+            let block = &self.body[location.block];
+            let mut uses = LocalUses::default();
+            let is_terminator = location.statement_index == block.statements.len();
+            if is_terminator {
+                uses.visit_terminator(block.terminator(), location);
+            } else {
+                uses.visit_statement(&block.statements[location.statement_index], location);
+            }
+            if uses.0.contains(&borrow) {
+                return false;
+            }
+            if uses.0.contains(&borrowed) {
+                return true;
+            }
+            if !is_terminator {
+                location = location.successor_within_block();
+                continue;
+            }
+            // Follow the control flow only as long as it is straight-line.
+            let mut successors = block
+                .terminator()
+                .successors()
+                .filter(|&&successor| !self.body[successor].is_cleanup);
+            match (successors.next(), successors.next()) {
+                (Some(&successor), None) if visited.insert(successor) => {
+                    location = successor.start_location();
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for SupportStatusVisitor<'a, 'tcx> {
+    fn visit_local_decl(&mut self, local: mir::Local, local_decl: &mir::LocalDecl<'tcx>) {
+        self.super_local_decl(local, local_decl);
+        self.check_type(local_decl.ty, local_decl.source_info.span);
+    }
+
+    fn visit_assign(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        rvalue: &mir::Rvalue<'tcx>,
+        location: mir::Location,
+    ) {
+        self.super_assign(place, rvalue, location);
+        if let mir::Rvalue::Ref(
+            _,
+            mir::BorrowKind::Mut {
+                allow_two_phase_borrow: true,
+            },
+            borrowed,
+        ) = rvalue
+        {
+            if let Some(borrow) = place.as_local() {
+                if self.is_used_before_activation(borrowed.local, borrow, location) {
+                    let span = self.body.source_info(location).span;
+                    self.status.unsupported("two-phase borrows", span);
+                }
+            }
+        }
+    }
+
+    fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: mir::Location) {
+        self.super_terminator(terminator, location);
+        if let mir::TerminatorKind::InlineAsm { .. } = terminator.kind {
+            self.status
+                .unsupported("inline assembly blocks", terminator.source_info.span);
+        }
+    }
+}
+
+/// The locals used by a statement or terminator.
+#[derive(Default)]
+struct LocalUses(Vec<mir::Local>);
+
+impl<'tcx> Visitor<'tcx> for LocalUses {
+    fn visit_local(&mut self, local: &mir::Local, context: PlaceContext, _location: mir::Location) {
+        if context.is_use() {
+            self.0.push(*local);
+        }
+    }
+}
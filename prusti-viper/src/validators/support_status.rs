@@ -0,0 +1,163 @@
+// © 2022, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use prusti_interface::environment::Environment;
+use rustc_span::Span;
+use serde::Serialize;
+use std::{fs, io, path::Path};
+
+/// How well Prusti supports a procedure or a feature used by it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupportKind {
+    Supported,
+    PartiallySupported,
+    Unsupported,
+}
+
+/// The first use of a feature that Prusti does not fully support.
+#[derive(Clone, Debug)]
+struct FeatureUse {
+    feature: &'static str,
+    kind: SupportKind,
+    span: Span,
+}
+
+/// The features used by a procedure that Prusti does not fully support.
+#[derive(Clone, Debug, Default)]
+pub struct SupportStatus {
+    features: Vec<FeatureUse>,
+}
+
+impl SupportStatus {
+    pub(super) fn unsupported(&mut self, feature: &'static str, span: Span) {
+        self.add(feature, SupportKind::Unsupported, span);
+    }
+
+    pub(super) fn partially_supported(&mut self, feature: &'static str, span: Span) {
+        self.add(feature, SupportKind::PartiallySupported, span);
+    }
+
+    /// Only the first use of each feature is recorded, to avoid reporting the
+    /// same feature for every temporary of the MIR.
+    fn add(&mut self, feature: &'static str, kind: SupportKind, span: Span) {
+        if !self
+            .features
+            .iter()
+            .any(|feature_use| feature_use.feature == feature)
+        {
+            self.features.push(FeatureUse {
+                feature,
+                kind,
+                span,
+            });
+        }
+    }
+
+    pub fn kind(&self) -> SupportKind {
+        self.features
+            .iter()
+            .map(|feature_use| feature_use.kind)
+            .max()
+            .unwrap_or(SupportKind::Supported)
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.kind() == SupportKind::Supported
+    }
+
+    /// Report the features that are not fully supported as compiler errors
+    /// or warnings.
+    pub fn report_support_status(
+        &self,
+        env: &Environment,
+        error_on_partially_supported: bool,
+        error_on_unsupported: bool,
+    ) {
+        for feature_use in &self.features {
+            let (message, is_error) = match feature_use.kind {
+                SupportKind::Supported => unreachable!(),
+                SupportKind::PartiallySupported => (
+                    format!(
+                        "[Prusti: unsupported feature] {} are only partially supported",
+                        feature_use.feature
+                    ),
+                    error_on_partially_supported,
+                ),
+                SupportKind::Unsupported => (
+                    format!(
+                        "[Prusti: unsupported feature] {} are not supported",
+                        feature_use.feature
+                    ),
+                    error_on_unsupported,
+                ),
+            };
+            if is_error {
+                env.span_err_with_help_and_notes(feature_use.span, &message, &None, &[]);
+            } else {
+                env.span_warn_with_help_and_notes(feature_use.span, &message, &None, &[]);
+            }
+        }
+    }
+
+    pub fn to_report(&self, env: &Environment, name: String, pure: bool) -> ProcedureSupportReport {
+        let source_map = env.codemap();
+        ProcedureSupportReport {
+            name,
+            pure,
+            status: self.kind(),
+            features: self
+                .features
+                .iter()
+                .map(|feature_use| FeatureReport {
+                    feature: feature_use.feature,
+                    kind: feature_use.kind,
+                    line: source_map.lookup_char_pos(feature_use.span.lo()).line,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The machine-readable support status of the procedures of a crate.
+#[derive(Debug, Serialize)]
+pub struct SupportReport {
+    pub crate_name: String,
+    /// `test` for a test harness, otherwise the crate type.
+    pub crate_type: String,
+    pub crate_hash: String,
+    pub procedures: Vec<ProcedureSupportReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProcedureSupportReport {
+    pub name: String,
+    pub pure: bool,
+    pub status: SupportKind,
+    pub features: Vec<FeatureReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeatureReport {
+    pub feature: &'static str,
+    pub kind: SupportKind,
+    pub line: usize,
+}
+
+impl SupportReport {
+    /// Write the report to `<dir>/<crate name>.<crate type>.<crate hash>.json`,
+    /// so that the crates of a workspace do not overwrite each other, even
+    /// when two packages have crates with the same name and type.
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "{}.{}.{}.json",
+            self.crate_name, self.crate_type, self.crate_hash
+        ));
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+}
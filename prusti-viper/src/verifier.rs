@@ -4,15 +4,16 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use prusti_common::vir::{optimizations::optimize_program};
+use prusti_common::vir::{optimizations::optimize_program, ToViperText};
 use prusti_common::{
     config, report::log, Stopwatch, vir::program::Program,
 };
 use crate::encoder::Encoder;
 use crate::encoder::counterexample_translation;
-// use prusti_filter::validators::Validator;
+use crate::validators::{SupportKind, SupportReport, Validator};
 use prusti_interface::data::VerificationResult;
 use prusti_interface::data::VerificationTask;
+use prusti_interface::data::VerificationSummary;
 use prusti_interface::environment::Environment;
 use prusti_interface::PrustiError;
 // use prusti_interface::specifications::TypedSpecificationMap;
@@ -22,10 +23,17 @@ use viper::{self, PersistentCache, Viper};
 
 
 use prusti_interface::specs::typed;
-use ::log::{info, debug, error};
-use prusti_server::{VerificationRequest, PrustiClient, process_verification_request, spawn_server_thread};
+use ::log::{info, debug, error, warn};
+use prusti_server::{
+    VerificationRequest, PrustiClient, process_verification_request, spawn_server_thread,
+    CancellationToken, Bundle, backend_version,
+};
+use rustc_middle::mir::pretty::write_mir_fn;
+use rustc_hir::def_id::LocalDefId;
 use rustc_span::DUMMY_SP;
 use prusti_server::tokio::runtime::Builder;
+use std::{fs, io, path::Path};
+use rustc_hash::FxHashSet;
 
 // /// A verifier builder is an object that lives entire program's
 // /// lifetime, has no mutable state, and is responsible for constructing
@@ -141,6 +149,9 @@ where
 {
     env: &'v Environment<'tcx>,
     encoder: Encoder<'v, 'tcx>,
+    summary: VerificationSummary,
+    /// The version of Prusti, recorded in verification bundles.
+    prusti_version: Option<String>,
 }
 
 impl<'v, 'tcx> Verifier<'v, 'tcx> {
@@ -151,9 +162,20 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         Verifier {
             env,
             encoder: Encoder::new(env, def_spec),
+            summary: VerificationSummary::default(),
+            prusti_version: None,
         }
     }
 
+    pub fn set_prusti_version(&mut self, prusti_version: String) {
+        self.prusti_version = Some(prusti_version);
+    }
+
+    /// The outcome of the last call to `verify` per procedure.
+    pub fn summary(&self) -> VerificationSummary {
+        self.summary
+    }
+
     pub fn verify(&mut self, task: &VerificationTask) -> VerificationResult {
         info!(
             "Received {} functions to be verified:",
@@ -175,78 +197,105 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             info!("   Source: {:?}", proc_span);
         }
 
-        // // Check support status, and queue encoding
-        // let validator = Validator::new(self.env.tcx());
-
-        // let report_support_status = config::report_support_status();
-        // let skip_unsupported_features = config::skip_unsupported_features();
-        // let error_on_partially_supported = config::error_on_partially_supported();
-        // let mut skipped_functions_count = 0;
-
-        // for &proc_id in &task.procedures {
-        //     let proc_name = self.env.get_absolute_item_name(proc_id);
-        //     let proc_span = self.env.get_def_span(proc_id);
-        //     let is_pure_function = self.env.has_prusti_attribute(proc_id, "pure");
-
-        //     let support_status = if is_pure_function {
-        //         validator.pure_function_support_status(proc_id)
-        //     } else {
-        //         validator.procedure_support_status(proc_id)
-        //     };
-
-        //     if report_support_status {
-        //         support_status.report_support_status(
-        //             &self.env,
-        //             is_pure_function,
-        //             // true ==> raise compiler errors for partially supported functions
-        //             error_on_partially_supported,
-        //             // true ==> raise compiler errors for unsupported functions
-        //             error_on_partially_supported || !skip_unsupported_features,
-        //         );
-        //     }
-
-        //     if !support_status.is_supported() && skip_unsupported_features {
-        //         warn!(
-        //             "Skip verification of {}, as it is not fully supported.",
-        //             proc_name
-        //         );
-        //         self.env.span_warn_with_help_and_note(
-        //             proc_span,
-        //             &format!(
-        //                 "this function will be ignored because it is not fully supported by \
-        //                 Prusti: {}",
-        //                 proc_name
-        //             ),
-        //             &Some(if report_support_status {
-        //                 "Disable the SKIP_UNSUPPORTED_FEATURES configuration flag to verify \
-        //                     this function anyway."
-        //                     .to_string()
-        //             } else {
-        //                 "Enable the REPORT_SUPPORT_STATUS configuration flag for more details \
-        //                     on why the function is not fully supported, or disable \
-        //                     SKIP_UNSUPPORTED_FEATURES to verify this function anyway."
-        //                     .to_string()
-        //             }),
-        //             &None,
-        //         );
-        //         skipped_functions_count += 1;
-        //     } else {
-        //         self.encoder.queue_procedure_encoding(proc_id);
-        //     }
-        // }
-        // info!(
-        //     "Out of {} functions, {} are not fully supported and have been skipped.",
-        //     task.procedures.len(),
-        //     skipped_functions_count,
-        // );
+        // Check support status, and queue encoding
+        let validator = Validator::new(self.env);
+
+        let report_support_status = config::report_support_status();
+        let skip_unsupported_features = config::skip_unsupported_features();
+        let error_on_partially_supported = config::error_on_partially_supported();
+        let support_status_report_dir = config::support_status_report_dir();
+        let mut support_reports = vec![];
+        let mut skipped_functions = FxHashSet::default();
+
+        for &proc_id in &task.procedures {
+            let proc_name = self.env.get_absolute_item_name(proc_id);
+            let proc_span = self.env.get_def_span(proc_id);
+            let is_pure_function = self.env.has_prusti_attribute(proc_id, "pure");
+
+            let support_status = if is_pure_function {
+                validator.pure_function_support_status(proc_id)
+            } else {
+                validator.procedure_support_status(proc_id)
+            };
+
+            if report_support_status {
+                support_status.report_support_status(
+                    self.env,
+                    // true ==> raise compiler errors for partially supported functions
+                    error_on_partially_supported,
+                    // true ==> raise compiler errors for unsupported functions
+                    error_on_partially_supported || !skip_unsupported_features,
+                );
+            }
+            if support_status_report_dir.is_some() {
+                support_reports.push(
+                    support_status.to_report(self.env, proc_name.clone(), is_pure_function)
+                );
+            }
+
+            // Partially supported functions are still verified.
+            if support_status.kind() == SupportKind::Unsupported && skip_unsupported_features {
+                warn!(
+                    "Skip verification of {}, as it is not supported.",
+                    proc_name
+                );
+                self.env.span_warn_with_help_and_notes(
+                    proc_span,
+                    &format!(
+                        "this function will be ignored because it is not supported by \
+                        Prusti: {}",
+                        proc_name
+                    ),
+                    &Some(if report_support_status {
+                        "Disable the SKIP_UNSUPPORTED_FEATURES configuration flag to verify \
+                            this function anyway."
+                            .to_string()
+                    } else {
+                        "Enable the REPORT_SUPPORT_STATUS configuration flag for more details \
+                            on why the function is not supported, or disable \
+                            SKIP_UNSUPPORTED_FEATURES to verify this function anyway."
+                            .to_string()
+                    }),
+                    &[],
+                );
+                skipped_functions.insert(proc_id);
+            }
+        }
+        info!(
+            "Out of {} functions, {} are not supported and have been skipped.",
+            task.procedures.len(),
+            skipped_functions.len(),
+        );
+
+        if let Some(dir) = support_status_report_dir {
+            let report = SupportReport {
+                crate_name: self.env.crate_name(),
+                crate_type: self.env.crate_kind(),
+                crate_hash: format!("{:016x}", self.env.crate_hash()),
+                procedures: support_reports,
+            };
+            if let Err(error) = report.write(&dir) {
+                error!("Failed to write the support status report to {}: {}", dir.display(), error);
+            }
+        }
+
+        self.summary = VerificationSummary {
+            unsupported: skipped_functions.len(),
+            ..VerificationSummary::default()
+        };
+        if config::support_status_only() {
+            return VerificationResult::Success;
+        }
 
         for &proc_id in task.procedures.iter().rev() {
-            // FIXME: Use the loop above.
-            self.encoder.queue_procedure_encoding(proc_id);
+            if !skipped_functions.contains(&proc_id) {
+                self.encoder.queue_procedure_encoding(proc_id);
+            }
         }
         self.encoder.process_encoding_queue();
 
         let encoding_errors_count = self.encoder.count_encoding_errors();
+        self.summary.discharged_overflow_checks = self.encoder.count_discharged_overflow_checks();
 
         let polymorphic_programs = self.encoder.get_viper_programs();
 
@@ -263,15 +312,34 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         };
         programs.extend(self.encoder.get_core_proof_programs());
 
+        let bundle_path = config::export_bundle();
+        let mut bundle = bundle_path.as_ref().map(|_| {
+            let mut bundle = Bundle::new(
+                self.prusti_version.clone().unwrap_or_else(|| "<unknown>".to_string())
+            );
+            bundle.add_file("config.txt".to_string(), config::dump());
+            bundle
+        });
+
         stopwatch.start_next("verifying Viper program");
-        let verification_results = verify_programs(self.env, programs);
+        let verification_results = verify_programs(self.env, programs, bundle.as_mut());
         stopwatch.finish();
 
         // Group verification results
         let mut verification_errors : Vec<_> = vec![];
         let mut consistency_errors : Vec<_> = vec![];
         let mut java_exceptions : Vec<_> = vec![];
+        let mut timeouts : Vec<_> = vec![];
+        let mut cancelled : Vec<_> = vec![];
+        let mut failed_programs = FxHashSet::default();
+        // With `SPLIT_PROGRAMS_BY_METHOD`, a function used by several methods
+        // is verified in each of their programs, so its errors are reported
+        // only once.
+        let mut reported_errors = FxHashSet::default();
         for (method_name, result) in verification_results.into_iter() {
+            if !result.is_success() {
+                failed_programs.insert(method_name.clone());
+            }
             match result {
                 viper::VerificationResult::Success => {}
                 viper::VerificationResult::ConsistencyErrors(errors) => {
@@ -281,13 +349,66 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                 }
                 viper::VerificationResult::Failure(errors) => {
                     for error in errors.into_iter() {
+                        let key = (
+                            method_name.clone(),
+                            error.full_id.clone(),
+                            error.pos_id.clone(),
+                            error.reason_pos_id.clone(),
+                        );
+                        if !reported_errors.insert(key) {
+                            continue;
+                        }
                         verification_errors.push((method_name.clone(), error));
                     }
                 }
                 viper::VerificationResult::JavaException(exception) => {
                     java_exceptions.push((method_name, exception));
                 }
+                viper::VerificationResult::Timeout => {
+                    timeouts.push(method_name);
+                }
+                viper::VerificationResult::Cancelled => {
+                    cancelled.push(method_name);
+                }
+            }
+        }
+
+        for &proc_id in &task.procedures {
+            if skipped_functions.contains(&proc_id) {
+                continue;
+            }
+            if self.env.has_prusti_attribute(proc_id, "trusted") {
+                // The body of a trusted procedure is not verified.
+                self.summary.trusted += 1;
+                continue;
+            }
+            match self.encoder.failed_encoding(proc_id) {
+                Some(true) => self.summary.unsupported += 1,
+                Some(false) => self.summary.failed += 1,
+                None if failed_programs.contains(&self.env.get_unique_item_name(proc_id))
+                    || failed_programs.contains(&self.env.get_absolute_item_name(proc_id)) => {
+                    self.summary.failed += 1
+                }
+                None => self.summary.verified += 1,
+            }
+        }
+
+        if let (Some(bundle), Some(bundle_path)) = (&mut bundle, &bundle_path) {
+            for &proc_id in &task.procedures {
+                let proc_name = self.env.get_unique_item_name(proc_id);
+                let failed = failed_programs.contains(&proc_name)
+                    || failed_programs.contains(&self.env.get_absolute_item_name(proc_id));
+                if !failed {
+                    continue;
+                }
+                if let Some(local_def_id) = proc_id.as_local() {
+                    bundle.add_file(
+                        format!("mir/{}.mir", proc_name),
+                        dump_mir(self.env, local_def_id),
+                    );
+                }
             }
+            write_bundle(bundle, bundle_path);
         }
 
         // Convert verification results to Prusti errors
@@ -301,14 +422,47 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             result = VerificationResult::Failure;
         }
 
+        // The span of the procedure that a Viper program was generated for.
+        let procedure_span = |method: &str| {
+            task.procedures.iter()
+                .find(|&&proc_id| {
+                    self.env.get_unique_item_name(proc_id) == method
+                        || self.env.get_absolute_item_name(proc_id) == method
+                })
+                .map(|&proc_id| self.env.get_def_span(proc_id))
+                .unwrap_or(DUMMY_SP)
+        };
+
         for (method, exception) in java_exceptions.into_iter() {
             error!("Java exception: {}", exception.get_stack_trace());
             PrustiError::internal(
-                format!("in {}: {}", method, exception), DUMMY_SP.into()
+                format!("in {}: {}", method, exception), procedure_span(&method).into()
             ).emit(self.env);
             result = VerificationResult::Failure;
         }
 
+        for method in timeouts.into_iter() {
+            PrustiError::timeout(
+                format!("the verifier ran out of time while verifying {}", method),
+                procedure_span(&method).into(),
+            ).set_help(
+                "the time budget of each method can be changed with the METHOD_TIMEOUT flag"
+            ).emit(self.env);
+            result = VerificationResult::Failure;
+        }
+
+        // The server cancels the requests of a session that a newer request
+        // superseded, so the results of this run are outdated anyway.
+        for method in cancelled.into_iter() {
+            let mut prusti_error = PrustiError::verification(
+                format!("the verification of {} was cancelled by a newer request", method),
+                procedure_span(&method).into(),
+            );
+            prusti_error.set_warning();
+            prusti_error.emit(self.env);
+            result = VerificationResult::Failure;
+        }
+
         // Report verification errors
         let mut prusti_errors: Vec<_> = vec![];
         for (method, verification_error) in verification_errors.into_iter() {
@@ -359,9 +513,34 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
     }
 }
 
-/// Verify a list of programs.
+/// Write the program as Viper text to `<emit_dir>/<program name>.vpr`.
+fn emit_viper_program(emit_dir: &Path, program: &Program) -> io::Result<()> {
+    fs::create_dir_all(emit_dir)?;
+    let path = emit_dir.join(format!("{}.vpr", program.get_name()));
+    fs::write(&path, program.to_viper_text())
+}
+
+/// Write the MIR of a procedure in the format of `-Zdump-mir`.
+fn dump_mir(env: &Environment, def_id: LocalDefId) -> String {
+    let mut buffer = vec![];
+    write_mir_fn(env.tcx(), &env.local_mir(def_id), &mut |_, _| Ok(()), &mut buffer)
+        .expect("writing to a buffer cannot fail");
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+fn write_bundle(bundle: &Bundle, path: &Path) {
+    match bundle.write(path) {
+        Ok(()) => info!("Verification bundle written to {}", path.display()),
+        Err(error) => error!(
+            "Failed to write the verification bundle to {}: {}", path.display(), error
+        ),
+    }
+}
+
+/// Verify a list of programs, adding the requests and their results to the
+/// bundle, if any.
 /// Returns a list of (program_name, verification_result) tuples.
-fn verify_programs(env: &Environment, programs: Vec<Program>)
+fn verify_programs(env: &Environment, programs: Vec<Program>, mut bundle: Option<&mut Bundle>)
     -> Vec<(String, viper::VerificationResult)>
 {
     let source_path = env.source_path();
@@ -371,17 +550,45 @@ fn verify_programs(env: &Environment, programs: Vec<Program>)
         .to_str()
         .unwrap()
         .to_owned();
-    let verification_requests = programs.into_iter().map(|mut program| {
+    // If enabled, verify every method separately so that the cache key of a
+    // method only depends on the method and the definitions it uses. Results
+    // are still reported under the name of the program that the method comes
+    // from.
+    let verification_requests = programs.into_iter().flat_map(|program| {
         let program_name = program.get_name().to_string();
-        // Prepend the Rust file name to the program.
-        program.set_name(format!("{}_{}", rust_program_name, program_name));
-        let request = VerificationRequest {
-            program,
-            backend_config: Default::default(),
+        let slices = if config::split_programs_by_method() {
+            program.split_by_method()
+        } else {
+            vec![program]
         };
+        slices.into_iter().map(move |slice| (program_name.clone(), slice))
+    }).map(|(program_name, mut program)| {
+        // Prepend the Rust file name to the program.
+        program.set_name(format!("{}_{}", rust_program_name, program.get_name()));
+        if let Some(emit_dir) = config::emit_viper() {
+            if let Err(error) = emit_viper_program(&emit_dir, &program) {
+                error!(
+                    "Failed to write the Viper program {} to {}: {}",
+                    program.get_name(), emit_dir.display(), error
+                );
+            }
+        }
+        let request = VerificationRequest::new(program, Default::default());
         (program_name, request)
+    }).collect::<Vec<_>>();
+    // The bundle is written before verifying, so that it is there even if
+    // the verification crashes.
+    let first_bundle_index = bundle.as_deref_mut().map(|bundle| {
+        let first_index = bundle.manifest().requests.len();
+        for (_, request) in &verification_requests {
+            bundle.add_request(request.clone());
+        }
+        if let Some(path) = config::export_bundle() {
+            write_bundle(bundle, &path);
+        }
+        first_index
     });
-    if let Some(server_address) = config::server_address() {
+    let results: Vec<_> = if let Some(server_address) = config::server_address() {
         let server_address = if server_address == "MOCK" {
             spawn_server_thread().to_string()
         } else {
@@ -403,7 +610,7 @@ fn verify_programs(env: &Environment, programs: Vec<Program>)
             .enable_all()
             .build()
             .expect("failed to construct Tokio runtime");
-        verification_requests.map(|(program_name, request)| {
+        verification_requests.into_iter().map(|(program_name, request)| {
             let remote_result = runtime.block_on(client.verify(request));
             let result = remote_result.unwrap_or_else(|error| {
                 panic!(
@@ -420,10 +627,29 @@ fn verify_programs(env: &Environment, programs: Vec<Program>)
         stopwatch.start_next("attach current thread to the JVM");
         let viper_thread = viper.attach_current_thread();
         stopwatch.finish();
+        if let (Some(bundle), Some((_, request))) =
+            (bundle.as_deref_mut(), verification_requests.first())
+        {
+            bundle.set_viper_version(
+                backend_version(&viper_thread, request.backend_config.backend)
+            );
+        }
         let mut cache = PersistentCache::load_cache(config::cache_path());
-        verification_requests.map(|(program_name, request)| {
-            let result = process_verification_request(&viper_thread, request, &mut cache);
+        let results = verification_requests.into_iter().map(|(program_name, request)| {
+            let result = process_verification_request(
+                &viper_thread, request, &mut cache, &CancellationToken::default()
+            );
             (program_name, result)
-        }).collect()
+        }).collect();
+        if config::enable_cache() {
+            info!("Verification cache: {} hits, {} misses", cache.hits(), cache.misses());
+        }
+        results
+    };
+    if let (Some(bundle), Some(first_index)) = (bundle, first_bundle_index) {
+        for (index, (_, result)) in results.iter().enumerate() {
+            bundle.set_result(first_index + index, result.clone());
+        }
     }
+    results
 }
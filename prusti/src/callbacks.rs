@@ -1,4 +1,5 @@
-use crate::verifier::verify;
+use crate::{contract_report::write_contract_report, verifier::verify};
+use log::error;
 use prusti_common::config;
 use prusti_interface::{
     environment::{mir_storage, Environment},
@@ -105,6 +106,15 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
                     println!("{}", value);
                 }
             }
+            if let Some(path) = config::contract_report_path() {
+                if let Err(error) = write_contract_report(&env, &def_spec, &path) {
+                    error!(
+                        "Failed to write the contract report to {}: {}",
+                        path.display(),
+                        error
+                    );
+                }
+            }
             if !config::no_verify() {
                 verify(env, def_spec);
             }
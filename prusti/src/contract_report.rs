@@ -0,0 +1,129 @@
+//! A JSON report of the contracts of the functions of the crate, which
+//! `prusti-lsp` shows in its hovers.
+
+use prusti_interface::{
+    environment::Environment,
+    specs::typed::{DefSpecificationMap, ProcedureSpecification, SpecificationSet},
+};
+use rustc_hir::def_id::{DefId, LocalDefId};
+use serde_json::{json, Value};
+use std::{fs, io, path::Path};
+
+/// Write the contract of every function that has a specification to `path`.
+/// Each entry contains the name of the function, the location of its name,
+/// its signature and its specification attributes as written in the source.
+pub fn write_contract_report(
+    env: &Environment<'_>,
+    def_spec: &DefSpecificationMap,
+    path: &Path,
+) -> io::Result<()> {
+    // External specifications are declared on a local stub.
+    let extern_stubs: Vec<(DefId, LocalDefId)> = def_spec
+        .extern_specs
+        .iter()
+        .map(|(&def_id, &stub)| (def_id, stub))
+        .collect();
+    let mut contracts = vec![];
+    for (&local_def_id, specification) in &def_spec.specs {
+        let specification = match specification {
+            SpecificationSet::Procedure(specification) => specification,
+            SpecificationSet::Loop(_) => continue,
+        };
+        let def_id = extern_stubs
+            .iter()
+            .find(|(_, stub)| *stub == local_def_id)
+            .map_or(local_def_id.to_def_id(), |(def_id, _)| *def_id);
+        let attributes = contract_attributes(env, specification);
+        if attributes.is_empty() {
+            continue;
+        }
+        contracts.push(contract(env, def_id, local_def_id, attributes));
+    }
+    contracts.sort_by_key(|contract| {
+        (
+            contract["file"].as_str().unwrap_or_default().to_string(),
+            contract["line"].as_u64(),
+            contract["column"].as_u64(),
+        )
+    });
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, Value::Array(contracts).to_string())
+}
+
+fn contract(
+    env: &Environment<'_>,
+    def_id: DefId,
+    local_def_id: LocalDefId,
+    attributes: Vec<String>,
+) -> Value {
+    let tcx = env.tcx();
+    let span = tcx
+        .def_ident_span(local_def_id.to_def_id())
+        .unwrap_or_else(|| tcx.def_span(local_def_id));
+    let location = env.codemap().lookup_char_pos(span.lo());
+    let signature = tcx
+        .hir()
+        .get_by_def_id(local_def_id)
+        .fn_sig()
+        .and_then(|sig| env.codemap().span_to_snippet(sig.span).ok());
+    json!({
+        "name": tcx.item_name(def_id).to_string(),
+        "path": env.get_absolute_item_name(def_id),
+        "file": location.file.name.prefer_local().to_string(),
+        "line": location.line,
+        "column": location.col.0 + 1,
+        "signature": signature,
+        "attributes": attributes,
+    })
+}
+
+/// The specification attributes of a procedure, with the assertions taken
+/// from the source code.
+fn contract_attributes(
+    env: &Environment<'_>,
+    specification: &ProcedureSpecification,
+) -> Vec<String> {
+    let snippet = |spec: LocalDefId| {
+        env.codemap()
+            .span_to_snippet(env.tcx().def_span(spec))
+            .unwrap_or_else(|_| "..".to_string())
+    };
+    let mut attributes = vec![];
+    if specification.pure.extract_inherit().unwrap_or(false) {
+        attributes.push("#[pure]".to_string());
+    }
+    if specification.trusted.extract_inherit().unwrap_or(false) {
+        attributes.push("#[trusted]".to_string());
+    }
+    for &pre in specification.pres.extract_with_selective_replacement_iter() {
+        attributes.push(format!("#[requires({})]", snippet(pre)));
+    }
+    for &post in specification
+        .posts
+        .extract_with_selective_replacement_iter()
+    {
+        attributes.push(format!("#[ensures({})]", snippet(post)));
+    }
+    for pledge in specification
+        .pledges
+        .extract_with_selective_replacement_iter()
+    {
+        attributes.push(match pledge.lhs {
+            Some(lhs) => format!(
+                "#[assert_on_expiry({}, {})]",
+                snippet(lhs),
+                snippet(pledge.rhs)
+            ),
+            None => format!("#[after_expiry({})]", snippet(pledge.rhs)),
+        });
+    }
+    if let Some(&body) = specification
+        .predicate_body
+        .extract_with_selective_replacement()
+    {
+        attributes.push(format!("predicate! {{ {} }}", snippet(body)));
+    }
+    attributes
+}
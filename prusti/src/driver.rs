@@ -32,6 +32,7 @@ extern crate smallvec;
 
 mod arg_value;
 mod callbacks;
+mod contract_report;
 mod verifier;
 
 use arg_value::arg_value;
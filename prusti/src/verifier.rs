@@ -3,11 +3,12 @@
 use log::{debug, trace, warn};
 use prusti_common::{config, report::user};
 use prusti_interface::{
-    data::{VerificationResult, VerificationTask},
+    data::{VerificationResult, VerificationSummary, VerificationTask},
     environment::Environment,
     specs::typed,
 };
 use prusti_viper::verifier::Verifier;
+use std::{fs, path::Path};
 
 pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
     trace!("[verify] enter");
@@ -41,6 +42,7 @@ pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
             }
         }
 
+        let mut summary = VerificationSummary::default();
         let verification_result = if verification_task.procedures.is_empty() {
             VerificationResult::Success
         } else {
@@ -48,12 +50,24 @@ pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
             env.dump_borrowck_info(&verification_task.procedures);
 
             let mut verifier = Verifier::new(&env, def_spec);
+            verifier.set_prusti_version(crate::get_prusti_version_info());
             let verification_result = verifier.verify(&verification_task);
             debug!("Verifier returned {:?}", verification_result);
+            summary = verifier.summary();
+            if summary.discharged_overflow_checks > 0 {
+                user::message(format!(
+                    "The interval analysis discharged {} overflow checks",
+                    summary.discharged_overflow_checks
+                ));
+            }
 
             verification_result
         };
 
+        if let Some(summary_dir) = config::verification_summary_dir() {
+            write_summary(&env, &summary_dir, &summary);
+        }
+
         match verification_result {
             VerificationResult::Success => {
                 if env.has_errors() {
@@ -77,3 +91,18 @@ pub fn verify(env: Environment<'_>, def_spec: typed::DefSpecificationMap) {
 
     trace!("[verify] exit");
 }
+
+/// Write the summary to `<crate name>.<kind>.json` in `dir`, so that the
+/// library, binaries and tests of a package get their own summary.
+fn write_summary(env: &Environment<'_>, dir: &Path, summary: &VerificationSummary) {
+    let kind = env.crate_kind();
+    let crate_name = env.crate_name();
+    let mut json = serde_json::to_value(summary).expect("could not serialize the summary");
+    json["crate"] = crate_name.clone().into();
+    json["kind"] = kind.clone().into();
+    fs::create_dir_all(dir)
+        .unwrap_or_else(|error| panic!("Could not create directory {}: {}", dir.display(), error));
+    let path = dir.join(format!("{}.{}.json", crate_name, kind));
+    fs::write(&path, json.to_string())
+        .unwrap_or_else(|error| panic!("Could not write summary to {}: {}", path.display(), error));
+}
@@ -5,6 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::{
+    collections::{BTreeMap, BTreeSet},
     env,
     error::Error,
     fs,
@@ -14,7 +15,7 @@ use std::{
 
 use log::{error, info, warn, LevelFilter};
 use rustwide::{cmd, logging, logging::LogStorage, Crate, Toolchain, WorkspaceBuilder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 struct CrateRecord {
@@ -80,6 +81,63 @@ fn get_rust_toolchain() -> RustToolchain {
     rust_toolchain.toolchain
 }
 
+/// The part of the support status report written by Prusti
+/// (see `SUPPORT_STATUS_REPORT_DIR`) that we aggregate.
+#[derive(Deserialize)]
+struct SupportReport {
+    procedures: Vec<ProcedureSupportReport>,
+}
+
+#[derive(Deserialize)]
+struct ProcedureSupportReport {
+    status: String,
+    features: Vec<FeatureReport>,
+}
+
+#[derive(Deserialize)]
+struct FeatureReport {
+    feature: String,
+    kind: String,
+}
+
+/// The support status of the procedures of all the verified crates.
+#[derive(Default, Serialize)]
+struct SupportSummary {
+    /// Number of procedures, by support status.
+    procedures: BTreeMap<String, usize>,
+    /// Number of procedures using a feature, by feature and support status.
+    features: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Number of crates having at least one procedure using a feature.
+    crates_by_feature: BTreeMap<String, usize>,
+}
+
+impl SupportSummary {
+    /// Add the reports written while verifying a crate, including the reports
+    /// of its dependencies.
+    fn add_crate(&mut self, reports_dir: &Path) -> Result<(), Box<dyn Error>> {
+        let mut crate_features = BTreeSet::new();
+        for entry in fs::read_dir(reports_dir)? {
+            let report: SupportReport = serde_json::from_slice(&fs::read(entry?.path())?)?;
+            for procedure in report.procedures {
+                *self.procedures.entry(procedure.status).or_default() += 1;
+                for feature in procedure.features {
+                    *self
+                        .features
+                        .entry(feature.feature.clone())
+                        .or_default()
+                        .entry(feature.kind)
+                        .or_default() += 1;
+                    crate_features.insert(feature.feature);
+                }
+            }
+        }
+        for feature in crate_features {
+            *self.crates_by_feature.entry(feature).or_default() += 1;
+        }
+        Ok(())
+    }
+}
+
 /// Find the Java home directory
 pub fn find_java_home() -> Option<PathBuf> {
     Command::new("java")
@@ -127,9 +185,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         .or_else(find_java_home)
         .expect("Please set JAVA_HOME");
     let host_java_policies = collect_java_policies();
+    let host_support_reports = Path::new("target/test-crates-support");
     let guest_prusti_home = Path::new("/opt/rustwide/prusti-home");
     let guest_viper_home = Path::new("/opt/rustwide/viper-home");
     let guest_z3_home = Path::new("/opt/rustwide/z3-home");
+    let guest_support_reports = Path::new("/opt/rustwide/support-reports");
     // Map JAVA at exactly the same location on the guest so that symlinks work.
     let guest_java_home = host_java_home.clone();
 
@@ -171,6 +231,11 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut failed_crates = vec![];
     // List of crates on which Prusti succeed.
     let mut successful_crates = vec![];
+    // Support status of the procedures of the verified crates.
+    let mut support_summary = SupportSummary::default();
+    if host_support_reports.exists() {
+        fs::remove_dir_all(host_support_reports)?;
+    }
 
     info!("Iterate over all {} crates...", crates_list.len());
     for (index, krate) in crates_list.iter().enumerate() {
@@ -210,6 +275,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         info!("Verify crate...");
         {
             let mut build_dir = workspace.build_dir(&format!("verify_{}", index));
+            let host_crate_support_reports = host_support_reports.join(format!("verify_{}", index));
+            fs::create_dir_all(&host_crate_support_reports)?;
 
             let mut sandbox = cmd::SandboxBuilder::new()
                 .memory_limit(Some(4024 * 1024 * 1024))
@@ -225,7 +292,12 @@ fn main() -> Result<(), Box<dyn Error>> {
                     cmd::MountKind::ReadOnly,
                 )
                 .mount(host_z3_home, guest_z3_home, cmd::MountKind::ReadOnly)
-                .mount(&host_java_home, &guest_java_home, cmd::MountKind::ReadOnly);
+                .mount(&host_java_home, &guest_java_home, cmd::MountKind::ReadOnly)
+                .mount(
+                    &host_crate_support_reports,
+                    guest_support_reports,
+                    cmd::MountKind::ReadWrite,
+                );
             for java_policy_path in &host_java_policies {
                 sandbox =
                     sandbox.mount(java_policy_path, java_policy_path, cmd::MountKind::ReadOnly);
@@ -245,6 +317,10 @@ fn main() -> Result<(), Box<dyn Error>> {
                         // Do not report errors for unsupported language features
                         .env("PRUSTI_SKIP_UNSUPPORTED_FEATURES", "true")
                         .env("PRUSTI_LOG_DIR", "/tmp/prusti_log")
+                        .env(
+                            "PRUSTI_SUPPORT_STATUS_REPORT_DIR",
+                            guest_support_reports.to_str().unwrap(),
+                        )
                         .run()?;
                     Ok(())
                 })
@@ -259,6 +335,13 @@ fn main() -> Result<(), Box<dyn Error>> {
             } else {
                 successful_crates.push(krate);
             }
+
+            if let Err(err) = support_summary.add_crate(&host_crate_support_reports) {
+                warn!(
+                    "Failed to read the support status reports of {}: {}",
+                    krate, err
+                );
+            }
         }
     }
 
@@ -282,6 +365,21 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    info!("Support status of the procedures:");
+    for (status, count) in &support_summary.procedures {
+        info!(" - {}: {}", status, count);
+    }
+    for (feature, kinds) in &support_summary.features {
+        info!(
+            "Feature '{}' is used in {} crates: {:?}",
+            feature, support_summary.crates_by_feature[feature], kinds
+        );
+    }
+    fs::write(
+        host_support_reports.join("summary.json"),
+        serde_json::to_string_pretty(&support_summary)?,
+    )?;
+
     // Panic
     assert!(failed_crates.is_empty(), "Failed to verify {} crates", failed_crates.len());
 
@@ -15,7 +15,7 @@ use std::{
 use verification_result::VerificationResult;
 
 pub trait Cache {
-    fn get(&self, request: u64) -> Option<VerificationResult>;
+    fn get(&mut self, request: u64) -> Option<VerificationResult>;
     fn insert(self, request: u64, result: VerificationResult) -> Option<VerificationResult>;
 }
 // We can change the representation here (e.g. adding fields):
@@ -24,6 +24,10 @@ pub struct PersistentCache {
     updated: bool,
     load_loc: PathBuf,
     data: HashMap<u64, VerificationResult>,
+    /// Number of lookups that found a result since the cache was loaded.
+    hits: u64,
+    /// Number of lookups that did not find a result since the cache was loaded.
+    misses: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,6 +46,8 @@ impl From<(PathBuf, ResultCache)> for PersistentCache {
             updated: false,
             load_loc,
             data,
+            hits: 0,
+            misses: 0,
         }
     }
 }
@@ -85,6 +91,12 @@ impl PersistentCache {
             Err(e) => error!("Failed to create cache file: {e}"),
         }
     }
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
     pub fn save(&mut self) {
         // Save cache to disk, if changed and save path is valid
         if self.updated && !self.load_loc.as_os_str().is_empty() {
@@ -104,8 +116,14 @@ impl Drop for PersistentCache {
 }
 
 impl Cache for &mut PersistentCache {
-    fn get(&self, request: u64) -> Option<VerificationResult> {
-        self.data.get(&request).cloned()
+    fn get(&mut self, request: u64) -> Option<VerificationResult> {
+        let result = self.data.get(&request).cloned();
+        if result.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        result
     }
     fn insert(self, request: u64, result: VerificationResult) -> Option<VerificationResult> {
         self.updated = true;
@@ -113,7 +131,7 @@ impl Cache for &mut PersistentCache {
     }
 }
 impl Cache for &Arc<Mutex<PersistentCache>> {
-    fn get(&self, request: u64) -> Option<VerificationResult> {
+    fn get(&mut self, request: u64) -> Option<VerificationResult> {
         let mut cache = self.lock().unwrap();
         cache.deref_mut().get(request)
     }
@@ -19,6 +19,10 @@ pub enum VerificationResult {
     ConsistencyErrors(Vec<String>),
     /// The verification raised a Java exception.
     JavaException(JavaException),
+    /// The verifier ran out of its time budget.
+    Timeout,
+    /// The verification was cancelled because a newer request superseded it.
+    Cancelled,
 }
 
 impl VerificationResult {
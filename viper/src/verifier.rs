@@ -6,10 +6,16 @@
 
 use ast_factory::*;
 use ast_utils::AstUtils;
-use jni::{objects::JObject, JNIEnv};
+use jni::{
+    objects::{GlobalRef, JObject},
+    JNIEnv, JavaVM,
+};
 use jni_utils::JniUtils;
 use silicon_counterexample::SiliconCounterexample;
-use std::path::PathBuf;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 use verification_backend::VerificationBackend;
 use verification_result::{VerificationError, VerificationResult};
 use viper_sys::wrappers::{scala, viper::*};
@@ -20,6 +26,37 @@ pub struct Verifier<'a> {
     verifier_instance: JObject<'a>,
     jni: JniUtils<'a>,
     ast_utils: AstUtils<'a>,
+    /// The name and the build version of the backend.
+    version: String,
+    /// Whether the verifier was stopped, possibly by a `VerifierStopHandle`.
+    stopped: Arc<Mutex<bool>>,
+}
+
+/// Stops a verifier from another thread. An ongoing verification of the
+/// stopped verifier returns `VerificationResult::Cancelled`.
+pub struct VerifierStopHandle {
+    jvm: JavaVM,
+    verifier_instance: GlobalRef,
+    stopped: Arc<Mutex<bool>>,
+}
+
+impl VerifierStopHandle {
+    pub fn stop(&self) {
+        let mut stopped = self.stopped.lock().unwrap();
+        if *stopped {
+            return;
+        }
+        let env = self
+            .jvm
+            .attach_current_thread()
+            .expect("failed to attach jvm thread");
+        let jni = JniUtils::new(&env);
+        debug!("Stopping the verifier of an ongoing verification");
+        jni.unwrap_result(
+            silver::verifier::Verifier::with(&env).call_stop(self.verifier_instance.as_obj()),
+        );
+        *stopped = true;
+    }
 }
 
 impl<'a> Verifier<'a> {
@@ -52,13 +89,14 @@ impl<'a> Verifier<'a> {
             }
         }));
 
-        ast_utils.with_local_frame(16, || {
+        let version = ast_utils.with_local_frame(16, || {
             let name =
                 jni.to_string(jni.unwrap_result(verifier_wrapper.call_name(verifier_instance)));
             let build_version = jni.to_string(
                 jni.unwrap_result(verifier_wrapper.call_buildVersion(verifier_instance)),
             );
             info!("Using backend {} version {}", name, build_version);
+            format!("{} {}", name, build_version)
         });
 
         Verifier {
@@ -67,9 +105,31 @@ impl<'a> Verifier<'a> {
             verifier_instance,
             jni,
             ast_utils,
+            version,
+            stopped: Arc::new(Mutex::new(false)),
         }
     }
 
+    /// The name and the build version of the backend, e.g. `Silicon 1.1`.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Create a handle to stop this verifier from another thread.
+    pub fn stop_handle(&self) -> VerifierStopHandle {
+        VerifierStopHandle {
+            jvm: self.jni.unwrap_result(self.env.get_java_vm()),
+            verifier_instance: self
+                .jni
+                .unwrap_result(self.env.new_global_ref(self.verifier_instance)),
+            stopped: self.stopped.clone(),
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        *self.stopped.lock().unwrap()
+    }
+
     #[must_use]
     pub fn parse_command_line(self, args: &[String]) -> Self {
         self.ast_utils.with_local_frame(16, || {
@@ -126,11 +186,19 @@ impl<'a> Verifier<'a> {
             }
 
             run_timed!("Viper verification", debug,
-                let viper_result = self.jni.unwrap_result(
+                let viper_result = self.jni.unwrap_or_exception(
                     self.verifier_wrapper
                         .call_verify(self.verifier_instance, program.to_jobject()),
                 );
             );
+            // Stopping the verifier makes the ongoing verification fail in
+            // arbitrary ways, so its result is ignored.
+            if self.is_stopped() {
+                debug!("The verification was cancelled");
+                return VerificationResult::Cancelled;
+            }
+            let viper_result = viper_result
+                .unwrap_or_else(|java_exception| panic!("{:?}", java_exception));
             debug!(
                 "Viper verification result: {}",
                 self.jni.to_string(viper_result)
@@ -161,6 +229,18 @@ impl<'a> Verifier<'a> {
                         .is_instance_of(viper_error, "viper/silver/verifier/VerificationError");
 
                     if !is_verification_error {
+                        let is_timeout = self
+                            .jni
+                            .is_instance_of(viper_error, "viper/silver/verifier/TimeoutOccurred");
+
+                        if is_timeout {
+                            debug!(
+                                "The verification timed out: {}",
+                                self.jni.to_string(viper_error)
+                            );
+                            return VerificationResult::Timeout;
+                        }
+
                         let is_aborted_exceptionally = self
                             .jni
                             .is_instance_of(viper_error, "viper/silver/verifier/AbortedExceptionally");
@@ -301,9 +381,16 @@ impl<'a> Verifier<'a> {
 
 impl<'a> Drop for Verifier<'a> {
     fn drop(&mut self) {
-        // Tell the verifier to stop its threads.
-        self.jni
-            .unwrap_result(self.verifier_wrapper.call_stop(self.verifier_instance));
+        // Tell the verifier to stop its threads, unless a stop handle already
+        // did.
+        {
+            let mut stopped = self.stopped.lock().unwrap();
+            if !*stopped {
+                self.jni
+                    .unwrap_result(self.verifier_wrapper.call_stop(self.verifier_instance));
+                *stopped = true;
+            }
+        }
         // Delete the local reference to the verifier in the JVM
         // so that the JVM garbage collector can clean it up.
         self.jni
@@ -31,6 +31,8 @@ pub enum Type {
     Closure(Closure),
     FunctionDef(FunctionDef),
     Projection(Projection),
+    /// A trait object `dyn Trait`.
+    Trait(Trait),
     Unsupported(Unsupported),
 }
 
@@ -145,6 +147,13 @@ pub struct Projection {
     pub arguments: Vec<Type>,
 }
 
+#[display(fmt = "dyn {}<{}>", name, "display::cjoin(arguments)")]
+pub struct Trait {
+    pub name: String,
+    /// Type arguments.
+    pub arguments: Vec<Type>,
+}
+
 #[display(fmt = "{}", name)]
 pub struct Unsupported {
     pub name: String,
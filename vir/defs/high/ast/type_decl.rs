@@ -12,7 +12,7 @@ pub enum TypeDecl {
     Tuple(Tuple),
     Struct(Struct),
     Enum(Enum),
-    // Union(Union),
+    Union(Union),
     Array(Array),
     // Slice(Slice),
     Reference(Reference),
@@ -76,6 +76,14 @@ pub struct Enum {
     pub variants: Vec<Struct>,
 }
 
+#[display(fmt = "{}", name)]
+pub struct Union {
+    pub name: String,
+    /// The typed views of the memory occupied by the union: one single-field
+    /// variant for each field of the union.
+    pub variants: Vec<Struct>,
+}
+
 #[display(fmt = "Array({}, {})", length, element_type)]
 pub struct Array {
     pub length: u64,
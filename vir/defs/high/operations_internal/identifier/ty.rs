@@ -26,6 +26,7 @@ impl WithIdentifier for ty::Type {
             ty::Type::Closure(ty) => ty.get_identifier(),
             ty::Type::FunctionDef(ty) => ty.get_identifier(),
             ty::Type::Projection(ty) => ty.get_identifier(),
+            ty::Type::Trait(ty) => ty.get_identifier(),
             ty::Type::Unsupported(ty) => ty.get_identifier(),
         }
     }
@@ -136,6 +137,14 @@ impl WithIdentifier for ty::Projection {
     }
 }
 
+impl WithIdentifier for ty::Trait {
+    fn get_identifier(&self) -> String {
+        let mut identifier = format!("dyn${}", self.name);
+        append_type_arguments(&mut identifier, &self.arguments);
+        identifier
+    }
+}
+
 impl WithIdentifier for ty::Unsupported {
     fn get_identifier(&self) -> String {
         format!("unsupported${}", self.name)
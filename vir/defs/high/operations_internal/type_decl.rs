@@ -1,6 +1,6 @@
 use super::super::ast::{
     field::FieldDecl,
-    type_decl::{Struct, Tuple},
+    type_decl::{Struct, Tuple, Union},
 };
 
 impl Tuple {
@@ -22,3 +22,20 @@ impl Struct {
         self.fields.iter().map(std::borrow::Cow::Borrowed)
     }
 }
+
+impl Union {
+    /// Iterates over the fields of the union, one per variant.
+    pub fn iter_fields(&self) -> impl Iterator<Item = std::borrow::Cow<'_, FieldDecl>> {
+        self.variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .map(std::borrow::Cow::Borrowed)
+    }
+    /// Returns the index of the variant that gives the typed view of the
+    /// field `field_name`.
+    pub fn get_variant_index(&self, field_name: &str) -> Option<usize> {
+        self.variants
+            .iter()
+            .position(|variant| variant.name == field_name)
+    }
+}
@@ -81,9 +81,12 @@ pub trait CfgReplacer<PathCtxt: Debug + Clone, Action: CheckNoOpAction + Debug>
 
     /// Compute actions that need to be performed before the join point,
     /// returning the merged branch context.
+    ///
+    /// ``curr_block_index`` is the block at which the branches join.
     fn prepend_join(
         &mut self,
         pctxts: Vec<&PathCtxt>,
+        curr_block_index: CfgBlockIndex,
     ) -> Result<(Vec<Action>, PathCtxt), Self::Error>;
 
     /// Convert actions to statements.
@@ -175,7 +178,7 @@ pub trait CfgReplacer<PathCtxt: Debug + Clone, Action: CheckNoOpAction + Debug>
             if incoming_pctxt.is_empty() {
                 pctxt = self.initial_context()?;
             } else {
-                let actions_and_pctxt = self.prepend_join(incoming_pctxt)?;
+                let actions_and_pctxt = self.prepend_join(incoming_pctxt, curr_block_index)?;
                 let actions = actions_and_pctxt.0;
                 pctxt = actions_and_pctxt.1;
                 for (&src_index, action) in incoming_edges.iter().zip(actions) {